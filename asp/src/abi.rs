@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use once_cell::sync::Lazy;
+use starknet::core::types::FieldElement;
+use crate::util::felt_prime;
 
 /// ABI Entry - represents a single entry in the ABI JSON
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +102,40 @@ static ERC20_ABI: Lazy<Vec<AbiEntry>> = Lazy::new(|| {
         .expect("Failed to parse ERC20 ABI")
 });
 
+/// Class hash of the Zylith class the embedded ABI was exported from (zylith/CONTRACT_ADDRESS.md)
+/// Update together with abis/zylith-abi.json when the contract is upgraded
+pub const EMBEDDED_ABI_CLASS_HASH: &str =
+    "0x4bb0d80090bb558d1b6bf5562992ee7f4daf3f8d077bcad1bc87c413fa19ed0";
+
+/// What to do when the deployed class differs from the embedded ABI's (CLASS_HASH_CHECK)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassHashCheck {
+    /// Don't check
+    Off,
+    /// Log a warning and keep running (default)
+    Warn,
+    /// Refuse to start
+    Strict,
+}
+
+impl ClassHashCheck {
+    pub fn from_env() -> Self {
+        match std::env::var("CLASS_HASH_CHECK").as_deref() {
+            Ok("off") => ClassHashCheck::Off,
+            Ok("strict") => ClassHashCheck::Strict,
+            _ => ClassHashCheck::Warn,
+        }
+    }
+}
+
+/// Expected Zylith class hash: EXPECTED_CLASS_HASH, or the embedded ABI's class
+pub fn expected_class_hash() -> String {
+    std::env::var("EXPECTED_CLASS_HASH")
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| EMBEDDED_ABI_CLASS_HASH.to_string())
+}
+
 /// Get Zylith ABI
 pub fn get_zylith_abi() -> &'static [AbiEntry] {
     &ZYLITH_ABI
@@ -124,36 +160,657 @@ pub fn find_function<'a>(abi: &'a [AbiEntry], function_name: &str) -> Result<&'a
     Err(format!("Function '{}' not found in ABI", function_name))
 }
 
-/// Validate that ABI contains all required functions
-pub fn validate_zylith_abi(abi: &[AbiEntry]) -> Result<(), String> {
-    let required_functions = vec![
-        "private_deposit",
-        "private_swap",
-        "private_withdraw",
-        "private_mint_liquidity",
-        "private_burn_liquidity",
-        "get_merkle_root",
-        "is_nullifier_spent",
-        "is_root_known",
-    ];
+/// Private operations a frontend builds UI for
+pub const PRIVATE_OPERATIONS: &[&str] = &[
+    "private_deposit",
+    "private_swap",
+    "private_withdraw",
+    "private_mint_liquidity",
+    "private_burn_liquidity",
+];
 
-    for func_name in required_functions {
-        find_function(abi, func_name)
-            .map_err(|_| format!("Required function '{}' not found in ABI", func_name))?;
+/// All functions declared in the ABI's interfaces, optionally only the private operations
+pub fn list_functions(abi: &[AbiEntry], private_only: bool) -> Vec<&InterfaceItem> {
+    abi.iter()
+        .filter_map(|entry| match entry {
+            AbiEntry::Interface { items, .. } => Some(items),
+            _ => None,
+        })
+        .flatten()
+        .filter(|item| item.item_type == "function")
+        .filter(|item| !private_only || PRIVATE_OPERATIONS.contains(&item.name.as_str()))
+        .collect()
+}
+
+/// A calldata parameter decoded with its ABI type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub value: serde_json::Value,
+}
+
+/// Reads felts off a calldata array, in order
+struct CalldataReader<'a> {
+    calldata: &'a [String],
+    position: usize,
+}
+
+impl CalldataReader<'_> {
+    fn next(&mut self, what: &str) -> Result<num_bigint::BigUint, String> {
+        let raw = self.calldata.get(self.position).ok_or_else(|| {
+            format!("Calldata ended at element {} while decoding '{}'", self.position, what)
+        })?;
+        let raw = raw.trim();
+        let value = match raw.strip_prefix("0x") {
+            Some(hex) => num_bigint::BigUint::parse_bytes(hex.as_bytes(), 16),
+            None => num_bigint::BigUint::parse_bytes(raw.as_bytes(), 10),
+        }
+        .ok_or_else(|| format!("Calldata element {} ('{}') is not a felt", self.position, raw))?;
+        if value >= felt_prime() {
+            return Err(format!("Calldata element {} ('{}') exceeds the field prime", self.position, raw));
+        }
+        self.position += 1;
+        Ok(value)
     }
+}
 
-    Ok(())
+/// Decode a function's calldata felts into named, typed parameters (reverses calldata.rs)
+/// u256 becomes one decimal, ContractAddress and felt252 hex, signed integers are un-wrapped
+/// from PRIME - n, and Array<T> is read as [len, ...elements]
+pub fn decode_calldata(
+    abi: &[AbiEntry],
+    function_name: &str,
+    calldata: &[String],
+) -> Result<Vec<DecodedParam>, String> {
+    let function = find_function(abi, function_name)?;
+    let mut reader = CalldataReader { calldata, position: 0 };
+
+    let params = function
+        .inputs
+        .iter()
+        .map(|input| {
+            Ok(DecodedParam {
+                name: input.name.clone(),
+                type_: input.type_.clone(),
+                value: decode_value(abi, &input.type_, &input.name, &mut reader)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if reader.position != calldata.len() {
+        return Err(format!(
+            "Calldata has {} element(s) but '{}' only uses {}",
+            calldata.len(),
+            function_name,
+            reader.position
+        ));
+    }
+    Ok(params)
+}
+
+/// Decode an event's data felts by member name, using the ABI's `event` entry, into `T`
+/// `event_name` is the short name (e.g. "Deposit"), which is also what its selector is derived
+/// from; the ABI must have exactly one struct event of that name, with data members only.
+/// Members are decoded like calldata (felt252 as hex, integers as decimal strings)
+pub fn decode_event<T: serde::de::DeserializeOwned>(
+    abi: &[AbiEntry],
+    event_name: &str,
+    data: &[String],
+) -> Result<T, String> {
+    let suffix = format!("::{}", event_name);
+    let mut events = abi.iter().filter_map(|entry| match entry {
+        AbiEntry::Event { name, kind, members, .. } if kind == "struct" && name.ends_with(&suffix) => Some(members),
+        _ => None,
+    });
+    let members = match (events.next(), events.next()) {
+        (Some(members), None) => members,
+        (None, _) => return Err(format!("ABI has no '{}' event", event_name)),
+        (Some(_), Some(_)) => return Err(format!("ABI has several '{}' events", event_name)),
+    };
+    if let Some(key) = members.iter().find(|member| member.kind == "key") {
+        return Err(format!("'{}' has key member '{}', only data members are supported", event_name, key.name));
+    }
+
+    let mut reader = CalldataReader { calldata: data, position: 0 };
+    let mut object = serde_json::Map::new();
+    for member in members {
+        let path = format!("{}.{}", event_name, member.name);
+        object.insert(member.name.clone(), decode_value(abi, &member.type_, &path, &mut reader)?);
+    }
+    if reader.position != data.len() {
+        return Err(format!(
+            "'{}' event has {} data element(s) but the ABI layout uses {}",
+            event_name,
+            data.len(),
+            reader.position
+        ));
+    }
+    serde_json::from_value(serde_json::Value::Object(object))
+        .map_err(|e| format!("'{}' event doesn't match the expected fields: {}", event_name, e))
+}
+
+fn decode_value(
+    abi: &[AbiEntry],
+    type_: &str,
+    name: &str,
+    reader: &mut CalldataReader,
+) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    if type_ == U256 {
+        let low = reader.next(name)?;
+        let high = reader.next(name)?;
+        if low.bits() > 128 || high.bits() > 128 {
+            return Err(format!("'{}' has a u256 limb wider than 128 bits", name));
+        }
+        let combined: num_bigint::BigUint = (high << 128usize) + low;
+        return Ok(Value::String(combined.to_string()));
+    }
+    if type_ == FELT {
+        return Ok(Value::String(format!("0x{:x}", reader.next(name)?)));
+    }
+    if type_ == ADDRESS {
+        return Ok(Value::String(format!("0x{:064x}", reader.next(name)?)));
+    }
+    if type_ == BOOL {
+        let value = reader.next(name)?;
+        return match u8::try_from(&value) {
+            Ok(0) => Ok(Value::Bool(false)),
+            Ok(1) => Ok(Value::Bool(true)),
+            _ => Err(format!("'{}' is not a bool: {}", name, value)),
+        };
+    }
+    if let Some(bits) = type_.strip_prefix("core::integer::u").and_then(|b| b.parse::<u64>().ok()) {
+        let value = reader.next(name)?;
+        if value.bits() > bits {
+            return Err(format!("'{}' does not fit in u{}: {}", name, bits, value));
+        }
+        return Ok(Value::String(value.to_string()));
+    }
+    if let Some(bits) = type_.strip_prefix("core::integer::i").and_then(|b| b.parse::<u32>().ok()) {
+        let value = reader.next(name)?;
+        let limit = num_bigint::BigUint::from(1u8) << (bits as usize - 1);
+        let prime = felt_prime();
+        // Non-negative values are stored as is, negative ones as PRIME - |n|
+        let decoded = if value < limit {
+            value.to_string()
+        } else if &prime - &value <= limit {
+            format!("-{}", &prime - &value)
+        } else {
+            return Err(format!("'{}' does not fit in i{}: {}", name, bits, value));
+        };
+        return Ok(Value::String(decoded));
+    }
+    if let Some(inner) = type_.strip_prefix("core::array::Array::<").and_then(|t| t.strip_suffix('>')) {
+        let len = reader.next(name)?;
+        let len = usize::try_from(&len).map_err(|_| format!("'{}' has an invalid array length", name))?;
+        let items = (0..len)
+            .map(|i| decode_value(abi, inner, &format!("{}[{}]", name, i), reader))
+            .collect::<Result<Vec<_>, String>>()?;
+        return Ok(Value::Array(items));
+    }
+    let members = abi.iter().find_map(|entry| match entry {
+        AbiEntry::Struct { name: struct_name, members } if struct_name == type_ => Some(members),
+        _ => None,
+    });
+    match members {
+        Some(members) => {
+            let mut object = serde_json::Map::new();
+            for member in members {
+                let path = format!("{}.{}", name, member.name);
+                object.insert(member.name.clone(), decode_value(abi, &member.type_, &path, reader)?);
+            }
+            Ok(Value::Object(object))
+        }
+        None => Err(format!("'{}' has unsupported type {}", name, type_)),
+    }
+}
+
+/// A function argument for encode_calldata, checked against the ABI type of its input
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiValue {
+    /// felt252 or ContractAddress
+    Felt(FieldElement),
+    Bool(bool),
+    /// Any unsigned integer, u8 through u256
+    Uint(num_bigint::BigUint),
+    /// Any signed integer, i8 through i128
+    Int(i128),
+    /// Array<T>
+    Array(Vec<AbiValue>),
+}
+
+impl AbiValue {
+    /// u256 from its (low, high) limbs
+    pub fn u256(low: u128, high: u128) -> Self {
+        AbiValue::Uint((num_bigint::BigUint::from(high) << 128usize) + low)
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            AbiValue::Felt(_) => "a felt",
+            AbiValue::Bool(_) => "a bool",
+            AbiValue::Uint(_) => "an unsigned integer",
+            AbiValue::Int(_) => "a signed integer",
+            AbiValue::Array(_) => "an array",
+        }
+    }
+}
+
+/// Encode a function's arguments into calldata felts following its ABI inputs (inverse of decode_calldata)
+/// ContractAddress and felt252 are one felt, u256 is [low, high], signed integers wrap to PRIME - n,
+/// bool is 0/1 and Array<T> is [len, ...elements]. Arguments must match the inputs in count and type
+pub fn encode_calldata(
+    abi: &[AbiEntry],
+    function_name: &str,
+    args: &[AbiValue],
+) -> Result<Vec<FieldElement>, String> {
+    let function = find_function(abi, function_name)?;
+    if args.len() != function.inputs.len() {
+        return Err(format!(
+            "'{}' takes {} argument(s), got {}",
+            function_name,
+            function.inputs.len(),
+            args.len()
+        ));
+    }
+
+    let mut felts = Vec::new();
+    for (input, arg) in function.inputs.iter().zip(args) {
+        encode_value(&input.type_, &input.name, arg, &mut felts)?;
+    }
+    felts
+        .iter()
+        .map(|felt| {
+            FieldElement::from_byte_slice_be(&felt.to_bytes_be())
+                .map_err(|e| format!("Failed to convert {} to a felt: {}", felt, e))
+        })
+        .collect()
+}
+
+fn encode_value(
+    type_: &str,
+    name: &str,
+    value: &AbiValue,
+    out: &mut Vec<num_bigint::BigUint>,
+) -> Result<(), String> {
+    use num_bigint::BigUint;
+
+    match value {
+        AbiValue::Felt(felt) if type_ == FELT || type_ == ADDRESS => {
+            out.push(BigUint::from_bytes_be(&felt.to_bytes_be()));
+            return Ok(());
+        }
+        AbiValue::Bool(flag) if type_ == BOOL => {
+            out.push(BigUint::from(*flag as u8));
+            return Ok(());
+        }
+        AbiValue::Uint(value) if type_ == U256 => {
+            if value.bits() > 256 {
+                return Err(format!("'{}' does not fit in u256: {}", name, value));
+            }
+            out.push(value & BigUint::from(u128::MAX));
+            out.push(value >> 128usize);
+            return Ok(());
+        }
+        _ => {}
+    }
+    if let Some(bits) = type_.strip_prefix("core::integer::u").and_then(|b| b.parse::<u64>().ok()) {
+        if let AbiValue::Uint(value) = value {
+            if value.bits() > bits {
+                return Err(format!("'{}' does not fit in u{}: {}", name, bits, value));
+            }
+            out.push(value.clone());
+            return Ok(());
+        }
+    }
+    if let Some(bits) = type_.strip_prefix("core::integer::i").and_then(|b| b.parse::<u32>().ok()) {
+        if let AbiValue::Int(value) = value {
+            // i128 covers every Cairo signed type; narrower ones are range-checked
+            if bits < 128 && !(-(1i128 << (bits - 1))..(1i128 << (bits - 1))).contains(value) {
+                return Err(format!("'{}' does not fit in i{}: {}", name, bits, value));
+            }
+            let magnitude = BigUint::from(value.unsigned_abs());
+            out.push(if *value < 0 { felt_prime() - magnitude } else { magnitude });
+            return Ok(());
+        }
+    }
+    if let Some(inner) = type_.strip_prefix("core::array::Array::<").and_then(|t| t.strip_suffix('>')) {
+        if let AbiValue::Array(items) = value {
+            out.push(BigUint::from(items.len()));
+            for (i, item) in items.iter().enumerate() {
+                encode_value(inner, &format!("{}[{}]", name, i), item, out)?;
+            }
+            return Ok(());
+        }
+    }
+    Err(format!("'{}' has type {}, got {}", name, type_, value.kind()))
 }
 
-/// Validate that ERC20 ABI contains required functions
-pub fn validate_erc20_abi(abi: &[AbiEntry]) -> Result<(), String> {
-    let required_functions = vec!["approve", "balance_of", "allowance"];
+/// Structured ABI validation error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    /// Required function is missing from the ABI
+    MissingFunction { function: String },
+    /// Function has a different number of inputs than calldata.rs builds
+    InputCountMismatch {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// Function input has a different type than calldata.rs assumes
+    InputTypeMismatch {
+        function: String,
+        index: usize,
+        parameter: String,
+        expected: String,
+        found: String,
+    },
+}
 
-    for func_name in required_functions {
-        find_function(abi, func_name)
-            .map_err(|_| format!("Required function '{}' not found in ERC20 ABI", func_name))?;
+impl std::fmt::Display for AbiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbiError::MissingFunction { function } => {
+                write!(f, "Required function '{}' not found in ABI", function)
+            }
+            AbiError::InputCountMismatch { function, expected, found } => write!(
+                f,
+                "Function '{}' has {} inputs, expected {}",
+                function, found, expected
+            ),
+            AbiError::InputTypeMismatch { function, index, parameter, expected, found } => write!(
+                f,
+                "Function '{}' input {} ('{}') has type '{}', expected '{}'",
+                function, index, parameter, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+const FELT: &str = "core::felt252";
+const BOOL: &str = "core::bool";
+const U128: &str = "core::integer::u128";
+const I32: &str = "core::integer::i32";
+const U256: &str = "core::integer::u256";
+const ADDRESS: &str = "core::starknet::contract_address::ContractAddress";
+const FELT_ARRAY: &str = "core::array::Array::<core::felt252>";
+
+/// Expected Zylith signatures as (function, [(parameter, type)])
+/// Must match the calldata layout built in calldata.rs
+const ZYLITH_SIGNATURES: &[(&str, &[(&str, &str)])] = &[
+    ("initialize", &[
+        ("token0", ADDRESS),
+        ("token1", ADDRESS),
+        ("fee", U128),
+        ("tick_spacing", I32),
+        ("sqrt_price_x128", U256),
+    ]),
+    ("private_deposit", &[("token", ADDRESS), ("amount", U256), ("commitment", FELT)]),
+    ("private_swap", &[
+        ("zero_for_one", BOOL),
+        ("amount_specified", U128),
+        ("sqrt_price_limit_x128", U256),
+        ("new_commitment", FELT),
+        ("proof", FELT_ARRAY),
+        ("public_inputs", FELT_ARRAY),
+    ]),
+    ("private_withdraw", &[
+        ("token", ADDRESS),
+        ("recipient", ADDRESS),
+        ("amount", U128),
+        ("proof", FELT_ARRAY),
+        ("public_inputs", FELT_ARRAY),
+    ]),
+    ("private_mint_liquidity", &[
+        ("tick_lower_felt", FELT),
+        ("tick_upper_felt", FELT),
+        ("liquidity", U128),
+        ("new_commitment", FELT),
+        ("proof", FELT_ARRAY),
+        ("public_inputs", FELT_ARRAY),
+    ]),
+    ("private_burn_liquidity", &[
+        ("tick_lower_felt", FELT),
+        ("tick_upper_felt", FELT),
+        ("liquidity", U128),
+        ("new_commitment", FELT),
+        ("proof", FELT_ARRAY),
+        ("public_inputs", FELT_ARRAY),
+    ]),
+    ("get_merkle_root", &[]),
+    ("is_nullifier_spent", &[("nullifier", FELT)]),
+    ("is_root_known", &[("root", FELT)]),
+];
+
+/// Expected ERC20 signatures as (function, [(parameter, type)])
+const ERC20_SIGNATURES: &[(&str, &[(&str, &str)])] = &[
+    ("approve", &[("spender", ADDRESS), ("amount", U256)]),
+    ("balance_of", &[("account", ADDRESS)]),
+    ("allowance", &[("owner", ADDRESS), ("spender", ADDRESS)]),
+];
+
+/// Check that every function in the table exists with the expected input types
+fn validate_signatures(
+    abi: &[AbiEntry],
+    signatures: &[(&str, &[(&str, &str)])],
+) -> Result<(), AbiError> {
+    for (func_name, expected_inputs) in signatures {
+        let item = find_function(abi, func_name).map_err(|_| AbiError::MissingFunction {
+            function: func_name.to_string(),
+        })?;
+
+        if item.inputs.len() != expected_inputs.len() {
+            return Err(AbiError::InputCountMismatch {
+                function: func_name.to_string(),
+                expected: expected_inputs.len(),
+                found: item.inputs.len(),
+            });
+        }
+
+        for (index, (input, (param_name, expected_type))) in
+            item.inputs.iter().zip(expected_inputs.iter()).enumerate()
+        {
+            if input.type_ != *expected_type {
+                return Err(AbiError::InputTypeMismatch {
+                    function: func_name.to_string(),
+                    index,
+                    parameter: param_name.to_string(),
+                    expected: expected_type.to_string(),
+                    found: input.type_.clone(),
+                });
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Validate that ABI contains all required functions with the expected signatures
+pub fn validate_zylith_abi(abi: &[AbiEntry]) -> Result<(), AbiError> {
+    validate_signatures(abi, ZYLITH_SIGNATURES)
+}
+
+/// Validate that ERC20 ABI contains required functions with the expected signatures
+pub fn validate_erc20_abi(abi: &[AbiEntry]) -> Result<(), AbiError> {
+    validate_signatures(abi, ERC20_SIGNATURES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_abis_match_signatures() {
+        assert_eq!(validate_zylith_abi(get_zylith_abi()), Ok(()));
+        assert_eq!(validate_erc20_abi(get_erc20_abi()), Ok(()));
+    }
+
+    #[test]
+    fn test_list_functions_private_filter() {
+        let private: Vec<&str> = list_functions(get_zylith_abi(), true)
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        for op in PRIVATE_OPERATIONS {
+            assert!(private.contains(op), "missing {}", op);
+        }
+        assert_eq!(private.len(), PRIVATE_OPERATIONS.len());
+
+        let all = list_functions(get_zylith_abi(), false);
+        assert!(all.len() > private.len());
+        assert!(all.iter().any(|f| f.name == "get_merkle_root" && f.state_mutability == "view"));
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_decode_deposit_event_by_member_name() {
+        #[derive(Debug, Deserialize)]
+        struct Deposit {
+            leaf_index: String,
+            commitment: String,
+        }
+
+        // Emitted as [commitment, leaf_index, root]
+        let data = strings(&["0xabc", "0x7", "0xdef"]);
+        let deposit: Deposit = decode_event(get_zylith_abi(), "Deposit", &data).unwrap();
+        assert_eq!(deposit.commitment, "0xabc");
+        assert_eq!(deposit.leaf_index, "7");
+
+        // A layout change is an error, not a misread
+        assert!(decode_event::<Deposit>(get_zylith_abi(), "Deposit", &strings(&["0xabc", "0x7"])).is_err());
+        let too_long = strings(&["0xabc", "0x7", "0xdef", "0x1"]);
+        assert!(decode_event::<Deposit>(get_zylith_abi(), "Deposit", &too_long).is_err());
+        assert!(decode_event::<Deposit>(get_zylith_abi(), "Deposit", &strings(&["0xabc", "0x100000000", "0x1"])).is_err());
+        assert!(decode_event::<Deposit>(get_zylith_abi(), "NoSuchEvent", &data).unwrap_err().contains("no"));
+    }
+
+    #[test]
+    fn test_decode_deposit_calldata() {
+        // [token, amount.low, amount.high, commitment] as built by build_deposit_calldata
+        let calldata = strings(&["0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7", "0x3e8", "0x1", "0xabc"]);
+        let params = decode_calldata(get_zylith_abi(), "private_deposit", &calldata).unwrap();
+
+        let names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["token", "amount", "commitment"]);
+        assert_eq!(
+            params[0].value,
+            "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"
+        );
+        // high * 2^128 + low
+        assert_eq!(params[1].value, "340282366920938463463374607431768212456");
+        assert_eq!(params[2].value, "0xabc");
+    }
+
+    #[test]
+    fn test_decode_swap_and_initialize_calldata() {
+        let swap = strings(&["1", "1000", "0", "1", "0x77", "2", "0xa", "0xb", "1", "0xc"]);
+        let params = decode_calldata(get_zylith_abi(), "private_swap", &swap).unwrap();
+        assert_eq!(params[0].value, true);
+        assert_eq!(params[2].value, "340282366920938463463374607431768211456");
+        assert_eq!(params[4].value, serde_json::json!(["0xa", "0xb"]));
+        assert_eq!(params[5].value, serde_json::json!(["0xc"]));
+
+        // tick_spacing = -60 is encoded as PRIME - 60
+        let minus_60 = format!("0x{:x}", felt_prime() - 60u8);
+        let initialize = strings(&["0x1", "0x2", "3000", &minus_60, "0", "1"]);
+        let params = decode_calldata(get_zylith_abi(), "initialize", &initialize).unwrap();
+        assert_eq!(params[3].value, "-60");
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_calldata() {
+        let abi = get_zylith_abi();
+        assert!(decode_calldata(abi, "private_deposit", &strings(&["0x1", "0x2"]))
+            .unwrap_err()
+            .contains("ended"));
+        assert!(decode_calldata(abi, "private_deposit", &strings(&["0x1", "0x2", "0x0", "0x3", "0x4"]))
+            .unwrap_err()
+            .contains("only uses 4"));
+        assert!(decode_calldata(abi, "private_swap", &strings(&["2", "1", "0", "0", "0", "0", "0"])).is_err());
+        assert!(decode_calldata(abi, "no_such_function", &[]).is_err());
+    }
+
+    #[test]
+    fn test_encode_calldata_round_trips_through_decode() {
+        let felt = |v: u128| AbiValue::Felt(FieldElement::from(v));
+        let encoded = encode_calldata(
+            get_zylith_abi(),
+            "private_swap",
+            &[
+                AbiValue::Bool(true),
+                AbiValue::Uint(1000u32.into()),
+                AbiValue::u256(0, 1),
+                felt(0x77),
+                AbiValue::Array(vec![felt(0xa), felt(0xb)]),
+                AbiValue::Array(vec![felt(0xc)]),
+            ],
+        )
+        .unwrap();
+        let as_strings: Vec<String> = encoded.iter().map(|f| format!("0x{:x}", f)).collect();
+        let params = decode_calldata(get_zylith_abi(), "private_swap", &as_strings).unwrap();
+        assert_eq!(params[0].value, true);
+        assert_eq!(params[1].value, "1000");
+        assert_eq!(params[2].value, "340282366920938463463374607431768211456");
+        assert_eq!(params[4].value, serde_json::json!(["0xa", "0xb"]));
+
+        let initialize = encode_calldata(
+            get_zylith_abi(),
+            "initialize",
+            &[felt(1), felt(2), AbiValue::Uint(3000u32.into()), AbiValue::Int(-60), AbiValue::u256(5, 0)],
+        )
+        .unwrap();
+        let as_strings: Vec<String> = initialize.iter().map(|f| format!("0x{:x}", f)).collect();
+        let params = decode_calldata(get_zylith_abi(), "initialize", &as_strings).unwrap();
+        assert_eq!(params[3].value, "-60");
+        assert_eq!(params[4].value, "5");
+    }
+
+    #[test]
+    fn test_encode_calldata_rejects_mismatches() {
+        let abi = get_zylith_abi();
+        let felt = AbiValue::Felt(FieldElement::ONE);
+        // Wrong count
+        assert!(encode_calldata(abi, "is_root_known", &[]).unwrap_err().contains("takes 1"));
+        // Wrong kind: u256 passed where a felt is expected
+        let err = encode_calldata(abi, "private_deposit", &[AbiValue::u256(1, 0), AbiValue::u256(1, 0), felt.clone()])
+            .unwrap_err();
+        assert!(err.contains("'token'"), "{}", err);
+        // Out of range: u128 amount and i32 tick spacing
+        let too_big = AbiValue::Uint(num_bigint::BigUint::from(1u8) << 128usize);
+        let withdraw = [felt.clone(), felt.clone(), too_big, AbiValue::Array(vec![]), AbiValue::Array(vec![])];
+        assert!(encode_calldata(abi, "private_withdraw", &withdraw).is_err());
+        let initialize = [felt.clone(), felt, AbiValue::Uint(0u8.into()), AbiValue::Int(1 << 31), AbiValue::u256(0, 0)];
+        assert!(encode_calldata(abi, "initialize", &initialize).unwrap_err().contains("i32"));
+    }
+
+    #[test]
+    fn test_type_drift_names_parameter() {
+        let mut abi = get_zylith_abi().to_vec();
+        for entry in abi.iter_mut() {
+            if let AbiEntry::Interface { items, .. } = entry {
+                for item in items.iter_mut().filter(|i| i.name == "private_deposit") {
+                    item.inputs[1].type_ = U128.to_string();
+                }
+            }
+        }
+
+        assert_eq!(
+            validate_zylith_abi(&abi),
+            Err(AbiError::InputTypeMismatch {
+                function: "private_deposit".to_string(),
+                index: 1,
+                parameter: "amount".to_string(),
+                expected: U256.to_string(),
+                found: U128.to_string(),
+            })
+        );
+    }
+}
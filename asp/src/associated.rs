@@ -0,0 +1,167 @@
+// Associated set: the operator-managed compliance set behind /associated/*
+// Unlike the deposit tree it is not bounded by on-chain deposits, so it can be capped
+// (ASSOCIATED_MAX_SIZE) and run as a rolling window (ASSOCIATED_EVICTION=fifo).
+
+use crate::merkle::MerkleTree;
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::ops::Deref;
+
+/// What happens when the associated set is full (ASSOCIATED_EVICTION)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Eviction {
+    /// Refuse new commitments (default)
+    Reject,
+    /// Overwrite the oldest commitment: slots are reused as a ring of `max_size` leaves
+    Fifo,
+}
+
+impl Eviction {
+    pub fn from_env() -> Self {
+        match std::env::var("ASSOCIATED_EVICTION").as_deref() {
+            Ok("fifo") => Eviction::Fifo,
+            _ => Eviction::Reject,
+        }
+    }
+}
+
+/// Result of an insert
+#[derive(Debug)]
+pub struct Inserted {
+    pub leaf_index: u32,
+    pub root: BigUint,
+    /// Commitment that was evicted to make room (FIFO only)
+    pub evicted: Option<BigUint>,
+    /// False when the commitment was already in the set: nothing changed and `leaf_index` is
+    /// its existing slot
+    pub inserted: bool,
+}
+
+/// Associated set tree with a size cap
+/// Derefs to the tree for reads (proofs, root); inserts must go through `insert`
+pub struct AssociatedSet {
+    tree: MerkleTree,
+    pub max_size: u64,
+    pub eviction: Eviction,
+    /// Commitments inserted so far; with FIFO the next slot is `inserted % max_size`
+    inserted: u64,
+}
+
+impl AssociatedSet {
+    /// `max_size` is clamped to [1, 2^depth]; None means the whole tree
+    pub fn new(depth: usize, max_size: Option<u64>, eviction: Eviction) -> Self {
+        let tree = MerkleTree::new(depth);
+        let max_size = max_size.unwrap_or(u64::MAX).clamp(1, tree.capacity());
+        Self { tree, max_size, eviction, inserted: 0 }
+    }
+
+    pub fn from_env(depth: usize) -> Self {
+        let max_size = std::env::var("ASSOCIATED_MAX_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok());
+        Self::new(depth, max_size, Eviction::from_env())
+    }
+
+    /// Commitments currently in the set (evicted ones excluded)
+    pub fn size(&self) -> u64 {
+        self.inserted.min(self.max_size)
+    }
+
+    /// Add a commitment; inserting one that is already in the set is a no-op
+    pub fn insert(&mut self, commitment: BigUint) -> Result<Inserted, String> {
+        if let Some(leaf_index) = self.tree.find_commitment_index(&commitment) {
+            return Ok(Inserted { leaf_index, root: self.tree.get_root(), evicted: None, inserted: false });
+        }
+
+        if self.inserted < self.max_size {
+            let leaf_index = self.tree.get_leaf_count();
+            let root = self.tree.insert(commitment);
+            self.inserted += 1;
+            return Ok(Inserted { leaf_index, root, evicted: None, inserted: true });
+        }
+
+        match self.eviction {
+            Eviction::Reject => Err(format!("Associated set is full ({} commitments)", self.max_size)),
+            Eviction::Fifo => {
+                let leaf_index = (self.inserted % self.max_size) as u32;
+                let evicted = self.tree.nodes.get(&(0, leaf_index)).cloned();
+                let root = self.tree.update_leaf(leaf_index, commitment)?;
+                self.inserted += 1;
+                Ok(Inserted { leaf_index, root, evicted, inserted: true })
+            }
+        }
+    }
+}
+
+impl Deref for AssociatedSet {
+    type Target = MerkleTree;
+
+    fn deref(&self) -> &MerkleTree {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_set_rejects() {
+        let mut set = AssociatedSet::new(4, Some(2), Eviction::Reject);
+        set.insert(BigUint::from(1u8)).unwrap();
+        set.insert(BigUint::from(2u8)).unwrap();
+        let root = set.get_root();
+
+        assert!(set.insert(BigUint::from(3u8)).unwrap_err().contains("full"));
+        assert_eq!(set.get_root(), root);
+        assert_eq!(set.size(), 2);
+    }
+
+    #[test]
+    fn test_fifo_evicts_oldest() {
+        let mut set = AssociatedSet::new(4, Some(2), Eviction::Fifo);
+        set.insert(BigUint::from(1u8)).unwrap();
+        set.insert(BigUint::from(2u8)).unwrap();
+
+        let third = set.insert(BigUint::from(3u8)).unwrap();
+        assert_eq!(third.leaf_index, 0);
+        assert_eq!(third.evicted, Some(BigUint::from(1u8)));
+        let fourth = set.insert(BigUint::from(4u8)).unwrap();
+        assert_eq!(fourth.leaf_index, 1);
+        assert_eq!(fourth.evicted, Some(BigUint::from(2u8)));
+
+        // Same root as a fresh tree holding only the two newest commitments
+        let mut expected = MerkleTree::new(4);
+        expected.insert(BigUint::from(3u8));
+        expected.insert(BigUint::from(4u8));
+        assert_eq!(set.get_root(), expected.get_root());
+        assert_eq!(set.size(), 2);
+        assert_eq!(set.get_leaf_count(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_insert_is_idempotent() {
+        let mut set = AssociatedSet::new(4, Some(2), Eviction::Reject);
+        let first = set.insert(BigUint::from(7u8)).unwrap();
+        assert!(first.inserted);
+        assert_eq!(first.leaf_index, 0);
+
+        let again = set.insert(BigUint::from(7u8)).unwrap();
+        assert!(!again.inserted);
+        assert_eq!(again.leaf_index, 0);
+        assert_eq!(again.root, first.root);
+        assert_eq!(set.get_leaf_count(), 1);
+        assert_eq!(set.size(), 1);
+
+        // The duplicate didn't use up capacity
+        assert_eq!(set.insert(BigUint::from(8u8)).unwrap().leaf_index, 1);
+    }
+
+    #[test]
+    fn test_max_size_is_clamped_to_capacity() {
+        let set = AssociatedSet::new(4, None, Eviction::Reject);
+        assert_eq!(set.max_size, 16);
+        assert_eq!(AssociatedSet::new(4, Some(0), Eviction::Reject).max_size, 1);
+    }
+}
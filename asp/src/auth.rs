@@ -0,0 +1,140 @@
+// Optional API key authentication for the expensive and state-changing endpoints
+// ASP_API_KEYS=key1,key2 requires "Authorization: Bearer <key>" on every route that runs the prover
+// (PROVER_ROUTES, also the per-IP rate limit's list), POST /associated/insert, POST /deposit/import
+// and POST /deposit/resync. Unset or empty: everything stays open, as in local development (except the
+// tree import and resync, which refuse to run without keys). Read endpoints are always public.
+
+use axum::http::Method;
+
+/// Routes that run the prover: the proof endpoints and the LP prepare endpoints, which build their
+/// proof in the same request. Cheap /api/proof/* helpers (estimate, checksum, job status) are not here.
+const PROVER_ROUTES: &[(Method, &str)] = &[
+    (Method::POST, "/api/proof/swap"),
+    (Method::POST, "/api/proof/swap/stream"),
+    (Method::POST, "/api/proof/lp-mint"),
+    (Method::POST, "/api/proof/withdraw"),
+    (Method::POST, "/api/withdraw/prepare"),
+    (Method::POST, "/api/liquidity/mint/prepare"),
+    (Method::POST, "/api/liquidity/burn/prepare"),
+];
+
+pub struct ApiKeys {
+    keys: Vec<String>,
+}
+
+impl ApiKeys {
+    /// Comma-separated ASP_API_KEYS; blank entries are ignored
+    pub fn from_env() -> Self {
+        Self::new(&std::env::var("ASP_API_KEYS").unwrap_or_default())
+    }
+
+    pub fn new(list: &str) -> Self {
+        let keys = list
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect();
+        ApiKeys { keys }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether a request to this route needs a key (never, when no keys are configured)
+    pub fn protects(&self, method: &Method, path: &str) -> bool {
+        if !self.enabled() {
+            return false;
+        }
+        let state_change = *method == Method::POST
+            && matches!(path, "/associated/insert" | "/deposit/import" | "/deposit/resync");
+        is_proof_generation(method, path) || state_change
+    }
+
+    /// Check an Authorization header value ("Bearer <key>")
+    pub fn authorizes(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        let token = token.trim();
+        // Compare against every key without stopping early, so timing doesn't reveal a match
+        self.keys
+            .iter()
+            .fold(false, |found, key| constant_time_eq(key.as_bytes(), token.as_bytes()) | found)
+    }
+}
+
+/// Whether a request runs the prover (one of PROVER_ROUTES); shared with the per-IP rate limit
+pub fn is_proof_generation(method: &Method, path: &str) -> bool {
+    PROVER_ROUTES.iter().any(|(m, p)| m == method && *p == path)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_without_keys() {
+        let keys = ApiKeys::new(" , ");
+        assert!(!keys.enabled());
+        assert!(!keys.protects(&Method::POST, "/api/proof/swap"));
+        assert!(!keys.protects(&Method::POST, "/associated/insert"));
+        assert!(!keys.protects(&Method::POST, "/deposit/resync"));
+        assert!(!keys.protects(&Method::POST, "/api/liquidity/mint/prepare"));
+    }
+
+    #[test]
+    fn test_protected_routes_and_bearer_check() {
+        let keys = ApiKeys::new("alpha, beta");
+        assert_eq!(keys.key_count(), 2);
+
+        assert!(keys.protects(&Method::POST, "/api/proof/swap"));
+        assert!(keys.protects(&Method::POST, "/api/proof/withdraw"));
+        assert!(keys.protects(&Method::POST, "/api/proof/swap/stream"));
+        assert!(keys.protects(&Method::POST, "/associated/insert"));
+        assert!(keys.protects(&Method::POST, "/deposit/import"));
+        assert!(keys.protects(&Method::POST, "/deposit/resync"));
+        assert!(keys.protects(&Method::POST, "/api/liquidity/mint/prepare"));
+        assert!(keys.protects(&Method::POST, "/api/liquidity/burn/prepare"));
+        assert!(!keys.protects(&Method::GET, "/api/liquidity/mint/prepare"));
+        assert!(!keys.protects(&Method::POST, "/api/proof/checksum"));
+        assert!(!keys.protects(&Method::GET, "/deposit/export"));
+        assert!(!keys.protects(&Method::GET, "/api/proof/status/abc"));
+        assert!(!keys.protects(&Method::POST, "/api/proof/estimate"));
+        assert!(!keys.protects(&Method::GET, "/associated/root"));
+        assert!(!keys.protects(&Method::GET, "/deposit/root"));
+
+        assert!(keys.authorizes(Some("Bearer alpha")));
+        assert!(keys.authorizes(Some("Bearer beta")));
+        assert!(!keys.authorizes(Some("Bearer gamma")));
+        assert!(!keys.authorizes(Some("Bearer alph")));
+        assert!(!keys.authorizes(Some("alpha")));
+        assert!(!keys.authorizes(None));
+    }
+
+    #[test]
+    fn test_prover_routes() {
+        // The rate limit keys on the same list, so the LP prepare endpoints are limited too
+        assert!(is_proof_generation(&Method::POST, "/api/liquidity/mint/prepare"));
+        assert!(is_proof_generation(&Method::POST, "/api/liquidity/burn/prepare"));
+        assert!(is_proof_generation(&Method::POST, "/api/proof/withdraw"));
+        assert!(is_proof_generation(&Method::POST, "/api/withdraw/prepare"));
+        assert!(is_proof_generation(&Method::POST, "/api/proof/swap/stream"));
+        assert!(!is_proof_generation(&Method::POST, "/api/proof/estimate"));
+        assert!(!is_proof_generation(&Method::POST, "/api/proof/checksum"));
+        assert!(!is_proof_generation(&Method::POST, "/api/proof/unknown"));
+        assert!(!is_proof_generation(&Method::GET, "/api/proof/status/abc"));
+    }
+}
@@ -1,6 +1,9 @@
 use starknet::{
     accounts::{Account, SingleOwnerAccount},
-    core::types::{BlockId, BlockTag, FieldElement},
+    core::types::{
+        BlockId, BlockTag, ExecutionResult, FieldElement, TransactionExecutionStatus,
+        TransactionStatus,
+    },
     core::utils::{get_selector_from_name, starknet_keccak},
     providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
     signers::{LocalWallet, SigningKey},
@@ -9,6 +12,13 @@ use std::str::FromStr;
 use std::sync::Arc;
 use url::Url;
 
+#[path = "../felt_conv.rs"]
+#[allow(dead_code)] // only i32_to_felt is used here
+mod felt_conv;
+#[path = "../tick_math.rs"]
+#[allow(dead_code)] // only validate_initial_sqrt_price is used here
+mod tick_math;
+
 /// Q128 constant for sqrt_price_x128 calculation
 /// Q128 = 2^128 = 340282366920938463463374607431768211456
 const Q128: &str = "340282366920938463463374607431768211456";
@@ -42,6 +52,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Calculate sqrt_price_x128 for 1:1 price (Q128)
     let sqrt_price = num_bigint::BigUint::from_str(Q128)?;
+    let tick = tick_math::validate_initial_sqrt_price(&sqrt_price)?;
     let (sqrt_price_low, sqrt_price_high) = u256_to_low_high(&sqrt_price);
 
     println!("🚀 Initializing Zylith Pool");
@@ -52,6 +63,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Fee: {} (0.3%)", fee);
     println!("Tick Spacing: {}", tick_spacing);
     println!("Sqrt Price X128: {} (1:1 price)", Q128);
+    println!("Initial Tick: {}", tick);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
     // Setup provider and account
@@ -134,12 +146,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         let status = provider.get_transaction_status(transaction_hash).await?;
         match status {
-            starknet::core::types::TransactionStatus::AcceptedOnL2(_)
-            | starknet::core::types::TransactionStatus::AcceptedOnL1(_) => {
+            TransactionStatus::AcceptedOnL2(TransactionExecutionStatus::Reverted)
+            | TransactionStatus::AcceptedOnL1(TransactionExecutionStatus::Reverted) => {
+                let reason = get_revert_reason(&provider, transaction_hash).await;
+                return Err(format!("Transaction reverted: {}", reason).into());
+            }
+            TransactionStatus::AcceptedOnL2(_) | TransactionStatus::AcceptedOnL1(_) => {
                 println!("✅ Transaction confirmed!");
                 break;
             }
-            starknet::core::types::TransactionStatus::Rejected => {
+            TransactionStatus::Rejected => {
                 return Err("Transaction was rejected".into());
             }
             _ => {
@@ -154,6 +170,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Read the revert reason from the transaction receipt
+async fn get_revert_reason(
+    provider: &JsonRpcClient<HttpTransport>,
+    transaction_hash: FieldElement,
+) -> String {
+    match provider.get_transaction_receipt(transaction_hash).await {
+        Ok(receipt) => match receipt.execution_result() {
+            ExecutionResult::Reverted { reason } if !reason.trim().is_empty() => reason.clone(),
+            _ => "revert reason not provided by RPC".to_string(),
+        },
+        Err(e) => format!("failed to fetch receipt: {}", e),
+    }
+}
+
 /// Build calldata for initialize function
 fn build_initialize_calldata(
     token0: &str,
@@ -166,15 +196,11 @@ fn build_initialize_calldata(
     let token0_felt = FieldElement::from_str(token0)?;
     let token1_felt = FieldElement::from_str(token1)?;
 
-    // Convert i32 to u128 for FieldElement
-    // In Cairo, i32 is stored as a felt252
-    let tick_spacing_u128 = tick_spacing as u128;
-
     Ok(vec![
         token0_felt,
         token1_felt,
         FieldElement::from(fee),
-        FieldElement::from(tick_spacing_u128),
+        felt_conv::i32_to_felt(tick_spacing),
         FieldElement::from(sqrt_price_low),
         FieldElement::from(sqrt_price_high),
     ])
@@ -1,30 +1,321 @@
-use starknet::core::types::{BlockId, BlockTag, FieldElement, FunctionCall};
+use num_bigint::BigUint;
+use serde::Serialize;
+use starknet::core::types::{
+    BlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1, BroadcastedTransaction,
+    ExecuteInvocation, ExecutionResult, FieldElement, FunctionCall, SimulationFlag, TransactionExecutionStatus,
+    TransactionStatus, TransactionTrace,
+};
 use starknet::core::utils::starknet_keccak;
 use starknet::providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider};
+use crate::util::parse_felt;
 use starknet_crypto::{pedersen_hash, FieldElement as CryptoFieldElement};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::{sleep, Duration};
 use url::Url;
 
+const DEFAULT_RPC_MAX_RETRIES: u32 = 3;
+const DEFAULT_RPC_RETRY_BASE_MS: u64 = 200;
+const RPC_RETRY_MAX_DELAY_MS: u64 = 5_000;
+const DEFAULT_ROOT_CACHE_TTL_MS: u64 = 3_000;
+
+/// Retries for transient RPC failures (RPC_MAX_RETRIES, RPC_RETRY_BASE_MS)
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    /// Attempts after the first one; 0 disables retries
+    max_retries: u32,
+    /// Delay before the first retry, doubled on each further retry
+    base_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    fn from_env() -> Self {
+        Self {
+            max_retries: std::env::var("RPC_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_RPC_MAX_RETRIES),
+            base_delay_ms: std::env::var("RPC_RETRY_BASE_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_RPC_RETRY_BASE_MS),
+        }
+    }
+
+    /// Delay before retry number `retry` (0-based), capped at RPC_RETRY_MAX_DELAY_MS
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u64.checked_shl(retry).unwrap_or(u64::MAX);
+        Duration::from_millis(self.base_delay_ms.saturating_mul(factor).min(RPC_RETRY_MAX_DELAY_MS))
+    }
+}
+
+/// Whether an RPC error is worth retrying (rate limits, timeouts, dropped connections, 5xx)
+/// Anything else (contract not found, reverted calls, bad input) is returned immediately
+pub fn is_transient_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "429",
+        "rate limit",
+        "ratelimit",
+        "too many requests",
+        "timed out",
+        "timeout",
+        "connection",
+        "502",
+        "503",
+        "504",
+        "bad gateway",
+        "service unavailable",
+        "temporarily unavailable",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// RPC endpoints in order of preference (RPC_URLS, or RPC_URL), shared by the client and the syncer
+/// Requests go to the preferred endpoint; when it fails with a transient error the next one
+/// becomes preferred, so a healthy endpoint keeps serving until it fails in turn
+pub struct RpcEndpoints {
+    endpoints: Vec<(String, JsonRpcClient<HttpTransport>)>,
+    preferred: AtomicUsize,
+}
+
+impl RpcEndpoints {
+    pub fn new(urls: &[String]) -> Result<Self, String> {
+        if urls.is_empty() {
+            return Err("No RPC URL configured".to_string());
+        }
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                let parsed = Url::parse(url).map_err(|e| format!("Invalid RPC URL '{}': {}", url, e))?;
+                Ok((url.clone(), JsonRpcClient::new(HttpTransport::new(parsed))))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self {
+            endpoints,
+            preferred: AtomicUsize::new(0),
+        })
+    }
+
+    /// RPC_URLS (comma-separated, first is preferred), else RPC_URL, else `default_url`
+    pub fn urls_from_env(default_url: &str) -> Vec<String> {
+        let split = |list: String| -> Vec<String> {
+            list.split(',').map(str::trim).filter(|url| !url.is_empty()).map(str::to_string).collect()
+        };
+        let urls = std::env::var("RPC_URLS").map(split).unwrap_or_default();
+        if !urls.is_empty() {
+            return urls;
+        }
+        let urls = std::env::var("RPC_URL").map(split).unwrap_or_default();
+        if !urls.is_empty() {
+            return urls;
+        }
+        vec![default_url.to_string()]
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.endpoints.iter().map(|(url, _)| url.as_str())
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Preferred endpoint: its index (for mark_failed) and provider
+    pub fn current(&self) -> (usize, &JsonRpcClient<HttpTransport>) {
+        let index = self.preferred.load(Ordering::SeqCst) % self.endpoints.len();
+        (index, &self.endpoints[index].1)
+    }
+
+    pub fn current_url(&self) -> &str {
+        &self.endpoints[self.current().0].0
+    }
+
+    /// Move on from endpoint `failed` if it is still the preferred one
+    /// (several requests failing on the same endpoint rotate only once)
+    pub fn mark_failed(&self, failed: usize) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let next = (failed + 1) % self.endpoints.len();
+        if self.preferred.compare_exchange(failed, next, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            eprintln!(
+                "[ASP] ⚠️  RPC {} is failing, switching to {}",
+                self.endpoints[failed].0, self.endpoints[next].0
+            );
+        }
+    }
+}
+
+/// Where a submitted transaction stands (GET /api/tx/:tx_hash/status)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Received by the sequencer, not in a block yet
+    Pending,
+    AcceptedL2,
+    AcceptedL1,
+    /// Never executed (failed validation)
+    Rejected,
+    /// Included in a block but execution failed
+    Reverted,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::AcceptedL2 => "accepted_l2",
+            TxStatus::AcceptedL1 => "accepted_l1",
+            TxStatus::Rejected => "rejected",
+            TxStatus::Reverted => "reverted",
+        }
+    }
+}
+
+/// Cairo short strings (e.g. 'INVALID_MERKLE_ROOT') found as hex felts in a revert trace, in order
+/// Contract asserts, ENTRYPOINT_FAILED wrappers and account errors all surface this way
+pub fn decode_revert_messages(reason: &str) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for token in reason.split(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+        let Some(digits) = token.strip_prefix("0x") else { continue };
+        let padded = if digits.len() % 2 == 1 { format!("0{}", digits) } else { digits.to_string() };
+        let Ok(bytes) = hex::decode(padded) else { continue };
+        let text: String = bytes.iter().skip_while(|b| **b == 0).map(|b| *b as char).collect();
+        let printable = (2..=31).contains(&text.len()) && text.chars().all(|c| (' '..='~').contains(&c));
+        if printable && text.chars().any(|c| c.is_ascii_alphabetic()) && !messages.contains(&text) {
+            messages.push(text);
+        }
+    }
+    messages
+}
+
+/// Outcome of a dry run (BlockchainClient::simulate_call)
+#[derive(Debug, Clone, Serialize)]
+pub struct Simulation {
+    pub success: bool,
+    pub revert_reason: Option<String>,
+    /// Cairo short strings decoded from revert_reason
+    pub revert_messages: Vec<String>,
+}
+
+impl Simulation {
+    fn succeeded() -> Self {
+        Simulation { success: true, revert_reason: None, revert_messages: Vec::new() }
+    }
+
+    fn reverted(reason: String) -> Self {
+        let revert_messages = decode_revert_messages(&reason);
+        Simulation { success: false, revert_reason: Some(reason), revert_messages }
+    }
+}
+
 pub struct BlockchainClient {
-    provider: JsonRpcClient<HttpTransport>,
+    rpc: Arc<RpcEndpoints>,
     zylith_address: FieldElement,
+    /// Read token0/token1 from the pool storage node (USE_STORAGE_FALLBACK)
+    /// Disabling it uses only the get_pool_token0/1 view calls
+    use_storage_fallback: bool,
+    retry: RetryPolicy,
+    root_cache: RootCache,
+}
+
+/// Short-lived copy of get_merkle_root (ROOT_CACHE_TTL_MS, 0 disables it), so bursts of reads
+/// share one RPC call. The syncer invalidates it when it applies new deposits
+struct RootCache {
+    ttl: Duration,
+    /// Bumped on invalidation; entries fetched under an older generation are stale
+    generation: AtomicU64,
+    /// Held while fetching, so concurrent misses wait for the same call
+    entry: tokio::sync::Mutex<Option<CachedRoot>>,
+}
+
+struct CachedRoot {
+    root: String,
+    fetched: Instant,
+    generation: u64,
+}
+
+impl RootCache {
+    fn from_env() -> Self {
+        let ttl_ms = std::env::var("ROOT_CACHE_TTL_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ROOT_CACHE_TTL_MS);
+        Self::new(Duration::from_millis(ttl_ms))
+    }
+
+    fn new(ttl: Duration) -> Self {
+        RootCache {
+            ttl,
+            generation: AtomicU64::new(0),
+            entry: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Cached root and its age, if it is younger than the TTL and not invalidated
+    fn lookup(&self, entry: &Option<CachedRoot>, now: Instant) -> Option<(String, Duration)> {
+        let cached = entry.as_ref()?;
+        let age = now.saturating_duration_since(cached.fetched);
+        let current = cached.generation == self.generation.load(Ordering::SeqCst);
+        (current && age < self.ttl).then(|| (cached.root.clone(), age))
+    }
+
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
 }
 
 impl BlockchainClient {
-    pub fn new(rpc_url: &str, zylith_address: &str) -> Result<Self, String> {
-        let url = Url::parse(rpc_url)
-            .map_err(|e| format!("Invalid RPC URL: {}", e))?;
-        
-        let provider = JsonRpcClient::new(HttpTransport::new(url));
-        
+    pub fn new(rpc: Arc<RpcEndpoints>, zylith_address: &str) -> Result<Self, String> {
         let zylith_addr = parse_felt(zylith_address)
             .map_err(|e| format!("Invalid Zylith address: {}", e))?;
 
+        // Default reads storage, for contracts without token view functions
+        let use_storage_fallback = std::env::var("USE_STORAGE_FALLBACK")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+            .unwrap_or(true);
+
         Ok(Self {
-            provider,
+            rpc,
             zylith_address: zylith_addr,
+            use_storage_fallback,
+            retry: RetryPolicy::from_env(),
+            root_cache: RootCache::from_env(),
         })
     }
 
+    /// Run an RPC request on the preferred endpoint, retrying transient failures with exponential
+    /// backoff; each transient failure moves on to the next endpoint, and every endpoint gets at
+    /// least one attempt. Permanent errors and the last transient error are returned as-is
+    async fn call_with_retry<'a, T, E, F, Fut>(&'a self, what: &str, request: F) -> Result<T, E>
+    where
+        F: Fn(&'a JsonRpcClient<HttpTransport>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let max_retries = self.retry.max_retries.max(self.rpc.endpoint_count() as u32 - 1);
+        let mut retry = 0;
+        loop {
+            let (endpoint, provider) = self.rpc.current();
+            match request(provider).await {
+                Err(e) if retry < max_retries && is_transient_error(&e.to_string()) => {
+                    self.rpc.mark_failed(endpoint);
+                    let delay = self.retry.delay(retry);
+                    eprintln!(
+                        "[ASP] ⚠️  {} failed ({}), retrying in {}ms ({}/{})",
+                        what, e, delay.as_millis(), retry + 1, max_retries
+                    );
+                    sleep(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Get Merkle root from contract
     pub async fn get_merkle_root(&self) -> Result<String, String> {
         let call = FunctionCall {
@@ -33,8 +324,8 @@ impl BlockchainClient {
             calldata: vec![],
         };
 
-        let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+        let result = self
+            .call_with_retry("get_merkle_root", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
             .await
             .map_err(|e| format!("Failed to call get_merkle_root: {}", e))?;
 
@@ -45,6 +336,49 @@ impl BlockchainClient {
         Ok(format!("0x{:x}", result[0]))
     }
 
+    /// get_merkle_root through the TTL cache, with how old the returned value is
+    /// Failures are not cached
+    pub async fn get_merkle_root_cached(&self) -> Result<(String, Duration), String> {
+        let mut entry = self.root_cache.entry.lock().await;
+        if let Some(hit) = self.root_cache.lookup(&entry, Instant::now()) {
+            return Ok(hit);
+        }
+        let generation = self.root_cache.generation.load(Ordering::SeqCst);
+        let root = self.get_merkle_root().await?;
+        *entry = Some(CachedRoot { root: root.clone(), fetched: Instant::now(), generation });
+        Ok((root, Duration::ZERO))
+    }
+
+    /// Drop the cached root (a deposit changed it on-chain)
+    pub fn invalidate_root_cache(&self) {
+        self.root_cache.invalidate();
+    }
+
+    /// RPC endpoint requests currently go to
+    pub fn rpc_endpoint(&self) -> &str {
+        self.rpc.current_url()
+    }
+
+    /// Latest block number, without retries (readiness probes apply their own timeout)
+    pub async fn get_block_number(&self) -> Result<u64, String> {
+        let (_, provider) = self.rpc.current();
+        provider
+            .block_number()
+            .await
+            .map_err(|e| format!("Failed to get block number: {}", e))
+    }
+
+    /// Class hash currently deployed at the Zylith address
+    pub async fn get_class_hash(&self) -> Result<String, String> {
+        let class_hash = self
+            .call_with_retry("get_class_hash_at", |provider| {
+                provider.get_class_hash_at(BlockId::Tag(BlockTag::Latest), self.zylith_address)
+            })
+            .await
+            .map_err(|e| format!("Failed to get class hash: {}", e))?;
+        Ok(format!("0x{:x}", class_hash))
+    }
+
     /// Check if nullifier is spent
     pub async fn is_nullifier_spent(&self, nullifier: &str) -> Result<bool, String> {
         let nullifier_felt = parse_felt(nullifier)?;
@@ -55,8 +389,8 @@ impl BlockchainClient {
             calldata: vec![nullifier_felt],
         };
 
-        let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+        let result = self
+            .call_with_retry("is_nullifier_spent", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
             .await
             .map_err(|e| format!("Failed to call is_nullifier_spent: {}", e))?;
 
@@ -78,8 +412,8 @@ impl BlockchainClient {
             calldata: vec![root_felt],
         };
 
-        let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+        let result = self
+            .call_with_retry("is_root_known", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
             .await
             .map_err(|e| format!("Failed to call is_root_known: {}", e))?;
 
@@ -106,37 +440,12 @@ impl BlockchainClient {
             calldata: vec![owner_addr],
         };
 
-        let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+        let result = self
+            .call_with_retry("balance_of", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
             .await
             .map_err(|e| format!("Failed to call balance_of: {}", e))?;
 
-        if result.len() < 2 {
-            return Err("Invalid response from balance_of (expected u256)".to_string());
-        }
-
-        // u256 is returned as [low, high]
-        let low = result[0];
-        let high = result[1];
-        
-        // Convert FieldElement to u128
-        let low_bytes = low.to_bytes_be();
-        let high_bytes = high.to_bytes_be();
-        
-        let low_u128 = u128::from_be_bytes([
-            low_bytes[16], low_bytes[17], low_bytes[18], low_bytes[19],
-            low_bytes[20], low_bytes[21], low_bytes[22], low_bytes[23],
-            low_bytes[24], low_bytes[25], low_bytes[26], low_bytes[27],
-            low_bytes[28], low_bytes[29], low_bytes[30], low_bytes[31],
-        ]);
-        let high_u128 = u128::from_be_bytes([
-            high_bytes[16], high_bytes[17], high_bytes[18], high_bytes[19],
-            high_bytes[20], high_bytes[21], high_bytes[22], high_bytes[23],
-            high_bytes[24], high_bytes[25], high_bytes[26], high_bytes[27],
-            high_bytes[28], high_bytes[29], high_bytes[30], high_bytes[31],
-        ]);
-
-        Ok((low_u128, high_u128))
+        crate::felt_conv::decode_u256(&result).map_err(|e| format!("Invalid response from balance_of: {}", e))
     }
 
     /// Get token allowance (ERC20) - returns (low, high) for u256
@@ -156,36 +465,12 @@ impl BlockchainClient {
             calldata: vec![owner_addr, spender_addr],
         };
 
-        let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+        let result = self
+            .call_with_retry("allowance", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
             .await
             .map_err(|e| format!("Failed to call allowance: {}", e))?;
 
-        if result.len() < 2 {
-            return Err("Invalid response from allowance (expected u256)".to_string());
-        }
-
-        // u256 is returned as [low, high]
-        let low = result[0];
-        let high = result[1];
-        
-        let low_bytes = low.to_bytes_be();
-        let high_bytes = high.to_bytes_be();
-        
-        let low_u128 = u128::from_be_bytes([
-            low_bytes[16], low_bytes[17], low_bytes[18], low_bytes[19],
-            low_bytes[20], low_bytes[21], low_bytes[22], low_bytes[23],
-            low_bytes[24], low_bytes[25], low_bytes[26], low_bytes[27],
-            low_bytes[28], low_bytes[29], low_bytes[30], low_bytes[31],
-        ]);
-        let high_u128 = u128::from_be_bytes([
-            high_bytes[16], high_bytes[17], high_bytes[18], high_bytes[19],
-            high_bytes[20], high_bytes[21], high_bytes[22], high_bytes[23],
-            high_bytes[24], high_bytes[25], high_bytes[26], high_bytes[27],
-            high_bytes[28], high_bytes[29], high_bytes[30], high_bytes[31],
-        ]);
-
-        Ok((low_u128, high_u128))
+        crate::felt_conv::decode_u256(&result).map_err(|e| format!("Invalid response from allowance: {}", e))
     }
 
     /// Check if pool is initialized
@@ -193,8 +478,10 @@ impl BlockchainClient {
         // Check initialized field: sn_keccak("initialized")
         let initialized_selector = starknet_keccak("initialized".as_bytes());
         
-        let storage_value = self.provider
-            .get_storage_at(self.zylith_address, initialized_selector, BlockId::Tag(BlockTag::Latest))
+        let storage_value = self
+            .call_with_retry("get_storage_at(initialized)", |provider| {
+                provider.get_storage_at(self.zylith_address, initialized_selector, BlockId::Tag(BlockTag::Latest))
+            })
             .await
             .map_err(|e| format!("Failed to read initialized storage: {}", e))?;
 
@@ -202,155 +489,219 @@ impl BlockchainClient {
         Ok(storage_value != FieldElement::ZERO)
     }
 
-    /// Get pool token0 address by reading storage directly
-    /// In Cairo, for storage nodes, the address calculation is complex.
-    /// We try multiple methods: pedersen_hash and direct base address
+    /// Get pool token0 address from the `pool` storage node
+    /// With USE_STORAGE_FALLBACK=false the get_pool_token0 view call is used instead
     pub async fn get_pool_token0(&self) -> Result<String, String> {
-        // First check if pool is initialized
+        self.get_pool_token("token0", "get_pool_token0").await
+    }
+
+    /// Get pool token1 address from the `pool` storage node
+    /// With USE_STORAGE_FALLBACK=false the get_pool_token1 view call is used instead
+    pub async fn get_pool_token1(&self) -> Result<String, String> {
+        self.get_pool_token("token1", "get_pool_token1").await
+    }
+
+    async fn get_pool_token(&self, member: &str, view: &str) -> Result<String, String> {
         let is_initialized = self.is_pool_initialized().await
             .map_err(|e| format!("Failed to check if pool is initialized: {}", e))?;
-        
         if !is_initialized {
             return Err("Pool is not initialized. Please initialize the pool first.".to_string());
         }
 
-        let pool_base = starknet_keccak("pool".as_bytes());
-        let token0_field = starknet_keccak("token0".as_bytes());
-        
-        // Method 1: Try pedersen_hash (standard for storage nodes)
-        let pool_base_crypto = CryptoFieldElement::from_bytes_be(&pool_base.to_bytes_be())
-            .map_err(|e| format!("Failed to convert pool_base: {}", e))?;
-        let token0_field_crypto = CryptoFieldElement::from_bytes_be(&token0_field.to_bytes_be())
-            .map_err(|e| format!("Failed to convert token0_field: {}", e))?;
-        
-        let storage_address_pedersen = pedersen_hash(&pool_base_crypto, &token0_field_crypto);
-        let storage_address1 = FieldElement::from_bytes_be(&storage_address_pedersen.to_bytes_be())
-            .map_err(|e| format!("Failed to convert pedersen result: {}", e))?;
-        
-        // Method 2: Try direct base (first field in storage node)
-        let storage_address2 = pool_base;
-        
-        // Method 3: Try base + field (alternative calculation)
-        let storage_address3 = pool_base + token0_field;
-        
-        // Try pedersen_hash first (most likely correct for storage nodes)
-        // Use tokio::time::timeout to avoid hanging on slow RPC calls
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(5),
-            self.provider.get_storage_at(self.zylith_address, storage_address1, BlockId::Tag(BlockTag::Latest))
-        ).await {
-            Ok(Ok(value)) if value != FieldElement::ZERO => {
-                // Normalize to 64 hex chars (remove leading zeros)
-                let hex_str = format!("{:064x}", value);
-                // Remove leading zeros but keep at least one char
-                let trimmed = hex_str.trim_start_matches('0');
-                let normalized = if trimmed.is_empty() { "0" } else { trimmed };
-                return Ok(format!("0x{}", normalized));
-            }
-            Ok(Ok(_)) => {
-                // Value is zero, try direct_base as fallback
-            }
-            Ok(Err(e)) => {
-                eprintln!("Warning: Failed to read storage using pedersen_hash: {}", e);
-            }
-            Err(_) => {
-                eprintln!("Warning: Timeout reading storage using pedersen_hash");
-            }
+        if !self.use_storage_fallback {
+            return self.call_pool_token_view(view).await;
         }
-        
-        // Fallback: Try direct_base (faster, less likely but worth trying)
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(3),
-            self.provider.get_storage_at(self.zylith_address, storage_address2, BlockId::Tag(BlockTag::Latest))
-        ).await {
-            Ok(Ok(value)) if value != FieldElement::ZERO => {
-                let hex_str = format!("{:064x}", value);
-                let trimmed = hex_str.trim_start_matches('0');
-                let normalized = if trimmed.is_empty() { "0" } else { trimmed };
-                return Ok(format!("0x{}", normalized));
-            }
-            _ => {}
+
+        let value = self.read_pool_slot(member, 0).await?;
+        if value == FieldElement::ZERO {
+            // `initialized` is written in the same transaction, so this means a node lagging behind
+            return Err(format!(
+                "{} is zero in pool storage (0x{:x}) although the pool is initialized. The RPC node may not have the initialization transaction yet, retry in a few seconds.",
+                member,
+                pool_member_address(member, 0)
+            ));
         }
-        
-        // All methods failed
-        Err(format!(
-            "token0 is zero at all attempted storage addresses. This usually means:\n1. The pool initialization transaction hasn't been confirmed yet (wait 10-30 seconds)\n2. The initialization transaction failed\n3. There's a delay in state propagation\n4. The storage address calculation is incorrect\n\nPlease verify the initialization transaction was successful at https://sepolia.starkscan.co and wait a few seconds before trying again.\n\nTried addresses:\n- pedersen_hash: 0x{:x}\n- direct_base: 0x{:x}\n- base_plus_field: 0x{:x}",
-            storage_address1, storage_address2, storage_address3
-        ))
+        Ok(format!("0x{:x}", value))
     }
 
-    /// Get pool token1 address by reading storage directly
-    /// In Cairo, for storage nodes, the address calculation is complex.
-    /// We try multiple methods: pedersen_hash and direct base address
-    pub async fn get_pool_token1(&self) -> Result<String, String> {
-        // First check if pool is initialized
-        let is_initialized = self.is_pool_initialized().await
-            .map_err(|e| format!("Failed to check if pool is initialized: {}", e))?;
-        
-        if !is_initialized {
-            return Err("Pool is not initialized. Please initialize the pool first.".to_string());
+    /// Current sqrt price (X128) of the pool
+    pub async fn get_pool_sqrt_price(&self) -> Result<BigUint, String> {
+        let (low, high) = tokio::try_join!(
+            self.read_pool_slot("sqrt_price_x128", 0),
+            self.read_pool_slot("sqrt_price_x128", 1),
+        )?;
+        crate::felt_conv::decode_u256(&[low, high]).map(|(low, high)| (BigUint::from(high) << 128) + low)
+    }
+
+    /// Current tick of the pool
+    pub async fn get_pool_tick(&self) -> Result<i32, String> {
+        crate::felt_conv::felt_to_i32(self.read_pool_slot("tick", 0).await?)
+    }
+
+    /// Liquidity in range at the current tick
+    pub async fn get_pool_liquidity(&self) -> Result<u128, String> {
+        crate::felt_conv::u128_from_felt(self.read_pool_slot("liquidity", 0).await?)
+    }
+
+    /// Pool parameters, price and liquidity from the pool storage node, read concurrently
+    pub async fn get_pool_state(&self) -> Result<PoolState, String> {
+        let (fee, tick_spacing, sqrt_price_x128, tick, liquidity) = tokio::try_join!(
+            self.read_pool_slot("fee", 0),
+            self.read_pool_slot("tick_spacing", 0),
+            self.get_pool_sqrt_price(),
+            self.get_pool_tick(),
+            self.get_pool_liquidity(),
+        )?;
+
+        Ok(PoolState {
+            fee: crate::felt_conv::u128_from_felt(fee)?,
+            tick_spacing: crate::felt_conv::felt_to_i32(tick_spacing)?,
+            sqrt_price_x128,
+            tick,
+            liquidity,
+        })
+    }
+
+    /// Read slot `offset` of a `pool` member (see pool_member_address)
+    async fn read_pool_slot(&self, member: &str, offset: u8) -> Result<FieldElement, String> {
+        let address = pool_member_address(member, offset);
+        self.call_with_retry(&format!("get_storage_at(pool.{})", member), |provider| {
+            provider.get_storage_at(self.zylith_address, address, BlockId::Tag(BlockTag::Latest))
+        })
+        .await
+        .map_err(|e| format!("Failed to read pool.{} storage: {}", member, e))
+    }
+
+    /// Read a pool token address through its view function (no storage guessing)
+    async fn call_pool_token_view(&self, entry_point: &str) -> Result<String, String> {
+        let call = FunctionCall {
+            contract_address: self.zylith_address,
+            entry_point_selector: get_selector(entry_point),
+            calldata: vec![],
+        };
+
+        let result = self
+            .call_with_retry(entry_point, |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Latest)))
+            .await
+            .map_err(|e| format!(
+                "Failed to call {} (storage fallback disabled via USE_STORAGE_FALLBACK=false): {}",
+                entry_point, e
+            ))?;
+
+        if result.is_empty() {
+            return Err(format!("Empty response from {}", entry_point));
         }
 
-        let pool_base = starknet_keccak("pool".as_bytes());
-        let token1_field = starknet_keccak("token1".as_bytes());
-        
-        // Method 1: Try pedersen_hash (standard for storage nodes)
-        let pool_base_crypto = CryptoFieldElement::from_bytes_be(&pool_base.to_bytes_be())
-            .map_err(|e| format!("Failed to convert pool_base: {}", e))?;
-        let token1_field_crypto = CryptoFieldElement::from_bytes_be(&token1_field.to_bytes_be())
-            .map_err(|e| format!("Failed to convert token1_field: {}", e))?;
-        
-        let storage_address_pedersen = pedersen_hash(&pool_base_crypto, &token1_field_crypto);
-        let storage_address1 = FieldElement::from_bytes_be(&storage_address_pedersen.to_bytes_be())
-            .map_err(|e| format!("Failed to convert pedersen result: {}", e))?;
-        
-        // Method 2: Try direct base + 1 (second field in storage node)
-        let storage_address2 = pool_base + FieldElement::ONE;
-        
-        // Try pedersen_hash first (most likely correct for storage nodes)
-        // Use tokio::time::timeout to avoid hanging on slow RPC calls
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(5),
-            self.provider.get_storage_at(self.zylith_address, storage_address1, BlockId::Tag(BlockTag::Latest))
-        ).await {
-            Ok(Ok(value)) if value != FieldElement::ZERO => {
-                // Normalize to 64 hex chars (remove leading zeros)
-                let hex_str = format!("{:064x}", value);
-                // Remove leading zeros but keep at least one char
-                let trimmed = hex_str.trim_start_matches('0');
-                let normalized = if trimmed.is_empty() { "0" } else { trimmed };
-                return Ok(format!("0x{}", normalized));
-            }
-            Ok(Ok(_)) => {
-                // Value is zero, try direct_base_plus_one as fallback
-            }
-            Ok(Err(e)) => {
-                eprintln!("Warning: Failed to read storage using pedersen_hash: {}", e);
-            }
-            Err(_) => {
-                eprintln!("Warning: Timeout reading storage using pedersen_hash");
+        Ok(format!("0x{:x}", result[0]))
+    }
+
+    /// Get the revert reason of a transaction from its receipt
+    /// Returns None if the transaction succeeded
+    pub async fn get_revert_reason(&self, tx_hash: &str) -> Result<Option<String>, String> {
+        let hash = parse_felt(tx_hash)?;
+
+        let receipt = self
+            .call_with_retry("get_transaction_receipt", |provider| provider.get_transaction_receipt(hash))
+            .await
+            .map_err(|e| format!("Failed to get transaction receipt: {}", e))?;
+
+        match receipt.execution_result() {
+            ExecutionResult::Succeeded => Ok(None),
+            ExecutionResult::Reverted { reason } if reason.trim().is_empty() => {
+                // Some RPC nodes don't expose the revert reason
+                Ok(Some("Transaction reverted (revert reason not provided by RPC)".to_string()))
             }
+            ExecutionResult::Reverted { reason } => Ok(Some(reason.clone())),
         }
-        
-        // Fallback: Try direct_base_plus_one (faster, less likely but worth trying)
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(3),
-            self.provider.get_storage_at(self.zylith_address, storage_address2, BlockId::Tag(BlockTag::Latest))
-        ).await {
-            Ok(Ok(value)) if value != FieldElement::ZERO => {
-                let hex_str = format!("{:064x}", value);
-                let trimmed = hex_str.trim_start_matches('0');
-                let normalized = if trimmed.is_empty() { "0" } else { trimmed };
-                return Ok(format!("0x{}", normalized));
+    }
+
+    /// Status of a transaction, plus its revert reason when it reverted
+    pub async fn get_transaction_status(&self, tx_hash: &str) -> Result<(TxStatus, Option<String>), String> {
+        let hash = parse_felt(tx_hash)?;
+
+        let status = self
+            .call_with_retry("get_transaction_status", |provider| provider.get_transaction_status(hash))
+            .await
+            .map_err(|e| format!("Failed to get transaction status: {}", e))?;
+
+        let status = match status {
+            TransactionStatus::Received => TxStatus::Pending,
+            TransactionStatus::Rejected => TxStatus::Rejected,
+            TransactionStatus::AcceptedOnL2(TransactionExecutionStatus::Reverted)
+            | TransactionStatus::AcceptedOnL1(TransactionExecutionStatus::Reverted) => TxStatus::Reverted,
+            TransactionStatus::AcceptedOnL2(_) => TxStatus::AcceptedL2,
+            TransactionStatus::AcceptedOnL1(_) => TxStatus::AcceptedL1,
+        };
+        let revert_reason = match status {
+            TxStatus::Reverted => self.get_revert_reason(tx_hash).await?,
+            _ => None,
+        };
+        Ok((status, revert_reason))
+    }
+
+    /// Dry-run calls against the pending block before the user signs them
+    /// With `sender`, the calls are simulated as one __execute__ from that account (validation and
+    /// fee charge skipped, so no signature is needed); this is what a deposit needs, since
+    /// transfer_from depends on the caller. Without it, a single call runs as a plain `call`, which
+    /// is enough for private_swap (root, nullifier and proof checks don't look at the caller)
+    /// Err only for RPC failures; a revert is a successful simulation with `success: false`
+    pub async fn simulate_call(
+        &self,
+        calls: &[(String, String, Vec<FieldElement>)],
+        sender: Option<&str>,
+    ) -> Result<Simulation, String> {
+        let outcome = match sender {
+            Some(sender) => {
+                let sender = parse_felt(sender).map_err(|e| format!("Invalid sender address: {}", e))?;
+                let nonce = self
+                    .call_with_retry("get_nonce", |provider| provider.get_nonce(BlockId::Tag(BlockTag::Pending), sender))
+                    .await
+                    .map_err(|e| format!("Failed to get nonce: {}", e))?;
+                let transaction = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+                    BroadcastedInvokeTransactionV1 {
+                        max_fee: FieldElement::ZERO,
+                        signature: vec![],
+                        nonce,
+                        sender_address: sender,
+                        calldata: crate::calldata::build_multicall_calldata(calls)?,
+                        is_query: true,
+                    },
+                ));
+                let flags = [SimulationFlag::SkipValidate, SimulationFlag::SkipFeeCharge];
+                self.call_with_retry("simulate_transaction", |provider| {
+                    provider.simulate_transaction(BlockId::Tag(BlockTag::Pending), &transaction, flags)
+                })
+                .await
+                .map(|simulated| match simulated.transaction_trace {
+                    TransactionTrace::Invoke(trace) => match trace.execute_invocation {
+                        ExecuteInvocation::Success(_) => None,
+                        ExecuteInvocation::Reverted(reverted) => Some(reverted.revert_reason),
+                    },
+                    _ => None,
+                })
             }
-            _ => {}
+            None => {
+                let [(contract_address, entry_point, calldata)] = calls else {
+                    return Err("Simulating several calls needs the sender account".to_string());
+                };
+                let call = FunctionCall {
+                    contract_address: parse_felt(contract_address)?,
+                    entry_point_selector: get_selector(entry_point),
+                    calldata: calldata.clone(),
+                };
+                self.call_with_retry("call", |provider| provider.call(call.clone(), BlockId::Tag(BlockTag::Pending)))
+                    .await
+                    .map(|_| None)
+            }
+        };
+
+        match outcome {
+            Ok(None) => Ok(Simulation::succeeded()),
+            Ok(Some(reason)) => Ok(Simulation::reverted(reason)),
+            Err(e) if is_transient_error(&e.to_string()) => Err(format!("Simulation failed: {}", e)),
+            // Execution errors come back as RPC errors; Debug keeps the trace data Display drops
+            Err(e) => Ok(Simulation::reverted(format!("{:?}", e))),
         }
-        
-        // All methods failed
-        Err(format!(
-            "token1 is zero at all attempted storage addresses. Pool may not be properly initialized.\n\nTried addresses:\n- pedersen_hash: 0x{:x}\n- direct_base_plus_one: 0x{:x}",
-            storage_address1, storage_address2
-        ))
     }
 
     /// Search for a specific commitment in Deposit events
@@ -358,19 +709,16 @@ impl BlockchainClient {
     /// This is much faster than waiting for full sync when looking for a specific commitment
     pub async fn find_commitment_in_events(&self, commitment: &str) -> Result<Option<u32>, String> {
         use starknet::core::types::EventFilter;
-        use num_bigint::BigUint;
         
         let commitment_felt = parse_felt(commitment)?;
         let commitment_bigint = BigUint::from_bytes_be(&commitment_felt.to_bytes_be());
         
-        // Deposit event selector (same as in syncer.rs)
-        let deposit_selector = FieldElement::from_hex_be("0x9149d2123147c5f43d258257fef0b7b969db78269369ebcf5ebb9eef8592f2")
-            .map_err(|e| format!("Failed to parse deposit selector: {}", e))?;
+        let deposit_selector = crate::syncer::get_event_selector("Deposit");
         
         // Always search from contract deployment block to ensure we find all deposits
         // This is critical - even if syncer missed events, we can still find them here
         let from_block = 4438440u64;
-        let latest_block = self.provider.block_number().await
+        let latest_block = self.call_with_retry("block_number", |provider| provider.block_number()).await
             .map_err(|e| format!("Failed to get latest block: {}", e))?;
         
         // Filter for all events from our contract
@@ -390,8 +738,10 @@ impl BlockchainClient {
         println!("[ASP] 🔍 Searching events from block {} to {}", from_block, latest_block);
         
         loop {
-            let events_page = self.provider
-                .get_events(filter.clone(), continuation_token.clone(), chunk_size)
+            let events_page = self
+                .call_with_retry("get_events", |provider| {
+                    provider.get_events(filter.clone(), continuation_token.clone(), chunk_size)
+                })
                 .await
                 .map_err(|e| format!("Failed to get events: {}", e))?;
             
@@ -400,27 +750,13 @@ impl BlockchainClient {
                 
                 // Check if this is a Deposit event (for nested events, selector can be in any key)
                 let is_deposit = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == deposit_selector);
+                    event.keys.contains(&deposit_selector);
                 
-                if is_deposit && event.data.len() >= 3 {
+                if is_deposit {
                     deposit_events_found += 1;
-                    // Parse commitment from data[0]
-                    let event_commitment_felt = event.data[0];
-                    let event_commitment_bigint = BigUint::from_bytes_be(&event_commitment_felt.to_bytes_be());
-                    
-                    // Skip logging commitment details
-                    
-                    if event_commitment_bigint == commitment_bigint {
-                        // Found it! Extract leaf_index from data[1]
-                        let leaf_index_felt = event.data[1];
-                        let leaf_index: u32 = {
-                            let bytes = leaf_index_felt.to_bytes_be();
-                            let mut arr = [0u8; 4];
-                            let start = bytes.len().saturating_sub(4);
-                            arr.copy_from_slice(&bytes[start..]);
-                            u32::from_be_bytes(arr)
-                        };
-                        
+                    let (event_commitment, leaf_index) = crate::syncer::decode_deposit_event(&event.data)?;
+
+                    if event_commitment == commitment_bigint {
                         println!("[ASP] ✅ Found commitment in events at index {} (searched {} events, {} deposit events)", leaf_index, events_searched, deposit_events_found);
                         return Ok(Some(leaf_index));
                     }
@@ -444,9 +780,138 @@ fn get_selector(function_name: &str) -> FieldElement {
     get_selector_from_name(function_name).unwrap_or(FieldElement::ZERO)
 }
 
-/// Parse felt252 from hex string
-fn parse_felt(hex_str: &str) -> Result<FieldElement, String> {
-    FieldElement::from_hex_be(hex_str)
-        .map_err(|e| format!("Failed to parse felt252 '{}': {}", hex_str, e))
+/// Pool parameters, price and liquidity as stored in the contract (PoolStorage in clmm/pool.cairo)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolState {
+    pub fee: u128,
+    pub tick_spacing: i32,
+    pub sqrt_price_x128: BigUint,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+/// Storage addresses are taken modulo 2^251 - 256 (storage_base_address_from_felt252)
+const STORAGE_ADDRESS_BOUND: [u8; 32] = {
+    let mut bound = [0xff; 32];
+    bound[0] = 0x07;
+    bound[31] = 0x00;
+    bound
+};
+
+/// Storage address of slot `offset` of a member of the `pool` storage node
+/// `pool: PoolStorage` is a #[starknet::storage_node], so each member lives at
+/// pedersen(sn_keccak("pool"), sn_keccak(member)); values wider than a felt (u256: low, high)
+/// continue at the following addresses
+fn pool_member_address(member: &str, offset: u8) -> FieldElement {
+    let base = CryptoFieldElement::from_bytes_be(&starknet_keccak(b"pool").to_bytes_be())
+        .expect("sn_keccak fits in a felt");
+    let selector = CryptoFieldElement::from_bytes_be(&starknet_keccak(member.as_bytes()).to_bytes_be())
+        .expect("sn_keccak fits in a felt");
+    let hash = FieldElement::from_bytes_be(&pedersen_hash(&base, &selector).to_bytes_be())
+        .expect("pedersen output is a felt");
+    storage_base_address(hash) + FieldElement::from(offset)
+}
+
+/// Reduce a felt into the storage address range, as Cairo does for storage paths
+fn storage_base_address(felt: FieldElement) -> FieldElement {
+    let bound = FieldElement::from_bytes_be(&STORAGE_ADDRESS_BOUND).expect("2^251 - 256 is a felt");
+    // The field prime is below twice the bound, so one subtraction suffices
+    if felt.to_bytes_be() >= STORAGE_ADDRESS_BOUND {
+        felt - bound
+    } else {
+        felt
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_cache_ttl_and_invalidation() {
+        let cache = RootCache::new(Duration::from_secs(3));
+        let fetched = Instant::now();
+        let entry = Some(CachedRoot { root: "0xabc".to_string(), fetched, generation: 0 });
+
+        let (root, age) = cache.lookup(&entry, fetched + Duration::from_secs(1)).unwrap();
+        assert_eq!(root, "0xabc");
+        assert_eq!(age, Duration::from_secs(1));
+        assert!(cache.lookup(&entry, fetched + Duration::from_secs(3)).is_none());
+        assert!(cache.lookup(&None, fetched).is_none());
+
+        // A deposit landed: the entry is stale even within the TTL
+        cache.invalidate();
+        assert!(cache.lookup(&entry, fetched).is_none());
+
+        let disabled = RootCache::new(Duration::ZERO);
+        assert!(disabled.lookup(&entry, fetched).is_none());
+    }
+
+    #[test]
+    fn test_retry_classification_and_backoff() {
+        assert!(is_transient_error("HTTP status 429 Too Many Requests"));
+        assert!(is_transient_error("error sending request: operation timed out"));
+        assert!(is_transient_error("503 Service Unavailable"));
+        assert!(!is_transient_error("Contract not found"));
+        assert!(!is_transient_error("Contract error: execution reverted"));
+
+        let policy = RetryPolicy { max_retries: 3, base_delay_ms: 200 };
+        assert_eq!(policy.delay(0), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(800));
+        assert_eq!(policy.delay(10), Duration::from_millis(RPC_RETRY_MAX_DELAY_MS));
+        assert_eq!(policy.delay(64), Duration::from_millis(RPC_RETRY_MAX_DELAY_MS));
+    }
+    #[test]
+    fn test_decode_revert_messages() {
+        let reason = "Error in the called contract (0x04d0b88ace5705d4ae1e5bf5a6f7f5c0e1f9a1c4e2c1e8d0a3f5b6c7d8e9f0a1):\n\
+            Error at pc=0:4835:\nExecution failed. Failure reason: \
+            (0x494e56414c49445f4d45524b4c455f524f4f54, 0x454e545259504f494e545f4641494c4544).";
+        assert_eq!(decode_revert_messages(reason), vec!["INVALID_MERKLE_ROOT", "ENTRYPOINT_FAILED"]);
+        assert!(decode_revert_messages("out of gas").is_empty());
+    }
+
+    #[test]
+    fn test_simulation_outcome() {
+        let ok = Simulation::succeeded();
+        assert!(ok.success && ok.revert_reason.is_none());
+
+        let reverted = Simulation::reverted("Error in the called contract (0x4e554c4c49464945525f5350454e54)".to_string());
+        assert!(!reverted.success);
+        assert_eq!(reverted.revert_messages, vec!["NULLIFIER_SPENT"]);
+    }
+
+    #[test]
+    fn test_rpc_failover_rotates_once_per_failure() {
+        let urls: Vec<String> = ["http://a:5050", "http://b:5050", "http://c:5050"].iter().map(|u| u.to_string()).collect();
+        let rpc = RpcEndpoints::new(&urls).unwrap();
+        assert_eq!(rpc.current_url(), "http://a:5050");
+
+        // Two requests failing on the same endpoint move on only one step
+        rpc.mark_failed(0);
+        rpc.mark_failed(0);
+        assert_eq!(rpc.current_url(), "http://b:5050");
+        rpc.mark_failed(1);
+        rpc.mark_failed(2);
+        assert_eq!(rpc.current_url(), "http://a:5050");
+
+        let single = RpcEndpoints::new(&urls[..1]).unwrap();
+        single.mark_failed(0);
+        assert_eq!(single.current().0, 0);
+        assert!(RpcEndpoints::new(&[]).is_err());
+        assert!(RpcEndpoints::new(&["not a url".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_pool_member_addresses() {
+        let bound = FieldElement::from_bytes_be(&STORAGE_ADDRESS_BOUND).unwrap();
+        assert_eq!(storage_base_address(bound + FieldElement::from(5u8)), FieldElement::from(5u8));
+        assert_eq!(storage_base_address(bound - FieldElement::ONE), bound - FieldElement::ONE);
+        assert_eq!(storage_base_address(FieldElement::MAX), FieldElement::MAX - bound);
+
+        // u256 high limb follows the low limb; members get unrelated addresses
+        let sqrt_price = pool_member_address("sqrt_price_x128", 0);
+        assert_eq!(pool_member_address("sqrt_price_x128", 1), sqrt_price + FieldElement::ONE);
+        assert_ne!(pool_member_address("token0", 0), pool_member_address("token1", 0));
+        assert_ne!(pool_member_address("token0", 0), starknet_keccak(b"pool"));
+    }
+}
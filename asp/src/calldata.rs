@@ -1,21 +1,17 @@
+use crate::abi::{encode_calldata, get_erc20_abi, get_zylith_abi, AbiValue};
+use crate::felt_conv::{felt_to_biguint, i32_to_felt};
+use crate::util::{parse_felt, parse_felt_or_decimal};
 use starknet::core::types::FieldElement;
-use std::str::FromStr;
 use num_bigint::BigUint;
-use num_traits::Num;
 
 /// Build calldata for ERC20 approve
 pub fn build_approve_calldata(spender: &str, amount_low: u128, amount_high: u128) -> Result<Vec<FieldElement>, String> {
     // approve(spender: ContractAddress, amount: u256)
-    // ContractAddress is a single felt252, NOT u256
-    // Calldata: [spender (felt252), amount.low, amount.high]
-    
-    let spender_felt = parse_felt(spender)?;
-    
-    Ok(vec![
-        spender_felt, // ContractAddress as single felt252
-        FieldElement::from(amount_low),
-        FieldElement::from(amount_high),
-    ])
+    encode_calldata(
+        get_erc20_abi(),
+        "approve",
+        &[AbiValue::Felt(parse_felt(spender)?), AbiValue::u256(amount_low, amount_high)],
+    )
 }
 
 /// Build calldata for private_deposit
@@ -26,18 +22,34 @@ pub fn build_deposit_calldata(
     commitment: &str,
 ) -> Result<Vec<FieldElement>, String> {
     // private_deposit(token: ContractAddress, amount: u256, commitment: felt252)
-    // ContractAddress is a single felt252, NOT u256
-    // Calldata: [token (felt252), amount.low, amount.high, commitment (felt252)]
-    
-    let token_felt = parse_felt(token)?;
-    let commitment_felt = parse_felt(commitment)?;
-    
-    Ok(vec![
-        token_felt, // ContractAddress as single felt252
-        FieldElement::from(amount_low),
-        FieldElement::from(amount_high),
-        commitment_felt,
-    ])
+    encode_calldata(
+        get_zylith_abi(),
+        "private_deposit",
+        &[
+            AbiValue::Felt(parse_felt(token)?),
+            AbiValue::u256(amount_low, amount_high),
+            AbiValue::Felt(parse_felt_or_decimal(commitment)?),
+        ],
+    )
+}
+
+/// Build calldata for an account's __execute__ running several calls in one transaction
+/// Each call is (contract address, entry point name, calldata); the layout is the Cairo 1 account
+/// one: [call count, then per call: to, selector, calldata length, calldata...]
+pub fn build_multicall_calldata(calls: &[(String, String, Vec<FieldElement>)]) -> Result<Vec<FieldElement>, String> {
+    use starknet::core::utils::get_selector_from_name;
+
+    let mut calldata = vec![FieldElement::from(calls.len())];
+    for (to, entry_point, data) in calls {
+        calldata.push(parse_felt(to)?);
+        calldata.push(
+            get_selector_from_name(entry_point)
+                .map_err(|e| format!("Invalid entry point '{}': {}", entry_point, e))?,
+        );
+        calldata.push(FieldElement::from(data.len()));
+        calldata.extend_from_slice(data);
+    }
+    Ok(calldata)
 }
 
 /// Build calldata for private_swap
@@ -51,42 +63,88 @@ pub fn build_swap_calldata(
     new_commitment: &str,
 ) -> Result<Vec<FieldElement>, String> {
     // private_swap(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
     //   zero_for_one: bool,
     //   amount_specified: u128,
     //   sqrt_price_limit_x128: u256,
-    //   new_commitment: felt252
+    //   new_commitment: felt252,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
     // )
-    
-    let mut calldata = Vec::new();
-    
-    // Format proof array: [length, ...elements]
-    calldata.push(FieldElement::from(proof.len() as u64));
-    for p in proof {
-        calldata.push(parse_felt(p)?);
+    // Arrays are the last parameters (moved to the end in the contract for Argent wallet compatibility)
+    encode_calldata(
+        get_zylith_abi(),
+        "private_swap",
+        &[
+            AbiValue::Bool(zero_for_one),
+            AbiValue::Uint(amount_specified.into()),
+            AbiValue::u256(sqrt_price_limit_low, sqrt_price_limit_high),
+            AbiValue::Felt(parse_felt_or_decimal(new_commitment)?),
+            felt_array(proof)?,
+            felt_array(public_inputs)?,
+        ],
+    )
+}
+
+/// Check that the swap parameters match what the proof commits to, so a mismatched
+/// private_swap is refused here instead of reverting on-chain
+/// Swap public inputs: [nullifier, root, new_commitment, amount_specified, zero_for_one, ...]
+pub fn check_swap_public_inputs(
+    public_inputs: &[String],
+    zero_for_one: bool,
+    amount_specified: u128,
+    new_commitment: &str,
+) -> Result<(), String> {
+    let input = |index: usize, name: &str| -> Result<FieldElement, String> {
+        let value = public_inputs
+            .get(index)
+            .ok_or_else(|| format!("public_inputs has no {} (index {})", name, index))?;
+        parse_felt_or_decimal(value)
+    };
+    if input(2, "new_commitment")? != parse_felt_or_decimal(new_commitment)? {
+        return Err("new_commitment does not match the proof's public inputs".to_string());
     }
-    
-    // Format public_inputs array: [length, ...elements]
-    calldata.push(FieldElement::from(public_inputs.len() as u64));
-    for pi in public_inputs {
-        calldata.push(parse_felt(pi)?);
+    if input(3, "amount_specified")? != FieldElement::from(amount_specified) {
+        return Err("amount_specified does not match the proof's public inputs".to_string());
     }
-    
-    // zero_for_one: bool -> 0 or 1
-    calldata.push(if zero_for_one { FieldElement::ONE } else { FieldElement::ZERO });
-    
-    // amount_specified: u128
-    calldata.push(FieldElement::from(amount_specified));
-    
-    // sqrt_price_limit_x128: u256 -> [low, high]
-    calldata.push(FieldElement::from(sqrt_price_limit_low));
-    calldata.push(FieldElement::from(sqrt_price_limit_high));
-    
-    // new_commitment: felt252
-    calldata.push(parse_felt(new_commitment)?);
-    
-    Ok(calldata)
+    if input(4, "zero_for_one")? != FieldElement::from(zero_for_one as u8) {
+        return Err("zero_for_one does not match the proof's public inputs".to_string());
+    }
+    Ok(())
+}
+
+/// Same check for private_withdraw
+/// Withdraw public inputs: [nullifier, root, recipient, amount]; the recipient is reduced into the
+/// BN254 field by the prover, so only the amount can be compared with the request
+pub fn check_withdraw_public_inputs(public_inputs: &[String], amount: u128) -> Result<(), String> {
+    if public_inputs.len() != 4 {
+        return Err(format!("Withdraw proof has {} public inputs, expected 4", public_inputs.len()));
+    }
+    if parse_felt_or_decimal(&public_inputs[3])? != FieldElement::from(amount) {
+        return Err("amount does not match the proof's public inputs".to_string());
+    }
+    Ok(())
+}
+
+/// Same check for private_mint_liquidity/private_burn_liquidity, which share the LP verifier
+/// LP public inputs: [nullifier, root, tick_lower, tick_upper, liquidity, new_commitment, position_commitment]
+pub fn check_liquidity_public_inputs(
+    public_inputs: &[String],
+    liquidity: u128,
+    new_commitment: &str,
+) -> Result<(), String> {
+    if public_inputs.len() != 7 {
+        return Err(format!(
+            "LP verifier expects 7 public inputs, the proof has {}",
+            public_inputs.len()
+        ));
+    }
+    if parse_felt_or_decimal(&public_inputs[4])? != FieldElement::from(liquidity) {
+        return Err("liquidity does not match the proof's public inputs".to_string());
+    }
+    if parse_felt_or_decimal(&public_inputs[5])? != parse_felt_or_decimal(new_commitment)? {
+        return Err("new_commitment does not match the proof's public inputs".to_string());
+    }
+    Ok(())
 }
 
 /// Build calldata for private_withdraw
@@ -98,39 +156,23 @@ pub fn build_withdraw_calldata(
     amount: u128,
 ) -> Result<Vec<FieldElement>, String> {
     // private_withdraw(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
     //   token: ContractAddress,
     //   recipient: ContractAddress,
-    //   amount: u128
+    //   amount: u128,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
     // )
-    
-    let mut calldata = Vec::new();
-    
-    // Format proof array
-    calldata.push(FieldElement::from(proof.len() as u64));
-    for p in proof {
-        calldata.push(parse_felt(p)?);
-    }
-    
-    // Format public_inputs array
-    calldata.push(FieldElement::from(public_inputs.len() as u64));
-    for pi in public_inputs {
-        calldata.push(parse_felt(pi)?);
-    }
-    
-    // token: ContractAddress -> single felt252
-    let token_felt = parse_felt(token)?;
-    calldata.push(token_felt);
-    
-    // recipient: ContractAddress -> single felt252
-    let recipient_felt = parse_felt(recipient)?;
-    calldata.push(recipient_felt);
-    
-    // amount: u128
-    calldata.push(FieldElement::from(amount));
-    
-    Ok(calldata)
+    encode_calldata(
+        get_zylith_abi(),
+        "private_withdraw",
+        &[
+            AbiValue::Felt(parse_felt(token)?),
+            AbiValue::Felt(parse_felt(recipient)?),
+            AbiValue::Uint(amount.into()),
+            felt_array(proof)?,
+            felt_array(public_inputs)?,
+        ],
+    )
 }
 
 /// Build calldata for private_mint_liquidity
@@ -143,75 +185,7 @@ pub fn build_mint_liquidity_calldata(
     liquidity: u128,
     new_commitment: &str,
 ) -> Result<Vec<String>, String> {
-    // private_mint_liquidity(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
-    //   tick_lower: i32,
-    //   tick_upper: i32,
-    //   liquidity: u128,
-    //   new_commitment: felt252
-    // )
-    
-    println!("[Calldata] Building mint_liquidity calldata:");
-    println!("[Calldata]   proof length: {}", proof.len());
-    println!("[Calldata]   public_inputs length: {}", public_inputs.len());
-    println!("[Calldata]   tick_lower: {} (i32)", tick_lower);
-    println!("[Calldata]   tick_upper: {} (i32)", tick_upper);
-    println!("[Calldata]   liquidity: {}", liquidity);
-    println!("[Calldata]   new_commitment: {}", new_commitment);
-    
-    let mut calldata = Vec::new();
-    
-    // Format proof array - convert to decimal strings
-    calldata.push(proof.len().to_string());
-    for p in proof {
-        // Parse and convert to decimal string
-        let felt = parse_felt(p)?;
-        let fe_str = format!("{:x}", felt); // Get hex without 0x prefix
-        let big_uint = BigUint::from_str_radix(&fe_str, 16)
-            .map_err(|e| format!("Failed to parse proof element '{}': {}", p, e))?;
-        calldata.push(big_uint.to_str_radix(10)); // Convert to decimal string
-    }
-    
-    // Format public_inputs array - convert to decimal strings
-    calldata.push(public_inputs.len().to_string());
-    for pi in public_inputs {
-        // Parse and convert to decimal string
-        let felt = parse_felt(pi)?;
-        let fe_str = format!("{:x}", felt); // Get hex without 0x prefix
-        let big_uint = BigUint::from_str_radix(&fe_str, 16)
-            .map_err(|e| format!("Failed to parse public input '{}': {}", pi, e))?;
-        calldata.push(big_uint.to_str_radix(10)); // Convert to decimal string
-    }
-    
-    println!("[Calldata] After arrays: calldata length = {}", calldata.len());
-    
-    // tick_lower: i32 -> send as signed integer string
-    // StarkNet.js will handle the conversion to felt252 internally
-    // Don't pre-convert to felt252 - let the SDK do it
-    let tick_lower_str = tick_lower.to_string(); // e.g., "-1000" or "1000"
-    println!("[Calldata] tick_lower: {} (i32) -> {} (string) - StarkNet.js will convert to felt252", 
-        tick_lower, tick_lower_str);
-    calldata.push(tick_lower_str);
-    
-    // tick_upper: i32 -> send as signed integer string
-    // StarkNet.js will handle the conversion to felt252 internally
-    let tick_upper_str = tick_upper.to_string(); // e.g., "-1000" or "1000"
-    println!("[Calldata] tick_upper: {} (i32) -> {} (string) - StarkNet.js will convert to felt252", 
-        tick_upper, tick_upper_str);
-    calldata.push(tick_upper_str);
-    
-    // liquidity: u128 -> decimal string
-    calldata.push(liquidity.to_string());
-    
-    // new_commitment: felt252 -> decimal string
-    let commitment_felt = parse_felt(new_commitment)?;
-    let commitment_str = format!("{:x}", commitment_felt); // Get hex without 0x prefix
-    let commitment_big = BigUint::from_str_radix(&commitment_str, 16)
-        .map_err(|e| format!("Failed to parse commitment '{}': {}", new_commitment, e))?;
-    calldata.push(commitment_big.to_str_radix(10)); // Convert to decimal string
-    
-    Ok(calldata)
+    build_liquidity_calldata("private_mint_liquidity", proof, public_inputs, tick_lower, tick_upper, liquidity, new_commitment)
 }
 
 /// Build calldata for private_burn_liquidity
@@ -224,41 +198,65 @@ pub fn build_burn_liquidity_calldata(
     new_commitment: &str,
 ) -> Result<Vec<String>, String> {
     // Same signature as mint
-    build_mint_liquidity_calldata(proof, public_inputs, tick_lower, tick_upper, liquidity, new_commitment)
+    build_liquidity_calldata("private_burn_liquidity", proof, public_inputs, tick_lower, tick_upper, liquidity, new_commitment)
 }
 
-/// Convert u256 amount to (low, high) tuple
-pub fn u256_to_low_high(amount: u128) -> (u128, u128) {
-    // For amounts that fit in u128, high is always 0
-    (amount, 0)
-}
+fn build_liquidity_calldata(
+    function_name: &str,
+    proof: &[String],
+    public_inputs: &[String],
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    new_commitment: &str,
+) -> Result<Vec<String>, String> {
+    // private_{mint,burn}_liquidity(
+    //   tick_lower_felt: felt252,
+    //   tick_upper_felt: felt252,
+    //   liquidity: u128,
+    //   new_commitment: felt252,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
+    // )
+    println!("[Calldata] Building {} calldata:", function_name);
+    println!("[Calldata]   proof length: {}", proof.len());
+    println!("[Calldata]   public_inputs length: {}", public_inputs.len());
+    println!("[Calldata]   tick_lower: {}, tick_upper: {} (i32)", tick_lower, tick_upper);
+    println!("[Calldata]   liquidity: {}", liquidity);
+    println!("[Calldata]   new_commitment: {}", new_commitment);
 
-// Note: ContractAddress in Cairo is a single felt252, NOT u256
-// It should be passed directly as a FieldElement, not split into low/high
+    // Ticks are passed as felt252 (negative ticks are PRIME - |tick|)
+    let calldata = encode_calldata(
+        get_zylith_abi(),
+        function_name,
+        &[
+            AbiValue::Felt(i32_to_felt(tick_lower)),
+            AbiValue::Felt(i32_to_felt(tick_upper)),
+            AbiValue::Uint(liquidity.into()),
+            AbiValue::Felt(parse_felt_or_decimal(new_commitment)?),
+            felt_array(proof)?,
+            felt_array(public_inputs)?,
+        ],
+    )?;
+    println!("[Calldata] calldata length = {}", calldata.len());
 
-/// Convert i32 to felt252 (handles negative values)
-/// In Cairo, i32 negative values use Cairo prime field arithmetic
-/// Negative value -n is represented as: CAIRO_PRIME - n
-/// CAIRO_PRIME = 2^251 + 17 * 2^192 + 1 = 0x800000000000011000000000000000000000000000000000000000000000001
-fn i32_to_felt(value: i32) -> FieldElement {
-    if value >= 0 {
-        FieldElement::from(value as u64)
-    } else {
-        // For negative: use Cairo prime field arithmetic
-        // Formula: CAIRO_PRIME - |value|
-        // CAIRO_PRIME = 3618502788666131106986593281521497120414687020801267626233049500247285301248
-        let cairo_prime_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-        let cairo_prime = FieldElement::from_str(cairo_prime_str)
-            .expect("Failed to parse CAIRO_PRIME constant");
-        
-        let abs_value = (-value) as u64;
-        let abs_felt = FieldElement::from(abs_value);
-        
-        // CAIRO_PRIME - abs_value (field arithmetic)
-        cairo_prime - abs_felt
+    Ok(calldata.iter().map(|felt| felt_to_biguint(felt).to_string()).collect())
+}
+
+/// Split a u256 value into its (low, high) u128 limbs
+pub fn u256_to_low_high(value: &BigUint) -> Result<(u128, u128), String> {
+    use num_traits::ToPrimitive;
+    if value.bits() > 256 {
+        return Err(format!("{} does not fit in a u256", value));
     }
+    let low = (value & BigUint::from(u128::MAX)).to_u128().expect("masked to 128 bits");
+    let high = (value >> 128u32).to_u128().expect("at most 128 bits left");
+    Ok((low, high))
 }
 
+// Note: ContractAddress in Cairo is a single felt252, NOT u256
+// It should be passed directly as a FieldElement, not split into low/high
+
 /// Build calldata for initialize
 pub fn build_initialize_calldata(
     token0: &str,
@@ -275,57 +273,126 @@ pub fn build_initialize_calldata(
     //     tick_spacing: i32,
     //     sqrt_price_x128: u256
     // )
-    // Calldata: [token0 (felt252), token1 (felt252), fee (u128), tick_spacing (i32), sqrt_price.low, sqrt_price.high]
-    
-    let token0_felt = parse_felt(token0)?;
-    let token1_felt = parse_felt(token1)?;
-    
-    // Convert i32 to u128 for FieldElement (i32 is signed, but we'll pass it as u128)
-    // In Cairo, i32 is stored as a felt252, which can represent negative values
-    // For simplicity, we'll pass it as u128 and let Cairo handle the conversion
-    let tick_spacing_u128 = tick_spacing as u128;
-    
-    Ok(vec![
-        token0_felt, // ContractAddress as single felt252
-        token1_felt, // ContractAddress as single felt252
-        FieldElement::from(fee),
-        FieldElement::from(tick_spacing_u128), // i32 as felt252
-        FieldElement::from(sqrt_price_low),
-        FieldElement::from(sqrt_price_high),
-    ])
+    encode_calldata(
+        get_zylith_abi(),
+        "initialize",
+        &[
+            AbiValue::Felt(parse_felt(token0)?),
+            AbiValue::Felt(parse_felt(token1)?),
+            AbiValue::Uint(fee.into()),
+            AbiValue::Int(tick_spacing.into()),
+            AbiValue::u256(sqrt_price_low, sqrt_price_high),
+        ],
+    )
 }
 
-/// Parse felt252 from hex string or decimal string
-/// Handles values that may exceed felt252 by applying modulo arithmetic
-fn parse_felt(value_str: &str) -> Result<FieldElement, String> {
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
-    // This value exceeds u128, so we use BigUint for calculations
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
-    // Parse value as BigUint (handles both hex and decimal)
-    let value_big = if value_str.starts_with("0x") {
-        // Parse hex string - remove "0x" prefix and parse as base 16
-        num_bigint::BigUint::from_str_radix(&value_str[2..], 16)
-            .map_err(|e| format!("Failed to parse hex value '{}': {}", value_str, e))?
-    } else {
-        // Parse decimal string
-        BigUint::from_str(value_str)
-            .map_err(|e| format!("Failed to parse decimal value '{}': {}", value_str, e))?
-    };
-    
-    // Apply felt252 modulo if value exceeds limit
-    let modulo_big = if value_big >= felt_max_big {
-        &value_big % &felt_max_big
-    } else {
-        value_big.clone()
-    };
-    
-    // Convert BigUint to string and parse as FieldElement
-    // FieldElement can handle the full felt252 range directly
-    let modulo_str = modulo_big.to_str_radix(10);
-    FieldElement::from_str(&modulo_str)
-        .map_err(|e| format!("Failed to convert to FieldElement: {}", e))
+/// A felt252 array argument from hex or decimal strings
+fn felt_array(values: &[String]) -> Result<AbiValue, String> {
+    values
+        .iter()
+        .map(|value| parse_felt_or_decimal(value).map(AbiValue::Felt))
+        .collect::<Result<Vec<_>, String>>()
+        .map(AbiValue::Array)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u256_limbs_in_deposit_calldata() {
+        // 2^128 + 5: needs the high limb
+        let amount = (BigUint::from(1u8) << 128u32) + 5u32;
+        let (low, high) = u256_to_low_high(&amount).unwrap();
+        assert_eq!((low, high), (5, 1));
+        assert_eq!(u256_to_low_high(&BigUint::from(u128::MAX)).unwrap(), (u128::MAX, 0));
+        assert!(u256_to_low_high(&(BigUint::from(1u8) << 256u32)).is_err());
+
+        let calldata = build_deposit_calldata("0x123", low, high, "0x456").unwrap();
+        assert_eq!(calldata[1], FieldElement::from(5u8));
+        assert_eq!(calldata[2], FieldElement::ONE);
+        let approve = build_approve_calldata("0x789", low, high).unwrap();
+        assert_eq!(&approve[1..], &[FieldElement::from(5u8), FieldElement::ONE]);
+    }
+
+    #[test]
+    fn test_bare_addresses_are_hex_and_bare_commitments_decimal() {
+        // Addresses are hex with or without 0x
+        let calldata = build_deposit_calldata("123", 1, 0, "0x10").unwrap();
+        assert_eq!(calldata[0], FieldElement::from(0x123u16));
+        assert_eq!(build_approve_calldata("abc", 1, 0).unwrap()[0], FieldElement::from(0xabcu16));
+        // Commitments are decimal, as the prover emits them, unless 0x-prefixed
+        assert_eq!(calldata[3], FieldElement::from(16u8));
+        let calldata = build_deposit_calldata("0x123", 1, 0, "10").unwrap();
+        assert_eq!(calldata[3], FieldElement::from(10u8));
+        assert!(build_deposit_calldata("0x123", 1, 0, "1f").is_err());
+    }
+    #[test]
+    fn test_multicall_layout() {
+        use starknet::core::utils::get_selector_from_name;
+
+        let calls = vec![
+            ("0x1".to_string(), "approve".to_string(), vec![FieldElement::from(2u8), FieldElement::from(3u8)]),
+            ("0x4".to_string(), "private_deposit".to_string(), vec![FieldElement::from(5u8)]),
+        ];
+        let calldata = build_multicall_calldata(&calls).unwrap();
+        assert_eq!(
+            calldata,
+            vec![
+                FieldElement::from(2u8),
+                FieldElement::ONE,
+                get_selector_from_name("approve").unwrap(),
+                FieldElement::from(2u8),
+                FieldElement::from(2u8),
+                FieldElement::from(3u8),
+                FieldElement::from(4u8),
+                get_selector_from_name("private_deposit").unwrap(),
+                FieldElement::ONE,
+                FieldElement::from(5u8),
+            ]
+        );
+        assert_eq!(build_multicall_calldata(&[]).unwrap(), vec![FieldElement::ZERO]);
+    }
+
+    #[test]
+    fn test_public_inputs_must_match_parameters() {
+        let public_inputs: Vec<String> = ["11", "22", "0x33", "1000", "1", "0", "0", "0", "0"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(check_swap_public_inputs(&public_inputs, true, 1000, "51").is_ok());
+        assert!(check_swap_public_inputs(&public_inputs, false, 1000, "0x33").is_err());
+        assert!(check_swap_public_inputs(&public_inputs, true, 999, "0x33").is_err());
+        assert!(check_swap_public_inputs(&public_inputs, true, 1000, "0x34").is_err());
+        assert!(check_swap_public_inputs(&public_inputs[..3], true, 1000, "0x33").is_err());
+
+        // LP: [nullifier, root, tick_lower, tick_upper, liquidity, new_commitment, position_commitment]
+        let lp_inputs: Vec<String> = ["11", "22", "60", "120", "500", "0x44", "0x55"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(check_liquidity_public_inputs(&lp_inputs, 500, "68").is_ok());
+        assert!(check_liquidity_public_inputs(&lp_inputs, 501, "0x44").is_err());
+        assert!(check_liquidity_public_inputs(&lp_inputs, 500, "0x45").is_err());
+        assert!(check_liquidity_public_inputs(&lp_inputs[..6], 500, "0x44").is_err());
+
+        // Withdraw: [nullifier, root, recipient, amount]
+        let withdraw_inputs: Vec<String> = ["11", "22", "33", "750"].iter().map(|s| s.to_string()).collect();
+        assert!(check_withdraw_public_inputs(&withdraw_inputs, 750).is_ok());
+        assert!(check_withdraw_public_inputs(&withdraw_inputs, 751).is_err());
+        assert!(check_withdraw_public_inputs(&withdraw_inputs[..3], 750).is_err());
+
+        let calldata = build_swap_calldata(&["7".to_string()], &public_inputs, true, 1000, 5, 0, "0x33").unwrap();
+        // zero_for_one, amount_specified, limit low/high, new_commitment, proof len, proof, inputs len
+        assert_eq!(calldata[..8].to_vec(), vec![
+            FieldElement::ONE,
+            FieldElement::from(1000u32),
+            FieldElement::from(5u8),
+            FieldElement::ZERO,
+            FieldElement::from(0x33u8),
+            FieldElement::ONE,
+            FieldElement::from(7u8),
+            FieldElement::from(9u8),
+        ]);
+    }
+}
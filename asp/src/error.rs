@@ -0,0 +1,92 @@
+// Error responses shared by every handler
+// Body: { "code": "invalid_amount", "message": "...", "details": {...} } with the matching HTTP status.
+// `code` is stable and meant for programs, `message` for humans, `details` (optional) carries
+// structured context such as the limits a request exceeded. `error` repeats the message for clients
+// written against the older { "error": "..." } bodies.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError { status, code, message: message.into(), details: None }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, message)
+    }
+
+    pub fn conflict(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, code, message)
+    }
+
+    pub fn internal(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, code, message)
+    }
+
+    /// The RPC node failed or could not be reached
+    pub fn rpc(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, "rpc_error", message)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// JSON body, also used for error events on SSE streams
+    pub fn body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "code": self.code,
+            "message": self.message,
+            "error": self.message,
+        });
+        if let Some(details) = &self.details {
+            body["details"] = details.clone();
+        }
+        body
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_body() {
+        let error = ApiError::bad_request("invalid_amount", "Invalid amount");
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            error.body(),
+            serde_json::json!({ "code": "invalid_amount", "message": "Invalid amount", "error": "Invalid amount" })
+        );
+
+        let error = ApiError::rpc("node unreachable").with_details(serde_json::json!({ "retry": true }));
+        assert_eq!(error.status, StatusCode::BAD_GATEWAY);
+        assert_eq!(error.code, "rpc_error");
+        assert_eq!(error.body()["details"]["retry"], true);
+    }
+}
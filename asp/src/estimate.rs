@@ -0,0 +1,360 @@
+// Proof generation time estimation
+// Swap proof time grows with the number of ticks crossed, so we bucket swaps by
+// estimated complexity and keep a rolling history of real proof timings per bucket.
+
+use crate::tick_math::{get_tick_at_sqrt_ratio, q128};
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+use serde::Serialize;
+use std::collections::VecDeque;
+use tracing::warn;
+
+/// Default max ticks between sqrt_price_old and new_sqrt_price_x128 for a swap proof
+/// (about a 5% sqrt price move around 1:1 with the contract's tick mapping)
+const DEFAULT_SWAP_MAX_TICK_DELTA: u32 = 100;
+
+/// Number of recent proof timings kept for the rolling average
+const HISTORY_SIZE: usize = 50;
+
+/// Swap complexity bucket, derived from estimated ticks crossed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Complexity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Complexity {
+    pub fn from_ticks(tick_delta: u32) -> Self {
+        if tick_delta < 5 {
+            Complexity::Low
+        } else if tick_delta < 10 {
+            Complexity::Medium
+        } else {
+            Complexity::High
+        }
+    }
+
+    /// Static estimate in minutes (used for logging)
+    pub fn minutes_label(&self) -> &'static str {
+        match self {
+            Complexity::Low => "1-2",
+            Complexity::Medium => "2-4",
+            Complexity::High => "4-10",
+        }
+    }
+
+    /// Static estimate in seconds (used when there's no history yet)
+    fn default_range_secs(&self) -> (f64, f64) {
+        match self {
+            Complexity::Low => (60.0, 120.0),
+            Complexity::Medium => (120.0, 240.0),
+            Complexity::High => (240.0, 600.0),
+        }
+    }
+}
+
+/// How zero sqrt prices in a request are handled (ZERO_SQRT_PRICE_POLICY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroPricePolicy {
+    /// sqrt_price_old = 0 means Q128 (1:1), new_sqrt_price_x128 = 0 means no price change (default)
+    Default,
+    /// Zero prices are rejected
+    Reject,
+}
+
+impl ZeroPricePolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("ZERO_SQRT_PRICE_POLICY").as_deref() {
+            Ok("reject") => ZeroPricePolicy::Reject,
+            _ => ZeroPricePolicy::Default,
+        }
+    }
+}
+
+/// Parse a decimal sqrt price (Q128.128); 2^128 and above parse natively
+pub fn parse_sqrt_price(field: &str, value: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(value.trim().as_bytes(), 10).ok_or_else(|| format!("Invalid {} format", field))
+}
+
+/// Apply the zero-price policy to (sqrt_price_old, new_sqrt_price_x128)
+pub fn resolve_sqrt_prices(
+    sqrt_price_old: BigUint,
+    new_sqrt_price_x128: BigUint,
+    policy: ZeroPricePolicy,
+) -> Result<(BigUint, BigUint), String> {
+    let sqrt_price_old = if sqrt_price_old.is_zero() {
+        if policy == ZeroPricePolicy::Reject {
+            return Err("sqrt_price_old must be nonzero".to_string());
+        }
+        warn!(field = "sqrt_price_old", "[ASP] ⚠️  sqrt price is zero, using default Q128 (1:1 price)");
+        q128()
+    } else {
+        sqrt_price_old
+    };
+
+    let new_sqrt_price_x128 = if new_sqrt_price_x128.is_zero() {
+        if policy == ZeroPricePolicy::Reject {
+            return Err("new_sqrt_price_x128 must be nonzero".to_string());
+        }
+        warn!(field = "new_sqrt_price_x128", "[ASP] ⚠️  sqrt price is zero, using sqrt_price_old (no price change)");
+        sqrt_price_old.clone()
+    } else {
+        new_sqrt_price_x128
+    };
+
+    Ok((sqrt_price_old, new_sqrt_price_x128))
+}
+
+/// Price ratio new/old (sqrt prices), for display only
+pub fn price_ratio(sqrt_price_old: &BigUint, new_sqrt_price_x128: &BigUint) -> f64 {
+    let old = sqrt_price_old.to_f64().unwrap_or(f64::INFINITY);
+    let new = new_sqrt_price_x128.to_f64().unwrap_or(f64::INFINITY);
+    new / old
+}
+
+/// Ticks between the two sqrt prices, using the contract's own tick mapping
+/// (tick_math::get_tick_at_sqrt_ratio), so the result is exact and matches what the pool stores
+pub fn tick_delta(sqrt_price_old: &BigUint, new_sqrt_price_x128: &BigUint) -> Result<u32, String> {
+    let old_tick = get_tick_at_sqrt_ratio(sqrt_price_old).map_err(|e| format!("sqrt_price_old: {}", e))?;
+    let new_tick = get_tick_at_sqrt_ratio(new_sqrt_price_x128).map_err(|e| format!("new_sqrt_price_x128: {}", e))?;
+    Ok(old_tick.abs_diff(new_tick))
+}
+
+/// Max tick delta accepted for a swap proof (SWAP_MAX_TICK_DELTA)
+/// Proving time grows with the ticks crossed, so larger moves are rejected up front
+pub fn max_tick_delta() -> u32 {
+    std::env::var("SWAP_MAX_TICK_DELTA")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_SWAP_MAX_TICK_DELTA)
+}
+
+/// Price move of a swap as the swap proof endpoint sees it, before any proving
+/// Shared with POST /api/proof/estimate so the estimate and the real request never disagree
+#[derive(Debug)]
+pub struct SwapPriceCheck {
+    pub sqrt_price_old: BigUint,
+    pub new_sqrt_price_x128: BigUint,
+    pub tick_delta: u32,
+    pub max_tick_delta: u32,
+}
+
+impl SwapPriceCheck {
+    /// Parse both decimal sqrt prices (absent counts as zero), apply the zero-price policy and
+    /// measure the ticks between them
+    pub fn new(
+        sqrt_price_old: Option<&str>,
+        new_sqrt_price_x128: Option<&str>,
+        policy: ZeroPricePolicy,
+        max_tick_delta: u32,
+    ) -> Result<Self, String> {
+        let parse = |field: &str, value: Option<&str>| match value {
+            Some(value) => parse_sqrt_price(field, value),
+            None => Ok(BigUint::zero()),
+        };
+        let (sqrt_price_old, new_sqrt_price_x128) = resolve_sqrt_prices(
+            parse("sqrt_price_old", sqrt_price_old)?,
+            parse("new_sqrt_price_x128", new_sqrt_price_x128)?,
+            policy,
+        )?;
+        let tick_delta = tick_delta(&sqrt_price_old, &new_sqrt_price_x128)?;
+        Ok(SwapPriceCheck {
+            sqrt_price_old,
+            new_sqrt_price_x128,
+            tick_delta,
+            max_tick_delta,
+        })
+    }
+
+    /// Whether the swap proof endpoint refuses this move (more than SWAP_MAX_TICK_DELTA ticks)
+    pub fn would_reject(&self) -> bool {
+        self.tick_delta > self.max_tick_delta
+    }
+
+    pub fn complexity(&self) -> Complexity {
+        Complexity::from_ticks(self.tick_delta)
+    }
+
+    pub fn price_ratio(&self) -> f64 {
+        price_ratio(&self.sqrt_price_old, &self.new_sqrt_price_x128)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProofTimeEstimate {
+    pub complexity: Complexity,
+    pub min_seconds: f64,
+    pub max_seconds: f64,
+    pub avg_seconds: Option<f64>,
+    pub samples: usize,
+    /// "history" when based on recent proofs, "default" otherwise
+    pub source: &'static str,
+}
+
+/// Rolling history of recent proof timings
+#[derive(Default)]
+pub struct ProofTimeHistory {
+    samples: VecDeque<(Complexity, f64)>,
+}
+
+impl ProofTimeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a successful proof generation
+    pub fn record(&mut self, tick_delta: u32, elapsed_secs: f64) {
+        if self.samples.len() == HISTORY_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((Complexity::from_ticks(tick_delta), elapsed_secs));
+    }
+
+    /// Average over all recent proofs regardless of complexity
+    pub fn average_seconds(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().map(|(_, secs)| *secs).sum::<f64>() / self.samples.len() as f64)
+    }
+
+    /// Estimate proof time for a swap of the given complexity
+    pub fn estimate(&self, tick_delta: u32) -> ProofTimeEstimate {
+        let complexity = Complexity::from_ticks(tick_delta);
+        let timings: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|(c, _)| *c == complexity)
+            .map(|(_, secs)| *secs)
+            .collect();
+
+        if timings.is_empty() {
+            let (min, max) = complexity.default_range_secs();
+            return ProofTimeEstimate {
+                complexity,
+                min_seconds: min,
+                max_seconds: max,
+                avg_seconds: None,
+                samples: 0,
+                source: "default",
+            };
+        }
+
+        let min = timings.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = timings.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let avg = timings.iter().sum::<f64>() / timings.len() as f64;
+
+        ProofTimeEstimate {
+            complexity,
+            min_seconds: min,
+            max_seconds: max,
+            avg_seconds: Some(avg),
+            samples: timings.len(),
+            source: "history",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_without_history_uses_defaults() {
+        let history = ProofTimeHistory::new();
+        let estimate = history.estimate(3);
+        assert_eq!(estimate.complexity, Complexity::Low);
+        assert_eq!(estimate.source, "default");
+        assert_eq!((estimate.min_seconds, estimate.max_seconds), (60.0, 120.0));
+    }
+
+    #[test]
+    fn test_estimate_uses_samples_of_same_complexity() {
+        let mut history = ProofTimeHistory::new();
+        history.record(2, 40.0);
+        history.record(4, 60.0);
+        history.record(20, 500.0);
+
+        let estimate = history.estimate(1);
+        assert_eq!(estimate.source, "history");
+        assert_eq!(estimate.samples, 2);
+        assert_eq!(estimate.min_seconds, 40.0);
+        assert_eq!(estimate.max_seconds, 60.0);
+        assert_eq!(estimate.avg_seconds, Some(50.0));
+    }
+
+    #[test]
+    fn test_tick_delta_uses_contract_ticks() {
+        let q = q128();
+        assert_eq!(tick_delta(&q, &q).unwrap(), 0);
+
+        // Up to tick 100 each tick moves the sqrt price by q128 / 20000
+        let up = &q + (&q * 40u32) / 20000u32;
+        let down = &q - (&q * 40u32) / 20000u32;
+        assert_eq!(tick_delta(&q, &up).unwrap(), 40);
+        assert_eq!(tick_delta(&up, &q).unwrap(), 40);
+        assert_eq!(tick_delta(&down, &up).unwrap(), 80);
+
+        // One unit below a tick's sqrt price still belongs to the tick below
+        assert_eq!(tick_delta(&q, &(&up - 1u8)).unwrap(), 39);
+
+        assert!(tick_delta(&BigUint::from(1u8), &q).is_err());
+    }
+
+    #[test]
+    fn test_sqrt_prices_at_and_around_q128() {
+        // 2^128 parses natively, no u128::MAX sentinel
+        let q128_price = parse_sqrt_price("sqrt_price_old", "340282366920938463463374607431768211456").unwrap();
+        assert_eq!(q128_price, q128());
+
+        // u128::MAX is a real price one below Q128 and stays distinct from it
+        let below = parse_sqrt_price("sqrt_price_old", &u128::MAX.to_string()).unwrap();
+        let above = parse_sqrt_price("sqrt_price_old", "340282366920938463463374607431768211457").unwrap();
+        assert_eq!(&below + 1u8, q128_price);
+        assert_eq!(&q128_price + 1u8, above);
+
+        let (old, new) = resolve_sqrt_prices(below.clone(), above.clone(), ZeroPricePolicy::Default).unwrap();
+        assert_eq!((old.to_string(), new.to_string()), (below.to_string(), above.to_string()));
+        assert_eq!(tick_delta(&old, &new).unwrap(), 1);
+
+        assert!(parse_sqrt_price("sqrt_price_old", "-1").is_err());
+    }
+
+    #[test]
+    fn test_zero_price_policy() {
+        let zero = BigUint::zero();
+        let price = BigUint::from(12345u32);
+
+        let (old, new) = resolve_sqrt_prices(zero.clone(), zero.clone(), ZeroPricePolicy::Default).unwrap();
+        assert_eq!(old, q128());
+        assert_eq!(new, q128());
+
+        let (old, new) = resolve_sqrt_prices(price.clone(), zero.clone(), ZeroPricePolicy::Default).unwrap();
+        assert_eq!(new, old);
+
+        assert!(resolve_sqrt_prices(zero.clone(), price.clone(), ZeroPricePolicy::Reject).is_err());
+        assert!(resolve_sqrt_prices(price, zero, ZeroPricePolicy::Reject).is_err());
+    }
+
+    #[test]
+    fn test_swap_price_check() {
+        // Absent and zero prices fall back to Q128 like the swap proof endpoint
+        let check = SwapPriceCheck::new(None, Some("0"), ZeroPricePolicy::Default, 100).unwrap();
+        assert_eq!(check.sqrt_price_old, q128());
+        assert_eq!(check.new_sqrt_price_x128, q128());
+        assert_eq!(check.tick_delta, 0);
+        assert!(!check.would_reject());
+        assert!(SwapPriceCheck::new(None, None, ZeroPricePolicy::Reject, 100).is_err());
+
+        let up = (q128() + (q128() * 40u32) / 20000u32).to_string();
+        let check = SwapPriceCheck::new(None, Some(&up), ZeroPricePolicy::Default, 100).unwrap();
+        assert_eq!(check.tick_delta, 40);
+        assert_eq!(check.complexity(), Complexity::High);
+        assert!(!check.would_reject());
+        assert!(SwapPriceCheck::new(None, Some(&up), ZeroPricePolicy::Default, 39).unwrap().would_reject());
+
+        assert!(SwapPriceCheck::new(Some("abc"), None, ZeroPricePolicy::Default, 100).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// Deposit feed between the syncer and WebSocket/SSE subscribers
+// The syncer publishes on a bounded broadcast channel and never waits on subscribers:
+// a subscriber that falls more than `capacity` messages behind is lagged and disconnected.
+// The last few events are also kept for replay, so a client reconnecting to /deposit/events
+// picks up what it missed in between.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Default number of deposit events buffered per subscriber
+const DEFAULT_BUFFER_SIZE: usize = 256;
+
+/// Default number of recent deposit events kept for replay on connect
+const DEFAULT_REPLAY_SIZE: usize = 32;
+
+/// Close reason sent to subscribers that fell behind
+pub const LAGGED_CLOSE_REASON: &str = "you lagged, re-fetch via HTTP";
+
+/// Deposit inserted into the local tree by the syncer
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositEvent {
+    pub leaf_index: u32,
+    pub commitment: String,
+    pub root: String,
+}
+
+pub struct DepositFeed {
+    sender: broadcast::Sender<DepositEvent>,
+    capacity: usize,
+    /// Most recent events, oldest first (at most replay_size)
+    recent: Mutex<VecDeque<DepositEvent>>,
+    replay_size: usize,
+    /// Messages dropped for lagged subscribers
+    dropped_messages: AtomicU64,
+    /// Subscribers disconnected for lagging
+    lagged_subscribers: AtomicU64,
+}
+
+impl DepositFeed {
+    pub fn with_replay(capacity: usize, replay_size: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            capacity,
+            recent: Mutex::new(VecDeque::with_capacity(replay_size)),
+            replay_size,
+            dropped_messages: AtomicU64::new(0),
+            lagged_subscribers: AtomicU64::new(0),
+        }
+    }
+
+    /// Buffer size from DEPOSIT_FEED_BUFFER (default 256), replay size from DEPOSIT_FEED_REPLAY (default 32)
+    pub fn from_env() -> Self {
+        let env_size = |var: &str, default: usize| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        Self::with_replay(
+            env_size("DEPOSIT_FEED_BUFFER", DEFAULT_BUFFER_SIZE),
+            env_size("DEPOSIT_FEED_REPLAY", DEFAULT_REPLAY_SIZE),
+        )
+    }
+
+    /// Publish a deposit without waiting on subscribers
+    pub fn publish(&self, event: DepositEvent) {
+        // Sent under the replay lock so subscribe_with_replay sees each event exactly once
+        let mut recent = self.recent.lock().unwrap();
+        if self.replay_size > 0 {
+            if recent.len() == self.replay_size {
+                recent.pop_front();
+            }
+            recent.push_back(event.clone());
+        }
+        // Err only means there are no subscribers right now
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe, plus the recent events with a leaf index above `after` (all kept ones if None)
+    /// Nothing is missed or repeated between the replay and the live receiver
+    pub fn subscribe_with_replay(&self, after: Option<u32>) -> (Vec<DepositEvent>, broadcast::Receiver<DepositEvent>) {
+        let recent = self.recent.lock().unwrap();
+        let replay = recent
+            .iter()
+            .filter(|event| after.is_none_or(|after| event.leaf_index > after))
+            .cloned()
+            .collect();
+        (replay, self.sender.subscribe())
+    }
+
+    /// Forget the replay buffer (the tree is being rebuilt, so its leaf indices no longer apply)
+    pub fn clear_replay(&self) {
+        self.recent.lock().unwrap().clear();
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DepositEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Record a subscriber that missed `skipped` messages and is being disconnected
+    pub fn record_lag(&self, skipped: u64) {
+        self.dropped_messages.fetch_add(skipped, Ordering::Relaxed);
+        self.lagged_subscribers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> serde_json::Value {
+        serde_json::json!({
+            "subscribers": self.sender.receiver_count(),
+            "buffer_size": self.capacity,
+            "replay_size": self.replay_size,
+            "dropped_messages": self.dropped_messages.load(Ordering::Relaxed),
+            "lagged_subscribers": self.lagged_subscribers.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(i: u32) -> DepositEvent {
+        DepositEvent {
+            leaf_index: i,
+            commitment: format!("0x{:x}", i),
+            root: "0x0".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_does_not_block_publisher() {
+        let feed = DepositFeed::with_replay(4, DEFAULT_REPLAY_SIZE);
+        let mut slow = feed.subscribe();
+
+        // Subscriber never reads while the syncer publishes far more than the buffer
+        let published = tokio::time::timeout(std::time::Duration::from_millis(100), async {
+            for i in 0..100 {
+                feed.publish(event(i));
+            }
+        })
+        .await;
+        assert!(published.is_ok(), "publisher blocked on a slow subscriber");
+
+        match slow.recv().await {
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                assert_eq!(skipped, 96);
+                feed.record_lag(skipped);
+            }
+            other => panic!("expected lagged subscriber, got {:?}", other),
+        }
+
+        let stats = feed.stats();
+        assert_eq!(stats["dropped_messages"], 96);
+        assert_eq!(stats["lagged_subscribers"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recent_events_on_subscribe() {
+        let feed = DepositFeed::with_replay(16, 3);
+        for i in 0..5 {
+            feed.publish(event(i));
+        }
+
+        let (replay, mut live) = feed.subscribe_with_replay(None);
+        assert_eq!(replay.iter().map(|e| e.leaf_index).collect::<Vec<_>>(), vec![2, 3, 4]);
+        let (replay, _) = feed.subscribe_with_replay(Some(3));
+        assert_eq!(replay.iter().map(|e| e.leaf_index).collect::<Vec<_>>(), vec![4]);
+
+        // Events after subscribing arrive live, not in the replay
+        feed.publish(event(5));
+        assert_eq!(live.recv().await.unwrap().leaf_index, 5);
+
+        feed.clear_replay();
+        assert!(feed.subscribe_with_replay(None).0.is_empty());
+    }
+}
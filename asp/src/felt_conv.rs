@@ -0,0 +1,103 @@
+// Conversions between Rust integers and felt252
+// Cairo signed integers are felts: -n is encoded as PRIME - n. Unsigned values read back from
+// events and storage must fit their type; out-of-range felts are an error, never truncated.
+
+use num_bigint::BigUint;
+use starknet::core::types::FieldElement;
+
+/// i32 (ticks, tick spacing) to felt252: -n is PRIME - n
+pub fn i32_to_felt(value: i32) -> FieldElement {
+    let abs = FieldElement::from(value.unsigned_abs());
+    if value >= 0 {
+        abs
+    } else {
+        FieldElement::ZERO - abs
+    }
+}
+
+/// felt252 to i32, accepting [0, i32::MAX] and [PRIME - 2^31, PRIME - 1]
+pub fn felt_to_i32(felt: FieldElement) -> Result<i32, String> {
+    let value = felt_to_biguint(&felt);
+    if value <= BigUint::from(i32::MAX as u32) {
+        return Ok(u32::try_from(value).unwrap() as i32);
+    }
+
+    let negated = felt_to_biguint(&(FieldElement::ZERO - felt));
+    if negated <= BigUint::from(1u64 << 31) {
+        return Ok(-(u32::try_from(negated).unwrap() as i64) as i32);
+    }
+
+    Err(format!("felt 0x{:x} is out of i32 range", felt))
+}
+
+/// felt252 to u128 (e.g. u128 amounts, u256 limbs)
+pub fn u128_from_felt(felt: FieldElement) -> Result<u128, String> {
+    let bytes = felt.to_bytes_be();
+    if bytes[..16].iter().any(|b| *b != 0) {
+        return Err(format!("felt 0x{:x} is out of u128 range", felt));
+    }
+    let mut low = [0u8; 16];
+    low.copy_from_slice(&bytes[16..]);
+    Ok(u128::from_be_bytes(low))
+}
+
+/// u256 return value ([low, high] limbs) to (low, high)
+pub fn decode_u256(result: &[FieldElement]) -> Result<(u128, u128), String> {
+    match result {
+        [low, high, ..] => Ok((u128_from_felt(*low)?, u128_from_felt(*high)?)),
+        _ => Err(format!("expected u256 (2 felts), got {} felts", result.len())),
+    }
+}
+
+pub fn felt_to_biguint(felt: &FieldElement) -> BigUint {
+    BigUint::from_bytes_be(&felt.to_bytes_be())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_i32_round_trip() {
+        for value in [0, 1, -1, 60, -60, 887272, -887272, i32::MAX, i32::MIN] {
+            assert_eq!(felt_to_i32(i32_to_felt(value)), Ok(value), "round trip of {}", value);
+        }
+    }
+
+    #[test]
+    fn test_negative_i32_is_prime_minus_abs() {
+        // -1 is PRIME - 1, the largest felt
+        assert_eq!(i32_to_felt(-1), FieldElement::MAX);
+        assert_eq!(i32_to_felt(-1000) + FieldElement::from(1000u32), FieldElement::ZERO);
+        // MIN_TICK
+        assert_eq!(i32_to_felt(-887272), FieldElement::MAX - FieldElement::from(887271u32));
+        assert_eq!(i32_to_felt(0), FieldElement::ZERO);
+    }
+
+    #[test]
+    fn test_felt_to_i32_rejects_out_of_range() {
+        assert!(felt_to_i32(FieldElement::from(i32::MAX as u64 + 1)).is_err());
+        let below_min = FieldElement::ZERO - FieldElement::from((1u64 << 31) + 1);
+        assert!(felt_to_i32(below_min).is_err());
+    }
+
+    #[test]
+    fn test_u128_round_trip_and_bounds() {
+        for value in [0u128, 1, u64::MAX as u128, u128::MAX] {
+            assert_eq!(u128_from_felt(FieldElement::from(value)), Ok(value));
+        }
+        assert!(u128_from_felt(i32_to_felt(-1)).is_err());
+    }
+    #[test]
+    fn test_decode_u256() {
+        for value in [0u128, 1, u128::MAX] {
+            for high in [0u128, 7, u128::MAX] {
+                let felts = [FieldElement::from(value), FieldElement::from(high)];
+                assert_eq!(decode_u256(&felts), Ok((value, high)));
+            }
+        }
+        assert!(decode_u256(&[FieldElement::ONE]).is_err());
+        // A limb above u128 is a malformed response, not a truncated amount
+        assert!(decode_u256(&[FieldElement::MAX, FieldElement::ZERO]).is_err());
+    }
+}
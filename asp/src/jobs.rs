@@ -0,0 +1,96 @@
+// Background proof jobs (POST /api/proof/swap?async=true)
+// The proof runs on a tokio task; clients poll GET /api/proof/status/:job_id instead of holding
+// the connection open for the whole proof. Jobs live in memory only and are dropped JOB_TTL after
+// they finish, so a restart loses them (clients resubmit).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a finished job stays available for polling
+const JOB_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum JobState {
+    /// Waiting for a prover slot
+    Pending,
+    Running,
+    /// Same body as the synchronous endpoint
+    Done { result: serde_json::Value },
+    Failed { error: String },
+}
+
+impl JobState {
+    fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done { .. } | JobState::Failed { .. })
+    }
+}
+
+struct Job {
+    state: JobState,
+    updated: Instant,
+}
+
+#[derive(Default)]
+pub struct ProofJobs {
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl ProofJobs {
+    /// Register a pending job and return its id
+    pub fn create(&self) -> String {
+        let id = format!("{:032x}", rand::random::<u128>());
+        let mut jobs = self.jobs.lock().unwrap();
+        prune(&mut jobs, JOB_TTL);
+        jobs.insert(id.clone(), Job { state: JobState::Pending, updated: Instant::now() });
+        id
+    }
+
+    pub fn set(&self, id: &str, state: JobState) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            job.state = state;
+            job.updated = Instant::now();
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobState> {
+        self.jobs.lock().unwrap().get(id).map(|job| job.state.clone())
+    }
+
+    /// Jobs not finished yet (pending or running)
+    pub fn active(&self) -> usize {
+        self.jobs.lock().unwrap().values().filter(|job| !job.state.is_finished()).count()
+    }
+}
+
+/// Drop finished jobs older than `ttl`; unfinished jobs are always kept
+fn prune(jobs: &mut HashMap<String, Job>, ttl: Duration) {
+    jobs.retain(|_, job| !job.state.is_finished() || job.updated.elapsed() <= ttl);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle_and_pruning() {
+        let jobs = ProofJobs::default();
+        let id = jobs.create();
+        assert_eq!(jobs.get(&id), Some(JobState::Pending));
+        assert_eq!(jobs.active(), 1);
+
+        jobs.set(&id, JobState::Running);
+        let running = jobs.create();
+        jobs.set(&id, JobState::Failed { error: "boom".to_string() });
+        assert_eq!(jobs.active(), 1);
+        assert_eq!(jobs.get("unknown"), None);
+
+        std::thread::sleep(Duration::from_millis(5));
+        let mut map = jobs.jobs.lock().unwrap();
+        prune(&mut map, Duration::from_millis(1));
+        assert!(!map.contains_key(&id));
+        assert!(map.contains_key(&running));
+    }
+}
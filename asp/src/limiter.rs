@@ -0,0 +1,229 @@
+// Proof generation concurrency limit
+// Proofs are CPU/memory heavy (rapidsnark/snarkjs), so only MAX_CONCURRENT_PROOFS run at once.
+// When the prover is saturated, PROOF_OVERLOAD_POLICY decides whether requests wait their turn
+// ("queue") or are rejected right away ("reject") with the queue depth and an estimated wait.
+// Queued requests give up after PROOF_QUEUE_TIMEOUT_SECS.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{timeout, Duration};
+
+/// One snarkjs prover can take ~8 GB, so by default proofs run one at a time
+const DEFAULT_MAX_CONCURRENT_PROOFS: usize = 1;
+const DEFAULT_QUEUE_TIMEOUT_SECS: u64 = 300;
+
+/// Used for wait estimates before any proof has been timed
+const DEFAULT_PROOF_SECONDS: f64 = 120.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverloadPolicy {
+    Reject,
+    Queue,
+}
+
+impl OverloadPolicy {
+    fn from_env() -> Self {
+        match std::env::var("PROOF_OVERLOAD_POLICY")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "reject" => OverloadPolicy::Reject,
+            _ => OverloadPolicy::Queue,
+        }
+    }
+}
+
+/// Returned when a proof request is rejected because the prover is saturated
+#[derive(Debug, Serialize)]
+pub struct Overloaded {
+    pub policy: OverloadPolicy,
+    /// The request waited in the queue for the whole queue timeout (queue policy)
+    pub timed_out: bool,
+    pub in_flight: usize,
+    pub max_concurrent: usize,
+    pub queue_depth: usize,
+    pub estimated_wait_seconds: f64,
+}
+
+pub struct ProofLimiter {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    policy: OverloadPolicy,
+    /// Requests waiting for a permit (queue policy)
+    waiting: AtomicUsize,
+    /// How long a queued request waits for a permit
+    queue_timeout: Duration,
+}
+
+impl ProofLimiter {
+    pub fn new(max_concurrent: usize, policy: OverloadPolicy) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            policy,
+            waiting: AtomicUsize::new(0),
+            queue_timeout: Duration::from_secs(DEFAULT_QUEUE_TIMEOUT_SECS),
+        }
+    }
+
+    pub fn with_queue_timeout(mut self, queue_timeout: Duration) -> Self {
+        self.queue_timeout = queue_timeout;
+        self
+    }
+
+    /// MAX_CONCURRENT_PROOFS (default 1), PROOF_OVERLOAD_POLICY=queue|reject (default queue)
+    /// and PROOF_QUEUE_TIMEOUT_SECS (default 300)
+    pub fn from_env() -> Self {
+        let max_concurrent = std::env::var("MAX_CONCURRENT_PROOFS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_PROOFS);
+        let queue_timeout_secs = std::env::var("PROOF_QUEUE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_QUEUE_TIMEOUT_SECS);
+        Self::new(max_concurrent, OverloadPolicy::from_env())
+            .with_queue_timeout(Duration::from_secs(queue_timeout_secs))
+    }
+
+    pub fn policy(&self) -> OverloadPolicy {
+        self.policy
+    }
+
+    /// A permit if one is free right now, without waiting or counting as queued
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore.clone().try_acquire_owned().ok()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// Rough wait: proofs ahead of this request, run max_concurrent at a time
+    pub fn estimated_wait_seconds(&self, avg_proof_seconds: Option<f64>) -> f64 {
+        let avg = avg_proof_seconds.unwrap_or(DEFAULT_PROOF_SECONDS);
+        let ahead = self.queue_depth() + 1;
+        (ahead as f64 / self.max_concurrent as f64).ceil() * avg
+    }
+
+    /// Get a permit to run a proof, waiting or rejecting according to the policy
+    /// The permit is released when dropped
+    pub async fn acquire(&self, avg_proof_seconds: Option<f64>) -> Result<OwnedSemaphorePermit, Overloaded> {
+        if let Some(permit) = self.try_acquire() {
+            return Ok(permit);
+        }
+
+        if self.policy == OverloadPolicy::Reject {
+            return Err(self.overloaded(false, avg_proof_seconds));
+        }
+
+        let queued = Queued::new(&self.waiting);
+        let permit = timeout(self.queue_timeout, self.semaphore.clone().acquire_owned()).await;
+        drop(queued);
+        match permit {
+            // The semaphore is never closed
+            Ok(permit) => Ok(permit.expect("proof semaphore closed")),
+            Err(_) => Err(self.overloaded(true, avg_proof_seconds)),
+        }
+    }
+
+    fn overloaded(&self, timed_out: bool, avg_proof_seconds: Option<f64>) -> Overloaded {
+        Overloaded {
+            policy: self.policy,
+            timed_out,
+            in_flight: self.in_flight(),
+            max_concurrent: self.max_concurrent,
+            queue_depth: self.queue_depth(),
+            estimated_wait_seconds: self.estimated_wait_seconds(avg_proof_seconds),
+        }
+    }
+}
+
+/// Counts a request in the queue depth until dropped, including when the waiting future is
+/// cancelled (a client that disconnects while queued)
+struct Queued<'a>(&'a AtomicUsize);
+
+impl<'a> Queued<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        Queued(waiting)
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reject_policy_reports_overload() {
+        let limiter = ProofLimiter::new(1, OverloadPolicy::Reject);
+        let _running = limiter.acquire(None).await.unwrap();
+
+        let overloaded = limiter.acquire(Some(60.0)).await.unwrap_err();
+        assert_eq!(overloaded.in_flight, 1);
+        assert_eq!(overloaded.queue_depth, 0);
+        assert_eq!(overloaded.estimated_wait_seconds, 60.0);
+    }
+
+    #[tokio::test]
+    async fn test_queue_policy_waits_for_permit() {
+        let limiter = Arc::new(ProofLimiter::new(1, OverloadPolicy::Queue));
+        let running = limiter.acquire(None).await.unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(None).await.is_ok() })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.queue_depth(), 1);
+
+        drop(running);
+        assert!(waiter.await.unwrap());
+        assert_eq!(limiter.queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_leaves_the_queue() {
+        let limiter = Arc::new(ProofLimiter::new(1, OverloadPolicy::Queue));
+        let _running = limiter.acquire(None).await.unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(None).await.is_ok() })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.queue_depth(), 1);
+
+        waiter.abort();
+        assert!(waiter.await.unwrap_err().is_cancelled());
+        assert_eq!(limiter.queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout_reports_overload() {
+        let limiter = ProofLimiter::new(1, OverloadPolicy::Queue)
+            .with_queue_timeout(Duration::from_millis(10));
+        let _running = limiter.acquire(None).await.unwrap();
+
+        let overloaded = limiter.acquire(None).await.unwrap_err();
+        assert!(overloaded.timed_out);
+        assert_eq!(overloaded.in_flight, 1);
+        assert_eq!(limiter.queue_depth(), 0);
+    }
+}
@@ -1,44 +1,189 @@
 mod abi;
+mod associated;
+mod auth;
 mod blockchain;
 mod calldata;
 mod commitment;
+mod error;
+mod estimate;
+mod events;
+mod felt_conv;
+mod jobs;
+mod limiter;
 mod merkle;
+mod metrics;
+mod nullifiers;
+mod persistence;
+mod pools;
 mod proof;
+mod proof_cache;
+mod proof_output;
+mod rate_limit;
+mod rpc_subscription;
+mod screening;
+mod shutdown;
+mod snarkjs_worker;
 mod syncer;
+mod tick_math;
+mod util;
+mod validation;
 
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
     http::StatusCode,
-    response::IntoResponse,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
-use blockchain::BlockchainClient;
+use associated::AssociatedSet;
+use auth::ApiKeys;
+use blockchain::{BlockchainClient, RpcEndpoints};
 use calldata::{
     build_approve_calldata, build_burn_liquidity_calldata, build_deposit_calldata,
-    build_initialize_calldata, build_mint_liquidity_calldata, build_swap_calldata,
-    build_withdraw_calldata, u256_to_low_high,
+    build_initialize_calldata, build_mint_liquidity_calldata, build_multicall_calldata, build_swap_calldata,
+    build_withdraw_calldata, check_liquidity_public_inputs, check_swap_public_inputs, check_withdraw_public_inputs,
+    u256_to_low_high,
 };
 use num_bigint::BigUint;
+use std::convert::Infallible;
 use std::str::FromStr;
-use commitment::{generate_commitment, generate_note};
-use merkle::{MerkleProof, MerkleTree, TREE_DEPTH};
+use commitment::{
+    decrypt_note, derive_note, encrypt_note, format_token_amount, generate_commitment, generate_commitment_parts,
+    generate_commitment_versioned, generate_note, generate_position_commitment, parse_token_amount, public_nullifier,
+    NoteData, CURRENT_SCHEME_VERSION, MIN_NOTE_SEED_LEN, SUPPORTED_SCHEME_VERSIONS,
+};
+use error::ApiError;
+use estimate::{ProofTimeHistory, SwapPriceCheck, ZeroPricePolicy};
+use events::{DepositEvent, DepositFeed, LAGGED_CLOSE_REASON};
+use jobs::{JobState, ProofJobs};
+use limiter::{OverloadPolicy, ProofLimiter};
+use metrics::Metrics;
+use rate_limit::RateLimiter;
+use merkle::{MerkleProof, MerkleTree, PathEncoding, RootFormat, TREE_DEPTH, TREE_SNAPSHOT_FILE};
+use nullifiers::{LocalStatus, NullifierSet};
+use proof_cache::ProofCache;
+use screening::{Screener, ScreeningRequest};
+use shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use syncer::Syncer;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use syncer::{ResyncSignal, SyncStatus, Syncer};
+use tokio_stream::{wrappers::UnboundedReceiverStream, StreamExt};
 use tower_http::cors::{Any, CorsLayer};
+use tracing::{debug, error, info, warn, Instrument};
+use validation::{validate_liquidity_amount, validate_swap_amounts, validate_tick_range};
 
 /// Application state with two Merkle trees and blockchain client
 #[derive(Clone)]
 struct AppState {
     /// Tree for deposit commitments (from on-chain events)
-    deposit_tree: Arc<Mutex<MerkleTree>>,
-    /// Tree for associated set (for compliance/subset proofs)
-    associated_tree: Arc<Mutex<MerkleTree>>,
+    deposit_tree: Arc<RwLock<MerkleTree>>,
+    /// Tree for associated set (for compliance/subset proofs), capped by ASSOCIATED_MAX_SIZE
+    associated_tree: Arc<RwLock<AssociatedSet>>,
     /// Blockchain client for reading on-chain state
     blockchain: Arc<BlockchainClient>,
     /// Zylith contract address
     zylith_address: String,
+    /// Recent swap proof timings (for proof time estimates)
+    proof_times: Arc<Mutex<ProofTimeHistory>>,
+    /// Live deposit feed for WebSocket subscribers
+    deposit_feed: Arc<DepositFeed>,
+    /// Limits concurrent proof generation (MAX_CONCURRENT_PROOFS / PROOF_OVERLOAD_POLICY)
+    proof_limiter: Arc<ProofLimiter>,
+    /// Times the watchdog had to respawn the syncer task
+    syncer_restarts: Arc<AtomicU64>,
+    /// Syncer health (halted by strict root verification)
+    sync_status: Arc<SyncStatus>,
+    /// Compliance screening webhook (SCREENING_WEBHOOK_URL)
+    screener: Arc<Screener>,
+    /// Spent nullifiers seen by the syncer
+    nullifiers: Arc<NullifierSet>,
+    /// Background proof jobs (POST /api/proof/swap?async=true)
+    proof_jobs: Arc<ProofJobs>,
+    /// Recent swap proofs by canonical input, served again to identical retries (PROOF_CACHE_SIZE)
+    proof_cache: Arc<ProofCache>,
+    /// Prometheus metrics (GET /metrics)
+    metrics: Arc<Metrics>,
+    /// Rewind requests for the running syncer (POST /deposit/resync)
+    resync: Arc<ResyncSignal>,
+    /// Bearer keys for proof generation, associated inserts and tree imports (ASP_API_KEYS)
+    api_keys: Arc<ApiKeys>,
+    /// Per-IP limit on proof generation requests (PROOF_RATE_LIMIT_PER_MIN)
+    rate_limiter: Arc<RateLimiter>,
+    /// Every synced pool contract, primary first (CONTRACT_ADDRESSES); the fields above that a
+    /// pool owns are the primary pool's
+    pools: Arc<Vec<SyncedPool>>,
+}
+
+/// Deposit tree of one pool contract and the state its syncer shares with the API
+#[derive(Clone)]
+struct SyncedPool {
+    address: String,
+    /// Canonical address (pools::pool_key) matched against ?pool=
+    key: String,
+    deposit_tree: Arc<RwLock<MerkleTree>>,
+    blockchain: Arc<BlockchainClient>,
+    deposit_feed: Arc<DepositFeed>,
+    sync_status: Arc<SyncStatus>,
+    nullifiers: Arc<NullifierSet>,
+    metrics: Arc<Metrics>,
+    resync: Arc<ResyncSignal>,
+}
+
+impl AppState {
+    /// This state with the tree, RPC client and syncer state of `pool` in place of the primary's
+    fn for_pool(&self, pool: &SyncedPool) -> AppState {
+        AppState {
+            deposit_tree: pool.deposit_tree.clone(),
+            blockchain: pool.blockchain.clone(),
+            zylith_address: pool.address.clone(),
+            deposit_feed: pool.deposit_feed.clone(),
+            sync_status: pool.sync_status.clone(),
+            nullifiers: pool.nullifiers.clone(),
+            metrics: pool.metrics.clone(),
+            resync: pool.resync.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+/// ?pool=0x... on the read endpoints
+#[derive(Deserialize)]
+struct PoolSelector {
+    pool: Option<String>,
+}
+
+/// App state of the pool picked by ?pool=, or of the primary pool without it
+struct PoolState(AppState);
+
+#[axum::async_trait]
+impl axum::extract::FromRequestParts<AppState> for PoolState {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(selector) = Query::<PoolSelector>::try_from_uri(&parts.uri)
+            .map_err(|e| ApiError::bad_request("invalid_query", e.body_text()))?;
+        let Some(address) = selector.pool else {
+            return Ok(PoolState(state.clone()));
+        };
+        let key = pools::pool_key(&address).map_err(|e| ApiError::bad_request("invalid_pool", e))?;
+        match state.pools.iter().find(|pool| pool.key == key) {
+            Some(pool) => Ok(PoolState(state.for_pool(pool))),
+            None => Err(ApiError::not_found("unknown_pool", format!("Pool {} is not synced by this ASP", address))
+                .with_details(serde_json::json!({
+                    "pools": state.pools.iter().map(|pool| pool.address.as_str()).collect::<Vec<_>>()
+                }))),
+        }
+    }
 }
 
 /// Response for tree info
@@ -53,55 +198,194 @@ struct TreeInfo {
 #[derive(Deserialize)]
 struct InsertRequest {
     commitment: String,
+    /// Depositor address, forwarded to the screening webhook when known
+    address: Option<String>,
 }
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    // RUST_LOG sets verbosity (default info); LOG_FORMAT=json writes one JSON object per line
+    let log_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    if std::env::var("LOG_FORMAT").is_ok_and(|format| format == "json") {
+        tracing_subscriber::fmt().with_env_filter(log_filter).json().init();
+    } else {
+        tracing_subscriber::fmt().with_env_filter(log_filter).init();
+    }
 
     // Get configuration from environment
-    let rpc_url = std::env::var("RPC_URL")
-        .unwrap_or_else(|_| "https://api.cartridge.gg/x/starknet/sepolia".to_string());
-    let contract_address = std::env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| {
-        "0x00c692a0a7b34ffe8c5484e6db9488dc881ceae9c9b05d67de21387ea9f3edd6".to_string()
-    });
+    let rpc_urls = RpcEndpoints::urls_from_env("https://api.cartridge.gg/x/starknet/sepolia");
+    let contract_addresses =
+        pools::contract_addresses_from_env("0x00c692a0a7b34ffe8c5484e6db9488dc881ceae9c9b05d67de21387ea9f3edd6")
+            .unwrap_or_else(|e| panic!("Contract configuration error: {}", e));
+    let contract_address = contract_addresses[0].clone();
 
     // Validate ABIs on startup
     let zylith_abi = abi::get_zylith_abi();
     abi::validate_zylith_abi(zylith_abi)
-        .expect("Zylith ABI validation failed");
+        .unwrap_or_else(|e| panic!("Zylith ABI validation failed: {}", e));
     
     let erc20_abi = abi::get_erc20_abi();
     abi::validate_erc20_abi(erc20_abi)
-        .expect("ERC20 ABI validation failed");
+        .unwrap_or_else(|e| panic!("ERC20 ABI validation failed: {}", e));
 
     println!("✓ ABIs validated successfully");
 
-    // Initialize blockchain client
-    let blockchain = Arc::new(
-        BlockchainClient::new(&rpc_url, &contract_address)
-            .expect("Failed to initialize blockchain client"),
-    );
+    proof::check_paths().unwrap_or_else(|e| panic!("Prover configuration error: {}", e));
+
+    // RPC endpoints (and failover state), shared by every blockchain client and syncer
+    let rpc = Arc::new(RpcEndpoints::new(&rpc_urls).unwrap_or_else(|e| panic!("RPC configuration error: {}", e)));
+
+    // Initialize the trees (each deposit tree from its snapshot when there is one)
+    let tree_depth = merkle::tree_depth_from_env().unwrap_or_else(|e| panic!("Invalid tree depth: {}", e));
+    println!("[ASP] 🌳 Merkle tree depth: {} ({} leaves)", tree_depth, 1u64 << tree_depth);
+    if tree_depth != TREE_DEPTH {
+        println!(
+            "[ASP] ⚠️  MERKLE_TREE_DEPTH={} differs from the default {}: roots and proofs only match a contract and circuits built with depth {}",
+            tree_depth, TREE_DEPTH, tree_depth
+        );
+    }
+    let persistence = persistence::Persistence::from_env();
+    let associated_tree = Arc::new(RwLock::new(AssociatedSet::from_env(tree_depth)));
+    let syncer_restarts = Arc::new(AtomicU64::new(0));
+    let screener = Arc::new(Screener::from_env());
+    let shutdown = Arc::new(Shutdown::default());
+
+    // One deposit tree and one supervised syncer per pool contract; the primary pool keeps the
+    // default state file names, the others get theirs suffixed with the address
+    let mut synced_pools = Vec::new();
+    let mut syncer_tasks = Vec::new();
+    for (i, address) in contract_addresses.iter().enumerate() {
+        let key = pools::pool_key(address).unwrap_or_else(|e| panic!("Contract configuration error: {}", e));
+        let (state_file, snapshot_file) = if i == 0 {
+            (syncer::STATE_FILE.to_string(), TREE_SNAPSHOT_FILE.to_string())
+        } else {
+            (pools::pool_file(syncer::STATE_FILE, &key), pools::pool_file(TREE_SNAPSHOT_FILE, &key))
+        };
+        let blockchain = Arc::new(
+            BlockchainClient::new(rpc.clone(), address).expect("Failed to initialize blockchain client"),
+        );
+        check_deployed_class(&blockchain, abi::ClassHashCheck::from_env()).await;
+
+        let (deposit_tree, snapshot_block) =
+            match MerkleTree::load_from_file(persistence, &snapshot_file, tree_depth) {
+                Ok(Some((tree, block))) => {
+                    println!(
+                        "[ASP] 🌳 Loaded deposit tree snapshot for {}: {} leaves up to block {}",
+                        address, tree.get_leaf_count(), block
+                    );
+                    check_loaded_root(&blockchain, &format!("0x{:x}", tree.get_root()), &snapshot_file, &state_file).await;
+                    (tree, Some(block))
+                }
+                Ok(None) => (MerkleTree::new(tree_depth), None),
+                Err(e) => {
+                    eprintln!("[ASP] ⚠️  Ignoring tree snapshot {}, syncing from scratch: {}", snapshot_file, e);
+                    (MerkleTree::new(tree_depth), None)
+                }
+            };
+        let pool = SyncedPool {
+            address: address.clone(),
+            key,
+            deposit_tree: Arc::new(RwLock::new(deposit_tree)),
+            blockchain,
+            deposit_feed: Arc::new(DepositFeed::from_env()),
+            sync_status: Arc::new(SyncStatus::default()),
+            nullifiers: Arc::new(NullifierSet::default()),
+            metrics: Arc::new(Metrics::default()),
+            resync: Arc::new(ResyncSignal::default()),
+        };
+
+        // Syncer for the deposit tree, with the blockchain client for root verification
+        let mut syncer = Syncer::new(rpc.clone(), address, pool.deposit_tree.clone(), tree_depth)
+            .with_blockchain_client(pool.blockchain.clone())
+            .with_deposit_feed(pool.deposit_feed.clone())
+            .with_status(pool.sync_status.clone())
+            .with_screener(screener.clone())
+            .with_nullifiers(pool.nullifiers.clone())
+            .with_metrics(pool.metrics.clone())
+            .with_shutdown(shutdown.clone())
+            .with_resync(pool.resync.clone())
+            .with_files(state_file, snapshot_file);
+        if let Some(block) = snapshot_block {
+            syncer = syncer.with_snapshot(block);
+        }
 
-    // Initialize both trees
-    let deposit_tree = Arc::new(Mutex::new(MerkleTree::new(TREE_DEPTH)));
-    let associated_tree = Arc::new(Mutex::new(MerkleTree::new(TREE_DEPTH)));
+        // Run syncer in background, supervised so a panic doesn't silently stop syncing
+        syncer_tasks.push(tokio::spawn(Arc::new(syncer).supervise(syncer_restarts.clone())));
+        synced_pools.push(pool);
+    }
+    if synced_pools.len() > 1 {
+        println!("[ASP] 🏊 Syncing {} pools (primary {})", synced_pools.len(), contract_address);
+    }
+    let primary = synced_pools[0].clone();
+
+    let api_keys = Arc::new(ApiKeys::from_env());
+    let rate_limiter = Arc::new(RateLimiter::from_env());
+    if api_keys.enabled() {
+        println!("[ASP] 🔑 API key auth enabled ({} key(s)) for proof generation, associated inserts, tree imports and resyncs", api_keys.key_count());
+    }
 
     let state = AppState {
-        deposit_tree: deposit_tree.clone(),
+        deposit_tree: primary.deposit_tree,
         associated_tree: associated_tree.clone(),
-        blockchain: blockchain.clone(),
+        blockchain: primary.blockchain,
         zylith_address: contract_address.clone(),
+        proof_times: Arc::new(Mutex::new(ProofTimeHistory::new())),
+        deposit_feed: primary.deposit_feed,
+        proof_limiter: Arc::new(ProofLimiter::from_env()),
+        syncer_restarts: syncer_restarts.clone(),
+        sync_status: primary.sync_status,
+        screener: screener.clone(),
+        nullifiers: primary.nullifiers,
+        proof_jobs: Arc::new(ProofJobs::default()),
+        proof_cache: Arc::new(ProofCache::from_env()),
+        metrics: primary.metrics,
+        resync: primary.resync,
+        api_keys,
+        rate_limiter: rate_limiter.clone(),
+        pools: Arc::new(synced_pools),
     };
 
-    // Initialize Syncer for deposit tree with blockchain client for root verification
-    let syncer = Syncer::new(&rpc_url, &contract_address, deposit_tree)
-        .with_blockchain_client(blockchain.clone());
-    
-    // Run syncer in background
-    tokio::spawn(async move {
-        syncer.run().await;
-    });
+    // Prune retained proof runs (only when PROOF_OUTPUT_DIR is set)
+    let proof_output = proof_output::ProofOutput::from_env();
+    if let Some(dir) = proof_output.root() {
+        println!("[ASP] 💾 Keeping proof runs in {:?} for {}h", dir, proof_output.retention.as_secs() / 3600);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                let removed = proof_output.prune();
+                if removed > 0 {
+                    println!("[ASP] 🧹 Removed {} expired proof run(s)", removed);
+                }
+            }
+        });
+    }
+
+    // Sweep temp files left by runs that never finished, then start the snarkjs worker now so
+    // the first proof doesn't pay for loading snarkjs
+    if let Ok(circuits_dir) = proof::circuits_dir().canonicalize() {
+        let swept = proof_output::sweep_stale(&circuits_dir, proof_output::STALE_TEMP_AGE);
+        if swept > 0 {
+            println!("[ASP] 🧹 Removed {} leftover proof temp file(s)", swept);
+        }
+        snarkjs_worker::start(&circuits_dir).await;
+    }
+
+    // Forget rate limit buckets of clients that went quiet
+    if rate_limiter.enabled() {
+        println!("[ASP] 🚦 Proof requests limited to {}/min per IP", rate_limiter.per_minute());
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(rate_limit::EVICTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                let evicted = rate_limiter.evict_idle(std::time::Instant::now());
+                if evicted > 0 {
+                    debug!("[ASP] 🚦 Evicted {} idle rate limit bucket(s), {} tracked", evicted, rate_limiter.tracked_clients());
+                }
+            }
+        });
+    }
 
     // Configure CORS
     let cors = CorsLayer::new()
@@ -112,11 +396,26 @@ async fn main() {
     let app = Router::new()
         // Deposit tree endpoints
         .route("/deposit/proof/:index", get(get_deposit_proof))
+        .route("/deposit/proof/:index/validity", get(get_deposit_proof_validity))
+        .route("/deposit/proofs", post(get_deposit_proofs))
+        .route("/deposit/verify", post(verify_deposit_proof))
+        .route("/deposit/proof/by-commitment/:commitment", get(get_deposit_proof_by_commitment))
         .route("/deposit/root", get(get_deposit_root))
+        .route("/deposit/roots", get(get_recent_roots))
         .route("/deposit/info", get(get_deposit_info))
+        .route("/deposit/next-index", get(get_deposit_next_index))
         .route("/deposit/index/:commitment", get(get_deposit_index))
+        .route("/deposit/find-note", post(find_deposit_by_note))
         .route("/deposit/resync", post(force_resync))
+        .route("/deposit/export", get(export_deposit_tree))
+        .route(
+            "/deposit/import",
+            post(import_deposit_tree).layer(axum::extract::DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
         .route("/deposit/list", get(list_deposits))
+        .route("/deposit/ws", get(deposit_ws))
+        .route("/deposit/ws/stats", get(deposit_ws_stats))
+        .route("/deposit/events", get(deposit_events))
         // Associated set tree endpoints
         .route("/associated/proof/:index", get(get_associated_proof))
         .route("/associated/root", get(get_associated_root))
@@ -125,25 +424,53 @@ async fn main() {
         // Legacy endpoints (for backwards compatibility)
         .route("/proof/:index", get(get_deposit_proof))
         .route("/root", get(get_deposit_root))
+        // ABI endpoints
+        .route("/api/abi/functions", get(get_abi_functions))
+        .route("/api/calldata/decode", post(decode_calldata_endpoint))
         // Blockchain read endpoints
+        .route("/api/pools", get(list_pools))
         .route("/api/pool/root", get(get_pool_root))
         .route("/api/pool/info", get(get_pool_info))
+        .route("/api/nullifier/derive", get(derive_nullifier))
         .route("/api/nullifier/:nullifier", get(check_nullifier))
+        .route("/api/nullifier/:nullifier/local", get(check_nullifier_local))
+        .route("/api/note/status", post(get_note_status))
+        .route("/api/note/encrypt", post(encrypt_note_endpoint))
+        .route("/api/note/decrypt", post(decrypt_note_endpoint))
         .route("/api/token/:address/balance/:owner", get(get_token_balance))
         .route("/api/token/:address/allowance/:owner/:spender", get(get_token_allowance))
         .route("/api/pool/initialized", get(check_pool_initialized))
+        .route("/api/tx/:tx_hash/revert-reason", get(get_tx_revert_reason))
+        .route("/api/tx/:tx_hash/status", get(get_tx_status))
         // Transaction preparation endpoints
+        .route("/api/commitment/parts", post(commitment_parts_endpoint))
+        .route("/api/commitment/verify", post(verify_commitment_endpoint))
         .route("/api/deposit/prepare", post(prepare_deposit))
         .route("/api/swap/prepare", post(prepare_swap))
+        .route("/api/swap/calldata", post(build_swap_calldata_endpoint))
         .route("/api/withdraw/prepare", post(prepare_withdraw))
+        .route("/api/withdraw/calldata", post(build_withdraw_calldata_endpoint))
         .route("/api/liquidity/mint/prepare", post(prepare_mint_liquidity))
         .route("/api/liquidity/burn/prepare", post(prepare_burn_liquidity))
         .route("/api/initialize/prepare", post(prepare_initialize))
         // ZK Proof generation endpoints
         .route("/api/proof/swap", post(generate_swap_proof_endpoint))
+        .route("/api/proof/swap/stream", post(generate_swap_proof_stream))
+        .route("/api/proof/status/:job_id", get(get_proof_job_status))
         .route("/api/proof/lp-mint", post(generate_lp_proof_endpoint))
+        .route("/api/proof/withdraw", post(generate_withdraw_proof_endpoint))
+        .route("/api/proof/checksum", post(proof_checksum_endpoint))
+        .route("/api/proof/estimate", post(estimate_proof_time))
+        // Alias kept for clients of the original path; /api/proof/estimate is the canonical one
+        .route("/api/estimate/proof-time", post(estimate_proof_time))
         // Health check
         .route("/health", get(health_check))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+        .route("/metrics", get(get_metrics))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit_proofs))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), require_api_key))
+        .layer(axum::middleware::from_fn(with_request_span))
         .layer(cors)
         .with_state(state);
 
@@ -152,80 +479,593 @@ async fn main() {
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     println!("ASP Server running on {}", addr);
-    println!("Zylith Contract: {}", contract_address);
-    println!("RPC URL: {}", rpc_url);
+    println!("Zylith Contract: {}", contract_addresses.join(", "));
+    println!("RPC URLs: {}", rpc.urls().collect::<Vec<_>>().join(", "));
     println!("\nEndpoints:");
-    println!("  GET  /deposit/proof/:index  - Get Merkle proof for deposit");
-    println!("  GET  /deposit/root          - Get current deposit tree root");
+    println!("  GET  /deposit/proof/:index  - Get Merkle proof for deposit (?root=, ?commitment= to check the leaf, ?path_encoding=hex|decimal, ?empty_slot=true, ?verify=true)");
+    println!("  GET  /deposit/proof/:index/validity - Check if a cached proof's root is still accepted (?root=)");
+    println!("  POST /deposit/proofs        - Merkle proofs for up to 256 indices in one request (?path_encoding=)");
+    println!("  POST /deposit/verify        - Verify a cached Merkle proof against the current/known roots");
+    println!("  GET  /deposit/proof/by-commitment/:commitment - Get Merkle proof for a commitment");
+    println!("  GET  /deposit/root          - Get current deposit tree root (?format=hex|hex_padded|decimal, ?block=)");
+    println!("  GET  /deposit/roots         - Most recent deposit roots with their leaf counts (?limit= up to 1000, ?format=)");
     println!("  GET  /deposit/info          - Get deposit tree info");
-    println!("  GET  /deposit/index/:commitment - Get leaf index for commitment");
+    println!("  GET  /deposit/list          - List deposits (?offset=, ?limit= up to 1000)");
+    println!("  GET  /deposit/next-index    - Leaf index the next deposit will get (advisory)");
+    println!("  GET  /deposit/index/:commitment - Get leaf index for commitment (202 = on-chain, pending sync; 404 = never deposited)");
+    println!("  POST /deposit/find-note     - Find leaf index for a note (optional scheme_version)");
     println!("  POST /deposit/resync        - Force re-sync from specific block");
+    println!("  GET  /deposit/export        - Serialized deposit tree, root, leaf count and spent nullifiers");
+    println!("  POST /deposit/import        - Replace the deposit tree with an exported one (needs ASP_API_KEYS)");
+    println!("  GET  /deposit/ws            - WebSocket feed of new deposits");
+    println!("  GET  /deposit/ws/stats      - Deposit feed subscribers and dropped messages");
+    println!("  GET  /deposit/events        - SSE feed of new deposits, replaying recent ones (?since=, Last-Event-ID)");
     println!("  GET  /associated/proof/:index - Get Merkle proof for associated set");
-    println!("  GET  /associated/root       - Get current associated set root");
+    println!("  GET  /associated/root       - Get current associated set root (?format=hex|hex_padded|decimal)");
     println!("  GET  /associated/info       - Get associated set tree info");
     println!("  POST /associated/insert     - Insert commitment into associated set");
-    println!("  GET  /api/pool/root         - Get Merkle root on-chain");
+    println!("  GET  /api/abi/functions     - List contract functions (?filter=private|all)");
+    println!("  POST /api/calldata/decode   - Decode a raw calldata array into named parameters");
+    println!("  GET  /api/pools             - Synced pool contracts; read endpoints take ?pool=<address> (default: primary)");
+    println!("  GET  /api/pool/root         - Get Merkle root on-chain (?format=hex|hex_padded|decimal)");
     println!("  GET  /api/pool/info         - Get pool info");
+    println!("  GET  /api/nullifier/derive?nullifier= - Public nullifier as used in proofs");
     println!("  GET  /api/nullifier/:nullifier - Check if nullifier is spent");
+    println!("  GET  /api/nullifier/:nullifier/local - Check spent status from synced events (RPC fallback)");
+    println!("  POST /api/note/status       - Check if a note exists and is spendable");
+    println!("  POST /api/note/encrypt      - Encrypt a note for backup with a password");
+    println!("  POST /api/note/decrypt      - Decrypt a note backup");
     println!("  GET  /api/token/:address/balance/:owner - Get token balance");
     println!("  GET  /api/token/:address/allowance/:owner/:spender - Get token allowance");
-    println!("  POST /api/deposit/prepare    - Prepare deposit transaction");
+    println!("  GET  /api/tx/:tx_hash/revert-reason - Get revert reason of a transaction");
+    println!("  GET  /api/tx/:tx_hash/status - Confirmation status (pending, accepted_l2, accepted_l1, rejected, reverted)");
+    println!("  POST /api/commitment/parts  - Commitment and its inner Poseidon(secret, nullifier)");
+    println!("  POST /api/commitment/verify - Check that a note matches a commitment");
+    println!("  POST /api/deposit/prepare    - Prepare deposit transaction (?multicall=true: one __execute__)");
     println!("  POST /api/swap/prepare      - Prepare swap transaction");
-    println!("  POST /api/withdraw/prepare  - Prepare withdraw transaction");
+    println!("  POST /api/swap/calldata     - Build private_swap from a swap proof");
+    println!("  POST /api/withdraw/prepare  - Prepare withdraw: Merkle proof, ZK proof and private_withdraw calldata");
+    println!("  POST /api/withdraw/calldata - Build private_withdraw from a withdraw proof");
     println!("  POST /api/liquidity/mint/prepare - Prepare mint liquidity transaction");
     println!("  POST /api/liquidity/burn/prepare - Prepare burn liquidity transaction");
+    println!("  POST /api/proof/swap        - Generate swap proof (?async=true returns a job_id)");
+    println!("  POST /api/proof/swap/stream - Generate swap proof with SSE progress (same body as /api/proof/swap)");
+    println!("  GET  /api/proof/status/:job_id - Status and result of an async proof job");
+    println!("  POST /api/proof/withdraw    - Generate withdraw proof and calldata");
+    println!("  POST /api/proof/checksum    - Recompute a proof's integrity checksum");
+    println!("  POST /api/proof/estimate    - Estimate swap proof time and whether it would be rejected");
+    println!("  POST /api/estimate/proof-time - Alias of /api/proof/estimate");
     println!("  GET  /health                - Health check");
+    println!("  GET  /health/live           - Liveness probe (process is serving)");
+    println!("  GET  /health/ready          - Readiness probe (RPC reachable, syncer within HEALTH_MAX_SYNC_LAG blocks)");
+    println!("  GET  /metrics               - Prometheus metrics (sync lag, proof counts and durations)");
+
+    // On SIGTERM/Ctrl-C: stop the syncer right away (it saves its cursor and tree snapshot)
+    // and let in-flight requests finish
+    let server_shutdown = shutdown.clone();
+    // Connection info gives the client IP for the proof rate limit
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown::signal().await;
+            info!("[ASP] 🛑 Shutdown requested, draining requests and stopping the syncer");
+            server_shutdown.trigger();
+        })
+        .await
+        .unwrap();
+
+    let syncers_stopped = async {
+        for task in syncer_tasks {
+            let _ = task.await;
+        }
+    };
+    match tokio::time::timeout(std::time::Duration::from_secs(SHUTDOWN_SYNCER_GRACE_SECS), syncers_stopped).await {
+        Ok(_) => info!("[ASP] 👋 Syncer state saved, exiting"),
+        Err(_) => warn!("[ASP] ⚠️  Syncer did not stop within {}s, exiting anyway", SHUTDOWN_SYNCER_GRACE_SECS),
+    }
+}
+
+/// How long main waits for the syncer to save its state after the server has stopped
+const SHUTDOWN_SYNCER_GRACE_SECS: u64 = 10;
+
+/// Cross-check a deposit tree loaded from disk against the contract
+/// A root the contract has seen is just behind (the syncer catches up); anything else is a bad snapshot
+async fn check_loaded_root(blockchain: &BlockchainClient, root: &str, snapshot_file: &str, state_file: &str) {
+    let onchain_root = match blockchain.get_merkle_root().await {
+        Ok(onchain_root) => onchain_root,
+        Err(e) => {
+            eprintln!("[ASP] ⚠️  Could not cross-check the loaded tree root: {}", e);
+            return;
+        }
+    };
+    if same_felt(&onchain_root, root) {
+        println!("[ASP] ✓ Loaded tree root matches on-chain root {}", root);
+        return;
+    }
+    match blockchain.is_root_known(root).await {
+        Ok(true) => println!("[ASP] 🌳 Loaded tree root {} is behind on-chain root {}, catching up", root, onchain_root),
+        Ok(false) => eprintln!(
+            "[ASP] ⚠️  Loaded tree root {} does not match on-chain root {} and is not a known root. \
+             Delete {} and {} to rebuild from events",
+            root, onchain_root, snapshot_file, state_file
+        ),
+        Err(e) => eprintln!(
+            "[ASP] ⚠️  Loaded tree root {} does not match on-chain root {} ({})",
+            root, onchain_root, e
+        ),
+    }
+}
+
+/// Compare the deployed contract's class hash with the one the embedded ABI belongs to
+/// A mismatch means calldata may be built from stale bindings (contract upgraded)
+async fn check_deployed_class(blockchain: &BlockchainClient, mode: abi::ClassHashCheck) {
+    if mode == abi::ClassHashCheck::Off {
+        return;
+    }
+    let expected = abi::expected_class_hash();
+    let problem = match blockchain.get_class_hash().await {
+        Ok(deployed) if same_felt(&deployed, &expected) => {
+            println!("✓ Deployed class hash matches the embedded ABI ({})", deployed);
+            return;
+        }
+        Ok(deployed) => format!(
+            "Deployed class hash {} does not match the embedded ABI's class {}. \
+             The contract was probably upgraded: update src/abis/zylith-abi.json (and EXPECTED_CLASS_HASH)",
+            deployed, expected
+        ),
+        Err(e) => format!("Could not verify the deployed class hash: {}", e),
+    };
 
-    axum::serve(listener, app).await.unwrap();
+    if mode == abi::ClassHashCheck::Strict {
+        panic!("CLASS_HASH_CHECK=strict: {}", problem);
+    }
+    eprintln!("⚠️  ==================================================");
+    eprintln!("⚠️  {}", problem);
+    eprintln!("⚠️  ==================================================");
 }
 
 // ==================== Deposit Tree Endpoints ====================
 
+/// Query params for proof endpoints
+#[derive(Deserialize)]
+struct ProofQuery {
+    /// Optional historical root to build the proof against
+    root: Option<String>,
+    /// Optional commitment the client expects at this index
+    commitment: Option<String>,
+    /// Encoding of leaf/path/root: hex (default) or decimal
+    #[serde(default)]
+    path_encoding: PathEncoding,
+    /// For an empty slot in [leaf_count, 2^depth), return the zero leaf's proof instead of 404
+    #[serde(default)]
+    empty_slot: bool,
+    /// Re-hash the proof before responding and report the result as `self_verified`
+    #[serde(default)]
+    verify: bool,
+}
+
+/// Query params for proof endpoints that only support an output encoding
+#[derive(Deserialize)]
+struct EncodingQuery {
+    #[serde(default)]
+    path_encoding: PathEncoding,
+}
+
+/// Query params for root endpoints: ?format=hex (default) | hex_padded | decimal
+#[derive(Deserialize)]
+struct RootQuery {
+    #[serde(default)]
+    format: RootFormat,
+}
+
+/// Merkle proof plus a server-side check of the leaf against the client's commitment
+#[derive(Serialize)]
+struct ProofResponse {
+    #[serde(flatten)]
+    proof: MerkleProof,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_commitment_matches: Option<bool>,
+    /// Result of verify_proof on the served proof (?verify=true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    self_verified: Option<bool>,
+}
+
+impl ProofResponse {
+    /// `proof` comes straight from the tree (hex); it is re-encoded after the commitment check
+    fn new(proof: MerkleProof, expected_commitment: Option<&str>, encoding: PathEncoding) -> Self {
+        let expected_commitment_matches = expected_commitment.map(|c| {
+            let matches = same_felt(&proof.leaf, c);
+            if !matches {
+                println!("[ASP] ⚠️  Leaf {} does not match expected commitment {}", proof.leaf, c);
+            }
+            matches
+        });
+        // Tree output is always valid hex
+        let proof = proof.encoded(encoding).expect("tree proof is valid hex");
+        Self { proof, expected_commitment_matches, self_verified: None }
+    }
+
+    /// Self-check a hex proof from the tree before it is encoded for the response
+    fn new_checked(proof: MerkleProof, expected_commitment: Option<&str>, query: &ProofQuery) -> Self {
+        let self_verified = query.verify.then(|| {
+            let valid = merkle::verify_proof(&proof);
+            if !valid {
+                eprintln!("[ASP] 🚨 Served proof for leaf {} does not hash to root {}", proof.leaf, proof.root);
+            }
+            valid
+        });
+        Self { self_verified, ..Self::new(proof, expected_commitment, query.path_encoding) }
+    }
+}
+
+/// Compare two hex felts by value (ignores 0x prefix and leading zeros)
+fn same_felt(a: &str, b: &str) -> bool {
+    matches!((util::parse_felt(a), util::parse_felt(b)), (Ok(a), Ok(b)) if a == b)
+}
+
 async fn get_deposit_proof(
     Path(index): Path<u32>,
-    State(state): State<AppState>,
+    Query(query): Query<ProofQuery>,
+    PoolState(state): PoolState,
 ) -> impl IntoResponse {
-    println!("[ASP] 📥 GET /deposit/proof/{}", index);
-    println!("[ASP] 🔄 Processing proof request for index {}...", index);
+    info!("[ASP] 📥 GET /deposit/proof/{}", index);
+    info!("[ASP] 🔄 Processing proof request for index {}...", index);
     
-    let tree = state.deposit_tree.lock().unwrap();
+    let tree = state.deposit_tree.read().unwrap();
     let leaf_count = tree.get_leaf_count();
 
+    // Proof against a specific historical root
+    if let Some(root_str) = query.root.as_deref() {
+        let root = match util::parse_felt_biguint(root_str) {
+            Ok(r) => r,
+            Err(e) => {
+                return ApiError::bad_request("invalid_root", format!("Invalid root format: {}", e)).into_response();
+            }
+        };
+
+        let leaf_count_at_root = match tree.leaf_count_at_root(&root) {
+            Some(count) => count,
+            None => {
+                warn!("[ASP] ❌ Root {} is not in the tree history", root_str);
+                return ApiError::not_found("unknown_root", "Unknown root")
+                    .with_details(serde_json::json!({
+                        "root": root_str,
+                        "current_root": format!("0x{:x}", tree.get_root())
+                    }))
+                    .into_response();
+            }
+        };
+
+        return match tree.get_proof_at(index, leaf_count_at_root) {
+            Some(proof) => {
+                info!("[ASP] ✅ Historical proof generated for index {} at root {} ({} leaves)",
+                    index, proof.root, leaf_count_at_root);
+                Json(ProofResponse::new_checked(proof, query.commitment.as_deref(), &query)).into_response()
+            }
+            None => ApiError::not_found("leaf_not_at_root", "Leaf did not exist yet at the requested root")
+                .with_details(serde_json::json!({
+                    "index": index,
+                    "root": root_str,
+                    "leaf_count_at_root": leaf_count_at_root
+                }))
+                .into_response(),
+        };
+    }
+
     match tree.get_proof(index) {
         Some(proof) => {
-            println!("[ASP] ✅ Proof generated successfully for index {}", index);
-            println!("[ASP]    Root: {}", proof.root);
-            println!("[ASP]    Path length: {}", proof.path.len());
-            println!("[ASP]    Leaf: {}", proof.leaf);
-            println!("[ASP] 📤 Sending proof response to client...");
-            println!("[ASP]    Response data: root={}, leaf={}, path_len={}, path_indices_len={}", 
+            info!("[ASP] ✅ Proof generated successfully for index {}", index);
+            info!("[ASP]    Root: {}", proof.root);
+            debug!("[ASP]    Path length: {}", proof.path.len());
+            info!("[ASP]    Leaf: {}", proof.leaf);
+            info!("[ASP] 📤 Sending proof response to client...");
+            info!("[ASP]    Response data: root={}, leaf={}, path_len={}, path_indices_len={}", 
                 proof.root, proof.leaf, proof.path.len(), proof.path_indices.len());
-            let response = Json(proof).into_response();
-            println!("[ASP] ✅ Proof response sent successfully (status 200)");
+            let response = Json(ProofResponse::new_checked(proof, query.commitment.as_deref(), &query)).into_response();
+            info!("[ASP] ✅ Proof response sent successfully (status 200)");
             response
         },
+        None if index as u64 >= tree.capacity() => {
+            warn!("[ASP] ❌ Index {} is beyond the tree capacity ({})", index, tree.capacity());
+            ApiError::bad_request("out_of_range", "Index out of range")
+                .with_details(serde_json::json!({
+                    "index": index,
+                    "capacity": tree.capacity()
+                }))
+                .into_response()
+        }
+        None if query.empty_slot => match tree.get_empty_slot_proof(index) {
+            Ok(proof) => {
+                info!("[ASP] ✅ Zero-leaf proof generated for empty slot {}", index);
+                Json(ProofResponse::new_checked(proof, None, &query)).into_response()
+            }
+            Err(e) => ApiError::not_found("slot_not_empty", e)
+                .with_details(serde_json::json!({ "index": index }))
+                .into_response(),
+        },
         None => {
-            println!("[ASP] ❌ Proof generation failed - leaf not found at index {}", index);
-            println!("[ASP]    Tree has {} leaves (indices 0-{})", leaf_count, leaf_count.saturating_sub(1));
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Leaf not found at index",
-                "index": index,
-                "tree_leaf_count": leaf_count,
-                "valid_indices": if leaf_count > 0 { format!("0-{}", leaf_count - 1) } else { "none".to_string() }
-            }))).into_response()
+            warn!("[ASP] ❌ Proof generation failed - leaf not found at index {}", index);
+            info!("[ASP]    Tree has {} leaves (indices 0-{})", leaf_count, leaf_count.saturating_sub(1));
+            ApiError::not_found("empty_slot", "Leaf not found at index")
+                .with_details(serde_json::json!({
+                    "index": index,
+                    "tree_leaf_count": leaf_count,
+                    "valid_indices": if leaf_count > 0 { format!("0-{}", leaf_count - 1) } else { "none".to_string() }
+                }))
+                .into_response()
         },
     }
 }
 
-async fn get_deposit_root(State(state): State<AppState>) -> impl IntoResponse {
-    let tree = state.deposit_tree.lock().unwrap();
-    let root = tree.get_root();
-    Json(format!("0x{:x}", root))
+/// Most indices accepted by POST /deposit/proofs
+const MAX_BATCH_PROOFS: usize = 256;
+
+#[derive(Deserialize)]
+struct BatchProofRequest {
+    indices: Vec<u32>,
+}
+
+/// Proofs for many leaves under one tree lock (portfolio views), all against the same root
+/// `proof` is null for indices that have no leaf
+async fn get_deposit_proofs(
+    PoolState(state): PoolState,
+    Query(query): Query<EncodingQuery>,
+    Json(payload): Json<BatchProofRequest>,
+) -> impl IntoResponse {
+    if payload.indices.len() > MAX_BATCH_PROOFS {
+        return ApiError::bad_request(
+            "too_many_indices",
+            format!("At most {} indices per request, got {}", MAX_BATCH_PROOFS, payload.indices.len()),
+        )
+        .into_response();
+    }
+
+    let tree = state.deposit_tree.read().unwrap();
+    let proofs: Vec<_> = payload
+        .indices
+        .iter()
+        .map(|&index| {
+            serde_json::json!({
+                "index": index,
+                "proof": tree.get_proof(index).map(|proof| ProofResponse::new(proof, None, query.path_encoding)),
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "root": format!("0x{:x}", tree.get_root()),
+        "proofs": proofs,
+    }))
+    .into_response()
+}
+
+/// Check a client-supplied proof: it must hash to its root, and that root must still be accepted
+/// (the current root or one the contract still knows), so wallets catch stale proofs before paying gas
+async fn verify_deposit_proof(
+    PoolState(state): PoolState,
+    Json(proof): Json<MerkleProof>,
+) -> impl IntoResponse {
+    let bad_request = |error: String| ApiError::bad_request("invalid_proof", error).into_response();
+
+    let depth = state.deposit_tree.read().unwrap().depth;
+    if proof.path.len() != depth {
+        return bad_request(format!("path must have {} elements, got {}", depth, proof.path.len()));
+    }
+    let computed = match merkle::compute_root(&proof) {
+        Ok(computed) => computed,
+        Err(e) => return bad_request(e),
+    };
+    let root = match PathEncoding::Hex.decode(&proof.root) {
+        Ok(root) => root,
+        Err(e) => return bad_request(e),
+    };
+
+    let current_root = state.deposit_tree.read().unwrap().get_root();
+    let hashes_to_root = computed == root;
+    let is_current_root = root == current_root;
+    let is_root_known = match state.blockchain.is_root_known(&format!("0x{:x}", root)).await {
+        Ok(known) => Some(known),
+        Err(e) => {
+            eprintln!("[ASP] ⚠️  is_root_known failed while verifying a proof: {}", e);
+            None
+        }
+    };
+
+    Json(serde_json::json!({
+        "valid": hashes_to_root && (is_current_root || is_root_known == Some(true)),
+        "hashes_to_root": hashes_to_root,
+        "is_current_root": is_current_root,
+        "is_root_known": is_root_known,
+        "current_root": format!("0x{:x}", current_root),
+    }))
+    .into_response()
+}
+
+/// Query params for the proof validity check
+#[derive(Deserialize)]
+struct ValidityQuery {
+    /// Root the client's cached proof was built against (default: current root)
+    root: Option<String>,
+}
+
+/// Whether a cached proof for a leaf can still be submitted
+/// Cheap check: local tree history plus one `is_root_known` call
+async fn get_deposit_proof_validity(
+    Path(index): Path<u32>,
+    Query(query): Query<ValidityQuery>,
+    PoolState(state): PoolState,
+) -> impl IntoResponse {
+    println!("[ASP] 📥 GET /deposit/proof/{}/validity", index);
+
+    let (root, current_root, leaf_count, leaf_count_at_root) = {
+        let tree = state.deposit_tree.read().unwrap();
+        let current_root = tree.get_root();
+        let leaf_count = tree.get_leaf_count();
+        let root = match query.root.as_deref() {
+            Some(root_str) => match util::parse_felt_biguint(root_str) {
+                Ok(r) => r,
+                Err(e) => {
+                    return ApiError::bad_request("invalid_root", e).into_response();
+                }
+            },
+            None => current_root.clone(),
+        };
+        let leaf_count_at_root = tree.leaf_count_at_root(&root);
+        (root, current_root, leaf_count, leaf_count_at_root)
+    };
+
+    let root_hex = format!("0x{:x}", root);
+    let leaf_count_at_root = match leaf_count_at_root {
+        Some(count) => count,
+        None => {
+            return ApiError::not_found("unknown_root", "Unknown root")
+                .with_details(serde_json::json!({
+                    "root": root_hex,
+                    "current_root": format!("0x{:x}", current_root)
+                }))
+                .into_response();
+        }
+    };
+    if index >= leaf_count_at_root {
+        return ApiError::not_found("leaf_not_at_root", "Leaf did not exist yet at the requested root")
+            .with_details(serde_json::json!({
+                "index": index,
+                "root": root_hex,
+                "leaf_count_at_root": leaf_count_at_root
+            }))
+            .into_response();
+    }
+
+    let is_root_known = match state.blockchain.is_root_known(&root_hex).await {
+        Ok(known) => known,
+        Err(e) => {
+            return ApiError::rpc(format!("Failed to check root on-chain: {}", e)).into_response();
+        }
+    };
+    let leaves_added_since = leaf_count - leaf_count_at_root;
+    println!("[ASP] {} Root {} for index {}: known={}, {} leaves added since",
+        if is_root_known { "✅" } else { "⚠️ " }, root_hex, index, is_root_known, leaves_added_since);
+
+    Json(serde_json::json!({
+        "index": index,
+        "root": root_hex,
+        "is_root_known": is_root_known,
+        "leaves_added_since": leaves_added_since,
+        "current_root": format!("0x{:x}", current_root),
+        // Re-fetch GET /deposit/proof/:index once the contract no longer accepts the root
+        "must_refetch": !is_root_known,
+    })).into_response()
+}
+
+/// Merkle proof for the leaf holding a commitment (local tree only)
+async fn get_deposit_proof_by_commitment(
+    Path(commitment): Path<String>,
+    Query(query): Query<EncodingQuery>,
+    PoolState(state): PoolState,
+) -> impl IntoResponse {
+    println!("[ASP] 📥 GET /deposit/proof/by-commitment/{}", commitment);
+
+    let commitment_bigint = match util::parse_felt_biguint(&commitment) {
+        Ok(c) => c,
+        Err(e) => {
+            return ApiError::bad_request("invalid_commitment", e).into_response();
+        }
+    };
+
+    let tree = state.deposit_tree.read().unwrap();
+    let proof = tree
+        .find_commitment_index(&commitment_bigint)
+        .and_then(|index| tree.get_proof(index).map(|proof| (index, proof)));
+
+    match proof {
+        Some((index, proof)) => {
+            println!("[ASP] ✅ Proof generated for commitment at index {}", index);
+            Json(serde_json::json!({
+                "index": index,
+                "proof": ProofResponse::new(proof, Some(&commitment), query.path_encoding),
+            })).into_response()
+        }
+        None => ApiError::not_found("commitment_not_found", "Commitment not found in local tree")
+            .with_details(serde_json::json!({
+                "commitment": commitment,
+                "tree_leaf_count": tree.get_leaf_count()
+            }))
+            .into_response(),
+    }
+}
+
+/// Query for GET /deposit/root: ?format= as on the other root endpoints, plus ?block=
+#[derive(Deserialize)]
+struct DepositRootQuery {
+    #[serde(default)]
+    format: RootFormat,
+    /// Root as of the end of this block instead of the current one
+    block: Option<u64>,
+}
+
+async fn get_deposit_root(PoolState(state): PoolState, Query(query): Query<DepositRootQuery>) -> Response {
+    let Some(block) = query.block else {
+        let root = state.deposit_tree.read().unwrap().get_root();
+        return Json(query.format.format(&root)).into_response();
+    };
+
+    // Deposits in later blocks may not be in the tree yet, so their root isn't known
+    let last_synced_block = state.metrics.last_synced_block();
+    if block > last_synced_block {
+        return ApiError::conflict(
+            "block_not_synced",
+            format!("Block {} is past the last synced block {}", block, last_synced_block),
+        )
+        .with_details(serde_json::json!({ "last_synced_block": last_synced_block }))
+        .into_response();
+    }
+
+    let tree = state.deposit_tree.read().unwrap();
+    let Some((leaf_count, root)) = tree
+        .leaf_count_at_block(block)
+        .and_then(|leaf_count| tree.root_at(leaf_count).map(|root| (leaf_count, root)))
+    else {
+        return ApiError::not_found(
+            "block_unknown",
+            format!("The tree has no deposit blocks recorded that far back (block {})", block),
+        )
+        .into_response();
+    };
+    Json(serde_json::json!({
+        "root": query.format.format(&root),
+        "block": block,
+        "leaf_count": leaf_count,
+    }))
+    .into_response()
+}
+
+/// Roots returned by GET /deposit/roots when ?limit= is not given, and the most it accepts
+const RECENT_ROOTS_DEFAULT_LIMIT: usize = 32;
+const RECENT_ROOTS_MAX_LIMIT: usize = 1000;
+
+/// Query for GET /deposit/roots
+#[derive(Deserialize)]
+struct RecentRootsQuery {
+    limit: Option<usize>,
+    #[serde(default)]
+    format: RootFormat,
+}
+
+/// Most recent deposit roots, newest first, each with the leaf count it covers
+/// The contract keeps every root it has had (is_root_known), so a proof built against any of
+/// these (GET /deposit/proof/:index?root=) is still accepted after the tree moves on
+async fn get_recent_roots(PoolState(state): PoolState, Query(query): Query<RecentRootsQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(RECENT_ROOTS_DEFAULT_LIMIT).min(RECENT_ROOTS_MAX_LIMIT);
+    let tree = state.deposit_tree.read().unwrap();
+    let roots: Vec<serde_json::Value> = tree
+        .recent_roots(limit)
+        .map(|(root, leaf_count)| serde_json::json!({ "root": query.format.format(root), "leaf_count": leaf_count }))
+        .collect();
+    Json(serde_json::json!({ "roots": roots }))
 }
 
-async fn get_deposit_info(State(state): State<AppState>) -> impl IntoResponse {
+async fn get_deposit_info(PoolState(state): PoolState) -> impl IntoResponse {
     println!("[ASP] 📥 GET /deposit/info");
-    let tree = state.deposit_tree.lock().unwrap();
+    let tree = state.deposit_tree.read().unwrap();
     let leaf_count = tree.get_leaf_count();
     
     // Log sample commitments for debugging (first 5) - only when explicitly requested
@@ -247,93 +1087,220 @@ async fn get_deposit_info(State(state): State<AppState>) -> impl IntoResponse {
     })
 }
 
+/// Leaf index the next deposit will land at, read together with the root in one lock
+/// Advisory only: any deposit confirmed before the client's (including ones not synced yet) shifts it,
+/// so the real index must be taken from the Deposit event or /deposit/index/:commitment afterwards
+async fn get_deposit_next_index(PoolState(state): PoolState) -> impl IntoResponse {
+    println!("[ASP] 📥 GET /deposit/next-index");
+    let tree = state.deposit_tree.read().unwrap();
+    Json(serde_json::json!({
+        "next_index": tree.get_leaf_count(),
+        "current_root": format!("0x{:x}", tree.get_root()),
+    }))
+}
+
 /// Force re-sync from a specific block
 /// This will reset the syncer state and start syncing from the specified block
 /// Body: { "from_block": 4438440 } (optional, defaults to contract deployment block)
+/// Zylith contract deployment block, the default rewind point for /deposit/resync
+const DEFAULT_RESYNC_FROM_BLOCK: u64 = 4438440;
+/// How long /deposit/resync waits for the syncer to apply the rewind before answering 202
+const RESYNC_ACK_TIMEOUT_SECS: u64 = 30;
+
 async fn force_resync(
+    PoolState(state): PoolState,
     Json(payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
-    use std::fs;
-    
     println!("\n[ASP] ========================================");
     println!("[ASP] 🔄 POST /deposit/resync - Force re-sync requested");
     println!("[ASP] ========================================");
-    
+
+    // Clearing the live tree takes proofs offline until the syncer catches up: never left open
+    if !state.api_keys.enabled() {
+        println!("[ASP] ❌ Resync refused: ASP_API_KEYS is not set");
+        println!("[ASP] ========================================\n");
+        return ApiError::forbidden("resync_disabled", "Resync is disabled unless ASP_API_KEYS is set").into_response();
+    }
+
     let block_number = payload.get("from_block")
         .and_then(|v| v.as_u64())
-        .unwrap_or(4438440); // Default to contract deployment block
-    
-    println!("[ASP] 📋 Resetting sync state to block {}", block_number);
-    
-    let state = serde_json::json!({
-        "last_synced_block": block_number
-    });
-    
-    if let Ok(json) = serde_json::to_string(&state) {
-        if let Err(e) = fs::write("asp_state.json", json) {
-            println!("[ASP] ❌ Failed to write state: {}", e);
+        .unwrap_or(DEFAULT_RESYNC_FROM_BLOCK);
+
+    println!("[ASP] 📋 Asking the syncer to rebuild the tree from block {}", block_number);
+
+    // The syncer applies it at its next loop (it stops the current pass between event pages)
+    let applied = state.resync.request(block_number);
+    match tokio::time::timeout(std::time::Duration::from_secs(RESYNC_ACK_TIMEOUT_SECS), applied).await {
+        Ok(Ok(last_synced_block)) => {
+            println!("[ASP] ✅ Syncer rewound to block {}", last_synced_block);
             println!("[ASP] ========================================\n");
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write state: {}", e)).into_response();
+            Json(serde_json::json!({
+                "success": true,
+                "last_synced_block": last_synced_block,
+                "leaf_count": state.deposit_tree.read().unwrap().get_leaf_count(),
+                "message": format!("Re-sync started from block {}", last_synced_block)
+            })).into_response()
+        }
+        Ok(Err(_)) => {
+            println!("[ASP] ⚠️  Resync to block {} superseded by a newer request", block_number);
+            println!("[ASP] ========================================\n");
+            ApiError::conflict("superseded", "Superseded by a newer resync request")
+                .with_details(serde_json::json!({ "from_block": block_number }))
+                .into_response()
+        }
+        Err(_) => {
+            println!("[ASP] ⏳ Syncer hasn't applied the resync yet, it will on its next loop");
+            println!("[ASP] ========================================\n");
+            (StatusCode::ACCEPTED, Json(serde_json::json!({
+                "success": true,
+                "pending": true,
+                "from_block": block_number,
+                "message": "The syncer will rebuild the tree from this block on its next loop"
+            }))).into_response()
         }
-    } else {
-        println!("[ASP] ❌ Failed to serialize state");
-        println!("[ASP] ========================================\n");
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to serialize state").into_response();
     }
-    
-    println!("[ASP] ✅ State file updated successfully");
-    println!("[ASP] ⚠️  IMPORTANT: Restart the ASP server for changes to take effect");
-    println!("[ASP] ========================================\n");
-    
-    Json(serde_json::json!({
-        "success": true,
-        "message": format!("Re-sync will start from block {}", block_number),
-        "note": "Restart the ASP server for changes to take effect"
-    })).into_response()
 }
 
-async fn get_deposit_index(
-    Path(commitment): Path<String>,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    use num_bigint::BigUint;
-    use num_traits::Num;
+/// Largest accepted POST /deposit/import body (a tree with millions of leaves is a few hundred MB)
+const IMPORT_BODY_LIMIT: usize = 512 * 1024 * 1024;
 
-    // Parse commitment from hex string
-    let commitment_str = commitment.trim_start_matches("0x");
-    let commitment_bigint = match BigUint::from_str_radix(commitment_str, 16) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to parse commitment '{}': {:?}", commitment_str, e);
-            return (StatusCode::BAD_REQUEST, format!("Invalid commitment format: {}", e)).into_response()
-        }
-    };
+/// Serialized deposit tree, for backups and for seeding another ASP without replaying every event
+async fn export_deposit_tree(PoolState(state): PoolState) -> impl IntoResponse {
+    // Read before the tree: deposits past this block may already be in the tree, which is
+    // harmless since replaying an existing leaf on the importing side is a no-op
+    let last_synced_block = state.metrics.last_synced_block();
+    let tree = state.deposit_tree.read().unwrap();
+    println!("[ASP] 📤 GET /deposit/export - {} leaves up to block {}", tree.get_leaf_count(), last_synced_block);
+    Json(serde_json::json!({
+        "root": format!("0x{:x}", tree.get_root()),
+        "leaf_count": tree.get_leaf_count(),
+        "tree": tree.to_snapshot(last_synced_block),
+        "spent_nullifiers": state.nullifiers.to_hex(),
+    }))
+}
+
+/// Body of POST /deposit/import (the GET /deposit/export response)
+#[derive(Deserialize)]
+struct ImportTreeRequest {
+    root: String,
+    tree: merkle::TreeSnapshot,
+    #[serde(default)]
+    spent_nullifiers: Vec<String>,
+}
 
+/// Replace the deposit tree with an exported one and continue syncing after its block
+/// Needs ASP_API_KEYS; the root is recomputed from the leaves and must be known on-chain
+async fn import_deposit_tree(
+    PoolState(state): PoolState,
+    Json(payload): Json<ImportTreeRequest>,
+) -> impl IntoResponse {
     println!("\n[ASP] ========================================");
-    println!("[ASP] 🔍 GET /deposit/index/{}", commitment_str.chars().take(20).collect::<String>());
+    println!("[ASP] 📥 POST /deposit/import - Tree import requested");
     println!("[ASP] ========================================");
-    
-    // First, check local tree (fast path)
-    let (found_locally, leaf_count) = {
-        let tree = state.deposit_tree.lock().unwrap();
-        let leaf_count = tree.get_leaf_count();
-        let found = tree.find_commitment_index(&commitment_bigint).is_some();
-        (found, leaf_count)
+    let reject = |error: ApiError| {
+        println!("[ASP] ❌ Import rejected: {:?}", error);
+        println!("[ASP] ========================================\n");
+        error.into_response()
     };
-    
-    println!("[ASP] 📊 Local tree status: {} leaves, found locally: {}", leaf_count, found_locally);
-    
-    if found_locally {
-        let tree = state.deposit_tree.lock().unwrap();
-        if let Some(index) = tree.find_commitment_index(&commitment_bigint) {
-            println!("[ASP] ✅ Found commitment in local tree at index {}", index);
+
+    // Replacing the tree is never left open, unlike the other protected routes
+    if !state.api_keys.enabled() {
+        return reject(ApiError::forbidden("import_disabled", "Tree import is disabled unless ASP_API_KEYS is set"));
+    }
+
+    let depth = state.deposit_tree.read().unwrap().depth;
+    let last_synced_block = payload.tree.last_synced_block();
+    let tree = match MerkleTree::from_snapshot(&payload.tree, depth) {
+        Ok(t) => t,
+        Err(e) => return reject(ApiError::bad_request("invalid_snapshot", e)),
+    };
+    let root = match PathEncoding::Hex.decode(&payload.root) {
+        Ok(r) => r,
+        Err(e) => return reject(ApiError::bad_request("invalid_root", format!("Invalid root: {}", e))),
+    };
+    let recomputed = tree.recompute_root();
+    if recomputed != root || tree.get_root() != root {
+        return reject(ApiError::bad_request("root_mismatch", format!(
+            "Root mismatch: leaves hash to 0x{:x}, snapshot stores 0x{:x}, expected 0x{:x}",
+            recomputed, tree.get_root(), root
+        )));
+    }
+    if tree.get_leaf_count() > 0 {
+        match state.blockchain.is_root_known(&payload.root).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return reject(ApiError::bad_request("unknown_root", format!("Root {} is not known on-chain", payload.root)))
+            }
+            Err(e) => return reject(ApiError::rpc(format!("Could not check the root on-chain: {}", e))),
+        }
+    }
+
+    let leaf_count = tree.get_leaf_count();
+    println!("[ASP] 📋 Handing {} leaves to the syncer, continuing after block {}", leaf_count, last_synced_block);
+    let applied = state.resync.request_import(tree, last_synced_block);
+    match tokio::time::timeout(std::time::Duration::from_secs(RESYNC_ACK_TIMEOUT_SECS), applied).await {
+        Ok(Ok(block)) => {
+            state.nullifiers.extend_from_hex(&payload.spent_nullifiers);
+            println!("[ASP] ✅ Tree imported: {} leaves, root {}", leaf_count, payload.root);
             println!("[ASP] ========================================\n");
-            return Json(serde_json::json!({
-                "index": index,
-                "found": true,
-                "source": "local_tree"
-            })).into_response();
+            Json(serde_json::json!({
+                "success": true,
+                "root": payload.root,
+                "leaf_count": leaf_count,
+                "last_synced_block": block,
+            })).into_response()
+        }
+        Ok(Err(_)) => reject(ApiError::conflict("superseded", "Superseded by a newer resync or import request")),
+        Err(_) => {
+            // Still pending: the syncer applies it on its next loop
+            state.nullifiers.extend_from_hex(&payload.spent_nullifiers);
+            println!("[ASP] ⏳ Syncer hasn't applied the import yet, it will on its next loop");
+            println!("[ASP] ========================================\n");
+            (StatusCode::ACCEPTED, Json(serde_json::json!({
+                "success": true,
+                "pending": true,
+                "leaf_count": leaf_count,
+                "last_synced_block": last_synced_block,
+            }))).into_response()
+        }
+    }
+}
+
+async fn get_deposit_index(
+    Path(commitment): Path<String>,
+    PoolState(state): PoolState,
+) -> impl IntoResponse {
+    // Parse commitment from hex string
+    let commitment_bigint = match util::parse_felt_biguint(&commitment) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to parse commitment '{}': {}", commitment, e);
+            return ApiError::bad_request("invalid_commitment", format!("Invalid commitment format: {}", e)).into_response()
         }
+    };
+
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 🔍 GET /deposit/index/{}", commitment.trim_start_matches("0x").chars().take(20).collect::<String>());
+    println!("[ASP] ========================================");
+    
+    // First, check local tree (fast path, single lookup)
+    let (local_index, leaf_count) = {
+        let tree = state.deposit_tree.read().unwrap();
+        (tree.find_commitment_index(&commitment_bigint), tree.get_leaf_count())
+    };
+    
+    println!("[ASP] 📊 Local tree status: {} leaves, found locally: {}", leaf_count, local_index.is_some());
+    
+    if let Some(index) = local_index {
+        println!("[ASP] ✅ Found commitment in local tree at index {}", index);
+        println!("[ASP] ========================================\n");
+        return Json(serde_json::json!({
+            "index": index,
+            "found": true,
+            "synced": true,
+            "status": "synced",
+            "source": "local_tree"
+        })).into_response();
     }
     
     // Not found locally - search in contract events directly (fast lookup)
@@ -341,80 +1308,270 @@ async fn get_deposit_index(
     
     match state.blockchain.find_commitment_in_events(&format!("0x{:x}", commitment_bigint)).await {
         Ok(Some(index)) => {
-            println!("[ASP] ✅ Found commitment in events at index {}. Adding to local tree...", index);
-            
-            // Add to local tree for future queries
-            // Get zero_leaf first (before acquiring mutable lock)
-            let (current_count, zero_leaf) = {
-                let tree = state.deposit_tree.lock().unwrap();
-                (tree.get_leaf_count(), tree.zeros[0].clone())
-            };
-            
-            // Now acquire mutable lock and do all operations
-            let mut tree = state.deposit_tree.lock().unwrap();
-            
-            // Handle gaps if needed
-            if index > current_count {
-                let gaps = index - current_count;
-                println!("   Filling {} gap(s) before index {}", gaps, index);
-                for i in 0..gaps {
-                    tree.insert_at_index(current_count + i, zero_leaf.clone());
-                }
-            }
-            
-            // Insert the commitment
-            if index == tree.get_leaf_count() {
-                tree.insert(commitment_bigint.clone());
-            } else {
-                tree.insert_at_index(index, commitment_bigint.clone());
-            }
-            
+            // On-chain but not synced yet: the index is final, the Merkle proof isn't available yet.
+            // The syncer inserts it (keeping the tree consistent); clients retry later.
+            println!("[ASP] ⏳ Found commitment in events at index {} - pending sync ({} leaves synced)", index, leaf_count);
             println!("[ASP] ========================================\n");
-            Json(serde_json::json!({
+            (StatusCode::ACCEPTED, Json(serde_json::json!({
                 "index": index,
                 "found": true,
-                "source": "contract_events"
-            })).into_response()
+                "synced": false,
+                "status": "pending_sync",
+                "source": "contract_events",
+                "message": "Commitment is on-chain but not synced yet. Retry later for its Merkle proof.",
+                "tree_leaf_count": leaf_count
+            }))).into_response()
         },
         Ok(None) => {
             println!("[ASP] ❌ Commitment not found in contract events");
             println!("[ASP] 📋 This could mean:");
             println!("  - The commitment was never deposited");
             println!("  - The commitment format doesn't match (check BN254 vs Starknet Poseidon)");
-            println!("  - The syncer hasn't processed the event yet");
             println!("[ASP] ========================================\n");
-            Json(serde_json::json!({
+            ApiError::not_found(
+                "commitment_not_found",
+                "Commitment not found in contract events. It was never deposited (or the deposit transaction is not accepted yet).",
+            )
+            .with_details(serde_json::json!({
                 "found": false,
-                "message": "Commitment not found in contract events. It may not have been deposited yet.",
                 "tree_leaf_count": leaf_count
-            })).into_response()
+            }))
+            .into_response()
         },
         Err(e) => {
             eprintln!("[ASP] ❌ Error searching events: {}", e);
             println!("[ASP] ========================================\n");
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to search events: {}", e)).into_response()
+            ApiError::rpc(format!("Failed to search events: {}", e)).into_response()
+        }
+    }
+}
+
+/// WebSocket feed of deposits inserted by the syncer
+async fn deposit_ws(ws: WebSocketUpgrade, PoolState(state): PoolState) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_deposit_socket(socket, state.deposit_feed))
+}
+
+async fn handle_deposit_socket(mut socket: WebSocket, feed: Arc<DepositFeed>) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut receiver = feed.subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                let text = match serde_json::to_string(&event) {
+                    Ok(t) => t,
+                    Err(_) => continue,
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                // Slow subscriber: drop it instead of slowing down the syncer
+                feed.record_lag(skipped);
+                println!("[ASP] ⚠️  Deposit subscriber lagged by {} messages, disconnecting", skipped);
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: 4000,
+                        reason: LAGGED_CLOSE_REASON.into(),
+                    })))
+                    .await;
+                break;
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn deposit_ws_stats(PoolState(state): PoolState) -> impl IntoResponse {
+    Json(state.deposit_feed.stats())
+}
+
+#[derive(Deserialize)]
+struct DepositEventsQuery {
+    /// Only replay deposits with a higher leaf index (Last-Event-ID does the same on reconnect)
+    since: Option<u32>,
+}
+
+/// SSE feed of deposits as the syncer inserts them ("deposit" events, id = leaf index)
+/// Starts with the recent events still buffered, so a reconnecting client doesn't miss any
+async fn deposit_events(
+    headers: axum::http::HeaderMap,
+    Query(query): Query<DepositEventsQuery>,
+    PoolState(state): PoolState,
+) -> impl IntoResponse {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u32>().ok());
+    let (replay, mut receiver) = state.deposit_feed.subscribe_with_replay(query.since.or(last_event_id));
+
+    let deposit_event = |event: &DepositEvent| {
+        Event::default()
+            .event("deposit")
+            .id(event.leaf_index.to_string())
+            .data(serde_json::to_string(event).unwrap_or_default())
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    let feed = state.deposit_feed.clone();
+    tokio::spawn(async move {
+        for event in &replay {
+            if tx.send(deposit_event(event)).is_err() {
+                return;
+            }
+        }
+        loop {
+            // Stop as soon as the client goes away, not at the next deposit
+            let received = tokio::select! {
+                received = receiver.recv() => received,
+                _ = tx.closed() => break,
+            };
+            match received {
+                Ok(event) => {
+                    if tx.send(deposit_event(&event)).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    // Same policy as the WebSocket: drop slow subscribers, they re-fetch via HTTP
+                    feed.record_lag(skipped);
+                    println!("[ASP] ⚠️  Deposit SSE subscriber lagged by {} messages, disconnecting", skipped);
+                    let _ = tx.send(sse_event("lagged", serde_json::json!({ "reason": LAGGED_CLOSE_REASON })));
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    Sse::new(UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>)).keep_alive(KeepAlive::default())
+}
+
+/// Request to find a note's leaf index from its secret data
+#[derive(Deserialize)]
+struct FindNoteRequest {
+    secret: String,
+    nullifier: String,
+    amount: String,
+    /// Commitment scheme hint; if omitted every supported scheme is tried (newest first)
+    scheme_version: Option<u8>,
+    /// Token decimals, echoed back with the amount
+    decimals: Option<u8>,
+}
+
+/// Find a note in the local deposit tree, computing its commitment per scheme version
+/// Used by migrating wallets whose notes may predate the current commitment scheme
+async fn find_deposit_by_note(
+    PoolState(state): PoolState,
+    Json(payload): Json<FindNoteRequest>,
+) -> impl IntoResponse {
+    let amount = match payload.amount.parse::<u128>() {
+        Ok(a) => a,
+        Err(_) => {
+            return ApiError::bad_request("invalid_amount", "Invalid amount").into_response();
+        }
+    };
+
+    let display_amount = match note_display_amount(amount, payload.decimals) {
+        Ok(d) => d,
+        Err(e) => {
+            return ApiError::bad_request("invalid_decimals", e).into_response();
+        }
+    };
+
+    let versions: Vec<u8> = match payload.scheme_version {
+        Some(v) => vec![v],
+        None => SUPPORTED_SCHEME_VERSIONS.to_vec(),
+    };
+
+    for version in versions {
+        let commitment = match generate_commitment_versioned(&payload.secret, &payload.nullifier, amount, version) {
+            Ok(c) => c,
+            Err(e) => {
+                return ApiError::bad_request("invalid_note", format!("Failed to generate commitment: {}", e))
+                    .into_response();
+            }
+        };
+        let commitment_bigint = match util::parse_felt_biguint(&commitment) {
+            Ok(c) => c,
+            Err(e) => {
+                return ApiError::internal("commitment_failed", format!("Invalid generated commitment: {}", e))
+                    .into_response();
+            }
+        };
+
+        let index = state.deposit_tree.read().unwrap().find_commitment_index(&commitment_bigint);
+        if let Some(index) = index {
+            return Json(serde_json::json!({
+                "found": true,
+                "index": index,
+                "commitment": commitment,
+                "scheme_version": version,
+                "amount": payload.amount,
+                "decimals": payload.decimals,
+                "display_amount": display_amount
+            })).into_response();
         }
     }
+
+    Json(serde_json::json!({
+        "found": false,
+        "message": "No commitment for this note found in the local tree"
+    })).into_response()
 }
 
 /// List all deposits in the tree with their indices
-async fn list_deposits(State(state): State<AppState>) -> impl IntoResponse {
-    let tree = state.deposit_tree.lock().unwrap();
-    let leaf_count = tree.get_leaf_count();
-    
-    let mut deposits = Vec::new();
-    for i in 0..leaf_count {
-        if let Some(leaf) = tree.nodes.get(&(0, i)) {
-            deposits.push(serde_json::json!({
+/// Page size for GET /deposit/list when ?limit= is not given, and the most it accepts
+const DEPOSIT_LIST_DEFAULT_LIMIT: u32 = 100;
+const DEPOSIT_LIST_MAX_LIMIT: u32 = 1000;
+
+/// Query for GET /deposit/list
+#[derive(Deserialize)]
+struct DepositListQuery {
+    #[serde(default)]
+    offset: u32,
+    limit: Option<u32>,
+}
+
+/// Deposits in index order, one page at a time (?offset=&limit=)
+/// `count` is the total number of leaves; the page is clamped to it
+async fn list_deposits(PoolState(state): PoolState, Query(query): Query<DepositListQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEPOSIT_LIST_DEFAULT_LIMIT).min(DEPOSIT_LIST_MAX_LIMIT);
+
+    // Copy the page out and format it after releasing the tree lock
+    let (leaf_count, offset, leaves) = {
+        let tree = state.deposit_tree.read().unwrap();
+        let leaf_count = tree.get_leaf_count();
+        let offset = query.offset.min(leaf_count);
+        if leaf_count == 0 {
+            (leaf_count, offset, Vec::new())
+        } else {
+            let end = offset.saturating_add(limit).min(leaf_count);
+            let leaves: Vec<(u32, BigUint)> = (offset..end)
+                .filter_map(|i| tree.nodes.get(&(0, i)).map(|leaf| (i, leaf.clone())))
+                .collect();
+            (leaf_count, offset, leaves)
+        }
+    };
+
+    let deposits: Vec<serde_json::Value> = leaves
+        .iter()
+        .map(|(i, leaf)| {
+            serde_json::json!({
                 "index": i,
                 "commitment": format!("0x{:x}", leaf),
                 "commitment_hex_no_prefix": format!("{:x}", leaf)
-            }));
-        }
-    }
-    
+            })
+        })
+        .collect();
+
     Json(serde_json::json!({
         "count": leaf_count,
+        "offset": offset,
+        "limit": limit,
         "deposits": deposits
     })).into_response()
 }
@@ -423,29 +1580,34 @@ async fn list_deposits(State(state): State<AppState>) -> impl IntoResponse {
 
 async fn get_associated_proof(
     Path(index): Path<u32>,
+    Query(query): Query<EncodingQuery>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
-    let tree = state.associated_tree.lock().unwrap();
+    let tree = state.associated_tree.read().unwrap();
 
     match tree.get_proof(index) {
-        Some(proof) => Json(proof).into_response(),
-        None => (StatusCode::NOT_FOUND, "Leaf not found at index").into_response(),
+        Some(proof) => Json(ProofResponse::new(proof, None, query.path_encoding)).into_response(),
+        None => ApiError::not_found("empty_slot", "Leaf not found at index").into_response(),
     }
 }
 
-async fn get_associated_root(State(state): State<AppState>) -> impl IntoResponse {
-    let tree = state.associated_tree.lock().unwrap();
+async fn get_associated_root(State(state): State<AppState>, Query(query): Query<RootQuery>) -> impl IntoResponse {
+    let tree = state.associated_tree.read().unwrap();
     let root = tree.get_root();
-    Json(format!("0x{:x}", root))
+    Json(query.format.format(&root))
 }
 
 async fn get_associated_info(State(state): State<AppState>) -> impl IntoResponse {
-    let tree = state.associated_tree.lock().unwrap();
-    Json(TreeInfo {
-        root: format!("0x{:x}", tree.get_root()),
-        leaf_count: tree.get_leaf_count(),
-        depth: tree.depth,
-    })
+    let tree = state.associated_tree.read().unwrap();
+    Json(serde_json::json!({
+        "root": format!("0x{:x}", tree.get_root()),
+        "leaf_count": tree.get_leaf_count(),
+        "depth": tree.depth,
+        "capacity": tree.capacity(),
+        "max_size": tree.max_size,
+        "size": tree.size(),
+        "eviction": tree.eviction,
+    }))
 }
 
 /// Insert a commitment into the associated set tree
@@ -454,59 +1616,157 @@ async fn insert_associated(
     State(state): State<AppState>,
     Json(payload): Json<InsertRequest>,
 ) -> impl IntoResponse {
-    use num_bigint::BigUint;
-    use num_traits::Num;
-
     // Parse commitment from hex string
-    let commitment_str = payload.commitment.trim_start_matches("0x");
-    let commitment = match BigUint::from_str_radix(commitment_str, 16) {
+    let commitment = match util::parse_felt_biguint(&payload.commitment) {
         Ok(c) => c,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, "Invalid commitment format").into_response()
+        Err(e) => {
+            return ApiError::bad_request("invalid_commitment", format!("Invalid commitment format: {}", e)).into_response()
         }
     };
 
-    let mut tree = state.associated_tree.lock().unwrap();
-    let new_root = tree.insert(commitment);
-    let leaf_index = tree.get_leaf_count() - 1;
+    let decision = state
+        .screener
+        .screen(&ScreeningRequest {
+            kind: "associated_insert",
+            commitment: format!("0x{:x}", commitment),
+            address: payload.address.clone(),
+        })
+        .await;
+    if !decision.allowed {
+        return ApiError::forbidden("screening_denied", "Commitment denied by screening")
+            .with_details(serde_json::json!({ "screening": decision }))
+            .into_response();
+    }
+
+    let mut tree = state.associated_tree.write().unwrap();
+    let inserted = match tree.insert(commitment) {
+        Ok(inserted) => inserted,
+        Err(e) => {
+            return ApiError::conflict("associated_set_full", e)
+                .with_details(serde_json::json!({
+                    "max_size": tree.max_size,
+                    "eviction": tree.eviction
+                }))
+                .into_response();
+        }
+    };
+    if let Some(evicted) = &inserted.evicted {
+        println!("[ASP] ♻️  Associated set full, evicted 0x{:x} at index {}", evicted, inserted.leaf_index);
+    }
 
     Json(serde_json::json!({
         "success": true,
-        "leaf_index": leaf_index,
-        "new_root": format!("0x{:x}", new_root)
+        "inserted": inserted.inserted,
+        "leaf_index": inserted.leaf_index,
+        "new_root": format!("0x{:x}", inserted.root),
+        "evicted": inserted.evicted.map(|c| format!("0x{:x}", c))
     }))
     .into_response()
 }
 
-// ==================== Blockchain Read Endpoints ====================
+// ==================== ABI Endpoints ====================
 
-async fn get_pool_root(State(state): State<AppState>) -> impl IntoResponse {
-    match state.blockchain.get_merkle_root().await {
-        Ok(root) => Json(serde_json::json!({ "root": root })).into_response(),
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get merkle root: {}", e))
-                .into_response()
+#[derive(Deserialize)]
+struct AbiFunctionsQuery {
+    /// "private" for the private operations only, "all" (default) for every function
+    filter: Option<String>,
+}
+
+/// Functions exposed by the deployed Zylith contract, from the embedded (validated) ABI
+async fn get_abi_functions(Query(query): Query<AbiFunctionsQuery>) -> impl IntoResponse {
+    let private_only = match query.filter.as_deref() {
+        None | Some("all") => false,
+        Some("private") => true,
+        Some(other) => {
+            return ApiError::bad_request(
+                "invalid_filter",
+                format!("Invalid filter '{}', expected 'private' or 'all'", other),
+            )
+            .into_response();
         }
+    };
+
+    let functions = abi::list_functions(abi::get_zylith_abi(), private_only);
+    Json(serde_json::json!({
+        "count": functions.len(),
+        "functions": functions,
+    })).into_response()
+}
+
+#[derive(Deserialize)]
+struct DecodeCalldataRequest {
+    /// Zylith function name, or an ERC20 one (e.g. "approve")
+    function: String,
+    calldata: Vec<String>,
+}
+
+/// Decode raw calldata felts into the function's named, typed parameters
+/// For checking what a failed transaction actually sent
+async fn decode_calldata_endpoint(Json(payload): Json<DecodeCalldataRequest>) -> impl IntoResponse {
+    let abi = if abi::find_function(abi::get_zylith_abi(), &payload.function).is_ok() {
+        abi::get_zylith_abi()
+    } else {
+        abi::get_erc20_abi()
+    };
+
+    match abi::decode_calldata(abi, &payload.function, &payload.calldata) {
+        Ok(params) => Json(serde_json::json!({
+            "function": payload.function,
+            "params": params,
+        })).into_response(),
+        Err(e) => ApiError::bad_request("invalid_calldata", e)
+            .with_details(serde_json::json!({ "function": payload.function }))
+            .into_response(),
+    }
+}
+
+// ==================== Blockchain Read Endpoints ====================
+
+/// Pool contracts this ASP syncs (CONTRACT_ADDRESSES), primary first
+async fn list_pools(State(state): State<AppState>) -> impl IntoResponse {
+    let pools: Vec<_> = state
+        .pools
+        .iter()
+        .enumerate()
+        .map(|(i, pool)| {
+            serde_json::json!({
+                "address": pool.address,
+                "primary": i == 0,
+                "leaf_count": pool.deposit_tree.read().unwrap().get_leaf_count(),
+                "last_synced_block": pool.metrics.last_synced_block(),
+                "sync_halted": pool.sync_status.halted_reason(),
+            })
+        })
+        .collect();
+    Json(serde_json::json!({ "pools": pools }))
+}
+
+async fn get_pool_root(PoolState(state): PoolState, Query(query): Query<RootQuery>) -> impl IntoResponse {
+    let root = state.blockchain.get_merkle_root_cached().await.and_then(|(root, age)| {
+        PathEncoding::Hex.decode(&root).map(|value| (query.format.format(&value), age))
+    });
+    match root {
+        Ok((root, age)) => Json(serde_json::json!({
+            "root": root,
+            "cache_age_ms": age.as_millis() as u64
+        })).into_response(),
+        Err(e) => ApiError::rpc(format!("Failed to get merkle root: {}", e)).into_response(),
     }
 }
 
-async fn check_pool_initialized(State(state): State<AppState>) -> impl IntoResponse {
+async fn check_pool_initialized(PoolState(state): PoolState) -> impl IntoResponse {
     match state.blockchain.is_pool_initialized().await {
         Ok(initialized) => Json(serde_json::json!({ "initialized": initialized })).into_response(),
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check pool status: {}", e))
-                .into_response()
-        }
+        Err(e) => ApiError::rpc(format!("Failed to check pool status: {}", e)).into_response(),
     }
 }
 
-async fn get_pool_info(State(state): State<AppState>) -> impl IntoResponse {
+async fn get_pool_info(PoolState(state): PoolState) -> impl IntoResponse {
     // First check if pool is initialized
     let is_initialized = match state.blockchain.is_pool_initialized().await {
         Ok(init) => init,
         Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check pool status: {}", e))
-                .into_response();
+            return ApiError::rpc(format!("Failed to check pool status: {}", e)).into_response();
         }
     };
 
@@ -517,123 +1777,495 @@ async fn get_pool_info(State(state): State<AppState>) -> impl IntoResponse {
         })).into_response();
     }
 
-    // Get pool tokens and merkle root
+    // Get pool tokens, pool state and merkle root
     let token0 = state.blockchain.get_pool_token0().await;
     let token1 = state.blockchain.get_pool_token1().await;
-    let root = state.blockchain.get_merkle_root().await;
+    let pool = state.blockchain.get_pool_state().await;
+    let root = state.blockchain.get_merkle_root_cached().await;
 
-    match (token0, token1, root) {
-        (Ok(t0), Ok(t1), Ok(r)) => Json(serde_json::json!({
+    match (token0, token1, pool, root) {
+        (Ok(t0), Ok(t1), Ok(pool), Ok((r, age))) => Json(serde_json::json!({
             "initialized": true,
             "merkle_root": r,
+            "cache_age_ms": age.as_millis() as u64,
             "contract_address": state.zylith_address,
             "token0": t0,
-            "token1": t1
+            "token1": t1,
+            "fee": pool.fee,
+            "tick_spacing": pool.tick_spacing,
+            "sqrt_price_x128": pool.sqrt_price_x128.to_string(),
+            "tick": pool.tick,
+            "liquidity": pool.liquidity.to_string()
         })).into_response(),
-        _ => {
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get pool info")
-                .into_response()
+        (.., Err(e), _) => ApiError::rpc(format!("Failed to read pool state: {}", e)).into_response(),
+        (Err(e), ..) | (_, Err(e), ..) | (.., Err(e)) => {
+            ApiError::rpc(format!("Failed to get pool info: {}", e)).into_response()
         }
     }
 }
 
 async fn check_nullifier(
     Path(nullifier): Path<String>,
-    State(state): State<AppState>,
+    PoolState(state): PoolState,
 ) -> impl IntoResponse {
     match state.blockchain.is_nullifier_spent(&nullifier).await {
         Ok(spent) => Json(serde_json::json!({ "spent": spent })).into_response(),
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check nullifier: {}", e))
-                .into_response()
-        }
+        Err(e) => ApiError::rpc(format!("Failed to check nullifier: {}", e)).into_response(),
     }
 }
 
-async fn get_token_balance(
-    Path((token_address, owner)): Path<(String, String)>,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    match state.blockchain.get_token_balance(&token_address, &owner).await {
-        Ok((low, high)) => Json(serde_json::json!({
-            "low": low.to_string(),
-            "high": high.to_string()
-        })).into_response(),
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get token balance: {}", e))
-                .into_response()
-        }
+/// Spent status from the syncer's NullifierSpent set, falling back to the RPC
+/// when the syncer hasn't completed a recent pass
+#[derive(Deserialize)]
+struct DeriveNullifierQuery {
+    nullifier: String,
+}
+
+/// Public nullifier for a note's nullifier, as it appears in proofs and in spent_nullifiers
+async fn derive_nullifier(Query(query): Query<DeriveNullifierQuery>) -> impl IntoResponse {
+    match public_nullifier(&query.nullifier) {
+        Ok(value) => Json(serde_json::json!({ "public_nullifier": value })).into_response(),
+        Err(e) => ApiError::bad_request("invalid_nullifier", e).into_response(),
     }
 }
 
-async fn get_token_allowance(
-    Path((token_address, owner, spender)): Path<(String, String, String)>,
-    State(state): State<AppState>,
+async fn check_nullifier_local(
+    Path(nullifier): Path<String>,
+    PoolState(state): PoolState,
 ) -> impl IntoResponse {
-    match state.blockchain.get_token_allowance(&token_address, &owner, &spender).await {
-        Ok((low, high)) => Json(serde_json::json!({
-            "low": low.to_string(),
-            "high": high.to_string()
-        })).into_response(),
+    let value = match util::parse_felt_biguint(&nullifier) {
+        Ok(n) => n,
         Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get token allowance: {}", e))
-                .into_response()
+            return ApiError::bad_request("invalid_nullifier", format!("Invalid nullifier format: {}", e)).into_response();
         }
+    };
+
+    match state.nullifiers.status(&value) {
+        LocalStatus::Spent => Json(serde_json::json!({
+            "spent": true,
+            "source": "local"
+        })).into_response(),
+        LocalStatus::Unspent { synced_block } => Json(serde_json::json!({
+            "spent": false,
+            "source": "local",
+            "synced_block": synced_block
+        })).into_response(),
+        LocalStatus::Unknown => match state.blockchain.is_nullifier_spent(&nullifier).await {
+            Ok(spent) => Json(serde_json::json!({
+                "spent": spent,
+                "source": "rpc"
+            })).into_response(),
+            Err(e) => ApiError::rpc(format!("Failed to check nullifier: {}", e)).into_response(),
+        },
     }
 }
 
-// ==================== Transaction Preparation Endpoints ====================
-
+/// Request to check a note's on-chain state
 #[derive(Deserialize)]
-struct PrepareDepositRequest {
+struct NoteStatusRequest {
+    secret: String,
+    nullifier: String,
     amount: String,
-    token_address: String,
-    user_address: String,
-}
-
-#[derive(Serialize)]
-struct PreparedTransaction {
-    contract_address: String,
-    entry_point: String,
-    calldata: Vec<String>,
 }
 
+/// Note state as a wallet displays it
+/// Fields are None when that part of the check could not be completed (see `errors`)
 #[derive(Serialize)]
-struct DepositPrepareResponse {
-    transactions: Vec<PreparedTransaction>,
+struct NoteStatusResponse {
     commitment: String,
-    note_data: NoteData,
+    exists: Option<bool>,
+    index: Option<u32>,
+    /// Whether the deposit is in the local tree (a Merkle proof can be served right away)
+    synced: bool,
+    spent: Option<bool>,
+    spendable: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
 }
 
-#[derive(Serialize)]
-struct NoteData {
-    secret: String,
-    nullifier: String,
-    amount: String,
+/// Recompute the commitment, locate it in the deposit tree and check the nullifier in one call
+async fn get_note_status(
+    PoolState(state): PoolState,
+    Json(payload): Json<NoteStatusRequest>,
+) -> impl IntoResponse {
+    println!("[ASP] 📥 POST /api/note/status");
+
+    let amount = match payload.amount.parse::<u128>() {
+        Ok(a) => a,
+        Err(_) => {
+            return ApiError::bad_request("invalid_amount", "Invalid amount").into_response();
+        }
+    };
+    let commitment = match generate_commitment(&payload.secret, &payload.nullifier, amount) {
+        Ok(c) => c,
+        Err(e) => {
+            return ApiError::bad_request("invalid_note", format!("Failed to generate commitment: {}", e))
+                .into_response();
+        }
+    };
+
+    let mut errors = Vec::new();
+
+    // Local tree first; fall back to on-chain events for deposits the syncer hasn't reached yet
+    let local_index = util::parse_felt_biguint(&commitment)
+        .ok()
+        .and_then(|c| state.deposit_tree.read().unwrap().find_commitment_index(&c));
+    let synced = local_index.is_some();
+    let (exists, index) = match local_index {
+        Some(index) => (Some(true), Some(index)),
+        None => match state.blockchain.find_commitment_in_events(&commitment).await {
+            Ok(Some(index)) => (Some(true), Some(index)),
+            Ok(None) => (Some(false), None),
+            Err(e) => {
+                errors.push(format!("Failed to search deposit events: {}", e));
+                (None, None)
+            }
+        },
+    };
+
+    let spent = match state.blockchain.is_nullifier_spent(&payload.nullifier).await {
+        Ok(spent) => Some(spent),
+        Err(e) => {
+            errors.push(format!("Failed to check nullifier: {}", e));
+            None
+        }
+    };
+
+    // Spendable needs a known deposit that is unspent and provable from the local tree
+    let spendable = match (exists, spent) {
+        (Some(false), _) | (_, Some(true)) => Some(false),
+        (Some(true), Some(false)) => Some(synced),
+        _ => None,
+    };
+
+    Json(NoteStatusResponse {
+        commitment,
+        exists,
+        index,
+        synced,
+        spent,
+        spendable,
+        errors,
+    })
+    .into_response()
 }
 
-async fn prepare_deposit(
+async fn get_token_balance(
+    Path((token_address, owner)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.blockchain.get_token_balance(&token_address, &owner).await {
+        Ok((low, high)) => Json(serde_json::json!({
+            "low": low.to_string(),
+            "high": high.to_string()
+        })).into_response(),
+        Err(e) => ApiError::rpc(format!("Failed to get token balance: {}", e)).into_response(),
+    }
+}
+
+async fn get_token_allowance(
+    Path((token_address, owner, spender)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.blockchain.get_token_allowance(&token_address, &owner, &spender).await {
+        Ok((low, high)) => Json(serde_json::json!({
+            "low": low.to_string(),
+            "high": high.to_string()
+        })).into_response(),
+        Err(e) => ApiError::rpc(format!("Failed to get token allowance: {}", e)).into_response(),
+    }
+}
+
+async fn get_tx_revert_reason(
+    Path(tx_hash): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.blockchain.get_revert_reason(&tx_hash).await {
+        Ok(reason) => Json(serde_json::json!({
+            "tx_hash": tx_hash,
+            "reverted": reason.is_some(),
+            "revert_reason": reason
+        })).into_response(),
+        Err(e) if e.contains("not found") || e.contains("NotFound") => {
+            ApiError::not_found("tx_not_found", format!("Transaction not found: {}", e)).into_response()
+        }
+        Err(e) => ApiError::rpc(format!("Failed to get revert reason: {}", e)).into_response(),
+    }
+}
+
+/// Confirmation status for wallets polling a submitted transaction
+/// A hash the node hasn't seen yet is 404 (it may still arrive; keep polling for a while)
+async fn get_tx_status(
+    Path(tx_hash): Path<String>,
     State(state): State<AppState>,
-    Json(payload): Json<PrepareDepositRequest>,
 ) -> impl IntoResponse {
-    // Parse amount
+    match state.blockchain.get_transaction_status(&tx_hash).await {
+        Ok((status, revert_reason)) => {
+            let revert_messages = revert_reason.as_deref().map(blockchain::decode_revert_messages);
+            Json(serde_json::json!({
+                "tx_hash": tx_hash,
+                "status": status.as_str(),
+                "revert_reason": revert_reason,
+                "revert_messages": revert_messages
+            })).into_response()
+        }
+        Err(e) if e.contains("not found") || e.contains("NotFound") => {
+            ApiError::not_found("tx_not_found", format!("Transaction not found: {}", e)).into_response()
+        }
+        Err(e) => ApiError::rpc(format!("Failed to get transaction status: {}", e)).into_response(),
+    }
+}
+
+// ==================== Commitment Endpoints ====================
+
+#[derive(Deserialize)]
+struct CommitmentPartsRequest {
+    secret: String,
+    nullifier: String,
+    amount: String,
+    /// Defaults to the current scheme
+    scheme_version: Option<u8>,
+}
+
+/// Commitment plus the intermediate Poseidon(secret, nullifier), for clients assembling circuit inputs
+async fn commitment_parts_endpoint(Json(payload): Json<CommitmentPartsRequest>) -> impl IntoResponse {
     let amount = match payload.amount.parse::<u128>() {
         Ok(a) => a,
         Err(_) => {
-            return (StatusCode::BAD_REQUEST, "Invalid amount").into_response();
+            return ApiError::bad_request("invalid_amount", "Invalid amount").into_response();
+        }
+    };
+    let scheme_version = payload.scheme_version.unwrap_or(CURRENT_SCHEME_VERSION);
+
+    match generate_commitment_parts(&payload.secret, &payload.nullifier, amount, scheme_version) {
+        Ok(parts) => Json(serde_json::json!({
+            "inner_hash": parts.inner_hash,
+            "commitment": parts.commitment,
+            "scheme_version": scheme_version,
+        }))
+        .into_response(),
+        Err(e) => ApiError::bad_request("invalid_note", e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct VerifyCommitmentRequest {
+    secret: String,
+    nullifier: String,
+    amount: String,
+    commitment: String,
+    /// Defaults to the current scheme
+    scheme_version: Option<u8>,
+}
+
+/// Does this note produce this commitment? Also reports the commitment's deposit index when
+/// the local tree has it
+async fn verify_commitment_endpoint(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyCommitmentRequest>,
+) -> impl IntoResponse {
+    let amount = match payload.amount.parse::<u128>() {
+        Ok(a) => a,
+        Err(_) => return ApiError::bad_request("invalid_amount", "Invalid amount").into_response(),
+    };
+    let expected = match util::parse_felt_biguint(&payload.commitment) {
+        Ok(c) => c,
+        Err(e) => return ApiError::bad_request("invalid_commitment", e).into_response(),
+    };
+    let scheme_version = payload.scheme_version.unwrap_or(CURRENT_SCHEME_VERSION);
+
+    let computed = match generate_commitment_versioned(&payload.secret, &payload.nullifier, amount, scheme_version) {
+        Ok(c) => c,
+        Err(e) => {
+            return ApiError::bad_request("invalid_note", format!("Failed to generate commitment: {}", e))
+                .into_response()
+        }
+    };
+    // Compare as numbers: clients may or may not zero-pad the hex
+    let matches = util::parse_felt_biguint(&computed).as_ref() == Ok(&expected);
+    let index = state.deposit_tree.read().unwrap().find_commitment_index(&expected);
+
+    Json(serde_json::json!({
+        "matches": matches,
+        "computed_commitment": computed,
+        "scheme_version": scheme_version,
+        "index": index,
+    }))
+    .into_response()
+}
+
+// ==================== Note Backup Endpoints ====================
+
+#[derive(Deserialize)]
+struct EncryptNoteRequest {
+    note: NoteData,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct DecryptNoteRequest {
+    blob: String,
+    password: String,
+}
+
+/// Encrypt a note for export (see commitment::encrypt_note for the format)
+async fn encrypt_note_endpoint(Json(payload): Json<EncryptNoteRequest>) -> impl IntoResponse {
+    if payload.password.is_empty() {
+        return ApiError::bad_request("invalid_password", "password must not be empty").into_response();
+    }
+
+    // Argon2 is deliberately slow, keep it off the async workers
+    match tokio::task::spawn_blocking(move || encrypt_note(&payload.note, &payload.password)).await {
+        Ok(blob) => Json(serde_json::json!({ "blob": blob })).into_response(),
+        Err(e) => ApiError::internal("task_failed", format!("Note encryption task failed: {}", e)).into_response(),
+    }
+}
+
+/// Decrypt a backup made by /api/note/encrypt
+async fn decrypt_note_endpoint(Json(payload): Json<DecryptNoteRequest>) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || decrypt_note(&payload.blob, &payload.password)).await {
+        Ok(Ok(note)) => Json(serde_json::json!({ "note": note })).into_response(),
+        Ok(Err(e)) => ApiError::bad_request("decryption_failed", e).into_response(),
+        Err(e) => ApiError::internal("task_failed", format!("Note decryption task failed: {}", e)).into_response(),
+    }
+}
+
+// ==================== Transaction Preparation Endpoints ====================
+
+#[derive(Deserialize)]
+struct PrepareDepositRequest {
+    /// Raw token units (what the commitment binds)
+    amount: Option<String>,
+    token_address: String,
+    user_address: String,
+    /// Token decimals, echoed in the note so the raw amount can't be mis-scaled
+    decimals: Option<u8>,
+    /// Human-readable amount (e.g. "1.5"), scaled by `decimals`; alternative to `amount`
+    display_amount: Option<String>,
+    /// Hex master seed (at least 16 bytes, e.g. a BIP39 seed); with `index`, the note is derived
+    /// with derive_note instead of generated at random
+    seed: Option<String>,
+    /// Counter of the note derived from `seed`
+    index: Option<u64>,
+}
+
+/// Note for a deposit: derived from seed + index when both are given, random otherwise
+fn deposit_note(payload: &PrepareDepositRequest) -> Result<(String, String), String> {
+    match (&payload.seed, payload.index) {
+        (Some(seed), Some(index)) => {
+            let seed = hex::decode(seed.trim().trim_start_matches("0x"))
+                .map_err(|_| "seed must be hex".to_string())?;
+            if seed.len() < MIN_NOTE_SEED_LEN {
+                return Err(format!("seed must be at least {} bytes", MIN_NOTE_SEED_LEN));
+            }
+            Ok(derive_note(&seed, index))
+        }
+        (Some(_), None) => Err("index is required with seed".to_string()),
+        (None, Some(_)) => Err("seed is required with index".to_string()),
+        (None, None) => Ok(generate_note()),
+    }
+}
+
+/// Raw deposit amount from `amount` and/or `display_amount` + `decimals` (must agree if both given)
+fn resolve_deposit_amount(payload: &PrepareDepositRequest) -> Result<BigUint, String> {
+    let raw = payload
+        .amount
+        .as_deref()
+        .map(|a| BigUint::from_str(a.trim()).map_err(|_| "Invalid amount".to_string()))
+        .transpose()?;
+    let scaled = match (&payload.display_amount, payload.decimals) {
+        (Some(display), Some(decimals)) => Some(BigUint::from(parse_token_amount(display, decimals)?)),
+        (Some(_), None) => return Err("display_amount requires decimals".to_string()),
+        (None, _) => None,
+    };
+    match (raw, scaled) {
+        (Some(raw), Some(scaled)) if raw != scaled => Err(format!(
+            "amount {} does not match display_amount {} with {} decimals ({})",
+            raw,
+            payload.display_amount.as_deref().unwrap_or_default(),
+            payload.decimals.unwrap_or_default(),
+            scaled
+        )),
+        (Some(amount), _) | (None, Some(amount)) => Ok(amount),
+        (None, None) => Err("amount or display_amount is required".to_string()),
+    }
+}
+
+#[derive(Serialize)]
+struct PreparedTransaction {
+    contract_address: String,
+    entry_point: String,
+    calldata: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct DepositPrepareResponse {
+    transactions: Vec<PreparedTransaction>,
+    commitment: String,
+    note_data: NoteData,
+    /// Present with ?simulate=true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    simulation: Option<blockchain::Simulation>,
+}
+
+/// Human-readable amount for a note, validating the client's decimals
+fn note_display_amount(amount: u128, decimals: Option<u8>) -> Result<Option<String>, String> {
+    decimals.map(|d| format_token_amount(amount, d)).transpose()
+}
+
+#[derive(Deserialize)]
+struct PrepareDepositQuery {
+    /// Return approve + private_deposit as one __execute__ on the user's account
+    #[serde(default)]
+    multicall: bool,
+    /// Dry-run approve + private_deposit from user_address before returning them
+    #[serde(default)]
+    simulate: bool,
+}
+
+async fn prepare_deposit(
+    State(state): State<AppState>,
+    Query(query): Query<PrepareDepositQuery>,
+    Json(payload): Json<PrepareDepositRequest>,
+) -> impl IntoResponse {
+    // Parse amount (u256 in approve/private_deposit)
+    let (amount_low, amount_high) = match resolve_deposit_amount(&payload).and_then(|a| u256_to_low_high(&a)) {
+        Ok(limbs) => limbs,
+        Err(e) => {
+            return ApiError::bad_request("invalid_amount", e).into_response();
+        }
+    };
+    // The note commits to a u128 amount and private_withdraw/private_swap take u128 amounts, so a
+    // deposit using the high limb could never be spent
+    if amount_high != 0 {
+        return ApiError::bad_request(
+            "invalid_amount",
+            "amount exceeds 2^128 - 1: notes, swaps and withdrawals carry u128 amounts, so the deposit could not be spent",
+        )
+        .into_response();
+    }
+    let amount = amount_low;
+
+    let display_amount = match note_display_amount(amount, payload.decimals) {
+        Ok(d) => d,
+        Err(e) => {
+            return ApiError::bad_request("invalid_decimals", e).into_response();
         }
     };
-    
-    let (amount_low, amount_high) = u256_to_low_high(amount);
 
     // Generate note (secret, nullifier)
-    let (secret, nullifier) = generate_note();
+    let (secret, nullifier) = match deposit_note(&payload) {
+        Ok(note) => note,
+        Err(e) => {
+            return ApiError::bad_request("invalid_seed", e).into_response();
+        }
+    };
 
     // Generate commitment
     let commitment = match generate_commitment(&secret, &nullifier, amount) {
         Ok(c) => c,
         Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate commitment: {}", e))
+            return ApiError::internal("commitment_failed", format!("Failed to generate commitment: {}", e))
                 .into_response();
         }
     };
@@ -646,37 +2278,67 @@ async fn prepare_deposit(
         .get_token_allowance(&payload.token_address, &payload.user_address, &state.zylith_address)
         .await;
 
-    let mut transactions = Vec::new();
-
     // Always include approve (frontend can skip if not needed)
     let approve_calldata = match build_approve_calldata(&state.zylith_address, amount_low, amount_high) {
         Ok(c) => c,
         Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build approve calldata: {}", e))
+            return ApiError::internal("calldata_failed", format!("Failed to build approve calldata: {}", e))
                 .into_response();
         }
     };
 
-    transactions.push(PreparedTransaction {
-        contract_address: payload.token_address.clone(),
-        entry_point: "approve".to_string(),
-        calldata: approve_calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
-    });
-
     // Build deposit calldata
     let deposit_calldata = match build_deposit_calldata(&payload.token_address, amount_low, amount_high, &commitment) {
         Ok(c) => c,
         Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build deposit calldata: {}", e))
+            return ApiError::internal("calldata_failed", format!("Failed to build deposit calldata: {}", e))
                 .into_response();
         }
     };
 
-    transactions.push(PreparedTransaction {
-        contract_address: state.zylith_address.clone(),
-        entry_point: "private_deposit".to_string(),
-        calldata: deposit_calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
-    });
+    let calls = vec![
+        (payload.token_address.clone(), "approve".to_string(), approve_calldata),
+        (state.zylith_address.clone(), "private_deposit".to_string(), deposit_calldata),
+    ];
+    let simulation = if query.simulate {
+        match state.blockchain.simulate_call(&calls, Some(&payload.user_address)).await {
+            Ok(simulation) => {
+                if !simulation.success {
+                    println!("[ASP] ⚠️  Deposit simulation reverted: {:?}", simulation.revert_messages);
+                }
+                Some(simulation)
+            }
+            Err(e) => {
+                return ApiError::rpc(format!("Failed to simulate deposit: {}", e)).into_response();
+            }
+        }
+    } else {
+        None
+    };
+    let transactions = if query.multicall {
+        // One transaction on the user's account, so the approve can't land without the deposit
+        let calldata = match build_multicall_calldata(&calls) {
+            Ok(c) => c,
+            Err(e) => {
+                return ApiError::internal("calldata_failed", format!("Failed to build multicall calldata: {}", e))
+                    .into_response();
+            }
+        };
+        vec![PreparedTransaction {
+            contract_address: payload.user_address.clone(),
+            entry_point: "__execute__".to_string(),
+            calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+        }]
+    } else {
+        calls
+            .into_iter()
+            .map(|(contract_address, entry_point, calldata)| PreparedTransaction {
+                contract_address,
+                entry_point,
+                calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+            })
+            .collect()
+    };
 
     Json(DepositPrepareResponse {
         transactions,
@@ -684,23 +2346,24 @@ async fn prepare_deposit(
         note_data: NoteData {
             secret,
             nullifier,
-            amount: payload.amount,
+            amount: amount.to_string(),
+            decimals: payload.decimals,
+            display_amount,
+            token: Some(payload.token_address.clone()),
         },
+        simulation,
     })
     .into_response()
 }
 
 #[derive(Deserialize)]
 struct PrepareSwapRequest {
-    // Input note data (user must provide this)
-    secret: String,
-    nullifier: String,
-    amount: String,
+    // Input note (secret/nullifier/amount stay client-side; only the index is needed)
     note_index: u32, // For getting Merkle proof
     // Swap parameters
     amount_specified: String,
     zero_for_one: bool,
-    sqrt_price_limit: Option<String>, // Optional, format: "low,high"
+    sqrt_price_limit: Option<String>, // Optional, "low,high" or decimal u256; no bound if omitted
     // Output note (will generate if not provided)
     new_secret: Option<String>,
     new_nullifier: Option<String>,
@@ -712,49 +2375,88 @@ struct SwapPrepareResponse {
     merkle_proof: MerkleProof,
     new_commitment: String,
     output_note_data: NoteData,
+    /// Price limit to pass to /api/swap/calldata, as "low,high" (u128 limbs, X128)
+    sqrt_price_limit: String,
+    /// Live pool state, to pass as sqrt_price_old/liquidity to /api/proof/swap
+    /// Omitted when the pool can't be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<SwapPoolState>,
+}
+
+#[derive(Serialize)]
+struct SwapPoolState {
+    /// Decimal, X128
+    sqrt_price_x128: String,
+    tick: i32,
+    liquidity: String,
+}
+
+/// Token a swap pays out: token1 when selling token0 (zero_for_one), token0 otherwise
+async fn swap_output_token(blockchain: &BlockchainClient, zero_for_one: bool) -> Result<String, String> {
+    if zero_for_one {
+        blockchain.get_pool_token1().await
+    } else {
+        blockchain.get_pool_token0().await
+    }
 }
 
 async fn prepare_swap(
     state: State<AppState>,
     payload: Json<PrepareSwapRequest>,
 ) -> impl IntoResponse {
-    println!("\n[ASP] ========================================");
-    println!("[ASP] 📥 POST /api/swap/prepare - Request received");
-    println!("[ASP] ========================================");
-    println!("[ASP] 🔄 Processing swap preparation...");
-    println!("[ASP]    Note index: {}", payload.note_index);
-    println!("[ASP]    Amount specified: {}", payload.amount_specified);
-    println!("[ASP]    Zero for one: {}", payload.zero_for_one);
-    println!("[ASP]    Has new_secret: {}", payload.new_secret.is_some());
-    println!("[ASP]    Has new_nullifier: {}", payload.new_nullifier.is_some());
-    println!("[ASP]    Has new_amount: {}", payload.new_amount.is_some());
+    debug!("[ASP] ========================================");
+    info!("[ASP] 📥 POST /api/swap/prepare - Request received");
+    debug!("[ASP] ========================================");
+    info!("[ASP] 🔄 Processing swap preparation...");
+    info!("[ASP]    Note index: {}", payload.note_index);
+    info!("[ASP]    Amount specified: {}", payload.amount_specified);
+    info!("[ASP]    Zero for one: {}", payload.zero_for_one);
+    info!("[ASP]    Has new_secret: {}", payload.new_secret.is_some());
+    info!("[ASP]    Has new_nullifier: {}", payload.new_nullifier.is_some());
+    info!("[ASP]    Has new_amount: {}", payload.new_amount.is_some());
     let start_time = std::time::Instant::now();
+
+    if let Err(e) = validate_swap_amounts(&payload.amount_specified, None) {
+        warn!("[ASP] ❌ {}", e);
+        debug!("[ASP] ========================================");
+        return ApiError::bad_request("invalid_amount", e).into_response();
+    }
+    let sqrt_price_limit = match swap_price_limit_limbs(payload.sqrt_price_limit.as_deref(), payload.zero_for_one) {
+        Ok(limbs) => limbs,
+        Err(e) => {
+            warn!("[ASP] ❌ {}", e);
+            debug!("[ASP] ========================================");
+            return ApiError::bad_request("invalid_price_limit", e).into_response();
+        }
+    };
+    info!("[ASP]    Sqrt price limit (low,high): {},{}", sqrt_price_limit.0, sqrt_price_limit.1);
     
     // Get Merkle proof for input note
-    println!("[ASP] 🔍 Fetching Merkle proof for index {}...", payload.note_index);
-    let deposit_tree = state.deposit_tree.lock().unwrap();
-    let merkle_proof = match deposit_tree.get_proof(payload.note_index) {
+    info!("[ASP] 🔍 Fetching Merkle proof for index {}...", payload.note_index);
+    // No tree guard may live across the pool reads below (the handler future must stay Send)
+    let lookup = state.deposit_tree.read().unwrap().get_proof(payload.note_index);
+    let merkle_proof = match lookup {
         Some(proof) => {
-            println!("[ASP] ✅ Merkle proof found for index {}", payload.note_index);
-            println!("[ASP]    Root: {}", proof.root);
-            println!("[ASP]    Path length: {}", proof.path.len());
+            info!("[ASP] ✅ Merkle proof found for index {}", payload.note_index);
+            info!("[ASP]    Root: {}", proof.root);
+            debug!("[ASP]    Path length: {}", proof.path.len());
             proof
         }
         None => {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ❌ Merkle proof not found for index {} (elapsed: {:.2}s)", payload.note_index, elapsed);
-            println!("[ASP] ========================================\n");
-            return (StatusCode::NOT_FOUND, format!("Merkle proof not found for index {}", payload.note_index)).into_response();
+            warn!("[ASP] ❌ Merkle proof not found for index {} (elapsed: {:.2}s)", payload.note_index, elapsed);
+            debug!("[ASP] ========================================");
+            return ApiError::not_found("empty_slot", format!("Merkle proof not found for index {}", payload.note_index))
+                .into_response();
         }
     };
-    drop(deposit_tree);
     
     // Generate output note if not provided
     let (new_secret, new_nullifier) = if let (Some(secret), Some(nullifier)) = (&payload.new_secret, &payload.new_nullifier) {
-        println!("[ASP] 📝 Using provided output note");
+        info!("[ASP] 📝 Using provided output note");
         (secret.clone(), nullifier.clone())
     } else {
-        println!("[ASP] 🔐 Generating new output note...");
+        info!("[ASP] 🔐 Generating new output note...");
         let (secret, nullifier) = generate_note();
         (secret, nullifier)
     };
@@ -764,25 +2466,52 @@ async fn prepare_swap(
         .unwrap_or(0);
     
     // Generate commitment for output note
-    println!("[ASP] 🔗 Generating commitment for output note...");
+    info!("[ASP] 🔗 Generating commitment for output note...");
     let new_commitment = match generate_commitment(&new_secret, &new_nullifier, new_amount) {
         Ok(c) => {
-            println!("[ASP] ✅ Output commitment generated");
+            info!("[ASP] ✅ Output commitment generated");
             c
         }
         Err(e) => {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ❌ Failed to generate output commitment (elapsed: {:.2}s): {}", elapsed, e);
-            println!("[ASP] ========================================\n");
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate output commitment: {}", e)).into_response();
+            error!("[ASP] ❌ Failed to generate output commitment (elapsed: {:.2}s): {}", elapsed, e);
+            debug!("[ASP] ========================================");
+            return ApiError::internal("commitment_failed", format!("Failed to generate output commitment: {}", e))
+                .into_response();
         }
     };
     
+    // Current price and liquidity, so the client computes its deltas against the real pool
+    let pool = match tokio::try_join!(
+        state.blockchain.get_pool_sqrt_price(),
+        state.blockchain.get_pool_tick(),
+        state.blockchain.get_pool_liquidity(),
+    ) {
+        Ok((sqrt_price_x128, tick, liquidity)) => Some(SwapPoolState {
+            sqrt_price_x128: sqrt_price_x128.to_string(),
+            tick,
+            liquidity: liquidity.to_string(),
+        }),
+        Err(e) => {
+            warn!("[ASP] ⚠️  Could not read pool state for swap preparation: {}", e);
+            None
+        }
+    };
+
+    // The output note holds the token the swap pays out: token1 for zero_for_one, token0 otherwise
+    let output_token = match swap_output_token(&state.blockchain, payload.zero_for_one).await {
+        Ok(token) => Some(token),
+        Err(e) => {
+            warn!("[ASP] ⚠️  Could not read the output token for swap preparation: {}", e);
+            None
+        }
+    };
+
     let elapsed = start_time.elapsed().as_secs_f64();
-    println!("[ASP] ✅ Swap preparation completed in {:.2}s", elapsed);
-    println!("[ASP] 📤 Returning prepared data (Merkle proof, commitment, output note)");
-    println!("[ASP] ℹ️  Note: ZK proof generation is handled separately via /api/proof/swap endpoint");
-    println!("[ASP] ========================================\n");
+    info!("[ASP] ✅ Swap preparation completed in {:.2}s", elapsed);
+    info!("[ASP] 📤 Returning prepared data (Merkle proof, commitment, output note)");
+    info!("[ASP] ℹ️  Note: ZK proof generation is handled separately via /api/proof/swap endpoint");
+    debug!("[ASP] ========================================");
     
     // Return prepared data (similar to deposit/prepare)
     // The frontend will use this data along with the ZK proof to construct the transaction
@@ -793,207 +2522,285 @@ async fn prepare_swap(
             secret: new_secret,
             nullifier: new_nullifier,
             amount: new_amount.to_string(),
+            decimals: None,
+            display_amount: None,
+            token: output_token,
         },
+        sqrt_price_limit: format!("{},{}", sqrt_price_limit.0, sqrt_price_limit.1),
+        pool,
     })
     .into_response()
 }
 
+/// Proof from /api/proof/swap plus the swap parameters, for /api/swap/calldata
 #[derive(Deserialize)]
-struct SwapProofRequest {
-    // Public inputs
-    nullifier: String,
-    root: String,
-    new_commitment: String,
+struct SwapCalldataRequest {
+    full_proof_with_hints: Vec<String>,
+    public_inputs: Vec<String>,
+    zero_for_one: bool,
     amount_specified: String,
-    zero_for_one: String, // "0" or "1"
-    amount0_delta: String,
-    amount1_delta: String,
-    new_sqrt_price_x128: String,
-    new_tick: String,
-    // Private inputs
-    secret_in: String,
-    amount_in: String,
-    secret_out: String,
-    nullifier_out: String,
-    amount_out: String,
-    #[serde(rename = "pathElements")]
-    path_elements: Vec<String>,
-    #[serde(rename = "pathIndices")]
-    path_indices: Vec<u32>,
-    sqrt_price_old: String,
-    liquidity: String,
-    // Note: pathElements and pathIndices are required (obtained from /api/swap/prepare)
-    // Removed note_index fallback - frontend must call prepareSwap first
+    /// "low,high" or decimal u256 (X128); no bound if omitted
+    sqrt_price_limit: Option<String>,
+    new_commitment: String,
 }
 
-async fn generate_swap_proof_endpoint(
-    state: State<AppState>,
-    payload: Json<SwapProofRequest>,
-) -> impl IntoResponse {
-    println!("\n[ASP] ========================================");
-    println!("[ASP] 📥 POST /api/proof/swap - ZK Proof generation request");
-    println!("[ASP] ========================================");
-    let start_time = std::time::Instant::now();
-    
+#[derive(Deserialize)]
+struct SwapCalldataQuery {
+    /// Dry-run private_swap before returning it
+    #[serde(default)]
+    simulate: bool,
+    /// Account to simulate from; without it the swap runs as a plain call
+    sender: Option<String>,
+}
+
+/// Swap price limit as (low, high) limbs, defaulting to no bound for the swap direction
+fn swap_price_limit_limbs(limit: Option<&str>, zero_for_one: bool) -> Result<(u128, u128), String> {
+    tick_math::parse_sqrt_price_limit(limit, zero_for_one).and_then(|limit| u256_to_low_high(&limit))
+}
+
+/// Build the private_swap transaction for a generated swap proof
+/// The parameters are checked against the proof's public inputs before encoding
+async fn build_swap_calldata_endpoint(
+    State(state): State<AppState>,
+    Query(query): Query<SwapCalldataQuery>,
+    Json(payload): Json<SwapCalldataRequest>,
+) -> impl IntoResponse {
+    info!("[ASP] 📥 POST /api/swap/calldata - Request received");
+    let bad_request = |code: &'static str, e: String| {
+        warn!("[ASP] ❌ Swap calldata rejected: {}", e);
+        ApiError::bad_request(code, e).into_response()
+    };
+
+    let amount_specified = match validate_swap_amounts(&payload.amount_specified, None) {
+        Ok(a) => a,
+        Err(e) => return bad_request("invalid_amount", e),
+    };
+    let sqrt_price_limit = match swap_price_limit_limbs(payload.sqrt_price_limit.as_deref(), payload.zero_for_one) {
+        Ok(limbs) => limbs,
+        Err(e) => return bad_request("invalid_price_limit", e),
+    };
+    if let Err(e) = check_swap_public_inputs(
+        &payload.public_inputs,
+        payload.zero_for_one,
+        amount_specified,
+        &payload.new_commitment,
+    ) {
+        return bad_request("public_inputs_mismatch", e);
+    }
+
+    let calldata = match build_swap_calldata(
+        &payload.full_proof_with_hints,
+        &payload.public_inputs,
+        payload.zero_for_one,
+        amount_specified,
+        sqrt_price_limit.0,
+        sqrt_price_limit.1,
+        &payload.new_commitment,
+    ) {
+        Ok(c) => c,
+        Err(e) => return bad_request("invalid_proof", format!("Failed to build swap calldata: {}", e)),
+    };
+
+    info!("[ASP] ✅ private_swap calldata built ({} felts)", calldata.len());
+    let transaction = PreparedTransaction {
+        contract_address: state.zylith_address.clone(),
+        entry_point: "private_swap".to_string(),
+        calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+    };
+    if !query.simulate {
+        return Json(serde_json::json!({ "transactions": [transaction] })).into_response();
+    }
+
+    let calls = [(state.zylith_address.clone(), "private_swap".to_string(), calldata)];
+    match state.blockchain.simulate_call(&calls, query.sender.as_deref()).await {
+        Ok(simulation) => {
+            if !simulation.success {
+                warn!("[ASP] ⚠️  Swap simulation reverted: {:?}", simulation.revert_messages);
+            }
+            Json(serde_json::json!({ "transactions": [transaction], "simulation": simulation })).into_response()
+        }
+        Err(e) => {
+            warn!("[ASP] ❌ Swap simulation failed: {}", e);
+            ApiError::rpc(e).into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SwapProofRequest {
+    // Public inputs
+    nullifier: String,
+    root: String,
+    new_commitment: String,
+    amount_specified: String,
+    zero_for_one: String, // "0" or "1"
+    amount0_delta: String,
+    amount1_delta: String,
+    new_sqrt_price_x128: String,
+    new_tick: String,
+    // Private inputs
+    secret_in: String,
+    amount_in: String,
+    secret_out: String,
+    nullifier_out: String,
+    amount_out: String,
+    #[serde(rename = "pathElements")]
+    path_elements: Vec<String>,
+    #[serde(rename = "pathIndices")]
+    path_indices: Vec<u32>,
+    sqrt_price_old: String,
+    liquidity: String,
+    // Note: pathElements and pathIndices are required (obtained from /api/swap/prepare)
+    // Removed note_index fallback - frontend must call prepareSwap first
+}
+
+/// Wait for (or be refused) a proof slot according to PROOF_OVERLOAD_POLICY
+/// Rejections are 429 with queue depth and estimated wait, plus Retry-After
+async fn acquire_proof_permit(state: &AppState) -> Result<tokio::sync::OwnedSemaphorePermit, Response> {
+    let avg_seconds = state.proof_times.lock().unwrap().average_seconds();
+    match state.proof_limiter.acquire(avg_seconds).await {
+        Ok(permit) => Ok(permit),
+        Err(overloaded) => {
+            let retry_after = (overloaded.estimated_wait_seconds.ceil() as u64).to_string();
+            Err(([(axum::http::header::RETRY_AFTER, retry_after)], overloaded_error(&overloaded)).into_response())
+        }
+    }
+}
+
+/// Error for a proof request the prover couldn't take
+/// Rejected right away: 429; waited the whole queue timeout: 503
+fn overloaded_error(overloaded: &limiter::Overloaded) -> ApiError {
+    warn!(
+        "[ASP] ⏳ Prover busy ({}/{} in flight, {} queued), {}",
+        overloaded.in_flight, overloaded.max_concurrent, overloaded.queue_depth,
+        if overloaded.timed_out { "queue wait timed out" } else { "rejecting request" }
+    );
+    debug!("[ASP] ========================================");
+    let error = if overloaded.timed_out {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "prover_timeout",
+            "Timed out waiting for a free prover slot, retry later",
+        )
+    } else {
+        ApiError::new(StatusCode::TOO_MANY_REQUESTS, "prover_busy", "Prover is at capacity, retry later")
+    };
+    error.with_details(serde_json::json!({
+        "policy": overloaded.policy,
+        "in_flight": overloaded.in_flight,
+        "max_concurrent": overloaded.max_concurrent,
+        "queue_depth": overloaded.queue_depth,
+        "estimated_wait_seconds": overloaded.estimated_wait_seconds,
+    }))
+}
+
+/// Validated swap proof request, ready for the prover
+struct SwapProofJob {
+    circuits_path: String,
+    input_json: serde_json::Value,
+    /// Ticks between sqrt_price_old and new_sqrt_price_x128
+    tick_delta: u32,
+    /// ProofCache key over the circuit and the whole input (root, path, public and private inputs)
+    cache_key: String,
+}
+
+/// A Merkle path for a proof must have one sibling and one direction per tree level, or the
+/// circuit fails with an opaque witness error
+fn check_path_length(path_elements: &[String], path_indices: &[u32], depth: usize) -> Result<(), String> {
+    if path_elements.len() != depth || path_indices.len() != depth {
+        return Err(format!(
+            "pathElements and pathIndices must have {} elements (tree depth), got {} and {}",
+            depth,
+            path_elements.len(),
+            path_indices.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Validate a swap proof request and build the circuit input
+/// Shared by the plain and streaming swap proof endpoints
+fn prepare_swap_proof_job(payload: &SwapProofRequest, tree_depth: usize) -> Result<SwapProofJob, ApiError> {
+    // Zero amounts would waste a full proving run on a no-op proof
+    let amount_specified = validate_swap_amounts(&payload.amount_specified, Some(&payload.amount_out))
+        .map_err(|e| ApiError::bad_request("invalid_amount", e))?;
+
     // Merkle proof must be provided in request (from prepareSwap)
     // Frontend should call /api/swap/prepare first to get Merkle proof
     if payload.path_elements.is_empty() || payload.path_indices.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "pathElements and pathIndices must be provided. Call /api/swap/prepare first to get Merkle proof."
-        }))).into_response();
+        return Err(ApiError::bad_request(
+            "missing_merkle_path",
+            "pathElements and pathIndices must be provided. Call /api/swap/prepare first to get Merkle proof.",
+        ));
+    }
+    if let Err(e) = check_path_length(&payload.path_elements, &payload.path_indices, tree_depth) {
+        return Err(ApiError::bad_request("invalid_merkle_path", e));
     }
     
     if payload.root.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "root must be provided. Call /api/swap/prepare first to get Merkle proof."
-        }))).into_response();
+        return Err(ApiError::bad_request(
+            "missing_root",
+            "root must be provided. Call /api/swap/prepare first to get Merkle proof.",
+        ));
     }
     
     let merkle_path = payload.path_elements.clone();
     let merkle_path_indices = payload.path_indices.clone();
     let root = payload.root.clone();
     
-    println!("[ASP] ✅ Using Merkle proof from request (obtained via prepareSwap)");
-    println!("[ASP]    Root: {}", root);
-    println!("[ASP]    Path length: {}", merkle_path.len());
+    info!("[ASP] ✅ Using Merkle proof from request (obtained via prepareSwap)");
+    info!("[ASP]    Root: {}", root);
+    debug!("[ASP]    Path length: {}", merkle_path.len());
     
-    // Parse amounts
-    let amount_in = match payload.amount_in.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_in format"
-            }))).into_response();
-        }
-    };
-    let amount_out = match payload.amount_out.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_out format"
-            }))).into_response();
-        }
-    };
-    let amount_specified = match payload.amount_specified.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_specified format"
-            }))).into_response();
-        }
-    };
+    // Validate amounts
+    if payload.amount_in.parse::<u128>().is_err() {
+        return Err(ApiError::bad_request("invalid_amount", "Invalid amount_in format"));
+    }
     
     // Validate swap complexity before generating proof
-    // Calculate estimated ticks crossed based on price difference
-    let sqrt_price_old = match payload.sqrt_price_old.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid sqrt_price_old format"
-            }))).into_response();
-        }
-    };
+    // Same check as POST /api/proof/estimate, so the estimate never disagrees with this path
+    let check = SwapPriceCheck::new(
+        Some(&payload.sqrt_price_old),
+        Some(&payload.new_sqrt_price_x128),
+        ZeroPricePolicy::from_env(),
+        estimate::max_tick_delta(),
+    )
+    .map_err(|e| ApiError::bad_request("invalid_sqrt_price", e))?;
+    let (tick_delta, max_tick_delta) = (check.tick_delta, check.max_tick_delta);
+    let sqrt_price_old_str = check.sqrt_price_old.to_string();
+    let new_sqrt_price_x128_str = check.new_sqrt_price_x128.to_string();
 
-    let new_sqrt_price_x128 = match payload.new_sqrt_price_x128.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid new_sqrt_price_x128 format"
-            }))).into_response();
-        }
-    };
-
-    // If frontend sends "0" (not yet implemented), use default Q128 (1:1 price)
-    // Q128 = 2^128 = 340282366920938463463374607431768211456
-    // BUT: u128::MAX = 2^128 - 1 = 340282366920938463463374607431768211455
-    // IMPORTANT: The circuit expects Q128 = 2^128, but Rust can't parse it
-    // Frontend sends U128_MAX string when value is Q128, we need to convert back to Q128 string for circuit
-    let q128: u128 = u128::MAX; // Use u128::MAX for Rust parsing
-    
-    let sqrt_price_old_final = if sqrt_price_old == 0 {
-        println!("[ASP] ⚠️  sqrt_price_old is zero, using default Q128 (1:1 price)");
-        q128
-    } else {
-        sqrt_price_old
-    };
-
-    let new_sqrt_price_x128_final = if new_sqrt_price_x128 == 0 {
-        println!("[ASP] ⚠️  new_sqrt_price_x128 is zero, using sqrt_price_old (no price change)");
-        sqrt_price_old_final
-    } else {
-        new_sqrt_price_x128
-    };
-    
-    // Convert u128::MAX back to Q128 string for circuit (circuit expects Q128 = 2^128)
-    // If value is u128::MAX, it means frontend sent Q128, so we send Q128 string to circuit
-    let sqrt_price_old_str = if sqrt_price_old_final == u128::MAX {
-        "340282366920938463463374607431768211456".to_string() // Q128 = 2^128
-    } else {
-        sqrt_price_old_final.to_string()
-    };
-    
-    let new_sqrt_price_x128_str = if new_sqrt_price_x128_final == u128::MAX {
-        "340282366920938463463374607431768211456".to_string() // Q128 = 2^128
-    } else {
-        new_sqrt_price_x128_final.to_string()
-    };
+    // Proving time grows with the ticks crossed; reject moves past SWAP_MAX_TICK_DELTA
+    if check.would_reject() {
+        warn!("[ASP] ⚠️  Swap rejected: {} ticks between prices (max {})", tick_delta, max_tick_delta);
+        info!("[ASP]    sqrt_price_old: {}", sqrt_price_old_str);
+        info!("[ASP]    new_sqrt_price_x128: {}", new_sqrt_price_x128_str);
 
-    // Calculate price ratio to estimate ticks crossed
-    // tick = log(sqrt_price) / log(1.0001) ≈ log(sqrt_price) * 10000
-    // For quick estimation: price_ratio = new_price / old_price
-    let price_ratio = (new_sqrt_price_x128_final as f64) / (sqrt_price_old_final as f64);
-
-    // Estimate ticks: log(ratio) * 10000 / log(1.0001)
-    // Simplified: if ratio is 1.01, that's ~100 ticks
-    // For MVP: reject if price change > 5% (roughly >50 ticks)
-    let max_price_change_ratio = 1.05f64; // 5% max change
-    let min_price_change_ratio = 0.95f64; // -5% min change
-
-    if price_ratio > max_price_change_ratio || price_ratio < min_price_change_ratio {
-        let price_change_pct = if price_ratio > 1.0 {
-            (price_ratio - 1.0) * 100.0
-        } else {
-            (1.0 - price_ratio) * 100.0
-        };
-        
-        println!("[ASP] ⚠️  Swap rejected: Price change too large ({:.2}%)", price_change_pct);
-        println!("[ASP]    sqrt_price_old: {}", sqrt_price_old_final);
-        println!("[ASP]    new_sqrt_price_x128: {}", new_sqrt_price_x128_final);
-        println!("[ASP]    Estimated ticks crossed: >50 (too many for MVP)");
-        
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": format!(
-                "Swap rejected: Price change too large ({:.2}%). This swap would cross too many ticks (>50), making proof generation too slow. Please use a tighter sqrt_price_limit or split into smaller swaps.",
-                price_change_pct
+        return Err(ApiError::bad_request(
+            "too_many_ticks",
+            format!(
+                "Swap rejected: the price moves {} ticks (max {}), making proof generation too slow. Please use a tighter sqrt_price_limit or split into smaller swaps.",
+                tick_delta, max_tick_delta
             ),
-            "price_change_percent": price_change_pct,
-            "sqrt_price_old": sqrt_price_old_final.to_string(),
-            "new_sqrt_price_x128": new_sqrt_price_x128_final.to_string(),
+        )
+        .with_details(serde_json::json!({
+            "tick_delta": tick_delta,
+            "max_tick_delta": max_tick_delta,
+            "sqrt_price_old": sqrt_price_old_str.clone(),
+            "new_sqrt_price_x128": new_sqrt_price_x128_str.clone(),
             "suggestion": "Use a sqrt_price_limit closer to current price to limit ticks crossed"
-        }))).into_response();
+        })));
     }
 
     // Log estimated complexity
-    let estimated_ticks = (price_ratio.ln() * 10000.0).abs();
-    println!("[ASP] 📊 Swap validation:");
-    println!("[ASP]    Price change: {:.2}%", (price_ratio - 1.0) * 100.0);
-    println!("[ASP]    Estimated ticks crossed: ~{:.0}", estimated_ticks);
-    println!("[ASP]    Estimated proof time: {} minutes", 
-        if estimated_ticks < 5.0 { "1-2" } 
-        else if estimated_ticks < 10.0 { "2-4" } 
-        else { "4-10" });
-    println!("[ASP]    Amount specified: {}", amount_specified);
-    println!("[ASP]    Zero for one: {}", payload.zero_for_one);
+    info!("[ASP] 📊 Swap validation:");
+    info!("[ASP]    Price change: {:.2}%", (check.price_ratio() - 1.0) * 100.0);
+    info!("[ASP]    Tick delta: {} (max {})", tick_delta, max_tick_delta);
+    info!("[ASP]    Estimated proof time: {} minutes", check.complexity().minutes_label());
+    info!("[ASP]    Amount specified: {}", amount_specified);
+    info!("[ASP]    Zero for one: {}", payload.zero_for_one);
     
-    // Get circuits path (relative to ASP directory, go up to project root)
-    let circuits_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("circuits")
-        .to_str()
-        .unwrap()
-        .to_string();
+    let circuits_path = proof::circuits_path();
     
     // Build input JSON directly from request payload (frontend already formats it correctly)
     // Update root and pathElements/pathIndices if we fetched them
@@ -1018,45 +2825,387 @@ async fn generate_swap_proof_endpoint(
         "liquidity": payload.liquidity,
     });
     
-    println!("[ASP] 🔧 Generating ZK proof...");
-    println!("[ASP]    Circuits path: {}", circuits_path);
+    let cache_key = ProofCache::key(&circuits_path, &input_json);
+    Ok(SwapProofJob {
+        circuits_path,
+        input_json,
+        tick_delta,
+        cache_key,
+    })
+}
+
+/// JSON body returned for a swap proof; `cached` when it was served from the proof cache
+fn swap_proof_body(swap_proof: &proof::SwapProof, tick_delta: u32, cached: bool) -> Result<serde_json::Value, String> {
+    let checksum = proof::proof_checksum(&swap_proof.proof, &swap_proof.public_inputs)
+        .map_err(|e| format!("Failed to compute proof checksum: {}", e))?;
+    Ok(serde_json::json!({
+        "full_proof_with_hints": swap_proof.proof,
+        "public_inputs": swap_proof.public_inputs,
+        "checksum": checksum,
+        "tick_delta": tick_delta,
+        "cached": cached,
+    }))
+}
+
+/// Proof from an identical recent request, if the cache still holds one
+fn cached_swap_proof(state: &AppState, job: &SwapProofJob) -> Option<proof::SwapProof> {
+    let hit = state.proof_cache.get(&job.cache_key, std::time::Instant::now());
+    if hit.is_some() {
+        info!("[ASP] ♻️  Serving cached swap proof for an identical request");
+    }
+    hit
+}
+
+/// Keep a freshly generated proof for retries of the same request
+fn cache_swap_proof(state: &AppState, job_cache_key: &str, swap_proof: &proof::SwapProof) {
+    if state.proof_cache.enabled() {
+        state.proof_cache.insert(job_cache_key.to_string(), swap_proof.clone(), std::time::Instant::now());
+        debug!("[ASP] ♻️  Cached swap proof ({} cached)", state.proof_cache.entry_count());
+    }
+}
+
+/// Seconds between "heartbeat" events on /api/proof/swap/stream
+const PROOF_HEARTBEAT_SECS: u64 = 5;
+
+fn sse_event(name: &str, data: serde_json::Value) -> Event {
+    Event::default().event(name).data(data.to_string())
+}
+
+/// SSE variant of /api/proof/swap: same JSON body, answered with an event stream
+/// POST rather than GET so the note secrets never end up in URLs, access logs or browser history
+/// Emits "progress" events ({"stage": ...}) as the prover moves on and a "heartbeat"
+/// ({"elapsed_seconds": ...}) every PROOF_HEARTBEAT_SECS, then "done" with the same body as
+/// /api/proof/swap, or "error"
+async fn generate_swap_proof_stream(
+    State(state): State<AppState>,
+    Json(payload): Json<SwapProofRequest>,
+) -> Response {
+    debug!("[ASP] ========================================");
+    info!("[ASP] 📥 POST /api/proof/swap/stream - ZK Proof generation request (SSE)");
+    debug!("[ASP] ========================================");
+
+    let job = match prepare_swap_proof_job(&payload, state.deposit_tree.read().unwrap().depth) {
+        Ok(job) => job,
+        Err(e) => return e.into_response(),
+    };
+    if let Some(swap_proof) = cached_swap_proof(&state, &job) {
+        let event = match swap_proof_body(&swap_proof, job.tick_delta, true) {
+            Ok(body) => sse_event("done", body),
+            Err(e) => sse_event("error", ApiError::internal("proof_failed", e).body()),
+        };
+        return Sse::new(tokio_stream::once(Ok::<_, Infallible>(event))).into_response();
+    }
+    // A free slot or the reject policy is settled before the stream starts (429 as on
+    // /api/proof/swap); waiting in the queue happens inside the stream, so the client gets a
+    // "queued" event and heartbeats instead of a silent connection a proxy may time out
+    let permit = match state.proof_limiter.try_acquire() {
+        Some(permit) => Some(permit),
+        None if state.proof_limiter.policy() == OverloadPolicy::Reject => match acquire_proof_permit(&state).await {
+            Ok(permit) => Some(permit),
+            Err(resp) => return resp,
+        },
+        None => None,
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    tokio::spawn(async move {
+        let start_time = std::time::Instant::now();
+
+        let heartbeat_tx = tx.clone();
+        let heartbeat = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(PROOF_HEARTBEAT_SECS));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let elapsed = serde_json::json!({ "elapsed_seconds": start_time.elapsed().as_secs() });
+                if heartbeat_tx.send(sse_event("heartbeat", elapsed)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Held until the prover finishes, even if the client disconnects
+        let _permit = match permit {
+            Some(permit) => permit,
+            None => {
+                let avg_seconds = state.proof_times.lock().unwrap().average_seconds();
+                let _ = tx.send(sse_event("progress", serde_json::json!({
+                    "stage": "queued",
+                    "queue_depth": state.proof_limiter.queue_depth() + 1,
+                    "estimated_wait_seconds": state.proof_limiter.estimated_wait_seconds(avg_seconds),
+                })));
+                // A client that disconnects while queued gives up its place
+                let acquired = tokio::select! {
+                    acquired = state.proof_limiter.acquire(avg_seconds) => acquired,
+                    _ = tx.closed() => {
+                        heartbeat.abort();
+                        info!("[ASP] 🔌 Client left the proof queue");
+                        return;
+                    }
+                };
+                match acquired {
+                    Ok(permit) => permit,
+                    Err(overloaded) => {
+                        heartbeat.abort();
+                        let _ = tx.send(sse_event("error", overloaded_error(&overloaded).body()));
+                        return;
+                    }
+                }
+            }
+        };
+        // Proof timings leave out the queue wait
+        let start_time = std::time::Instant::now();
+
+        let stage_tx = tx.clone();
+        let on_stage = move |stage: proof::ProofStage| {
+            info!("[ASP] 📡 Proof stage: {:?}", stage);
+            let _ = stage_tx.send(sse_event("progress", serde_json::json!({ "stage": stage })));
+        };
+
+        let result = proof::generate_swap_proof_with_progress(&job.circuits_path, job.input_json, &on_stage).await;
+        state.metrics.record_proof("swap", start_time.elapsed().as_secs_f64(), result.is_ok());
+        let result = result
+            .map_err(|e| format!("Proof generation failed: {}", e))
+            .and_then(|swap_proof| {
+                let elapsed = start_time.elapsed().as_secs_f64();
+                state.proof_times.lock().unwrap().record(job.tick_delta, elapsed);
+                info!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
+                cache_swap_proof(&state, &job.cache_key, &swap_proof);
+                swap_proof_body(&swap_proof, job.tick_delta, false)
+            });
+        heartbeat.abort();
+
+        let event = match result {
+            Ok(body) => sse_event("done", body),
+            Err(e) => {
+                error!("[ASP] ❌ {}", e);
+                sse_event("error", ApiError::internal("proof_failed", e).body())
+            }
+        };
+        let _ = tx.send(event);
+        debug!("[ASP] ========================================");
+    }.instrument(tracing::Span::current()));
+
+    Sse::new(UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Query for /api/proof/swap
+#[derive(Deserialize, Default)]
+struct SwapProofQuery {
+    /// Return a job_id right away and run the proof in the background
+    #[serde(default, rename = "async")]
+    run_async: bool,
+}
+
+/// Run a swap proof job in the background, recording its progress in state.proof_jobs
+/// Waits for a prover slot like a queued request; an overloaded prover fails the job
+async fn run_swap_proof_job(state: AppState, job_id: String, job: SwapProofJob) {
+    if let Some(swap_proof) = cached_swap_proof(&state, &job) {
+        let job_state = match swap_proof_body(&swap_proof, job.tick_delta, true) {
+            Ok(result) => JobState::Done { result },
+            Err(error) => JobState::Failed { error },
+        };
+        state.proof_jobs.set(&job_id, job_state);
+        return;
+    }
+    let avg_seconds = state.proof_times.lock().unwrap().average_seconds();
+    let _permit = match state.proof_limiter.acquire(avg_seconds).await {
+        Ok(p) => p,
+        Err(overloaded) => {
+            warn!("[ASP] ⏳ Proof job {} not started: prover busy ({} queued)", job_id, overloaded.queue_depth);
+            state.proof_jobs.set(&job_id, JobState::Failed {
+                error: "Prover is at capacity, retry later".to_string(),
+            });
+            return;
+        }
+    };
+    state.proof_jobs.set(&job_id, JobState::Running);
+    info!("[ASP] 🔧 Proof job {} running", job_id);
+
+    let start_time = std::time::Instant::now();
+    let result = proof::generate_swap_proof(&job.circuits_path, job.input_json).await;
+    state.metrics.record_proof("swap", start_time.elapsed().as_secs_f64(), result.is_ok());
+    let result = result
+        .map_err(|e| format!("Proof generation failed: {}", e))
+        .and_then(|swap_proof| {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            state.proof_times.lock().unwrap().record(job.tick_delta, elapsed);
+            info!("[ASP] ✅ Proof job {} done in {:.2}s", job_id, elapsed);
+            cache_swap_proof(&state, &job.cache_key, &swap_proof);
+            swap_proof_body(&swap_proof, job.tick_delta, false)
+        });
+
+    let job_state = match result {
+        Ok(result) => JobState::Done { result },
+        Err(error) => {
+            error!("[ASP] ❌ Proof job {} failed: {}", job_id, error);
+            JobState::Failed { error }
+        }
+    };
+    state.proof_jobs.set(&job_id, job_state);
+}
+
+async fn get_proof_job_status(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.proof_jobs.get(&job_id) {
+        Some(job_state) => {
+            let mut body = serde_json::to_value(&job_state).unwrap_or_default();
+            body["job_id"] = serde_json::json!(job_id);
+            Json(body).into_response()
+        }
+        None => ApiError::not_found("unknown_job", "Unknown or expired job_id").into_response(),
+    }
+}
+
+async fn generate_swap_proof_endpoint(
+    state: State<AppState>,
+    Query(query): Query<SwapProofQuery>,
+    payload: Json<SwapProofRequest>,
+) -> impl IntoResponse {
+    debug!("[ASP] ========================================");
+    info!("[ASP] 📥 POST /api/proof/swap - ZK Proof generation request");
+    debug!("[ASP] ========================================");
+    let start_time = std::time::Instant::now();
+    
+    let job = match prepare_swap_proof_job(&payload, state.deposit_tree.read().unwrap().depth) {
+        Ok(job) => job,
+        Err(e) => return e.into_response(),
+    };
+
+    if query.run_async {
+        let job_id = state.proof_jobs.create();
+        info!("[ASP] 🗂️  Queued proof job {} ({} active)", job_id, state.proof_jobs.active());
+        debug!("[ASP] ========================================");
+        tokio::spawn(run_swap_proof_job(state.0.clone(), job_id.clone(), job).instrument(tracing::Span::current()));
+        return (StatusCode::ACCEPTED, Json(serde_json::json!({
+            "job_id": job_id,
+            "status": "pending",
+            "status_url": format!("/api/proof/status/{}", job_id),
+        }))).into_response();
+    }
+
+    if let Some(swap_proof) = cached_swap_proof(&state, &job) {
+        debug!("[ASP] ========================================");
+        return match swap_proof_body(&swap_proof, job.tick_delta, true) {
+            Ok(body) => Json(body).into_response(),
+            Err(e) => ApiError::internal("proof_failed", e).into_response(),
+        };
+    }
+
+    let SwapProofJob { circuits_path, input_json, tick_delta, cache_key } = job;
     
+    info!("[ASP] 🔧 Generating ZK proof...");
+    info!("[ASP]    Circuits path: {}", circuits_path);
+    
+    let _permit = match acquire_proof_permit(&state).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
     // Generate proof - pass JSON directly to proof generator
-    match proof::generate_swap_proof(&circuits_path, input_json).await {
+    let result = proof::generate_swap_proof(&circuits_path, input_json).await;
+    state.metrics.record_proof("swap", start_time.elapsed().as_secs_f64(), result.is_ok());
+    match result {
         Ok(swap_proof) => {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
-            println!("[ASP]    Proof length: {}, Public inputs: {}", 
+            state.proof_times.lock().unwrap().record(tick_delta, elapsed);
+            cache_swap_proof(&state, &cache_key, &swap_proof);
+            info!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
+            info!("[ASP]    Proof length: {}, Public inputs: {}", 
                 swap_proof.proof.len(), swap_proof.public_inputs.len());
             
             // Log the actual values being returned
-            println!("[ASP] 📋 Returning proof with {} elements:", swap_proof.proof.len());
+            info!("[ASP] 📋 Returning proof with {} elements:", swap_proof.proof.len());
             for (i, val) in swap_proof.proof.iter().enumerate() {
-                println!("[ASP]    proof[{}]: {}", i, val);
+                debug!("[ASP]    proof[{}]: {}", i, val);
             }
-            println!("[ASP] 📋 Returning public_inputs with {} elements:", swap_proof.public_inputs.len());
+            info!("[ASP] 📋 Returning public_inputs with {} elements:", swap_proof.public_inputs.len());
             for (i, val) in swap_proof.public_inputs.iter().enumerate() {
-                println!("[ASP]    public_inputs[{}]: {}", i, val);
+                debug!("[ASP]    public_inputs[{}]: {}", i, val);
             }
             
-            println!("[ASP] ========================================\n");
+            debug!("[ASP] ========================================");
             
-            Json(serde_json::json!({
-                "full_proof_with_hints": swap_proof.proof,
-                "public_inputs": swap_proof.public_inputs,
-            })).into_response()
+            match swap_proof_body(&swap_proof, tick_delta, false) {
+                Ok(body) => Json(body).into_response(),
+                Err(e) => ApiError::internal("proof_failed", e).into_response(),
+            }
         }
         Err(e) => {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
-            println!("[ASP] ========================================\n");
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Proof generation failed: {}", e)
-            }))).into_response()
+            error!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            debug!("[ASP] ========================================");
+            ApiError::internal("proof_failed", format!("Proof generation failed: {}", e)).into_response()
         }
     }
 }
 
+#[derive(Deserialize)]
+struct EstimateProofTimeRequest {
+    /// Absent or "0": Q128 (1:1), as in /api/proof/swap
+    sqrt_price_old: Option<String>,
+    /// Absent or "0": no price change
+    new_sqrt_price_x128: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProofChecksumRequest {
+    full_proof_with_hints: Vec<String>,
+    public_inputs: Vec<String>,
+    /// Checksum stored by the client; if given, the response says whether it still matches
+    checksum: Option<String>,
+}
+
+/// Recompute the checksum returned with a proof, to detect corruption before submission
+async fn proof_checksum_endpoint(Json(payload): Json<ProofChecksumRequest>) -> impl IntoResponse {
+    match proof::proof_checksum(&payload.full_proof_with_hints, &payload.public_inputs) {
+        Ok(checksum) => {
+            let matches = payload.checksum.as_deref().map(|c| same_felt(c, &checksum));
+            Json(serde_json::json!({
+                "checksum": checksum,
+                "matches": matches,
+            })).into_response()
+        }
+        Err(e) => ApiError::bad_request("invalid_proof", e).into_response(),
+    }
+}
+
+/// Estimate swap proof generation time from the expected price change, without proving anything
+/// Runs the same tick check as /api/proof/swap and says whether that request would be rejected;
+/// uses the rolling average of recent proofs at the same complexity when available
+async fn estimate_proof_time(
+    State(state): State<AppState>,
+    Json(payload): Json<EstimateProofTimeRequest>,
+) -> impl IntoResponse {
+    let check = match SwapPriceCheck::new(
+        payload.sqrt_price_old.as_deref(),
+        payload.new_sqrt_price_x128.as_deref(),
+        ZeroPricePolicy::from_env(),
+        estimate::max_tick_delta(),
+    ) {
+        Ok(check) => check,
+        Err(e) => {
+            return ApiError::bad_request("invalid_sqrt_price", e).into_response();
+        }
+    };
+    let estimate = state.proof_times.lock().unwrap().estimate(check.tick_delta);
+
+    Json(serde_json::json!({
+        "tick_delta": check.tick_delta,
+        "max_tick_delta": check.max_tick_delta,
+        "price_change_percent": (check.price_ratio() - 1.0) * 100.0,
+        "would_reject": check.would_reject(),
+        "estimated_minutes": check.complexity().minutes_label(),
+        "sqrt_price_old": check.sqrt_price_old.to_string(),
+        "new_sqrt_price_x128": check.new_sqrt_price_x128.to_string(),
+        "estimate": estimate,
+    })).into_response()
+}
+
 #[derive(Deserialize)]
 struct LpProofRequest {
     // Public inputs
@@ -1080,25 +3229,29 @@ struct LpProofRequest {
 }
 
 async fn generate_lp_proof_endpoint(
-    _state: State<AppState>,
+    state: State<AppState>,
     payload: Json<LpProofRequest>,
 ) -> impl IntoResponse {
     println!("\n[ASP] ========================================");
     println!("[ASP] 📥 POST /api/proof/lp-mint - ZK Proof generation request");
     println!("[ASP] ========================================");
     let start_time = std::time::Instant::now();
+
+    if let Err(e) = validate_liquidity_amount(&payload.liquidity) {
+        return ApiError::bad_request("invalid_liquidity", e).into_response();
+    }
     
     // Merkle proof must be provided in request
     if payload.path_elements.is_empty() || payload.path_indices.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "pathElements and pathIndices must be provided."
-        }))).into_response();
+        return ApiError::bad_request("missing_merkle_path", "pathElements and pathIndices must be provided.")
+            .into_response();
+    }
+    if let Err(e) = check_path_length(&payload.path_elements, &payload.path_indices, state.deposit_tree.read().unwrap().depth) {
+        return ApiError::bad_request("invalid_merkle_path", e).into_response();
     }
     
     if payload.root.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "root must be provided."
-        }))).into_response();
+        return ApiError::bad_request("missing_root", "root must be provided.").into_response();
     }
     
     let merkle_path = payload.path_elements.clone();
@@ -1109,15 +3262,7 @@ async fn generate_lp_proof_endpoint(
     println!("[ASP]    Root: {}", root);
     println!("[ASP]    Path length: {}", merkle_path.len());
     
-    // Get circuits path (relative to ASP directory, go up to project root)
-    let circuits_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("circuits")
-        .to_str()
-        .unwrap()
-        .to_string();
+    let circuits_path = proof::circuits_path();
     
     // Build input JSON directly from request payload
     let input_json = serde_json::json!({
@@ -1140,8 +3285,15 @@ async fn generate_lp_proof_endpoint(
     println!("[ASP] 🔧 Generating ZK proof with rapidsnark...");
     println!("[ASP]    Circuits path: {}", circuits_path);
     
+    let _permit = match acquire_proof_permit(&state).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
     // Generate proof using rapidsnark
-    match proof::generate_lp_proof(&circuits_path, input_json).await {
+    let result = proof::generate_lp_proof(&circuits_path, input_json).await;
+    state.metrics.record_proof("lp", start_time.elapsed().as_secs_f64(), result.is_ok());
+    match result {
         Ok(lp_proof) => {
             let elapsed = start_time.elapsed().as_secs_f64();
             println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
@@ -1169,9 +3321,7 @@ async fn generate_lp_proof_endpoint(
             let elapsed = start_time.elapsed().as_secs_f64();
             println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
             println!("[ASP] ========================================\n");
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Proof generation failed: {}", e)
-            }))).into_response()
+            ApiError::internal("proof_failed", format!("Proof generation failed: {}", e)).into_response()
         }
     }
 }
@@ -1188,12 +3338,321 @@ struct PrepareWithdrawRequest {
     token_address: Option<String>, // Optional, will use note's token if not provided
 }
 
+#[derive(Serialize)]
+struct WithdrawPrepareResponse {
+    merkle_proof: MerkleProof,
+    commitment: String,
+    recipient: String,
+    token_address: String,
+    amount: String,
+    full_proof_with_hints: Vec<String>,
+    public_inputs: Vec<String>,
+    transactions: Vec<PreparedTransaction>,
+}
+
+/// Validated withdraw request: note ownership and addresses checked against the deposit tree
+struct CheckedWithdraw {
+    merkle_proof: MerkleProof,
+    commitment: String,
+    amount: u128,
+    token_address: String,
+}
+
+/// Validate a Starknet contract address (non-zero felt below 2^251)
+fn validate_contract_address(field: &str, address: &str) -> Result<(), String> {
+    use num_traits::Zero;
+    let value = util::parse_felt_biguint(address).map_err(|e| format!("Invalid {}: {}", field, e))?;
+    if value.is_zero() {
+        return Err(format!("Invalid {}: zero address", field));
+    }
+    if value.bits() > 251 {
+        return Err(format!("Invalid {}: exceeds the contract address range", field));
+    }
+    Ok(())
+}
+
+/// Check a withdraw request against the deposit tree
+/// The commitment recomputed from (secret, nullifier, amount) must be the leaf at note_index,
+/// which proves note ownership and that the requested amount is the note's full amount
+fn check_withdraw_request(
+    state: &AppState,
+    payload: &PrepareWithdrawRequest,
+) -> Result<CheckedWithdraw, ApiError> {
+    let amount = payload.amount.parse::<u128>()
+        .map_err(|_| ApiError::bad_request("invalid_amount", "Invalid amount"))?;
+    if amount == 0 {
+        return Err(ApiError::bad_request("invalid_amount", "Amount must be greater than zero"));
+    }
+
+    validate_contract_address("recipient", &payload.recipient)
+        .map_err(|e| ApiError::bad_request("invalid_address", e))?;
+    let token_address = payload.token_address.clone()
+        .ok_or_else(|| ApiError::bad_request("invalid_address", "token_address must be provided"))?;
+    validate_contract_address("token_address", &token_address)
+        .map_err(|e| ApiError::bad_request("invalid_address", e))?;
+
+    let merkle_proof = state.deposit_tree.read().unwrap()
+        .get_proof(payload.note_index)
+        .ok_or_else(|| ApiError::not_found(
+            "empty_slot",
+            format!("Merkle proof not found for index {}", payload.note_index),
+        ))?;
+
+    let commitment = generate_commitment(&payload.secret, &payload.nullifier, amount)
+        .map_err(|e| ApiError::bad_request("invalid_note", format!("Failed to generate commitment: {}", e)))?;
+
+    if !same_felt(&merkle_proof.leaf, &commitment) {
+        return Err(ApiError::forbidden(
+            "note_mismatch",
+            format!(
+                "Note does not match leaf at index {}: secret, nullifier and amount must match the deposited commitment",
+                payload.note_index
+            ),
+        ));
+    }
+
+    Ok(CheckedWithdraw {
+        merkle_proof,
+        commitment,
+        amount,
+        token_address,
+    })
+}
+
+/// 409 if the note's nullifier is already spent, checked before any proof work
+/// Answers from the synced NullifierSpent set when it is current, otherwise asks the contract;
+/// an RPC failure is logged and let through (the contract rejects a double spend anyway)
+async fn reject_spent_nullifier(state: &AppState, nullifier: &str) -> Result<(), Response> {
+    let local = util::parse_felt_biguint(nullifier)
+        .map(|n| state.nullifiers.status(&n))
+        .unwrap_or(LocalStatus::Unknown);
+    let spent = match local {
+        LocalStatus::Spent => true,
+        LocalStatus::Unspent { .. } => false,
+        LocalStatus::Unknown => match state.blockchain.is_nullifier_spent(nullifier).await {
+            Ok(spent) => spent,
+            Err(e) => {
+                println!("[ASP] ⚠️  Could not check nullifier, continuing: {}", e);
+                false
+            }
+        },
+    };
+
+    if spent {
+        println!("[ASP] ❌ Nullifier already spent");
+        println!("[ASP] ========================================\n");
+        return Err(ApiError::conflict("nullifier_spent", "Note has already been spent").into_response());
+    }
+    Ok(())
+}
+
+/// Prepare a withdraw end to end: check the note, generate the withdraw proof and return it with the
+/// Merkle proof and the private_withdraw transaction (same proving path as /api/proof/withdraw)
 async fn prepare_withdraw(
-    _state: State<AppState>,
-    _payload: Json<PrepareWithdrawRequest>,
+    State(state): State<AppState>,
+    Json(payload): Json<PrepareWithdrawRequest>,
+) -> impl IntoResponse {
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/withdraw/prepare - Request received");
+    println!("[ASP] ========================================");
+    println!("[ASP]    Note index: {}", payload.note_index);
+    println!("[ASP]    Recipient: {}", payload.recipient);
+
+    let checked = match check_withdraw_request(&state, &payload) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("[ASP] ❌ Withdraw rejected: {:?}", e);
+            println!("[ASP] ========================================\n");
+            return e.into_response();
+        }
+    };
+
+    if let Err(resp) = reject_spent_nullifier(&state, &payload.nullifier).await {
+        return resp;
+    }
+
+    println!("[ASP] ✅ Note ownership verified for index {}", payload.note_index);
+
+    let (withdraw_proof, transaction) = match prove_withdraw(&state, &payload, &checked).await {
+        Ok(proved) => proved,
+        Err(resp) => return resp,
+    };
+    println!("[ASP] ========================================\n");
+
+    Json(WithdrawPrepareResponse {
+        merkle_proof: checked.merkle_proof,
+        commitment: checked.commitment,
+        recipient: payload.recipient,
+        token_address: checked.token_address,
+        amount: checked.amount.to_string(),
+        full_proof_with_hints: withdraw_proof.proof,
+        public_inputs: withdraw_proof.public_inputs,
+        transactions: vec![transaction],
+    })
+    .into_response()
+}
+
+/// Generate the withdraw proof for a checked request and build its private_withdraw transaction
+/// Shared by /api/withdraw/prepare and /api/proof/withdraw
+async fn prove_withdraw(
+    state: &AppState,
+    payload: &PrepareWithdrawRequest,
+    checked: &CheckedWithdraw,
+) -> Result<(proof::WithdrawProof, PreparedTransaction), Response> {
+    let start_time = std::time::Instant::now();
+    let circuits_path = proof::circuits_path();
+
+    let inputs = proof::WithdrawProofInputs {
+        secret: payload.secret.clone(),
+        nullifier: payload.nullifier.clone(),
+        amount: checked.amount,
+        merkle_path: checked.merkle_proof.path.clone(),
+        merkle_path_indices: checked.merkle_proof.path_indices.clone(),
+        root: checked.merkle_proof.root.clone(),
+        recipient: payload.recipient.clone(),
+        token_address: checked.token_address.clone(),
+    };
+
+    println!("[ASP] 🔧 Generating ZK proof...");
+    println!("[ASP]    Circuits path: {}", circuits_path);
+
+    let _permit = acquire_proof_permit(state).await?;
+
+    let result = proof::generate_withdraw_proof(&circuits_path, inputs).await;
+    state.metrics.record_proof("withdraw", start_time.elapsed().as_secs_f64(), result.is_ok());
+    let withdraw_proof = match result {
+        Ok(p) => p,
+        Err(e) => {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            println!("[ASP] ========================================\n");
+            return Err(ApiError::internal("proof_failed", format!("Proof generation failed: {}", e)).into_response());
+        }
+    };
+
+    if let Err(e) = check_withdraw_public_inputs(&withdraw_proof.public_inputs, checked.amount) {
+        println!("[ASP] ❌ Proof does not match the request: {}", e);
+        println!("[ASP] ========================================\n");
+        return Err(ApiError::internal("proof_mismatch", format!("Proof does not match the request: {}", e))
+            .into_response());
+    }
+
+    let calldata = build_withdraw_calldata(
+        &withdraw_proof.proof,
+        &withdraw_proof.public_inputs,
+        &checked.token_address,
+        &payload.recipient,
+        checked.amount,
+    )
+    .map_err(|e| {
+        ApiError::internal("calldata_failed", format!("Failed to build withdraw calldata: {}", e)).into_response()
+    })?;
+
+    println!("[ASP] ✅ Withdraw proof generated successfully in {:.2}s", start_time.elapsed().as_secs_f64());
+    let transaction = PreparedTransaction {
+        contract_address: state.zylith_address.clone(),
+        entry_point: "private_withdraw".to_string(),
+        calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+    };
+    Ok((withdraw_proof, transaction))
+}
+
+/// Generate the withdraw ZK proof and the full private_withdraw calldata
+/// Takes the same request as /api/withdraw/prepare; the Merkle proof is read from the deposit tree
+async fn generate_withdraw_proof_endpoint(
+    State(state): State<AppState>,
+    Json(payload): Json<PrepareWithdrawRequest>,
 ) -> impl IntoResponse {
-    // TODO: Implement withdraw preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/proof/withdraw - ZK Proof generation request");
+    println!("[ASP] ========================================");
+
+    let checked = match check_withdraw_request(&state, &payload) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("[ASP] ❌ Withdraw rejected: {:?}", e);
+            println!("[ASP] ========================================\n");
+            return e.into_response();
+        }
+    };
+
+    if let Err(resp) = reject_spent_nullifier(&state, &payload.nullifier).await {
+        return resp;
+    }
+
+    println!("[ASP] ✅ Note ownership verified, root: {}", checked.merkle_proof.root);
+
+    let (withdraw_proof, transaction) = match prove_withdraw(&state, &payload, &checked).await {
+        Ok(proved) => proved,
+        Err(resp) => return resp,
+    };
+    println!("[ASP] ========================================\n");
+
+    Json(serde_json::json!({
+        "full_proof_with_hints": withdraw_proof.proof,
+        "public_inputs": withdraw_proof.public_inputs,
+        "transactions": [transaction],
+    })).into_response()
+}
+
+/// Withdraw proof generated elsewhere plus the withdraw parameters, for /api/withdraw/calldata
+#[derive(Deserialize)]
+struct WithdrawCalldataRequest {
+    #[serde(alias = "full_proof_with_hints")]
+    proof: Vec<String>,
+    public_inputs: Vec<String>,
+    token: String,
+    recipient: String,
+    /// Decimal u128, in the token's smallest unit
+    amount: String,
+}
+
+/// Build the private_withdraw transaction for a withdraw proof the client already has
+/// The amount is checked against the proof's public inputs before encoding
+async fn build_withdraw_calldata_endpoint(
+    State(state): State<AppState>,
+    Json(payload): Json<WithdrawCalldataRequest>,
+) -> impl IntoResponse {
+    println!("[ASP] 📥 POST /api/withdraw/calldata - Request received");
+    let bad_request = |code: &'static str, e: String| {
+        println!("[ASP] ❌ Withdraw calldata rejected: {}", e);
+        ApiError::bad_request(code, e).into_response()
+    };
+
+    let amount = match payload.amount.trim().parse::<u128>() {
+        Ok(0) => return bad_request("invalid_amount", "Amount must be greater than zero".to_string()),
+        Ok(a) => a,
+        Err(_) => return bad_request("invalid_amount", format!("Invalid amount: {}", payload.amount)),
+    };
+    if let Err(e) = validate_contract_address("recipient", &payload.recipient) {
+        return bad_request("invalid_address", e);
+    }
+    if let Err(e) = validate_contract_address("token", &payload.token) {
+        return bad_request("invalid_address", e);
+    }
+    if let Err(e) = check_withdraw_public_inputs(&payload.public_inputs, amount) {
+        return bad_request("public_inputs_mismatch", e);
+    }
+
+    let calldata = match build_withdraw_calldata(
+        &payload.proof,
+        &payload.public_inputs,
+        &payload.token,
+        &payload.recipient,
+        amount,
+    ) {
+        Ok(c) => c,
+        Err(e) => return bad_request("invalid_proof", format!("Failed to build withdraw calldata: {}", e)),
+    };
+
+    println!("[ASP] ✅ private_withdraw calldata built ({} felts)", calldata.len());
+    Json(serde_json::json!({
+        "transactions": [PreparedTransaction {
+            contract_address: state.zylith_address.clone(),
+            entry_point: "private_withdraw".to_string(),
+            calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+        }],
+    })).into_response()
 }
 
 #[derive(Deserialize)]
@@ -1213,20 +3672,233 @@ struct PrepareLiquidityRequest {
     new_amount: Option<String>,
 }
 
+/// Mint and burn go through the same LP circuit and verifier; only the entry point differs
+#[derive(Clone, Copy)]
+enum LiquidityOp {
+    Mint,
+    Burn,
+}
+
+impl LiquidityOp {
+    fn name(self) -> &'static str {
+        match self {
+            LiquidityOp::Mint => "mint",
+            LiquidityOp::Burn => "burn",
+        }
+    }
+
+    fn entry_point(self) -> &'static str {
+        match self {
+            LiquidityOp::Mint => "private_mint_liquidity",
+            LiquidityOp::Burn => "private_burn_liquidity",
+        }
+    }
+}
+
+/// Generate the LP proof and private_mint_liquidity calldata for an input note
+/// The Merkle proof is read from the deposit tree; the change note (amount - liquidity) is
+/// generated unless new_secret/new_nullifier are provided
 async fn prepare_mint_liquidity(
-    _state: State<AppState>,
-    _payload: Json<PrepareLiquidityRequest>,
-) -> impl IntoResponse {
-    // TODO: Implement mint liquidity preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    State(state): State<AppState>,
+    Json(payload): Json<PrepareLiquidityRequest>,
+) -> Response {
+    prepare_liquidity(state, payload, LiquidityOp::Mint).await
 }
 
+/// Generate the LP proof and private_burn_liquidity calldata for an input note
+/// Same inputs as mint: the position is the one committed to by the input note's secret and the
+/// ticks, and the change note is amount - liquidity, as the LP circuit requires
 async fn prepare_burn_liquidity(
-    _state: State<AppState>,
-    _payload: Json<PrepareLiquidityRequest>,
-) -> impl IntoResponse {
-    // TODO: Implement burn liquidity preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    State(state): State<AppState>,
+    Json(payload): Json<PrepareLiquidityRequest>,
+) -> Response {
+    prepare_liquidity(state, payload, LiquidityOp::Burn).await
+}
+
+async fn prepare_liquidity(state: AppState, payload: PrepareLiquidityRequest, op: LiquidityOp) -> Response {
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/liquidity/{}/prepare - Request received", op.name());
+    println!("[ASP] ========================================");
+    println!("[ASP]    Note index: {}", payload.note_index);
+    println!("[ASP]    Ticks: [{}, {}], liquidity: {}", payload.tick_lower, payload.tick_upper, payload.liquidity);
+    let start_time = std::time::Instant::now();
+
+    let reject = |error: ApiError| {
+        println!("[ASP] ❌ {:?}", error);
+        println!("[ASP] ========================================\n");
+        error.into_response()
+    };
+    let bad_request = |code: &'static str, e: String| reject(ApiError::bad_request(code, e));
+
+    let liquidity = match validate_liquidity_amount(&payload.liquidity) {
+        Ok(l) => l,
+        Err(e) => return bad_request("invalid_liquidity", e),
+    };
+    if let Err(e) = validate_tick_range(payload.tick_lower, payload.tick_upper) {
+        return bad_request("invalid_tick_range", e);
+    }
+    let amount = match payload.amount.parse::<u128>() {
+        Ok(a) => a,
+        Err(_) => return bad_request("invalid_amount", "Invalid amount".to_string()),
+    };
+    // The LP circuit enforces amount_out = amount_in - liquidity
+    let new_amount = match amount.checked_sub(liquidity) {
+        Some(a) => a,
+        None => {
+            return bad_request(
+                "invalid_liquidity",
+                format!("liquidity {} exceeds the note amount {}", liquidity, amount),
+            )
+        }
+    };
+    if let Some(requested) = &payload.new_amount {
+        if requested.parse::<u128>().ok() != Some(new_amount) {
+            return bad_request("invalid_amount", format!("new_amount must be amount - liquidity ({})", new_amount));
+        }
+    }
+
+    let merkle_proof = match state.deposit_tree.read().unwrap().get_proof(payload.note_index) {
+        Some(proof) => proof,
+        None => {
+            return reject(ApiError::not_found(
+                "empty_slot",
+                format!("Merkle proof not found for index {}", payload.note_index),
+            ));
+        }
+    };
+
+    let commitment = match generate_commitment(&payload.secret, &payload.nullifier, amount) {
+        Ok(c) => c,
+        Err(e) => return bad_request("invalid_note", format!("Failed to generate commitment: {}", e)),
+    };
+    if !same_felt(&merkle_proof.leaf, &commitment) {
+        return reject(ApiError::forbidden(
+            "note_mismatch",
+            format!(
+                "Note does not match leaf at index {}: secret, nullifier and amount must match the deposited commitment",
+                payload.note_index
+            ),
+        ));
+    }
+
+    if let Err(resp) = reject_spent_nullifier(&state, &payload.nullifier).await {
+        return resp;
+    }
+
+    // Output (change) note
+    let (new_secret, new_nullifier) = match (&payload.new_secret, &payload.new_nullifier) {
+        (Some(secret), Some(nullifier)) => (secret.clone(), nullifier.clone()),
+        _ => generate_note(),
+    };
+    let new_commitment = match generate_commitment(&new_secret, &new_nullifier, new_amount) {
+        Ok(c) => c,
+        Err(e) => return bad_request("invalid_note", format!("Failed to generate output commitment: {}", e)),
+    };
+
+    // May shell out to the Node.js helper, so keep it off the async workers
+    let (secret, tick_lower, tick_upper) = (payload.secret.clone(), payload.tick_lower, payload.tick_upper);
+    let position_commitment = match tokio::task::spawn_blocking(move || {
+        generate_position_commitment(&secret, tick_lower, tick_upper)
+    }).await {
+        Ok(Ok(c)) => c,
+        Ok(Err(e)) => return bad_request("invalid_note", format!("Failed to generate position commitment: {}", e)),
+        Err(e) => return reject(ApiError::internal("task_failed", format!("Position commitment task failed: {}", e))),
+    };
+
+    println!("[ASP] ✅ Note ownership verified, root: {}", merkle_proof.root);
+    println!("[ASP]    New commitment: {}", new_commitment);
+    println!("[ASP]    Position commitment: {}", position_commitment);
+
+    let circuits_path = proof::circuits_path();
+
+    let inputs = proof::MintProofInputs {
+        secret: payload.secret.clone(),
+        nullifier: payload.nullifier.clone(),
+        amount,
+        merkle_path: merkle_proof.path.clone(),
+        merkle_path_indices: merkle_proof.path_indices.clone(),
+        root: merkle_proof.root.clone(),
+        tick_lower: payload.tick_lower,
+        tick_upper: payload.tick_upper,
+        liquidity,
+        new_secret: new_secret.clone(),
+        new_nullifier: new_nullifier.clone(),
+        new_amount,
+        new_commitment: new_commitment.clone(),
+        position_commitment: position_commitment.clone(),
+    };
+
+    println!("[ASP] 🔧 Generating ZK proof...");
+    println!("[ASP]    Circuits path: {}", circuits_path);
+
+    let _permit = match acquire_proof_permit(&state).await {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let result = match op {
+        LiquidityOp::Mint => proof::generate_mint_liquidity_proof(&circuits_path, inputs).await,
+        LiquidityOp::Burn => proof::generate_burn_liquidity_proof(&circuits_path, inputs).await,
+    };
+    state.metrics.record_proof("lp", start_time.elapsed().as_secs_f64(), result.is_ok());
+    let lp_proof = match result {
+        Ok(p) => p,
+        Err(e) => {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            println!("[ASP] ========================================\n");
+            return ApiError::internal("proof_failed", format!("Proof generation failed: {}", e)).into_response();
+        }
+    };
+
+    // The contract rejects a proof whose liquidity or new_commitment differ from the arguments
+    if let Err(e) = check_liquidity_public_inputs(&lp_proof.public_inputs, liquidity, &new_commitment) {
+        return reject(ApiError::internal("proof_mismatch", format!("Proof does not match the request: {}", e)));
+    }
+
+    let build_calldata = match op {
+        LiquidityOp::Mint => build_mint_liquidity_calldata,
+        LiquidityOp::Burn => build_burn_liquidity_calldata,
+    };
+    let calldata = match build_calldata(
+        &lp_proof.proof,
+        &lp_proof.public_inputs,
+        payload.tick_lower,
+        payload.tick_upper,
+        liquidity,
+        &new_commitment,
+    ) {
+        Ok(c) => c,
+        Err(e) => {
+            return ApiError::internal("calldata_failed", format!("Failed to build {} liquidity calldata: {}", op.name(), e))
+                .into_response();
+        }
+    };
+
+    let elapsed = start_time.elapsed().as_secs_f64();
+    println!("[ASP] ✅ {} liquidity proof generated successfully in {:.2}s", op.name(), elapsed);
+    println!("[ASP] ========================================\n");
+
+    Json(serde_json::json!({
+        "full_proof_with_hints": lp_proof.proof,
+        "public_inputs": lp_proof.public_inputs,
+        "merkle_proof": merkle_proof,
+        "new_commitment": new_commitment,
+        "position_commitment": position_commitment,
+        "output_note_data": NoteData {
+            secret: new_secret,
+            nullifier: new_nullifier,
+            amount: new_amount.to_string(),
+            decimals: None,
+            display_amount: None,
+            token: None,
+        },
+        "transactions": [PreparedTransaction {
+            contract_address: state.zylith_address.clone(),
+            entry_point: op.entry_point().to_string(),
+            calldata,
+        }],
+    })).into_response()
 }
 
 /// Request to prepare initialize transaction
@@ -1260,7 +3932,7 @@ async fn prepare_initialize(
         match BigUint::from_str(&price_str) {
             Ok(p) => p,
             Err(e) => {
-                return (StatusCode::BAD_REQUEST, format!("Invalid sqrt_price_x128: {}", e))
+                return ApiError::bad_request("invalid_sqrt_price", format!("Invalid sqrt_price_x128: {}", e))
                     .into_response();
             }
         }
@@ -1269,13 +3941,28 @@ async fn prepare_initialize(
         match BigUint::from_str("340282366920938463463374607431768211456") {
             Ok(p) => p,
             Err(e) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse Q128: {}", e))
+                return ApiError::internal("invalid_sqrt_price", format!("Failed to parse Q128: {}", e))
                     .into_response();
             }
         }
     };
     
-    let (sqrt_price_low, sqrt_price_high) = u256_to_low_high_bigint(&sqrt_price);
+    // The contract derives the initial tick from the sqrt price; reject prices whose tick is out of range
+    let tick = match tick_math::validate_initial_sqrt_price(&sqrt_price) {
+        Ok(t) => t,
+        Err(e) => {
+            return ApiError::bad_request("invalid_sqrt_price", format!("Invalid sqrt_price_x128: {}", e))
+                .into_response();
+        }
+    };
+    
+    let (sqrt_price_low, sqrt_price_high) = match u256_to_low_high(&sqrt_price) {
+        Ok(limbs) => limbs,
+        Err(e) => {
+            return ApiError::bad_request("invalid_sqrt_price", format!("Invalid sqrt_price_x128: {}", e))
+                .into_response();
+        }
+    };
     
     // Build calldata
     let calldata = match build_initialize_calldata(
@@ -1288,7 +3975,7 @@ async fn prepare_initialize(
     ) {
         Ok(c) => c,
         Err(e) => {
-            return (StatusCode::BAD_REQUEST, format!("Failed to build calldata: {}", e))
+            return ApiError::bad_request("calldata_failed", format!("Failed to build calldata: {}", e))
                 .into_response();
         }
     };
@@ -1311,6 +3998,7 @@ async fn prepare_initialize(
         "token1": token1,
         "fee": fee,
         "tick_spacing": tick_spacing,
+        "tick": tick,
         "sqrt_price_x128": {
             "low": sqrt_price_low.to_string(),
             "high": sqrt_price_high.to_string()
@@ -1318,24 +4006,169 @@ async fn prepare_initialize(
     }))).into_response()
 }
 
-/// Convert u256 (BigUint) to low and high u128
-fn u256_to_low_high_bigint(value: &BigUint) -> (u128, u128) {
-    use num_traits::ToPrimitive;
-    let mask_128 = BigUint::from(1u128) << 128u32;
-    let low = value % &mask_128;
-    let high = value >> 128u32;
-    
-    let low_val = low.to_u128().unwrap_or(0);
-    let high_val = high.to_u128().unwrap_or(0);
-    
-    (low_val, high_val)
-}
-
 // ==================== Health Check ====================
 
-async fn health_check() -> impl IntoResponse {
+async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
+    let sync_halted = state.sync_status.halted_reason();
     Json(serde_json::json!({
-        "status": "ok",
-        "version": "0.1.0"
+        "status": if sync_halted.is_some() { "degraded" } else { "ok" },
+        "version": "0.1.0",
+        "syncer_restarts": state.syncer_restarts.load(Ordering::Relaxed),
+        "sync_halted": sync_halted
     }))
 }
+
+/// Readiness: how long the RPC has to answer (HEALTH_RPC_TIMEOUT_SECS)
+const DEFAULT_HEALTH_RPC_TIMEOUT_SECS: u64 = 3;
+/// Readiness: most blocks the syncer may trail the chain head (HEALTH_MAX_SYNC_LAG)
+const DEFAULT_HEALTH_MAX_SYNC_LAG: u64 = 50;
+
+fn health_setting(var: &str, default: u64) -> u64 {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+/// Liveness probe: the process is up and answering requests, nothing else is checked
+async fn health_live() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness probe: 200 when the RPC answers get_merkle_root and the chain head in time, the
+/// syncer isn't halted and it trails the head by at most HEALTH_MAX_SYNC_LAG blocks; 503 otherwise
+async fn health_ready(PoolState(state): PoolState) -> Response {
+    let timeout_secs = health_setting("HEALTH_RPC_TIMEOUT_SECS", DEFAULT_HEALTH_RPC_TIMEOUT_SECS);
+    let max_sync_lag = health_setting("HEALTH_MAX_SYNC_LAG", DEFAULT_HEALTH_MAX_SYNC_LAG);
+    let last_synced_block = state.metrics.last_synced_block();
+    let mut problems = Vec::new();
+
+    let rpc = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), async {
+        tokio::try_join!(state.blockchain.get_merkle_root(), state.blockchain.get_block_number())
+    })
+    .await;
+    let chain_head = match rpc {
+        Ok(Ok((_, head))) => Some(head),
+        Ok(Err(e)) => {
+            problems.push(format!("RPC error: {}", e));
+            None
+        }
+        Err(_) => {
+            problems.push(format!("RPC did not answer within {}s", timeout_secs));
+            None
+        }
+    };
+    let sync_lag = chain_head.map(|head| head.saturating_sub(last_synced_block));
+    if let Some(lag) = sync_lag.filter(|lag| *lag > max_sync_lag) {
+        problems.push(format!("Syncer is {} blocks behind the chain head (max {})", lag, max_sync_lag));
+    }
+    if let Some(reason) = state.sync_status.halted_reason() {
+        problems.push(format!("Syncer halted: {}", reason));
+    }
+
+    let ready = problems.is_empty();
+    if !ready {
+        warn!("[ASP] Not ready: {}", problems.join("; "));
+    }
+    (
+        if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE },
+        Json(serde_json::json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "rpc_reachable": chain_head.is_some(),
+            "rpc_endpoint": state.blockchain.rpc_endpoint(),
+            "chain_head": chain_head,
+            "last_synced_block": last_synced_block,
+            "sync_lag": sync_lag,
+            "max_sync_lag": max_sync_lag,
+            "problems": problems
+        })),
+    )
+        .into_response()
+}
+
+/// Require "Authorization: Bearer <key>" on the routes ApiKeys protects (when ASP_API_KEYS is set)
+async fn require_api_key(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    if state.api_keys.protects(request.method(), request.uri().path()) {
+        let authorization = request
+            .headers()
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok());
+        if !state.api_keys.authorizes(authorization) {
+            warn!("[ASP] 🔒 Rejected {} {}: missing or invalid API key", request.method(), request.uri().path());
+            return (
+                [(axum::http::header::WWW_AUTHENTICATE, "Bearer")],
+                ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "Missing or invalid API key"),
+            )
+                .into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Per-IP token bucket on the routes that run the prover (PROOF_RATE_LIMIT_PER_MIN); 429 with Retry-After when empty
+/// Runs after the API key check, so unauthenticated requests don't use up a client's budget
+async fn rate_limit_proofs(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|info| info.0.ip());
+    if let Some(ip) = client.filter(|_| auth::is_proof_generation(request.method(), request.uri().path())) {
+        if let Err(retry_after) = state.rate_limiter.check(ip, std::time::Instant::now()) {
+            let retry_after = retry_after.as_secs_f64().ceil() as u64;
+            warn!("[ASP] 🚦 Rate limited {} {} from {} (retry in {}s)", request.method(), request.uri().path(), ip, retry_after);
+            return (
+                [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+                ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", "Too many proof requests, retry later")
+                    .with_details(serde_json::json!({
+                        "limit_per_minute": state.rate_limiter.per_minute(),
+                        "retry_after_seconds": retry_after,
+                    })),
+            )
+                .into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Run each request inside a span carrying its request id, so its log lines can be filtered
+/// together. A client-supplied x-request-id is reused; the id is echoed in the response.
+async fn with_request_span(request: axum::extract::Request, next: axum::middleware::Next) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 64)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = axum::http::HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+    response
+}
+
+/// Prometheus text exposition of sync and proof metrics
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let gauges = metrics::Gauges {
+        deposit_leaves: state.deposit_tree.read().unwrap().get_leaf_count(),
+        syncer_restarts: state.syncer_restarts.load(Ordering::Relaxed),
+    };
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(&gauges),
+    )
+}
@@ -3,13 +3,40 @@ use ark_ff::{BigInteger, PrimeField};
 use light_poseidon::{Poseidon, PoseidonHasher};
 use num_bigint::BigUint;
 use num_traits::Num;
+use crate::persistence::Persistence;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// Merkle Tree Depth (matches Cairo contract)
 /// Contract uses depth 25
 pub const TREE_DEPTH: usize = 25;
 
+/// Deepest tree MERKLE_TREE_DEPTH may ask for: leaf indices are u32
+pub const MAX_TREE_DEPTH: usize = 32;
+
+/// Depth for both trees: MERKLE_TREE_DEPTH, or TREE_DEPTH when unset
+/// Anything but TREE_DEPTH only matches a contract and circuits built with that depth
+pub fn tree_depth_from_env() -> Result<usize, String> {
+    parse_tree_depth(std::env::var("MERKLE_TREE_DEPTH").ok().as_deref())
+}
+
+fn parse_tree_depth(value: Option<&str>) -> Result<usize, String> {
+    let value = match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(value) => value,
+        None => return Ok(TREE_DEPTH),
+    };
+    let depth = value
+        .parse::<usize>()
+        .map_err(|_| format!("MERKLE_TREE_DEPTH must be a number, got '{}'", value))?;
+    if !(1..=MAX_TREE_DEPTH).contains(&depth) {
+        return Err(format!("MERKLE_TREE_DEPTH must be between 1 and {}, got {}", MAX_TREE_DEPTH, depth));
+    }
+    Ok(depth)
+}
+
+/// Deposit tree snapshot, written by the syncer so restarts don't replay every Deposit event
+pub const TREE_SNAPSHOT_FILE: &str = "asp_tree.json";
+
 /// Mask used in Cairo contract to ensure BN254 hash fits in felt252
 /// 0x3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff (250 bits)
 const MASK: &str = "3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
@@ -22,6 +49,133 @@ pub struct MerkleProof {
     pub root: String,
 }
 
+/// Encoding of leaf, path and root in a serialized proof
+/// Proofs are built as 0x-prefixed hex; snarkjs witness inputs are usually decimal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathEncoding {
+    #[default]
+    Hex,
+    Decimal,
+}
+
+impl PathEncoding {
+    pub fn encode(&self, value: &BigUint) -> String {
+        match self {
+            PathEncoding::Hex => format!("0x{:x}", value),
+            PathEncoding::Decimal => value.to_str_radix(10),
+        }
+    }
+
+    pub fn decode(&self, value: &str) -> Result<BigUint, String> {
+        let parsed = match self {
+            PathEncoding::Hex => BigUint::from_str_radix(value.trim_start_matches("0x"), 16),
+            PathEncoding::Decimal => BigUint::from_str_radix(value, 10),
+        };
+        parsed.map_err(|e| format!("Invalid {:?} value {}: {}", self, value, e))
+    }
+}
+
+/// Output format of a root (?format=)
+/// Circuit inputs need decimal; some clients compare fixed-width 64-char hex
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootFormat {
+    #[default]
+    Hex,
+    HexPadded,
+    Decimal,
+}
+
+impl RootFormat {
+    pub fn format(&self, value: &BigUint) -> String {
+        match self {
+            RootFormat::Hex => format!("0x{:x}", value),
+            RootFormat::HexPadded => format!("0x{:064x}", value),
+            RootFormat::Decimal => value.to_str_radix(10),
+        }
+    }
+}
+
+impl MerkleProof {
+    /// Re-encode leaf, path and root from `from` to `to`
+    pub fn reencode(self, from: PathEncoding, to: PathEncoding) -> Result<Self, String> {
+        if from == to {
+            return Ok(self);
+        }
+        let convert = |value: &str| from.decode(value).map(|v| to.encode(&v));
+        Ok(MerkleProof {
+            leaf: convert(&self.leaf)?,
+            path: self.path.iter().map(|p| convert(p)).collect::<Result<_, _>>()?,
+            path_indices: self.path_indices,
+            root: convert(&self.root)?,
+        })
+    }
+
+    /// Proofs from the tree are hex; convert to the requested encoding
+    pub fn encoded(self, encoding: PathEncoding) -> Result<Self, String> {
+        self.reencode(PathEncoding::Hex, encoding)
+    }
+}
+
+/// Recompute the root from a hex proof's leaf, path and path indices
+/// Errors on malformed hex, mismatched path/indices lengths, or indices other than 0/1
+pub fn compute_root(proof: &MerkleProof) -> Result<BigUint, String> {
+    if proof.path.len() != proof.path_indices.len() {
+        return Err(format!(
+            "path has {} elements but path_indices has {}",
+            proof.path.len(),
+            proof.path_indices.len()
+        ));
+    }
+    let mask = BigUint::from_str_radix(MASK, 16).unwrap();
+    let mut current_hash = PathEncoding::Hex.decode(&proof.leaf)?;
+    for (sibling, index) in proof.path.iter().zip(&proof.path_indices) {
+        let sibling = PathEncoding::Hex.decode(sibling)?;
+        let (left, right) = match index {
+            0 => (current_hash, sibling),
+            1 => (sibling, current_hash),
+            other => return Err(format!("path index must be 0 or 1, got {}", other)),
+        };
+        current_hash = MerkleTree::hash_and_mask(&[left, right], &mask);
+    }
+    Ok(current_hash)
+}
+
+/// Whether a hex proof hashes up to its own `root` (false on any malformed input)
+pub fn verify_proof(proof: &MerkleProof) -> bool {
+    match (compute_root(proof), PathEncoding::Hex.decode(&proof.root)) {
+        (Ok(computed), Ok(root)) => computed == root,
+        _ => false,
+    }
+}
+
+/// On-disk form of a tree (felts as hex; JSON maps can't have tuple keys)
+/// Also the body of GET /deposit/export and POST /deposit/import
+#[derive(Serialize, Deserialize)]
+pub struct TreeSnapshot {
+    depth: usize,
+    next_index: u32,
+    /// (level, index, value)
+    nodes: Vec<(usize, u32, String)>,
+    /// (root, leaf count), oldest first
+    root_history: Vec<(String, u32)>,
+    /// Last block whose deposits are all in this tree
+    last_synced_block: u64,
+    /// (leaf index, block) of deposits applied by the syncer; absent in older snapshots
+    #[serde(default)]
+    deposit_blocks: Vec<(u32, u64)>,
+    /// Block by which the leaves before the first deposit_blocks entry were all deposited
+    #[serde(default)]
+    untracked_leaves_block: Option<u64>,
+}
+
+impl TreeSnapshot {
+    pub fn last_synced_block(&self) -> u64 {
+        self.last_synced_block
+    }
+}
+
 /// Merkle Tree with proper intermediate node storage for correct proof generation
 pub struct MerkleTree {
     pub depth: usize,
@@ -35,6 +189,15 @@ pub struct MerkleTree {
     pub current_root: BigUint,
     /// Mask for BN254 -> felt252 conversion
     pub mask: BigUint,
+    /// Every root the tree has had, with the leaf count at that point (oldest first)
+    pub root_history: Vec<(BigUint, u32)>,
+    /// Leaf index of every nonzero commitment, kept in sync by insert_at_index
+    commitment_index: HashMap<BigUint, u32>,
+    /// (leaf index, block it was deposited in), ascending in both; see record_deposit_block
+    deposit_blocks: Vec<(u32, u64)>,
+    /// Set when leaves came from a snapshot without deposit blocks: the block by which every
+    /// leaf before the first deposit_blocks entry was deposited
+    untracked_leaves_block: Option<u64>,
 }
 
 impl MerkleTree {
@@ -55,6 +218,10 @@ impl MerkleTree {
             zeros,
             current_root: initial_root,
             mask,
+            root_history: Vec::new(),
+            commitment_index: HashMap::new(),
+            deposit_blocks: Vec::new(),
+            untracked_leaves_block: None,
         }
     }
 
@@ -73,8 +240,15 @@ impl MerkleTree {
             self.next_index = index + 1;
         }
 
-        // Store leaf at level 0
-        self.nodes.insert((0, index), leaf.clone());
+        // Store leaf at level 0, replacing any previous leaf in the commitment index
+        if let Some(previous) = self.nodes.insert((0, index), leaf.clone()) {
+            if self.commitment_index.get(&previous) == Some(&index) {
+                self.commitment_index.remove(&previous);
+            }
+        }
+        if leaf != BigUint::from(0u8) {
+            self.commitment_index.entry(leaf.clone()).or_insert(index);
+        }
 
         // Update path from leaf to root
         let mut current_hash = leaf;
@@ -82,7 +256,7 @@ impl MerkleTree {
 
         for level in 0..self.depth {
             // Determine left and right children for current position
-            let (left, right) = if current_idx % 2 == 0 {
+            let (left, right) = if current_idx.is_multiple_of(2) {
                 // Current node is left child
                 let right_idx = current_idx + 1;
                 // CRITICAL: Use 0 for missing siblings (matching Cairo contract)
@@ -114,28 +288,69 @@ impl MerkleTree {
         }
 
         self.current_root = current_hash.clone();
+        self.root_history.push((current_hash.clone(), self.next_index));
         current_hash
     }
 
+    /// Replace an existing leaf (0 clears it) and update its path, returning the new root
+    pub fn update_leaf(&mut self, index: u32, leaf: BigUint) -> Result<BigUint, String> {
+        if index >= self.next_index {
+            return Err(format!(
+                "Index {} has no leaf to update (leaf count {})",
+                index, self.next_index
+            ));
+        }
+        Ok(self.insert_at_index(index, leaf))
+    }
+
+    /// Number of leaf slots (2^depth)
+    pub fn capacity(&self) -> u64 {
+        1u64 << self.depth
+    }
+
     /// Generate a Merkle proof for a leaf at the given index
+    /// Only for occupied slots: None for empty slots and indices beyond capacity
+    /// (see get_empty_slot_proof for the zero leaf)
     pub fn get_proof(&self, index: u32) -> Option<MerkleProof> {
         // Check if leaf exists
         let leaf = self.nodes.get(&(0, index))?;
+        Some(self.proof_for(index, leaf))
+    }
 
+    /// Proof of the zero leaf at an empty slot in [leaf_count, 2^depth), against the current root
+    /// Errors for occupied slots and for indices beyond capacity.
+    /// Like the contract, empty subtrees are 0 (not Poseidon(0, 0)), so hashing the zero leaf up this
+    /// path reproduces the root only while the slot's sibling leaf is occupied.
+    pub fn get_empty_slot_proof(&self, index: u32) -> Result<MerkleProof, String> {
+        if index as u64 >= self.capacity() {
+            return Err(format!(
+                "Index {} is beyond the tree capacity ({} leaves)",
+                index,
+                self.capacity()
+            ));
+        }
+        if self.nodes.contains_key(&(0, index)) {
+            return Err(format!("Index {} is occupied", index));
+        }
+        Ok(self.proof_for(index, &BigUint::from(0u8)))
+    }
+
+    /// Siblings from `index` up to the current root
+    fn proof_for(&self, index: u32, leaf: &BigUint) -> MerkleProof {
         let mut path = Vec::with_capacity(self.depth);
         let mut path_indices = Vec::with_capacity(self.depth);
         let mut current_idx = index;
 
         for level in 0..self.depth {
             // Determine sibling index
-            let sibling_idx = if current_idx % 2 == 0 {
+            let sibling_idx = if current_idx.is_multiple_of(2) {
                 current_idx + 1
             } else {
                 current_idx - 1
             };
 
             // Path index: 0 if current is left (sibling on right), 1 if current is right (sibling on left)
-            path_indices.push((current_idx % 2) as u32);
+            path_indices.push(current_idx % 2);
             
             // Get sibling (use 0 if not present - matching Cairo contract)
             // CRITICAL: Cairo contract uses 0 for missing siblings, not recursive hash
@@ -151,14 +366,124 @@ impl MerkleTree {
             current_idx /= 2;
         }
 
-        Some(MerkleProof {
+        MerkleProof {
             leaf: format!("0x{:x}", leaf),
             path,
             path_indices,
             root: format!("0x{:x}", self.current_root),
+        }
+    }
+
+    /// The last `limit` roots with the leaf count each one covers, newest first
+    pub fn recent_roots(&self, limit: usize) -> impl Iterator<Item = &(BigUint, u32)> {
+        self.root_history.iter().rev().take(limit)
+    }
+
+    /// Leaf count the tree had when its root was `root`, if that root is in the history
+    pub fn leaf_count_at_root(&self, root: &BigUint) -> Option<u32> {
+        self.root_history
+            .iter()
+            .rev()
+            .find(|(r, _)| r == root)
+            .map(|(_, count)| *count)
+    }
+
+    /// Note the block the deposit at leaf `index` was emitted in
+    /// Only deposits past the last recorded one count (replays are ignored), and blocks never go
+    /// backwards, so the list stays sorted for leaf_count_at_block
+    pub fn record_deposit_block(&mut self, index: u32, block: u64) {
+        match self.deposit_blocks.last() {
+            Some(&(last_index, _)) if index <= last_index => {}
+            Some(&(_, last_block)) => self.deposit_blocks.push((index, block.max(last_block))),
+            None => self.deposit_blocks.push((index, block)),
+        }
+    }
+
+    /// Leaf count the tree had at the end of `block`, from the recorded deposit blocks
+    /// The caller must only ask about blocks the syncer has fully processed. None when some of
+    /// those leaves have no known block (a tree restored from a snapshot without deposit blocks,
+    /// asked about a block before that snapshot's)
+    pub fn leaf_count_at_block(&self, block: u64) -> Option<u32> {
+        let after = self.deposit_blocks.partition_point(|(_, b)| *b <= block);
+        // Deposits are append-only: every leaf before the first one after `block` came earlier
+        let leaf_count = match self.deposit_blocks.get(after) {
+            Some(&(index, _)) => index,
+            None => self.next_index,
+        };
+        if after > 0 || leaf_count == 0 {
+            return Some(leaf_count);
+        }
+        // Only untracked leaves come before `block`'s deposits
+        match self.untracked_leaves_block {
+            Some(untracked) if untracked <= block => Some(leaf_count),
+            _ => None,
+        }
+    }
+
+    /// Root the tree had when it held `leaf_count` leaves (see get_proof_at)
+    pub fn root_at(&self, leaf_count: u32) -> Option<BigUint> {
+        if leaf_count > self.next_index {
+            return None;
+        }
+        Some(self.node_at(self.depth, 0, leaf_count))
+    }
+
+    /// Generate a Merkle proof for `index` as it was when the tree had `leaf_count` leaves
+    /// Deposits are append-only, so any earlier state can be rebuilt by treating
+    /// leaves at or beyond `leaf_count` as missing (0, matching Cairo contract)
+    pub fn get_proof_at(&self, index: u32, leaf_count: u32) -> Option<MerkleProof> {
+        if index >= leaf_count || leaf_count > self.next_index {
+            return None;
+        }
+        let leaf = self.nodes.get(&(0, index))?;
+
+        let mut path = Vec::with_capacity(self.depth);
+        let mut path_indices = Vec::with_capacity(self.depth);
+        let mut current_idx = index;
+
+        for level in 0..self.depth {
+            let sibling_idx = if current_idx.is_multiple_of(2) {
+                current_idx + 1
+            } else {
+                current_idx - 1
+            };
+            path_indices.push(current_idx % 2);
+            path.push(format!("0x{:x}", self.node_at(level, sibling_idx, leaf_count)));
+            current_idx /= 2;
+        }
+
+        Some(MerkleProof {
+            leaf: format!("0x{:x}", leaf),
+            path,
+            path_indices,
+            root: format!("0x{:x}", self.node_at(self.depth, 0, leaf_count)),
         })
     }
 
+    /// Value of node (level, index) when the tree had `leaf_count` leaves
+    fn node_at(&self, level: usize, index: u32, leaf_count: u32) -> BigUint {
+        let first_leaf = (index as u64) << level;
+        let end_leaf = (index as u64 + 1) << level;
+
+        if first_leaf >= leaf_count as u64 {
+            // Subtree was entirely empty at that point
+            return BigUint::from(0u8);
+        }
+        if level == 0 || end_leaf <= leaf_count as u64 {
+            // Subtree was already complete and hasn't changed since
+            return self
+                .nodes
+                .get(&(level, index))
+                .cloned()
+                .unwrap_or_else(|| BigUint::from(0u8));
+        }
+
+        // Subtree was partially filled - recompute from its children
+        let left = self.node_at(level - 1, index * 2, leaf_count);
+        let right = self.node_at(level - 1, index * 2 + 1, leaf_count);
+        Self::hash_and_mask(&[left, right], &self.mask)
+    }
+
     /// Get the current root
     pub fn get_root(&self) -> BigUint {
         self.current_root.clone()
@@ -172,15 +497,103 @@ impl MerkleTree {
     /// Find the index of a commitment in the tree
     /// Returns None if the commitment is not found
     pub fn find_commitment_index(&self, commitment: &BigUint) -> Option<u32> {
-        // Search through all leaves (level 0)
-        for index in 0..self.next_index {
-            if let Some(leaf) = self.nodes.get(&(0, index)) {
-                if leaf == commitment {
-                    return Some(index);
-                }
+        self.commitment_index.get(commitment).copied()
+    }
+
+    /// Serializable copy of the tree, tagged with the last block whose deposits it contains
+    pub fn to_snapshot(&self, last_synced_block: u64) -> TreeSnapshot {
+        let hex = |v: &BigUint| format!("0x{:x}", v);
+        TreeSnapshot {
+            depth: self.depth,
+            next_index: self.next_index,
+            nodes: self.nodes.iter().map(|((level, index), v)| (*level, *index, hex(v))).collect(),
+            root_history: self.root_history.iter().map(|(root, count)| (hex(root), *count)).collect(),
+            last_synced_block,
+            deposit_blocks: self.deposit_blocks.clone(),
+            untracked_leaves_block: self.untracked_leaves_block,
+        }
+    }
+
+    /// Rebuild a tree from a snapshot; `depth` must match the snapshot's
+    /// The stored nodes are trusted as-is (see recompute_root to check them)
+    pub fn from_snapshot(snapshot: &TreeSnapshot, depth: usize) -> Result<Self, String> {
+        if snapshot.depth != depth {
+            return Err(format!("Tree snapshot has depth {}, expected {}", snapshot.depth, depth));
+        }
+        let parse = |v: &str| {
+            BigUint::from_str_radix(v.trim_start_matches("0x"), 16)
+                .map_err(|e| format!("Invalid value {} in tree snapshot: {}", v, e))
+        };
+
+        let mut tree = MerkleTree::new(depth);
+        tree.next_index = snapshot.next_index;
+        for (level, index, value) in &snapshot.nodes {
+            if *level > depth {
+                return Err(format!("Tree snapshot has a node at level {} (depth {})", level, depth));
+            }
+            let value = parse(value)?;
+            if *level == 0 && value != BigUint::from(0u8) {
+                let first = tree.commitment_index.entry(value.clone()).or_insert(*index);
+                *first = (*first).min(*index);
             }
+            tree.nodes.insert((*level, *index), value);
+        }
+        for (root, count) in &snapshot.root_history {
+            tree.root_history.push((parse(root)?, *count));
+        }
+        tree.deposit_blocks = snapshot.deposit_blocks.clone();
+        tree.untracked_leaves_block = snapshot.untracked_leaves_block;
+        if tree.deposit_blocks.is_empty() && tree.next_index > 0 && tree.untracked_leaves_block.is_none() {
+            // Older snapshot: all its leaves were deposited by its last synced block
+            tree.untracked_leaves_block = Some(snapshot.last_synced_block);
         }
-        None
+        tree.current_root = tree.nodes.get(&(depth, 0)).cloned().unwrap_or_else(|| BigUint::from(0u8));
+        Ok(tree)
+    }
+
+    /// Root hashed from the leaves alone, ignoring the stored inner nodes
+    /// Equal to get_root() unless the inner nodes are inconsistent (e.g. a tampered snapshot)
+    pub fn recompute_root(&self) -> BigUint {
+        let mut level_nodes: BTreeMap<u32, BigUint> = self
+            .nodes
+            .iter()
+            .filter(|((level, _), _)| *level == 0)
+            .map(|((_, index), v)| (*index, v.clone()))
+            .collect();
+        if level_nodes.is_empty() {
+            return BigUint::from(0u8);
+        }
+        for _ in 0..self.depth {
+            let parents: BTreeSet<u32> = level_nodes.keys().map(|index| index / 2).collect();
+            level_nodes = parents
+                .into_iter()
+                .map(|parent| {
+                    // Missing siblings are 0, as in insert_at_index
+                    let child = |index: u32| level_nodes.get(&index).cloned().unwrap_or_else(|| BigUint::from(0u8));
+                    (parent, Self::hash_and_mask(&[child(parent * 2), child(parent * 2 + 1)], &self.mask))
+                })
+                .collect();
+        }
+        level_nodes.remove(&0).unwrap_or_else(|| BigUint::from(0u8))
+    }
+
+    /// Snapshot the tree, tagged with the last block whose deposits it contains
+    pub fn save_to_file(&self, persistence: Persistence, path: &str, last_synced_block: u64) -> Result<(), String> {
+        let snapshot = self.to_snapshot(last_synced_block);
+        let json = serde_json::to_string(&snapshot).map_err(|e| format!("Failed to serialize tree: {}", e))?;
+        persistence.write(path, &json)
+    }
+
+    /// Load a snapshot written by save_to_file: Ok(None) if there is none
+    /// Returns the tree and the last block it covers; `depth` must match the snapshot's
+    pub fn load_from_file(persistence: Persistence, path: &str, depth: usize) -> Result<Option<(Self, u64)>, String> {
+        let Some(json) = persistence.read(path) else {
+            return Ok(None);
+        };
+        let snapshot: TreeSnapshot =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid tree snapshot {}: {}", path, e))?;
+        let tree = MerkleTree::from_snapshot(&snapshot, depth).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Some((tree, snapshot.last_synced_block)))
     }
 
     /// Hash two nodes using Poseidon BN254 and mask to felt252
@@ -270,4 +683,288 @@ mod tests {
 
         assert_eq!(format!("0x{:x}", current_hash), proof.root);
     }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let path = std::env::temp_dir().join(format!("zylith_tree_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut tree = MerkleTree::new(4);
+        for leaf in [11u64, 22, 33] {
+            tree.insert(BigUint::from(leaf));
+        }
+        tree.save_to_file(Persistence::Disk, path, 1234).unwrap();
+
+        let (loaded, block) = MerkleTree::load_from_file(Persistence::Disk, path, 4).unwrap().unwrap();
+        assert_eq!(block, 1234);
+        assert_eq!(loaded.get_root(), tree.get_root());
+        assert_eq!(loaded.get_leaf_count(), 3);
+        assert_eq!(loaded.get_proof(1).unwrap().path, tree.get_proof(1).unwrap().path);
+        assert_eq!(loaded.root_history, tree.root_history);
+        assert!(MerkleTree::load_from_file(Persistence::Disk, path, 5).is_err());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(MerkleTree::load_from_file(Persistence::Memory, path, 4).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recompute_root_detects_tampered_nodes() {
+        let mut tree = MerkleTree::new(4);
+        assert_eq!(tree.recompute_root(), BigUint::from(0u8));
+        for leaf in [11u64, 22, 33] {
+            tree.insert(BigUint::from(leaf));
+        }
+        tree.insert_at_index(6, BigUint::from(0u8));
+        assert_eq!(tree.recompute_root(), tree.get_root());
+
+        let mut snapshot = tree.to_snapshot(7);
+        let imported = MerkleTree::from_snapshot(&snapshot, 4).unwrap();
+        assert_eq!(imported.recompute_root(), tree.get_root());
+
+        // A swapped leaf no longer hashes up to the stored root
+        for node in snapshot.nodes.iter_mut().filter(|(level, index, _)| *level == 0 && *index == 1) {
+            node.2 = "0x17".to_string();
+        }
+        let tampered = MerkleTree::from_snapshot(&snapshot, 4).unwrap();
+        assert_eq!(tampered.get_root(), tree.get_root());
+        assert_ne!(tampered.recompute_root(), tree.get_root());
+        assert!(MerkleTree::from_snapshot(&snapshot, 5).is_err());
+    }
+
+    #[test]
+    fn test_update_leaf() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(BigUint::from(1u8));
+        tree.insert(BigUint::from(2u8));
+        let mut expected = MerkleTree::new(4);
+        expected.insert(BigUint::from(1u8));
+        expected.insert(BigUint::from(3u8));
+
+        let root = tree.update_leaf(1, BigUint::from(3u8)).unwrap();
+        assert_eq!(root, expected.get_root());
+        assert_eq!(tree.get_leaf_count(), 2);
+        assert!(tree.update_leaf(2, BigUint::from(3u8)).is_err());
+    }
+
+    #[test]
+    fn test_commitment_index() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(BigUint::from(11u8));
+        tree.insert_at_index(3, BigUint::from(33u8));
+        tree.insert_at_index(2, BigUint::from(0u8));
+        assert_eq!(tree.find_commitment_index(&BigUint::from(11u8)), Some(0));
+        assert_eq!(tree.find_commitment_index(&BigUint::from(33u8)), Some(3));
+        assert_eq!(tree.find_commitment_index(&BigUint::from(0u8)), None);
+
+        // Replaced leaves leave the index
+        tree.update_leaf(0, BigUint::from(44u8)).unwrap();
+        assert_eq!(tree.find_commitment_index(&BigUint::from(11u8)), None);
+        assert_eq!(tree.find_commitment_index(&BigUint::from(44u8)), Some(0));
+
+        // ...and the index is rebuilt when loading a snapshot
+        let path = std::env::temp_dir().join(format!("zylith_tree_index_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        tree.save_to_file(Persistence::Disk, path, 1).unwrap();
+        let (loaded, _) = MerkleTree::load_from_file(Persistence::Disk, path, 4).unwrap().unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.find_commitment_index(&BigUint::from(44u8)), Some(0));
+        assert_eq!(loaded.find_commitment_index(&BigUint::from(33u8)), Some(3));
+        assert_eq!(loaded.find_commitment_index(&BigUint::from(11u8)), None);
+    }
+
+    #[test]
+    fn test_empty_slot_proof() {
+        let mut tree = MerkleTree::new(4);
+        let empty = tree.get_empty_slot_proof(0).unwrap();
+        assert_eq!(empty.leaf, "0x0");
+        assert_eq!(empty.root, "0x0");
+        assert!(tree.get_proof(0).is_none());
+
+        tree.insert(BigUint::from(12345u64));
+        // Next slot: the sibling leaf is occupied, so the zero leaf hashes up to the current root
+        let next = tree.get_empty_slot_proof(1).unwrap();
+        assert_eq!(next.root, format!("0x{:x}", tree.get_root()));
+        assert!(verify_proof(&next));
+
+        // Last slot is still an empty slot, not out of range
+        let last = tree.get_empty_slot_proof(15).unwrap();
+        assert_eq!(last.path_indices, vec![1, 1, 1, 1]);
+        assert_eq!(last.root, next.root);
+    }
+
+    #[test]
+    fn test_empty_slot_proof_rejects_occupied_and_out_of_range() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(BigUint::from(12345u64));
+        assert!(tree.get_empty_slot_proof(0).unwrap_err().contains("occupied"));
+        assert!(tree.get_empty_slot_proof(16).unwrap_err().contains("capacity"));
+        assert!(tree.get_proof(16).is_none());
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        for leaf in [11u64, 22, 33] {
+            tree.insert(BigUint::from(leaf));
+        }
+        let proof = tree.get_proof(2).unwrap();
+        assert!(verify_proof(&proof));
+
+        let mut wrong_leaf = proof.clone();
+        wrong_leaf.leaf = "0x2c".to_string();
+        assert!(!verify_proof(&wrong_leaf));
+
+        let mut short = proof.clone();
+        short.path.pop();
+        assert!(!verify_proof(&short));
+        short.path_indices.pop();
+        assert!(!verify_proof(&short));
+
+        let mut bad_index = proof.clone();
+        bad_index.path_indices[0] = 2;
+        assert!(!verify_proof(&bad_index));
+
+        let mut bad_hex = proof;
+        bad_hex.path[3] = "0xzz".to_string();
+        assert!(!verify_proof(&bad_hex));
+    }
+
+    #[test]
+    fn test_proof_at_historical_root() {
+        let mut tree = MerkleTree::new(4);
+        let mut old_tree = MerkleTree::new(4);
+
+        for leaf in [11u64, 22, 33] {
+            tree.insert(BigUint::from(leaf));
+            old_tree.insert(BigUint::from(leaf));
+        }
+        let old_root = tree.get_root();
+        tree.insert(BigUint::from(44u64));
+        tree.insert(BigUint::from(55u64));
+
+        assert_eq!(tree.leaf_count_at_root(&old_root), Some(3));
+
+        // Proof rebuilt at leaf count 3 matches the proof the tree gave back then
+        for index in 0..3 {
+            let historical = tree.get_proof_at(index, 3).expect("Proof should exist");
+            let original = old_tree.get_proof(index).expect("Proof should exist");
+            assert_eq!(historical.path, original.path);
+            assert_eq!(historical.root, original.root);
+        }
+
+        // Index 3 didn't exist yet at that root
+        assert!(tree.get_proof_at(3, 3).is_none());
+    }
+
+    #[test]
+    fn test_root_formats() {
+        let root = BigUint::from(0xabcu32);
+        assert_eq!(RootFormat::Hex.format(&root), "0xabc");
+        assert_eq!(RootFormat::HexPadded.format(&root), format!("0x{}abc", "0".repeat(61)));
+        assert_eq!(RootFormat::Decimal.format(&root), "2748");
+        assert_eq!(
+            serde_json::from_str::<RootFormat>("\"hex_padded\"").unwrap(),
+            RootFormat::HexPadded
+        );
+    }
+
+    #[test]
+    fn test_path_encoding_round_trip() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(BigUint::from(12345u64));
+        tree.insert(BigUint::from(67890u64));
+        let hex_proof = tree.get_proof(1).expect("Proof should exist");
+
+        let decimal_proof = hex_proof.clone().encoded(PathEncoding::Decimal).unwrap();
+        assert_eq!(decimal_proof.leaf, "67890");
+        assert!(decimal_proof.path.iter().all(|p| !p.starts_with("0x")));
+        assert_eq!(
+            PathEncoding::Decimal.decode(&decimal_proof.root).unwrap(),
+            PathEncoding::Hex.decode(&hex_proof.root).unwrap()
+        );
+
+        let back_to_hex = decimal_proof
+            .reencode(PathEncoding::Decimal, PathEncoding::Hex)
+            .unwrap();
+        assert_eq!(back_to_hex.leaf, hex_proof.leaf);
+        assert_eq!(back_to_hex.path, hex_proof.path);
+        assert_eq!(back_to_hex.root, hex_proof.root);
+        assert_eq!(back_to_hex.path_indices, hex_proof.path_indices);
+
+        // Hex is the default and a no-op
+        let unchanged = hex_proof.clone().encoded(PathEncoding::default()).unwrap();
+        assert_eq!(unchanged.path, hex_proof.path);
+    }
+
+    #[test]
+    fn test_recent_roots_newest_first() {
+        let mut tree = MerkleTree::new(4);
+        let roots: Vec<BigUint> = (1u8..=3).map(|leaf| tree.insert(BigUint::from(leaf))).collect();
+
+        let recent: Vec<(BigUint, u32)> = tree.recent_roots(2).cloned().collect();
+        assert_eq!(recent, vec![(roots[2].clone(), 3), (roots[1].clone(), 2)]);
+        assert_eq!(tree.recent_roots(10).count(), 3);
+        assert_eq!(tree.leaf_count_at_root(&recent[1].0), Some(2));
+    }
+
+    #[test]
+    fn test_root_at_block() {
+        let mut tree = MerkleTree::new(4);
+        assert_eq!(tree.leaf_count_at_block(5), Some(0));
+        let mut roots = Vec::new();
+        for (index, block) in [(0u32, 10u64), (1, 10), (2, 12)] {
+            roots.push(tree.insert(BigUint::from(index + 1)));
+            tree.record_deposit_block(index, block);
+        }
+        // A replayed deposit doesn't move the recorded blocks
+        tree.record_deposit_block(1, 20);
+
+        assert_eq!(tree.leaf_count_at_block(9), Some(0));
+        assert_eq!(tree.leaf_count_at_block(10), Some(2));
+        assert_eq!(tree.leaf_count_at_block(11), Some(2));
+        assert_eq!(tree.leaf_count_at_block(12), Some(3));
+        assert_eq!(tree.root_at(2), Some(roots[1].clone()));
+        assert_eq!(tree.root_at(3), Some(tree.get_root()));
+        assert_eq!(tree.root_at(0), Some(BigUint::from(0u8)));
+        assert_eq!(tree.root_at(4), None);
+
+        // Snapshots keep the blocks
+        let restored = MerkleTree::from_snapshot(&tree.to_snapshot(12), 4).unwrap();
+        assert_eq!(restored.leaf_count_at_block(11), Some(2));
+    }
+
+    #[test]
+    fn test_root_at_block_from_snapshot_without_blocks() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(BigUint::from(1u8));
+        tree.insert(BigUint::from(2u8));
+        // As written before deposit blocks were recorded
+        let mut snapshot = serde_json::to_value(tree.to_snapshot(50)).unwrap();
+        snapshot.as_object_mut().unwrap().remove("deposit_blocks");
+        snapshot.as_object_mut().unwrap().remove("untracked_leaves_block");
+        let snapshot: TreeSnapshot = serde_json::from_value(snapshot).unwrap();
+
+        let mut restored = MerkleTree::from_snapshot(&snapshot, 4).unwrap();
+        restored.insert(BigUint::from(3u8));
+        restored.record_deposit_block(2, 60);
+        // When the two snapshot leaves landed is unknown, only that it was by block 50
+        assert_eq!(restored.leaf_count_at_block(49), None);
+        assert_eq!(restored.leaf_count_at_block(50), Some(2));
+        assert_eq!(restored.leaf_count_at_block(60), Some(3));
+        // Without recorded blocks (e.g. the associated tree) nothing is known
+        assert_eq!(tree.leaf_count_at_block(50), None);
+    }
+
+    #[test]
+    fn test_tree_depth_override() {
+        assert_eq!(parse_tree_depth(None), Ok(TREE_DEPTH));
+        assert_eq!(parse_tree_depth(Some("")), Ok(TREE_DEPTH));
+        assert_eq!(parse_tree_depth(Some("20")), Ok(20));
+        assert!(parse_tree_depth(Some("0")).is_err());
+        assert!(parse_tree_depth(Some("33")).is_err());
+        assert!(parse_tree_depth(Some("deep")).is_err());
+
+        let mut tree = MerkleTree::new(20);
+        tree.insert(BigUint::from(1u8));
+        assert_eq!(tree.get_proof(0).unwrap().path.len(), 20);
+    }
 }
@@ -0,0 +1,160 @@
+// Prometheus metrics (GET /metrics)
+// A handful of gauges, counters and one histogram, rendered by hand in the text exposition
+// format. The syncer updates the sync gauges; proof handlers record every proof they run.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Upper bounds (seconds) of the proof duration histogram buckets; proofs take minutes
+const PROOF_DURATION_BUCKETS: [f64; 9] = [5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0];
+
+#[derive(Default)]
+pub struct Metrics {
+    last_synced_block: AtomicU64,
+    chain_head: AtomicU64,
+    /// Unix time of the last sync pass that reached the chain head
+    last_sync_unix: AtomicU64,
+    /// Per circuit
+    proofs: Mutex<BTreeMap<&'static str, ProofStats>>,
+}
+
+#[derive(Default)]
+struct ProofStats {
+    succeeded: u64,
+    failed: u64,
+    /// Successful proofs per bucket (not cumulative; summed when rendering)
+    buckets: [u64; PROOF_DURATION_BUCKETS.len()],
+    seconds_sum: f64,
+}
+
+/// Values read from elsewhere in the app state at scrape time
+pub struct Gauges {
+    pub deposit_leaves: u32,
+    pub syncer_restarts: u64,
+}
+
+impl Metrics {
+    /// Chain head as seen by the syncer's last poll
+    pub fn set_chain_head(&self, block: u64) {
+        self.chain_head.store(block, Ordering::Relaxed);
+    }
+
+    /// Last block the syncer fully processed (0 before its first pass)
+    pub fn last_synced_block(&self) -> u64 {
+        self.last_synced_block.load(Ordering::Relaxed)
+    }
+
+    /// The syncer processed every event up to `block`
+    pub fn set_synced(&self, block: u64) {
+        self.last_synced_block.store(block, Ordering::Relaxed);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.last_sync_unix.store(now, Ordering::Relaxed);
+    }
+
+    /// A proof for `circuit` finished; only successful proofs go into the duration histogram
+    pub fn record_proof(&self, circuit: &'static str, seconds: f64, ok: bool) {
+        let mut proofs = self.proofs.lock().unwrap();
+        let stats = proofs.entry(circuit).or_default();
+        if !ok {
+            stats.failed += 1;
+            return;
+        }
+        stats.succeeded += 1;
+        stats.seconds_sum += seconds;
+        if let Some(bucket) = PROOF_DURATION_BUCKETS.iter().position(|le| seconds <= *le) {
+            stats.buckets[bucket] += 1;
+        }
+    }
+
+    /// Text exposition format
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let synced = self.last_synced_block.load(Ordering::Relaxed);
+        let head = self.chain_head.load(Ordering::Relaxed);
+        let mut out = String::new();
+
+        metric(&mut out, "zylith_deposit_tree_leaves", "gauge", "Leaves in the deposit tree");
+        let _ = writeln!(out, "zylith_deposit_tree_leaves {}", gauges.deposit_leaves);
+        metric(&mut out, "zylith_last_synced_block", "gauge", "Last block fully processed by the syncer");
+        let _ = writeln!(out, "zylith_last_synced_block {}", synced);
+        metric(&mut out, "zylith_chain_head_block", "gauge", "Latest block reported by the RPC to the syncer");
+        let _ = writeln!(out, "zylith_chain_head_block {}", head);
+        metric(&mut out, "zylith_sync_lag_blocks", "gauge", "Blocks between the chain head and the last synced block");
+        let _ = writeln!(out, "zylith_sync_lag_blocks {}", head.saturating_sub(synced));
+        metric(
+            &mut out,
+            "zylith_last_sync_timestamp_seconds",
+            "gauge",
+            "Unix time of the last sync pass that reached the chain head",
+        );
+        let _ = writeln!(out, "zylith_last_sync_timestamp_seconds {}", self.last_sync_unix.load(Ordering::Relaxed));
+        metric(&mut out, "zylith_syncer_restarts_total", "counter", "Syncer task restarts after a panic");
+        let _ = writeln!(out, "zylith_syncer_restarts_total {}", gauges.syncer_restarts);
+
+        let proofs = self.proofs.lock().unwrap();
+        metric(&mut out, "zylith_proofs_total", "counter", "Proofs generated successfully");
+        for (circuit, stats) in proofs.iter() {
+            let _ = writeln!(out, "zylith_proofs_total{{circuit=\"{}\"}} {}", circuit, stats.succeeded);
+        }
+        metric(&mut out, "zylith_proof_failures_total", "counter", "Proof generations that failed");
+        for (circuit, stats) in proofs.iter() {
+            let _ = writeln!(out, "zylith_proof_failures_total{{circuit=\"{}\"}} {}", circuit, stats.failed);
+        }
+        metric(&mut out, "zylith_proof_duration_seconds", "histogram", "Duration of successful proofs");
+        for (circuit, stats) in proofs.iter() {
+            let mut cumulative = 0;
+            for (le, count) in PROOF_DURATION_BUCKETS.iter().zip(stats.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "zylith_proof_duration_seconds_bucket{{circuit=\"{}\",le=\"{}\"}} {}",
+                    circuit, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "zylith_proof_duration_seconds_bucket{{circuit=\"{}\",le=\"+Inf\"}} {}",
+                circuit, stats.succeeded
+            );
+            let _ = writeln!(out, "zylith_proof_duration_seconds_sum{{circuit=\"{}\"}} {}", circuit, stats.seconds_sum);
+            let _ = writeln!(out, "zylith_proof_duration_seconds_count{{circuit=\"{}\"}} {}", circuit, stats.succeeded);
+        }
+        out
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_sync_gauges_and_proof_histogram() {
+        let metrics = Metrics::default();
+        metrics.set_chain_head(120);
+        metrics.set_synced(100);
+        metrics.record_proof("swap", 8.0, true);
+        metrics.record_proof("swap", 45.0, true);
+        metrics.record_proof("swap", 3000.0, true);
+        metrics.record_proof("swap", 2.0, false);
+
+        let text = metrics.render(&Gauges { deposit_leaves: 7, syncer_restarts: 1 });
+        assert!(text.contains("zylith_deposit_tree_leaves 7\n"));
+        assert!(text.contains("zylith_sync_lag_blocks 20\n"));
+        assert!(text.contains("zylith_proofs_total{circuit=\"swap\"} 3\n"));
+        assert!(text.contains("zylith_proof_failures_total{circuit=\"swap\"} 1\n"));
+        assert!(text.contains("zylith_proof_duration_seconds_bucket{circuit=\"swap\",le=\"5\"} 0\n"));
+        assert!(text.contains("zylith_proof_duration_seconds_bucket{circuit=\"swap\",le=\"60\"} 2\n"));
+        assert!(text.contains("zylith_proof_duration_seconds_bucket{circuit=\"swap\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("zylith_proof_duration_seconds_sum{circuit=\"swap\"} 3053\n"));
+        assert!(text.contains("# TYPE zylith_proof_duration_seconds histogram\n"));
+    }
+}
@@ -0,0 +1,105 @@
+// Spent nullifiers seen by the syncer (NullifierSpent events), so spent checks can be answered
+// without an RPC call. The set is persisted with the syncer cursor in asp_state.json.
+
+use num_bigint::BigUint;
+use num_traits::Num;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How recent the last completed sync pass must be for "not in the set" to mean "not spent"
+pub const LOCAL_NULLIFIER_MAX_AGE: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct NullifierSet {
+    spent: RwLock<HashSet<BigUint>>,
+    /// Block and time of the last sync pass that reached the chain head
+    synced: RwLock<Option<(u64, Instant)>>,
+}
+
+/// Answer from the local set
+#[derive(Debug, PartialEq, Eq)]
+pub enum LocalStatus {
+    Spent,
+    /// Not in the set and the syncer is caught up (as of this block)
+    Unspent { synced_block: u64 },
+    /// Not in the set, but the syncer is behind: ask the chain
+    Unknown,
+}
+
+impl NullifierSet {
+    pub fn insert(&self, nullifier: BigUint) -> bool {
+        self.spent.write().unwrap().insert(nullifier)
+    }
+
+    /// The syncer processed every event up to `block`, the chain head at that time
+    pub fn mark_synced(&self, block: u64) {
+        *self.synced.write().unwrap() = Some((block, Instant::now()));
+    }
+
+    pub fn status(&self, nullifier: &BigUint) -> LocalStatus {
+        self.status_within(nullifier, LOCAL_NULLIFIER_MAX_AGE)
+    }
+
+    fn status_within(&self, nullifier: &BigUint, max_age: Duration) -> LocalStatus {
+        if self.spent.read().unwrap().contains(nullifier) {
+            return LocalStatus::Spent;
+        }
+        match *self.synced.read().unwrap() {
+            Some((block, at)) if at.elapsed() <= max_age => LocalStatus::Unspent { synced_block: block },
+            _ => LocalStatus::Unknown,
+        }
+    }
+
+    /// Hex list for the state file
+    pub fn to_hex(&self) -> Vec<String> {
+        let mut list: Vec<String> = self.spent.read().unwrap().iter().map(|n| format!("0x{:x}", n)).collect();
+        list.sort();
+        list
+    }
+
+    /// Restore from the state file, skipping malformed entries
+    pub fn extend_from_hex(&self, list: &[String]) {
+        let mut spent = self.spent.write().unwrap();
+        for hex in list {
+            if let Ok(n) = BigUint::from_str_radix(hex.trim_start_matches("0x"), 16) {
+                spent.insert(n);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_status() {
+        let set = NullifierSet::default();
+        let spent = BigUint::from(0xabcu32);
+        let other = BigUint::from(0xdefu32);
+        set.insert(spent.clone());
+
+        assert_eq!(set.status(&spent), LocalStatus::Spent);
+        // Never synced: absence proves nothing
+        assert_eq!(set.status(&other), LocalStatus::Unknown);
+
+        set.mark_synced(42);
+        assert_eq!(set.status(&other), LocalStatus::Unspent { synced_block: 42 });
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(set.status_within(&other, Duration::from_millis(1)), LocalStatus::Unknown);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let set = NullifierSet::default();
+        set.insert(BigUint::from(1u8));
+        set.insert(BigUint::from(0xffu8));
+
+        let restored = NullifierSet::default();
+        restored.extend_from_hex(&set.to_hex());
+        restored.extend_from_hex(&["not hex".to_string()]);
+        assert_eq!(restored.to_hex(), set.to_hex());
+        assert_eq!(restored.status(&BigUint::from(0xffu8)), LocalStatus::Spent);
+    }
+}
@@ -0,0 +1,119 @@
+// Where the ASP keeps its on-disk state (syncer cursor, and later tree snapshots)
+// PERSISTENCE=memory keeps everything in memory only: nothing is read from or written to disk,
+// so tests and ephemeral deployments run hermetically and never clobber a real state file.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Persistence {
+    /// Read and write state files (default)
+    Disk,
+    /// Never touch the filesystem; reads find nothing, writes are dropped
+    Memory,
+}
+
+impl Persistence {
+    /// PERSISTENCE=memory|disk (default disk)
+    pub fn from_env() -> Self {
+        match std::env::var("PERSISTENCE").as_deref() {
+            Ok("memory") => Persistence::Memory,
+            _ => Persistence::Disk,
+        }
+    }
+
+    /// Contents of a state file, or None if it doesn't exist (always None in memory mode)
+    pub fn read(&self, path: impl AsRef<Path>) -> Option<String> {
+        match self {
+            Persistence::Disk => fs::read_to_string(path).ok(),
+            Persistence::Memory => None,
+        }
+    }
+
+    /// Write a state file (no-op in memory mode)
+    /// The file is replaced atomically: a crash mid-write leaves the previous version in place
+    pub fn write(&self, path: impl AsRef<Path>, contents: &str) -> Result<(), String> {
+        match self {
+            Persistence::Disk => {
+                let path = path.as_ref();
+                write_atomic(path, contents).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+            }
+            Persistence::Memory => Ok(()),
+        }
+    }
+}
+
+/// Write to a temp file next to `path`, fsync it, then rename it over `path`
+/// (rename is atomic within a filesystem, which the sibling temp file guarantees)
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result?;
+
+    // Persist the rename itself; not supported everywhere, so best effort
+    if let Some(dir) = path.parent().map(|p| if p.as_os_str().is_empty() { Path::new(".") } else { p }) {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("zylith_persistence_{}_{}", name, nanos))
+    }
+
+    #[test]
+    fn test_memory_mode_writes_no_files() {
+        let path = temp_path("memory");
+
+        Persistence::Memory.write(&path, "{\"last_synced_block\":42}").unwrap();
+        assert!(!path.exists());
+        assert_eq!(Persistence::Memory.read(&path), None);
+    }
+
+    #[test]
+    fn test_memory_mode_ignores_existing_files() {
+        let path = temp_path("existing");
+        Persistence::Disk.write(&path, "{\"last_synced_block\":42}").unwrap();
+
+        assert_eq!(Persistence::Disk.read(&path).as_deref(), Some("{\"last_synced_block\":42}"));
+        assert_eq!(Persistence::Memory.read(&path), None);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_disk_write_replaces_file_without_leftovers() {
+        let path = temp_path("atomic");
+        Persistence::Disk.write(&path, "{\"last_synced_block\":1}").unwrap();
+        Persistence::Disk.write(&path, "{\"last_synced_block\":2}").unwrap();
+
+        assert_eq!(Persistence::Disk.read(&path).as_deref(), Some("{\"last_synced_block\":2}"));
+        let mut tmp_name = path.file_name().unwrap().to_os_string();
+        tmp_name.push(".tmp");
+        assert!(!path.with_file_name(tmp_name).exists());
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,72 @@
+// Several Zylith pools served by one ASP
+// CONTRACT_ADDRESSES=0xA,0xB lists the pool contracts to sync and takes precedence over
+// CONTRACT_ADDRESS; the first one is the primary pool. Every pool gets its own deposit tree,
+// syncer, cursor file and tree snapshot, and the read endpoints pick one with ?pool=0x...
+// (the primary pool when absent). The primary pool keeps the unsuffixed file names.
+
+use num_bigint::BigUint;
+
+/// Pool contract addresses from CONTRACT_ADDRESSES, else CONTRACT_ADDRESS, else `default`
+/// Blank entries are ignored and repeated addresses kept once, in order
+pub fn contract_addresses_from_env(default: &str) -> Result<Vec<String>, String> {
+    let list = std::env::var("CONTRACT_ADDRESSES")
+        .ok()
+        .filter(|list| !list.trim().is_empty())
+        .or_else(|| std::env::var("CONTRACT_ADDRESS").ok())
+        .unwrap_or_else(|| default.to_string());
+    parse_addresses(&list)
+}
+
+fn parse_addresses(list: &str) -> Result<Vec<String>, String> {
+    let mut addresses: Vec<String> = Vec::new();
+    let mut keys = Vec::new();
+    for address in list.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        let key = pool_key(address)?;
+        if !keys.contains(&key) {
+            keys.push(key);
+            addresses.push(address.to_string());
+        }
+    }
+    if addresses.is_empty() {
+        return Err("No contract address configured".to_string());
+    }
+    Ok(addresses)
+}
+
+/// Canonical form of a contract address (lowercase hex without leading zeros), used to look pools up
+pub fn pool_key(address: &str) -> Result<String, String> {
+    let hex = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .ok_or_else(|| format!("Contract address {} must be 0x-prefixed hex", address))?;
+    let value = BigUint::parse_bytes(hex.as_bytes(), 16)
+        .filter(|value| value.bits() <= 251)
+        .ok_or_else(|| format!("Invalid contract address {}", address))?;
+    Ok(format!("0x{:x}", value))
+}
+
+/// State file name for a secondary pool: "asp_state.json" becomes "asp_state_0xabc.json"
+pub fn pool_file(base: &str, key: &str) -> String {
+    match base.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}_{}.{}", stem, key, extension),
+        None => format!("{}_{}", base, key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_and_keys() {
+        let addresses = parse_addresses(" 0x00C692a0, ,0xbeef,0xc692a0 ").unwrap();
+        assert_eq!(addresses, vec!["0x00C692a0", "0xbeef"]);
+        assert_eq!(pool_key("0x00C692a0").unwrap(), "0xc692a0");
+        assert!(pool_key("c692a0").is_err());
+        assert!(pool_key("0xzz").is_err());
+        assert!(parse_addresses(" , ").is_err());
+
+        assert_eq!(pool_file("asp_state.json", "0xbeef"), "asp_state_0xbeef.json");
+        assert_eq!(pool_file("asp_tree", "0xbeef"), "asp_tree_0xbeef");
+    }
+}
@@ -1,29 +1,209 @@
 // ZK Proof generation using Circom/snarkjs
 // This module will execute Circom circuits to generate proofs
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs;
-use serde_json;
+use crate::proof_output::ProofOutput;
+use crate::util::felt_prime;
 use tokio::process::Command;
 
+/// Circuits directory (wasm, zkeys, snarkjs node_modules): ZYLITH_CIRCUITS_DIR, or
+/// ../circuits relative to the working directory
+pub fn circuits_dir() -> PathBuf {
+    env_path("ZYLITH_CIRCUITS_DIR").unwrap_or_else(|| {
+        std::env::current_dir()
+            .unwrap_or_default()
+            .parent()
+            .map(|dir| dir.join("circuits"))
+            .unwrap_or_else(|| PathBuf::from("../circuits"))
+    })
+}
+
+/// circuits_dir() as the string the proof functions take
+pub fn circuits_path() -> String {
+    circuits_dir().to_string_lossy().into_owned()
+}
+
+/// rapidsnark prover binary: RAPIDSNARK_BIN, or asp/bin/prover in the source tree
+/// When the default is missing, proofs fall back to snarkjs
+fn rapidsnark_bin() -> PathBuf {
+    env_path("RAPIDSNARK_BIN").unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("bin").join("prover"))
+}
+
+/// Garaga calldata converter: GARAGA_SCRIPT, or scripts/convert_garaga.py in the source tree
+fn garaga_script() -> PathBuf {
+    env_path("GARAGA_SCRIPT").unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("scripts").join("convert_garaga.py")
+    })
+}
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var(var).ok().filter(|v| !v.trim().is_empty()).map(PathBuf::from)
+}
+
+/// Check the prover paths at startup so a bad deployment fails before the first proof
+/// rapidsnark is only required when RAPIDSNARK_BIN is set explicitly
+pub fn check_paths() -> Result<(), String> {
+    let circuits = circuits_dir();
+    if !circuits.join("build").is_dir() {
+        return Err(format!(
+            "Circuits directory {:?} not found or not built (no build/); set ZYLITH_CIRCUITS_DIR",
+            circuits
+        ));
+    }
+    let garaga = garaga_script();
+    if !garaga.is_file() {
+        return Err(format!("Garaga script {:?} not found; set GARAGA_SCRIPT", garaga));
+    }
+    let rapidsnark = rapidsnark_bin();
+    if env_path("RAPIDSNARK_BIN").is_some() && !rapidsnark.is_file() {
+        return Err(format!("RAPIDSNARK_BIN {:?} not found", rapidsnark));
+    }
+    Ok(())
+}
+
+/// Swap proof pipeline stages, reported to progress callbacks as they are reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofStage {
+    /// Witness calculated with snarkjs
+    WitnessCalculated,
+    /// Groth16 proving started (rapidsnark or snarkjs)
+    Proving,
+    /// Converting the proof to Garaga calldata
+    Converting,
+}
+
 /// Generate swap proof using rapidsnark (fast) with correct format conversion
 pub async fn generate_swap_proof(
     circuits_path: &str,
     input_json: serde_json::Value,
 ) -> Result<SwapProof, String> {
-    println!("[Proof] 🔄 Starting swap proof generation with rapidsnark...");
+    generate_swap_proof_with_progress(circuits_path, input_json, &|_| {}).await
+}
+
+/// Same as generate_swap_proof, calling `on_stage` at each stage transition
+pub async fn generate_swap_proof_with_progress(
+    circuits_path: &str,
+    input_json: serde_json::Value,
+    on_stage: &(dyn Fn(ProofStage) + Send + Sync),
+) -> Result<SwapProof, String> {
+    let proof = run_circuit_proof(circuits_path, &SWAP_CIRCUIT, input_json, on_stage).await?;
+    Ok(SwapProof {
+        proof: proof.proof,
+        public_inputs: proof.public_inputs,
+    })
+}
+
+/// Artifacts and output shape of a Circom circuit, consumed by run_circuit_proof
+struct CircuitSpec {
+    /// Artifacts: build/<name>/<name>_js/<name>.wasm and build/zkeys/<name>.zkey
+    name: &'static str,
+    /// Garaga proof calldata elements (A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y)
+    expected_proof_len: usize,
+    expected_public_len: usize,
+    /// felt252 public inputs by index, logged with a check that they fit in u128
+    felt_inputs: &'static [(usize, &'static str)],
+    /// Reduce every public input into the felt252 range instead of only felt_inputs
+    /// (circuits with signed public inputs such as ticks must keep their field encoding)
+    reduce_all_public_inputs: bool,
+    /// Rewrite the Garaga calldata as decimal felt252 values
+    normalize_proof_calldata: bool,
+    /// NODE_OPTIONS heap size for the snarkjs fallback prover
+    snarkjs_heap_mb: u32,
+}
+
+// Public inputs order (swap circuit):
+// 0: nullifier (felt252)
+// 1: root (felt252)
+// 2: new_commitment (felt252)
+// 3: amount_specified (u128)
+// 4: zero_for_one (bool, 0 or 1)
+// 5: expected_amount0_delta (i128 as u256)
+// 6: expected_amount1_delta (i128 as u256)
+// 7: expected_new_sqrt_price_x128 (u256)
+// 8: expected_new_tick (i32 as u256)
+//
+// NOTE: The verifier returns values as u256, and the contract converts felt252 values
+// (indices 0, 1, 2) from u256 to felt252 using try_into().unwrap().
+// This requires that the u256.high = 0, which is guaranteed if the value < 2^128.
+// However, felt252 values can be up to 2^250, so values >= 2^128 will have high != 0.
+// The contract should use .low instead of try_into(), but since we can't modify the contract,
+// we ensure the values are properly formatted as felt252 (within felt252 range).
+const SWAP_CIRCUIT: CircuitSpec = CircuitSpec {
+    name: "swap",
+    expected_proof_len: 8,
+    expected_public_len: 9,
+    felt_inputs: &[(0, "nullifier"), (1, "root"), (2, "new_commitment")],
+    reduce_all_public_inputs: true,
+    normalize_proof_calldata: false,
+    snarkjs_heap_mb: 8192,
+};
+
+// Public inputs order (LP circuit, mint and burn):
+// 0: nullifier (felt252)
+// 1: root (felt252)
+// 2: tick_lower (i32)
+// 3: tick_upper (i32)
+// 4: liquidity (u128)
+// 5: new_commitment (felt252)
+// 6: position_commitment (felt252)
+//
+// The contract converts the felt252 values (indices 0, 1, 5, 6) from u256 using
+// reconstruction (high * q128 + low) when high != 0.
+const LP_CIRCUIT: CircuitSpec = CircuitSpec {
+    name: "lp",
+    expected_proof_len: 8,
+    expected_public_len: 7,
+    felt_inputs: &[(0, "nullifier"), (1, "root"), (5, "new_commitment"), (6, "position_commitment")],
+    reduce_all_public_inputs: false,
+    normalize_proof_calldata: true,
+    snarkjs_heap_mb: 4096,
+};
+
+// Public inputs order (withdraw circuit):
+// 0: nullifier (felt252)
+// 1: root (felt252)
+// 2: recipient (ContractAddress)
+// 3: amount (u128)
+const WITHDRAW_CIRCUIT: CircuitSpec = CircuitSpec {
+    name: "withdraw",
+    expected_proof_len: 8,
+    expected_public_len: 4,
+    felt_inputs: &[(0, "nullifier"), (1, "root")],
+    reduce_all_public_inputs: true,
+    normalize_proof_calldata: true,
+    snarkjs_heap_mb: 4096,
+};
+
+/// Proof calldata and public inputs as returned by run_circuit_proof
+struct CircuitProof {
+    proof: Vec<String>,
+    public_inputs: Vec<String>,
+}
+
+/// Proof pipeline shared by all circuits: write input, calculate witness, prove with
+/// rapidsnark (snarkjs fallback), add protocol field, convert to Garaga calldata and
+/// normalize the public inputs to felt252. Temp files are removed when ProofFiles is dropped,
+/// on success and on every error or cancellation path.
+async fn run_circuit_proof(
+    circuits_path: &str,
+    spec: &CircuitSpec,
+    input_json: serde_json::Value,
+    on_stage: &(dyn Fn(ProofStage) + Send + Sync),
+) -> Result<CircuitProof, String> {
+    let name = spec.name;
+    println!("[Proof] 🔄 Starting {} proof generation with rapidsnark...", name);
     let start_time = std::time::Instant::now();
     
-    // Create temporary files
-    let temp_dir = std::env::temp_dir();
+    // Run files: flat temp files, or <PROOF_OUTPUT_DIR>/<circuit>/job-<id>/ (kept)
     let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
         .unwrap().as_nanos();
-    let input_file = temp_dir.join(format!("swap_input_{}.json", timestamp));
-    let witness_file = temp_dir.join(format!("swap_witness_{}.wtns", timestamp));
-    let proof_file = temp_dir.join(format!("swap_proof_{}.json", timestamp));
-    let public_file = temp_dir.join(format!("swap_public_{}.json", timestamp));
+    let files = ProofOutput::from_env().files(name, &timestamp.to_string())?;
+    let (input_file, witness_file, proof_file, public_file) =
+        (&files.input, &files.witness, &files.proof, &files.public);
     
-    fs::write(&input_file, serde_json::to_string_pretty(&input_json).unwrap())
+    fs::write(input_file, serde_json::to_string_pretty(&input_json).unwrap())
         .map_err(|e| format!("Failed to write input file: {}", e))?;
     
     println!("[Proof] 📝 Input file created: {:?}", input_file);
@@ -31,12 +211,12 @@ pub async fn generate_swap_proof(
     // Paths to circuit files
     let circuits_dir = Path::new(circuits_path).canonicalize()
         .map_err(|e| format!("Failed to canonicalize circuits path: {}", e))?;
-    let wasm_path = circuits_dir.join("build").join("swap").join("swap_js").join("swap.wasm");
-    let zkey_path = circuits_dir.join("build").join("zkeys").join("swap.zkey");
+    let wasm_path = circuits_dir.join("build").join(name)
+        .join(format!("{}_js", name)).join(format!("{}.wasm", name));
+    let zkey_path = circuits_dir.join("build").join("zkeys").join(format!("{}.zkey", name));
     
     // Check for rapidsnark binary
-    let asp_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let rapidsnark_path = asp_dir.join("bin").join("prover");
+    let rapidsnark_path = rapidsnark_bin();
     let use_rapidsnark = rapidsnark_path.exists();
     
     if !wasm_path.exists() {
@@ -46,73 +226,81 @@ pub async fn generate_swap_proof(
         return Err(format!("ZKey file not found: {:?}", zkey_path));
     }
     
+    // Long-lived node process for the snarkjs steps; None runs one node process per step
+    let worker = crate::snarkjs_worker::get(&circuits_dir);
+    
     // Step 1: Calculate witness using snarkjs (this is fast)
     println!("[Proof] 🔧 Step 1: Calculating witness with snarkjs...");
-    let witness_script = format!(
-        r#"
-        const snarkjs = require('snarkjs');
-        const fs = require('fs');
-        const path = require('path');
+    let witness_start = std::time::Instant::now();
+    if let Some(worker) = worker {
+        if let Err(e) = worker.witness(input_file, &wasm_path, witness_file).await {
+            return Err(format!("Witness calculation failed: {}", e));
+        }
+    } else {
+        let witness_script = format!(
+            r#"
+            const snarkjs = require('snarkjs');
+            const fs = require('fs');
+            const path = require('path');
         
-        (async () => {{
-            try {{
-                const input = JSON.parse(fs.readFileSync('{}', 'utf8'));
-                const wasmPath = path.resolve('{}');
+            (async () => {{
+                try {{
+                    const input = JSON.parse(fs.readFileSync('{}', 'utf8'));
+                    const wasmPath = path.resolve('{}');
                 
-                console.log('Calculating witness...');
-                const startTime = Date.now();
+                    console.log('Calculating witness...');
+                    const startTime = Date.now();
                 
-                const {{ wtns }} = await snarkjs;
-                await wtns.calculate(input, wasmPath, '{}');
+                    const {{ wtns }} = await snarkjs;
+                    await wtns.calculate(input, wasmPath, '{}');
                 
-                const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                console.log('Witness calculated in', elapsed, 'seconds');
-            }} catch (error) {{
-                console.error('Error:', error.message);
-                console.error('Stack:', error.stack);
-                process.exit(1);
-            }}
-        }})();
-        "#,
-        input_file.to_str().unwrap().replace('\\', "/"),
-        wasm_path.to_str().unwrap().replace('\\', "/"),
-        witness_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let script_file = circuits_dir.join(format!("witness_script_{}.js", timestamp));
-    fs::write(&script_file, witness_script)
-        .map_err(|e| format!("Failed to write witness script: {}", e))?;
+                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
+                    console.log('Witness calculated in', elapsed, 'seconds');
+                }} catch (error) {{
+                    console.error('Error:', error.message);
+                    console.error('Stack:', error.stack);
+                    process.exit(1);
+                }}
+            }})();
+            "#,
+            input_file.to_str().unwrap().replace('\\', "/"),
+            wasm_path.to_str().unwrap().replace('\\', "/"),
+            witness_file.to_str().unwrap().replace('\\', "/")
+        );
     
-    let witness_start = std::time::Instant::now();
-    let witness_output = Command::new("node")
-        .env("NODE_OPTIONS", "--max-old-space-size=4096")
-        .arg(script_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run witness calculation: {}", e))?;
+        let script_file = files.script(&circuits_dir, &format!("witness_script_{}.js", timestamp))?;
+        fs::write(&script_file, witness_script)
+            .map_err(|e| format!("Failed to write witness script: {}", e))?;
     
-    let _ = fs::remove_file(&script_file);
+        let witness_output = Command::new("node")
+            .env("NODE_OPTIONS", "--max-old-space-size=4096")
+            .arg(&script_file)
+            .current_dir(&circuits_dir)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run witness calculation: {}", e))?;
     
-    if !witness_output.status.success() {
-        let stderr = String::from_utf8_lossy(&witness_output.stderr);
-        let stdout = String::from_utf8_lossy(&witness_output.stdout);
-        let _ = fs::remove_file(&input_file);
-        return Err(format!("Witness calculation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
+        if !witness_output.status.success() {
+            let stderr = String::from_utf8_lossy(&witness_output.stderr);
+            let stdout = String::from_utf8_lossy(&witness_output.stdout);
+            return Err(format!("Witness calculation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
+        }
     }
     
     println!("[Proof] ✅ Witness calculated in {:.2}s", witness_start.elapsed().as_secs_f64());
+    on_stage(ProofStage::WitnessCalculated);
     
     // Step 2: Generate proof (use rapidsnark if available, otherwise snarkjs)
+    on_stage(ProofStage::Proving);
     if use_rapidsnark {
         println!("[Proof] 🔧 Step 2: Generating proof with rapidsnark (fast C++ prover)...");
         let proof_start = std::time::Instant::now();
         
         let rapidsnark_output = Command::new(&rapidsnark_path)
             .arg(&zkey_path)
-            .arg(&witness_file)
-            .arg(&proof_file)
-            .arg(&public_file)
+            .arg(witness_file)
+            .arg(proof_file)
+            .arg(public_file)
             .output()
             .await
             .map_err(|e| format!("Failed to run rapidsnark: {}", e))?;
@@ -120,136 +308,143 @@ pub async fn generate_swap_proof(
         if !rapidsnark_output.status.success() {
             let stderr = String::from_utf8_lossy(&rapidsnark_output.stderr);
             let stdout = String::from_utf8_lossy(&rapidsnark_output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
             return Err(format!("rapidsnark failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
         }
         
         println!("[Proof] ✅ Proof generated with rapidsnark in {:.2}s", proof_start.elapsed().as_secs_f64());
     } else {
         println!("[Proof] 🔧 Step 2: Generating proof with snarkjs (fallback)...");
-        let proof_script = format!(
-            r#"
-            const snarkjs = require('snarkjs');
-            const fs = require('fs');
+        let proof_start = std::time::Instant::now();
+        if let Some(worker) = worker {
+            if let Err(e) = worker.prove(&zkey_path, witness_file, proof_file, public_file).await {
+                return Err(format!("snarkjs proof failed: {}", e));
+            }
+        } else {
+            let proof_script = format!(
+                r#"
+                const snarkjs = require('snarkjs');
+                const fs = require('fs');
             
-            (async () => {{
-                try {{
-                    console.log('Generating proof...');
-                    const startTime = Date.now();
+                (async () => {{
+                    try {{
+                        console.log('Generating proof...');
+                        const startTime = Date.now();
                     
-                    const {{ proof, publicSignals }} = await snarkjs.groth16.prove(
-                        '{}',
-                        '{}'
-                    );
+                        const {{ proof, publicSignals }} = await snarkjs.groth16.prove(
+                            '{}',
+                            '{}'
+                        );
                     
-                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                    console.log('Proof generated in', elapsed, 'seconds');
+                        const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
+                        console.log('Proof generated in', elapsed, 'seconds');
                     
-                    fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-                    fs.writeFileSync('{}', JSON.stringify(publicSignals, null, 2));
-                }} catch (error) {{
-                    console.error('Error:', error.message);
-                    process.exit(1);
-                }}
-            }})();
-            "#,
-            zkey_path.to_str().unwrap().replace('\\', "/"),
-            witness_file.to_str().unwrap().replace('\\', "/"),
-            proof_file.to_str().unwrap().replace('\\', "/"),
-            public_file.to_str().unwrap().replace('\\', "/")
-        );
-        
-        let script_file2 = circuits_dir.join(format!("proof_script_{}.js", timestamp));
-        fs::write(&script_file2, proof_script)
-            .map_err(|e| format!("Failed to write proof script: {}", e))?;
-        
-        let proof_start = std::time::Instant::now();
-        let mut child = Command::new("node")
-            .env("NODE_OPTIONS", "--max-old-space-size=8192")
-            .arg(script_file2.file_name().unwrap())
-            .current_dir(&circuits_dir)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn node: {}", e))?;
-        
-        // Wait with progress updates
-        let mut last_log = std::time::Instant::now();
-        let output = loop {
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    let output = child.wait_with_output().await
-                        .map_err(|e| format!("Failed to get output: {}", e))?;
-                    break output;
-                }
-                Ok(None) => {
-                    if last_log.elapsed().as_secs() >= 30 {
-                        println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
-                        last_log = std::time::Instant::now();
+                        fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
+                        fs.writeFileSync('{}', JSON.stringify(publicSignals, null, 2));
+                    }} catch (error) {{
+                        console.error('Error:', error.message);
+                        process.exit(1);
+                    }}
+                }})();
+                "#,
+                zkey_path.to_str().unwrap().replace('\\', "/"),
+                witness_file.to_str().unwrap().replace('\\', "/"),
+                proof_file.to_str().unwrap().replace('\\', "/"),
+                public_file.to_str().unwrap().replace('\\', "/")
+            );
+        
+            let script_file2 = files.script(&circuits_dir, &format!("proof_script_{}.js", timestamp))?;
+            fs::write(&script_file2, proof_script)
+                .map_err(|e| format!("Failed to write proof script: {}", e))?;
+        
+            let mut child = Command::new("node")
+                .env("NODE_OPTIONS", format!("--max-old-space-size={}", spec.snarkjs_heap_mb))
+                .arg(&script_file2)
+                .current_dir(&circuits_dir)
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("Failed to spawn node: {}", e))?;
+        
+            // Wait with progress updates
+            let mut last_log = std::time::Instant::now();
+            let output = loop {
+                match child.try_wait() {
+                    Ok(Some(_)) => {
+                        let output = child.wait_with_output().await
+                            .map_err(|e| format!("Failed to get output: {}", e))?;
+                        break output;
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+                    Ok(None) => {
+                        if last_log.elapsed().as_secs() >= 30 {
+                            println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
+                            last_log = std::time::Instant::now();
+                        }
+                        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+                    }
+                    Err(e) => return Err(format!("Error waiting: {}", e)),
                 }
-                Err(e) => return Err(format!("Error waiting: {}", e)),
-            }
-        };
-        
-        let _ = fs::remove_file(&script_file2);
+            };
         
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Err(format!("snarkjs proof failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                return Err(format!("snarkjs proof failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
+            }
         }
         
         println!("[Proof] ✅ Proof generated with snarkjs in {:.2}s", proof_start.elapsed().as_secs_f64());
     }
     
     // Step 3: Add protocol field to proof (required by convert_garaga.py script)
+    on_stage(ProofStage::Converting);
     println!("[Proof] 🔧 Step 3: Adding protocol field to proof...");
-    let add_protocol_script = format!(
-        r#"
-        const fs = require('fs');
-        const proof = JSON.parse(fs.readFileSync('{}', 'utf8'));
+    if let Some(worker) = worker {
+        worker.normalize_proof(proof_file).await
+            .map_err(|e| format!("Failed to add protocol field: {}", e))?;
+    } else {
+        let add_protocol_script = format!(
+            r#"
+            const fs = require('fs');
+            const proof = JSON.parse(fs.readFileSync('{}', 'utf8'));
         
-        // Add protocol field if not present (required by convert_garaga.py)
-        if (!proof.protocol) {{
-            proof.protocol = "groth16";
-        }}
+            // Add protocol field if not present (required by convert_garaga.py)
+            if (!proof.protocol) {{
+                proof.protocol = "groth16";
+            }}
         
-        // Ensure pi_a, pi_b, pi_c are in correct format (remove extra elements)
-        if (proof.pi_a && proof.pi_a.length > 2) {{
-            proof.pi_a = [proof.pi_a[0], proof.pi_a[1]];
-        }}
-        if (proof.pi_b && proof.pi_b.length > 2) {{
-            proof.pi_b = [proof.pi_b[0], proof.pi_b[1]];
-        }}
-        if (proof.pi_c && proof.pi_c.length > 2) {{
-            proof.pi_c = [proof.pi_c[0], proof.pi_c[1]];
-        }}
+            // Ensure pi_a, pi_b, pi_c are in correct format (remove extra elements)
+            if (proof.pi_a && proof.pi_a.length > 2) {{
+                proof.pi_a = [proof.pi_a[0], proof.pi_a[1]];
+            }}
+            if (proof.pi_b && proof.pi_b.length > 2) {{
+                proof.pi_b = [proof.pi_b[0], proof.pi_b[1]];
+            }}
+            if (proof.pi_c && proof.pi_c.length > 2) {{
+                proof.pi_c = [proof.pi_c[0], proof.pi_c[1]];
+            }}
         
-        fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-        "#,
-        proof_file.to_str().unwrap().replace('\\', "/"),
-        proof_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let protocol_file = circuits_dir.join(format!("add_protocol_{}.js", timestamp));
-    fs::write(&protocol_file, add_protocol_script)
-        .map_err(|e| format!("Failed to write protocol script: {}", e))?;
+            fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
+            "#,
+            proof_file.to_str().unwrap().replace('\\', "/"),
+            proof_file.to_str().unwrap().replace('\\', "/")
+        );
     
-    let protocol_output = Command::new("node")
-        .arg(protocol_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run protocol script: {}", e))?;
+        let protocol_file = files.script(&circuits_dir, &format!("add_protocol_{}.js", timestamp))?;
+        fs::write(&protocol_file, add_protocol_script)
+            .map_err(|e| format!("Failed to write protocol script: {}", e))?;
     
-    let _ = fs::remove_file(&protocol_file);
+        let protocol_output = Command::new("node")
+            .arg(&protocol_file)
+            .current_dir(&circuits_dir)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run protocol script: {}", e))?;
     
-    if !protocol_output.status.success() {
-        let stderr = String::from_utf8_lossy(&protocol_output.stderr);
-        return Err(format!("Failed to add protocol field: {}", stderr));
+        if !protocol_output.status.success() {
+            let stderr = String::from_utf8_lossy(&protocol_output.stderr);
+            return Err(format!("Failed to add protocol field: {}", stderr));
+        }
     }
     
     println!("[Proof] ✅ Protocol field added to proof");
@@ -258,10 +453,7 @@ pub async fn generate_swap_proof(
     println!("[Proof] 🔧 Step 4: Converting proof to Garaga format and generating calldata...");
     let garaga_start = std::time::Instant::now();
     
-    // Get script path (relative to project root)
-    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent()
-        .ok_or("Failed to get project root")?;
-    let script_path = project_root.join("scripts").join("convert_garaga.py");
+    let script_path = garaga_script();
     
     if !script_path.exists() {
         return Err(format!("Garaga conversion script not found: {:?}", script_path));
@@ -270,7 +462,7 @@ pub async fn generate_swap_proof(
     // Call Python script to convert proof and generate calldata directly
     let script_output = Command::new("python3")
         .arg(&script_path)
-        .arg(&proof_file)
+        .arg(proof_file)
         .output()
         .await
         .map_err(|e| format!("Failed to run convert_garaga.py script: {}", e))?;
@@ -282,10 +474,7 @@ pub async fn generate_swap_proof(
         println!("[Proof] 📋 STDERR:\n{}", stderr);
         println!("[Proof] 📋 STDOUT:\n{}", stdout);
         println!("[Proof] 💾 Proof saved at: {:?}", proof_file);
-        
-        let _ = fs::remove_file(&input_file);
-        let _ = fs::remove_file(&witness_file);
-        let _ = fs::remove_file(&public_file);
+        files.keep(proof_file);
         
         return Err(format!(
             "Garaga conversion script failed.\n\
@@ -299,820 +488,233 @@ pub async fn generate_swap_proof(
     
     // Parse calldata from script output (JSON array)
     let script_stdout = String::from_utf8_lossy(&script_output.stdout);
-    let proof_calldata: Vec<String> = serde_json::from_str(script_stdout.trim())
+    let proof_calldata_raw: Vec<String> = serde_json::from_str(script_stdout.trim())
         .map_err(|e| format!("Failed to parse calldata from script: {}. Output: {}", e, script_stdout))?;
+    let proof_calldata = if spec.normalize_proof_calldata {
+        normalize_proof_calldata(&proof_calldata_raw)?
+    } else {
+        proof_calldata_raw
+    };
     
     println!("[Proof] ✅ Garaga calldata generated in {:.2}s", garaga_start.elapsed().as_secs_f64());
     println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
     
     // Read public signals for the response
     let public_signals: Vec<serde_json::Value> = serde_json::from_str(
-        &fs::read_to_string(&public_file)
+        &fs::read_to_string(public_file)
             .map_err(|e| format!("Failed to read public signals: {}", e))?
     ).map_err(|e| format!("Failed to parse public signals: {}", e))?;
     
-    // CRITICAL: Validate public_signals length BEFORE processing
-    if public_signals.len() != 9 {
-        return Err(format!(
-            "Invalid public signals length: expected 9 elements (swap circuit), got {}. \
-            This indicates the circuit did not generate the expected number of public inputs. \
-            Check the circuit output and ensure all 9 public inputs are being generated.",
-            public_signals.len()
-        ));
-    }
-    
-    // Apply felt252 modulo to public inputs to prevent overflow
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
-    use num_bigint::BigUint;
-    use num_traits::Num;
-    use std::str::FromStr;
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
-    // Public inputs order (swap circuit):
-    // 0: nullifier (felt252)
-    // 1: root (felt252)
-    // 2: new_commitment (felt252)
-    // 3: amount_specified (u128)
-    // 4: zero_for_one (bool, 0 or 1)
-    // 5: expected_amount0_delta (i128 as u256)
-    // 6: expected_amount1_delta (i128 as u256)
-    // 7: expected_new_sqrt_price_x128 (u256)
-    // 8: expected_new_tick (i32 as u256)
-    //
-    // NOTE: The verifier returns values as u256, and the contract converts felt252 values
-    // (indices 0, 1, 2) from u256 to felt252 using try_into().unwrap().
-    // This requires that the u256.high = 0, which is guaranteed if the value < 2^128.
-    // However, felt252 values can be up to 2^250, so values >= 2^128 will have high != 0.
-    // The contract should use .low instead of try_into(), but since we can't modify the contract,
-    // we ensure the values are properly formatted as felt252 (within felt252 range).
-    // The actual conversion issue must be handled by the contract using .low for felt252 values.
+    let public_inputs = normalize_public_inputs(spec, &public_signals)?;
     
-    // Read public signals and apply felt252 modulo
-    let public_inputs: Vec<String> = public_signals
-        .iter()
-        .enumerate()
-        .map(|(idx, s)| {
-            let value_str = s.as_str()
-                .ok_or_else(|| format!("Public signal at index {} is not a string: {:?}", idx, s))?;
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if value_str.starts_with("0x") {
-                BigUint::from_str_radix(&value_str[2..], 16)
-                    .map_err(|e| format!("Failed to parse hex value at index {}: {}", idx, e))?
-            } else {
-                BigUint::from_str(value_str)
-                    .map_err(|e| format!("Failed to parse decimal value at index {}: {}", idx, e))?
-            };
-            
-            // Apply felt252 modulo if value exceeds limit
-            let modulo_big = if value_big >= felt_max_big {
-                &value_big % &felt_max_big
-            } else {
-                value_big.clone()
-            };
-            
-            // Convert to string (decimal format for felt252)
-            Ok(modulo_big.to_string())
-        })
-        .collect::<Result<Vec<String>, String>>()?;
-    
-    // Proof calldata should only contain the 8 proof elements (A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y)
-    // Public inputs are returned separately
-    // The contract expects: proof (8 elements) and public_inputs (9 elements) as separate arrays
+    // Proof calldata should only contain the proof elements; public inputs are returned separately
     let proof_len = proof_calldata.len();
     
-    println!("[Proof]    Proof calldata length: {} elements (should be 8)", proof_len);
-    println!("[Proof]    Public inputs length: {} elements (should be 9)", public_inputs.len());
+    println!("[Proof]    Proof calldata length: {} elements (should be {})", proof_len, spec.expected_proof_len);
+    println!("[Proof]    Public inputs length: {} elements (should be {})", public_inputs.len(), spec.expected_public_len);
     
-    // Verify proof has exactly 8 elements
-    if proof_len != 8 {
-        return Err(format!("Invalid proof length: expected 8 elements, got {}", proof_len));
+    if proof_len != spec.expected_proof_len {
+        return Err(format!("Invalid proof length: expected {} elements, got {}", spec.expected_proof_len, proof_len));
     }
     
-    // Verify public inputs have exactly 9 elements
-    if public_inputs.len() != 9 {
-        return Err(format!("Invalid public inputs length: expected 9 elements, got {}", public_inputs.len()));
-    }
-    
-    // Log and validate felt252 values (indices 0, 1, 2) to help diagnose conversion issues
-    // The contract converts these from u256 to felt252 using try_into(), which requires high = 0
-    let u128_max_big = BigUint::from_str("340282366920938463463374607431768211455") // 2^128 - 1
-        .map_err(|_| "Failed to parse U128_MAX".to_string())?;
-    
-    for (idx, val_str) in public_inputs.iter().take(3).enumerate() {
-        let val_big = BigUint::from_str(val_str)
-            .unwrap_or_else(|_| BigUint::from(0u8));
-        let fits_in_u128 = val_big < u128_max_big;
-        let field_name = match idx {
-            0 => "nullifier",
-            1 => "root",
-            2 => "new_commitment",
-            _ => unreachable!(),
-        };
-        println!("[Proof]    {}[{}]: {} (fits in u128: {})", field_name, idx, val_str, fits_in_u128);
-        if !fits_in_u128 {
-            println!("[Proof]    ⚠️  WARNING: {} value >= 2^128, verifier will return u256 with high != 0", field_name);
-            println!("[Proof]    ⚠️  Contract's try_into() will fail. Contract should use .low instead.");
+    // Log felt252 values to help diagnose u256 -> felt252 conversion issues in the contract
+    let u128_max_big = num_bigint::BigUint::from(u128::MAX);
+    for (idx, field_name) in spec.felt_inputs {
+        if let Some(val_str) = public_inputs.get(*idx) {
+            let fits_in_u128 = parse_proof_value(val_str).is_some_and(|v| v < u128_max_big);
+            println!("[Proof]    {}[{}]: {} (fits in u128: {})", field_name, idx, val_str, fits_in_u128);
+            if !fits_in_u128 {
+                println!("[Proof]    ⚠️  WARNING: {} value >= 2^128, verifier will return u256 with high != 0", field_name);
+            }
         }
     }
     
-    // Clean up temp files
-    let _ = fs::remove_file(&input_file);
-    let _ = fs::remove_file(&witness_file);
-    let _ = fs::remove_file(&proof_file);
-    let _ = fs::remove_file(&public_file);
+    // Temp files are removed when `files` is dropped
+    if let Some(job_dir) = &files.job_dir {
+        println!("[Proof] 💾 Run files kept in {:?}", job_dir);
+    }
     
     let elapsed = start_time.elapsed().as_secs_f64();
     println!("[Proof] ✅ Total proof time: {:.2}s ({})", elapsed, 
         if use_rapidsnark { "with rapidsnark" } else { "with snarkjs" });
     
-    Ok(SwapProof {
-        proof: proof_calldata, // Only the 8 proof elements, not combined with public inputs
+    Ok(CircuitProof {
+        proof: proof_calldata,
         public_inputs,
     })
 }
 
-/// Normalize rapidsnark proof format to snarkjs format for Garaga
-/// snarkjs format: { pi_a: [x, y], pi_b: [[x0, x1], [y0, y1]], pi_c: [x, y] }
-/// NO protocol, NO curve fields - Garaga detects curve from VK
-fn normalize_proof_for_garaga(proof: serde_json::Value) -> Result<serde_json::Value, String> {
-    let mut normalized = serde_json::Map::new();
-    
-    // Extract and normalize pi_a
-    // snarkjs format: [x, y] (2 elements)
-    // rapidsnark format: [x, y, "1"] (3 elements)
-    // Garaga expects snarkjs format (2 elements)
-    if let Some(pi_a) = proof.get("pi_a").and_then(|v| v.as_array()) {
-        if pi_a.len() >= 2 {
-            let mut pi_a_normalized = Vec::new();
-            pi_a_normalized.push(pi_a[0].clone());
-            pi_a_normalized.push(pi_a[1].clone());
-            // Always use 2 elements (snarkjs format) - remove "1" if present
-            normalized.insert("pi_a".to_string(), serde_json::Value::Array(pi_a_normalized));
-        } else {
-            return Err(format!("Invalid pi_a format: expected at least 2 elements, got {}", pi_a.len()));
-        }
+/// Reduce a BN254 field element into the felt252 range (modulo the Starknet prime)
+fn reduce_to_felt(value: num_bigint::BigUint) -> num_bigint::BigUint {
+    let prime = felt_prime();
+    if value >= prime {
+        value % prime
     } else {
-        return Err("Missing pi_a".to_string());
+        value
     }
-    
-    // Extract and normalize pi_b
-    // rapidsnark may output pi_b as nested arrays [[x0, x1], [y0, y1], [1, 0]]
-    // or as a flat array [x0, x1, y0, y1, 1, 0]
-    if let Some(pi_b) = proof.get("pi_b").and_then(|v| v.as_array()) {
-        let mut pi_b_normalized = Vec::new();
-        
-        if pi_b.len() >= 2 {
-            // Check if first element is an array (nested format)
-            if let Some(b_x) = pi_b[0].as_array() {
-                // Nested format: [[x0, x1], [y0, y1], ...]
-                if b_x.len() >= 2 {
-                    let mut b_x_normalized = Vec::new();
-                    // CRITICAL: rapidsnark exports as [imaginario, real] but Garaga expects [real, imaginario]
-                    // Swap coordinates: [a, b] -> [b, a]
-                    b_x_normalized.push(b_x[1].clone()); // real
-                    b_x_normalized.push(b_x[0].clone()); // imaginario
-                    pi_b_normalized.push(serde_json::Value::Array(b_x_normalized));
-                } else {
-                    return Err("Invalid pi_b[0] format: expected at least 2 elements".to_string());
-                }
-                
-                if pi_b.len() >= 2 {
-                    if let Some(b_y) = pi_b[1].as_array() {
-                        if b_y.len() >= 2 {
-                            let mut b_y_normalized = Vec::new();
-                            // CRITICAL: Swap coordinates for Garaga compatibility
-                            b_y_normalized.push(b_y[1].clone()); // real
-                            b_y_normalized.push(b_y[0].clone()); // imaginario
-                            pi_b_normalized.push(serde_json::Value::Array(b_y_normalized));
-                        } else {
-                            return Err("Invalid pi_b[1] format: expected at least 2 elements".to_string());
-                        }
-                    } else {
-                        return Err("pi_b[1] is not an array".to_string());
-                    }
-                }
-            } else if pi_b.len() >= 6 {
-                // Flat format: [x0, x1, y0, y1, 1, 0]
-                // CRITICAL: Swap coordinates for Garaga compatibility
-                let mut b_x_normalized = Vec::new();
-                b_x_normalized.push(pi_b[1].clone()); // real (swap)
-                b_x_normalized.push(pi_b[0].clone()); // imaginario (swap)
-                pi_b_normalized.push(serde_json::Value::Array(b_x_normalized));
-                
-                let mut b_y_normalized = Vec::new();
-                b_y_normalized.push(pi_b[3].clone()); // real (swap)
-                b_y_normalized.push(pi_b[2].clone()); // imaginario (swap)
-                pi_b_normalized.push(serde_json::Value::Array(b_y_normalized));
-            } else {
-                return Err(format!("Invalid pi_b format: expected nested arrays or 6+ elements, got {} elements", pi_b.len()));
-            }
-        } else {
-            return Err(format!("Invalid pi_b format: expected at least 2 elements, got {}", pi_b.len()));
-        }
-        
-        normalized.insert("pi_b".to_string(), serde_json::Value::Array(pi_b_normalized));
-    } else {
-        return Err("Missing pi_b".to_string());
-    }
-    
-    // Extract and normalize pi_c
-    // snarkjs format: [x, y] (2 elements)
-    // rapidsnark format: [x, y, "1"] (3 elements)
-    // Garaga expects snarkjs format (2 elements)
-    if let Some(pi_c) = proof.get("pi_c").and_then(|v| v.as_array()) {
-        if pi_c.len() >= 2 {
-            let mut pi_c_normalized = Vec::new();
-            pi_c_normalized.push(pi_c[0].clone());
-            pi_c_normalized.push(pi_c[1].clone());
-            // Always use 2 elements (snarkjs format) - remove "1" if present
-            normalized.insert("pi_c".to_string(), serde_json::Value::Array(pi_c_normalized));
-        } else {
-            return Err(format!("Invalid pi_c format: expected at least 2 elements, got {}", pi_c.len()));
-        }
-    } else {
-        return Err("Missing pi_c".to_string());
-    }
-    
-    // snarkjs proof format does NOT include 'protocol' or 'curve' fields
-    // Garaga detects the curve from the VK file
-    // So we should NOT include these fields to match snarkjs format exactly
-    
-    Ok(serde_json::Value::Object(normalized))
 }
 
-/// Parse Garaga CLI array output format
-/// Garaga can output in different formats, we support the "array" format
-fn parse_garaga_array_output(output: &str) -> Result<Vec<String>, String> {
-    // Garaga array format output looks like:
-    // [0x123, 0x456, ...] or array elements on separate lines
-    let trimmed = output.trim();
-    
-    // Try parsing as JSON array first
-    if trimmed.starts_with('[') && trimmed.ends_with(']') {
-        // Remove brackets and split by comma
-        let inner = &trimmed[1..trimmed.len()-1];
-        let values: Vec<String> = inner
-            .split(',')
-            .map(|s| {
-                let s = s.trim();
-                // Remove quotes if present
-                if s.starts_with('"') && s.ends_with('"') {
-                    s[1..s.len()-1].to_string()
-                } else {
-                    s.to_string()
-                }
-            })
-            .filter(|s| !s.is_empty())
-            .collect();
-        
-        if values.is_empty() {
-            return Err("Garaga output is empty".to_string());
-        }
-        
-        return Ok(values);
-    }
-    
-    // Try parsing line by line (snforge format)
-    let lines: Vec<String> = trimmed
-        .lines()
-        .map(|l| l.trim().to_string())
-        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("//"))
-        .collect();
-    
-    if !lines.is_empty() {
-        return Ok(lines);
-    }
-    
-    Err(format!("Failed to parse Garaga output: {}", trimmed))
-}
-
-/// Generate withdraw proof using Circom circuit
-pub async fn generate_withdraw_proof(
-    _circuits_path: &str,
-    _inputs: WithdrawProofInputs,
-) -> Result<WithdrawProof, String> {
-    // TODO: Implement Circom proof generation
-    Err("Withdraw proof generation not yet implemented".to_string())
-}
-
-/// Generate LP proof (mint/burn) using rapidsnark (fast) with correct format conversion
-/// This function works for both mint and burn operations (same circuit)
-pub async fn generate_lp_proof(
-    circuits_path: &str,
-    input_json: serde_json::Value,
-) -> Result<LiquidityProof, String> {
-    println!("[Proof] 🔄 Starting LP proof generation with rapidsnark...");
-    let start_time = std::time::Instant::now();
-    
-    // Create temporary files
-    let temp_dir = std::env::temp_dir();
-    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
-        .unwrap().as_nanos();
-    let input_file = temp_dir.join(format!("lp_input_{}.json", timestamp));
-    let witness_file = temp_dir.join(format!("lp_witness_{}.wtns", timestamp));
-    let proof_file = temp_dir.join(format!("lp_proof_{}.json", timestamp));
-    let public_file = temp_dir.join(format!("lp_public_{}.json", timestamp));
-    
-    fs::write(&input_file, serde_json::to_string_pretty(&input_json).unwrap())
-        .map_err(|e| format!("Failed to write input file: {}", e))?;
-    
-    println!("[Proof] 📝 Input file created: {:?}", input_file);
-    
-    // Paths to circuit files
-    let circuits_dir = Path::new(circuits_path).canonicalize()
-        .map_err(|e| format!("Failed to canonicalize circuits path: {}", e))?;
-    let wasm_path = circuits_dir.join("build").join("lp").join("lp_js").join("lp.wasm");
-    let zkey_path = circuits_dir.join("build").join("zkeys").join("lp.zkey");
-    
-    // Check for rapidsnark binary
-    let asp_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let rapidsnark_path = asp_dir.join("bin").join("prover");
-    let use_rapidsnark = rapidsnark_path.exists();
-    
-    if !wasm_path.exists() {
-        return Err(format!("WASM file not found: {:?}", wasm_path));
-    }
-    if !zkey_path.exists() {
-        return Err(format!("ZKey file not found: {:?}", zkey_path));
-    }
-    
-    // Step 1: Calculate witness using snarkjs (this is fast)
-    println!("[Proof] 🔧 Step 1: Calculating witness with snarkjs...");
-    let witness_script = format!(
-        r#"
-        const snarkjs = require('snarkjs');
-        const fs = require('fs');
-        const path = require('path');
-        
-        (async () => {{
-            try {{
-                const input = JSON.parse(fs.readFileSync('{}', 'utf8'));
-                const wasmPath = path.resolve('{}');
-                
-                console.log('Calculating witness...');
-                const startTime = Date.now();
-                
-                const {{ wtns }} = await snarkjs;
-                await wtns.calculate(input, wasmPath, '{}');
-                
-                const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                console.log('Witness calculated in', elapsed, 'seconds');
-            }} catch (error) {{
-                console.error('Error:', error.message);
-                console.error('Stack:', error.stack);
-                process.exit(1);
-            }}
-        }})();
-        "#,
-        input_file.to_str().unwrap().replace('\\', "/"),
-        wasm_path.to_str().unwrap().replace('\\', "/"),
-        witness_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let script_file = circuits_dir.join(format!("witness_script_{}.js", timestamp));
-    fs::write(&script_file, witness_script)
-        .map_err(|e| format!("Failed to write witness script: {}", e))?;
-    
-    let witness_start = std::time::Instant::now();
-    let witness_output = Command::new("node")
-        .env("NODE_OPTIONS", "--max-old-space-size=4096")
-        .arg(script_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run witness calculation: {}", e))?;
-    
-    let _ = fs::remove_file(&script_file);
-    
-    if !witness_output.status.success() {
-        let stderr = String::from_utf8_lossy(&witness_output.stderr);
-        let stdout = String::from_utf8_lossy(&witness_output.stdout);
-        let _ = fs::remove_file(&input_file);
-        return Err(format!("Witness calculation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-    }
-    
-    println!("[Proof] ✅ Witness calculated in {:.2}s", witness_start.elapsed().as_secs_f64());
-    
-    // Step 2: Generate proof (use rapidsnark if available, otherwise snarkjs)
-    if use_rapidsnark {
-        println!("[Proof] 🔧 Step 2: Generating proof with rapidsnark (fast C++ prover)...");
-        let proof_start = std::time::Instant::now();
-        
-        let rapidsnark_output = Command::new(&rapidsnark_path)
-            .arg(&zkey_path)
-            .arg(&witness_file)
-            .arg(&proof_file)
-            .arg(&public_file)
-            .output()
-            .await
-            .map_err(|e| format!("Failed to run rapidsnark: {}", e))?;
-        
-        if !rapidsnark_output.status.success() {
-            let stderr = String::from_utf8_lossy(&rapidsnark_output.stderr);
-            let stdout = String::from_utf8_lossy(&rapidsnark_output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
-            return Err(format!("rapidsnark failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-        }
-        
-        println!("[Proof] ✅ Proof generated with rapidsnark in {:.2}s", proof_start.elapsed().as_secs_f64());
-    } else {
-        println!("[Proof] 🔧 Step 2: Generating proof with snarkjs (fallback)...");
-        let proof_script = format!(
-            r#"
-            const snarkjs = require('snarkjs');
-            const fs = require('fs');
-            
-            (async () => {{
-                try {{
-                    console.log('Generating proof...');
-                    const startTime = Date.now();
-                    
-                    const {{ proof, publicSignals }} = await snarkjs.groth16.prove(
-                        '{}',
-                        '{}'
-                    );
-                    
-                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                    console.log('Proof generated in', elapsed, 'seconds');
-                    
-                    fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-                    fs.writeFileSync('{}', JSON.stringify(publicSignals, null, 2));
-                }} catch (error) {{
-                    console.error('Error:', error.message);
-                    process.exit(1);
-                }}
-            }})();
-            "#,
-            zkey_path.to_str().unwrap().replace('\\', "/"),
-            witness_file.to_str().unwrap().replace('\\', "/"),
-            proof_file.to_str().unwrap().replace('\\', "/"),
-            public_file.to_str().unwrap().replace('\\', "/")
-        );
-        
-        let script_file2 = circuits_dir.join(format!("proof_script_{}.js", timestamp));
-        fs::write(&script_file2, proof_script)
-            .map_err(|e| format!("Failed to write proof script: {}", e))?;
-        
-        let proof_start = std::time::Instant::now();
-        let mut child = Command::new("node")
-            .env("NODE_OPTIONS", "--max-old-space-size=4096")
-            .arg(script_file2.file_name().unwrap())
-            .current_dir(&circuits_dir)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn node: {}", e))?;
-        
-        // Wait with progress updates
-        let mut last_log = std::time::Instant::now();
-        let output = loop {
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    let output = child.wait_with_output().await
-                        .map_err(|e| format!("Failed to get output: {}", e))?;
-                    break output;
-                }
-                Ok(None) => {
-                    if last_log.elapsed().as_secs() >= 30 {
-                        println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
-                        last_log = std::time::Instant::now();
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                }
-                Err(e) => return Err(format!("Error waiting: {}", e)),
-            }
-        };
-        
-        let _ = fs::remove_file(&script_file2);
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
-            return Err(format!("snarkjs proof failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-        }
-        
-        println!("[Proof] ✅ Proof generated with snarkjs in {:.2}s", proof_start.elapsed().as_secs_f64());
-    }
-    
-    // Step 3: Add protocol field to proof (required by convert_garaga.py script)
-    println!("[Proof] 🔧 Step 3: Adding protocol field to proof...");
-    let add_protocol_script = format!(
-        r#"
-        const fs = require('fs');
-        const proof = JSON.parse(fs.readFileSync('{}', 'utf8'));
-        
-        // Add protocol field if not present (required by convert_garaga.py)
-        if (!proof.protocol) {{
-            proof.protocol = "groth16";
-        }}
-        
-        // Ensure pi_a, pi_b, pi_c are in correct format (remove extra elements)
-        if (proof.pi_a && proof.pi_a.length > 2) {{
-            proof.pi_a = [proof.pi_a[0], proof.pi_a[1]];
-        }}
-        if (proof.pi_b && proof.pi_b.length > 2) {{
-            proof.pi_b = [proof.pi_b[0], proof.pi_b[1]];
-        }}
-        if (proof.pi_c && proof.pi_c.length > 2) {{
-            proof.pi_c = [proof.pi_c[0], proof.pi_c[1]];
-        }}
-        
-        fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-        "#,
-        proof_file.to_str().unwrap().replace('\\', "/"),
-        proof_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let protocol_file = circuits_dir.join(format!("add_protocol_{}.js", timestamp));
-    fs::write(&protocol_file, add_protocol_script)
-        .map_err(|e| format!("Failed to write protocol script: {}", e))?;
-    
-    let protocol_output = Command::new("node")
-        .arg(protocol_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run protocol script: {}", e))?;
-    
-    let _ = fs::remove_file(&protocol_file);
-    
-    if !protocol_output.status.success() {
-        let stderr = String::from_utf8_lossy(&protocol_output.stderr);
-        return Err(format!("Failed to add protocol field: {}", stderr));
-    }
-    
-    println!("[Proof] ✅ Protocol field added to proof");
-    
-    // Step 4: Convert proof to Garaga format and generate calldata using Python script
-    println!("[Proof] 🔧 Step 4: Converting proof to Garaga format and generating calldata...");
-    let garaga_start = std::time::Instant::now();
-    
-    // Get script path (relative to project root)
-    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent()
-        .ok_or("Failed to get project root")?;
-    let script_path = project_root.join("scripts").join("convert_garaga.py");
-    
-    if !script_path.exists() {
-        return Err(format!("Garaga conversion script not found: {:?}", script_path));
-    }
-    
-    // Call Python script to convert proof and generate calldata directly
-    let script_output = Command::new("python3")
-        .arg(&script_path)
-        .arg(&proof_file)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run convert_garaga.py script: {}", e))?;
-    
-    if !script_output.status.success() {
-        let stderr = String::from_utf8_lossy(&script_output.stderr);
-        let stdout = String::from_utf8_lossy(&script_output.stdout);
-        println!("[Proof] ❌ Python script failed.");
-        println!("[Proof] 📋 STDERR:\n{}", stderr);
-        println!("[Proof] 📋 STDOUT:\n{}", stdout);
-        println!("[Proof] 💾 Proof saved at: {:?}", proof_file);
-        
-        let _ = fs::remove_file(&input_file);
-        let _ = fs::remove_file(&witness_file);
-        let _ = fs::remove_file(&public_file);
-        
-        return Err(format!(
-            "Garaga conversion script failed.\n\
-             STDERR: {}\n\
-             STDOUT: {}\n\
-             \n\
-             Proof file at: {:?}",
-            stderr, stdout, proof_file
-        ));
-    }
-    
-    // Parse calldata from script output (JSON array)
-    let script_stdout = String::from_utf8_lossy(&script_output.stdout);
-    let proof_calldata_raw: Vec<String> = serde_json::from_str(script_stdout.trim())
-        .map_err(|e| format!("Failed to parse calldata from script: {}. Output: {}", e, script_stdout))?;
-    
-    // Apply felt252 modulo to all proof values (BN254 field values can exceed felt252 max)
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
-    use num_bigint::BigUint;
-    use num_traits::Num;
-    use std::str::FromStr;
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
-    let proof_calldata: Vec<String> = proof_calldata_raw
-        .iter()
-        .map(|val_str| {
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if val_str.starts_with("0x") {
-                BigUint::from_str_radix(&val_str[2..], 16)
-                    .unwrap_or_else(|_| BigUint::from(0u8))
-            } else {
-                BigUint::from_str(val_str)
-                    .unwrap_or_else(|_| BigUint::from(0u8))
-            };
-            
-            // Apply felt252 modulo if value exceeds limit
-            let modulo_big = if value_big >= felt_max_big {
-                &value_big % &felt_max_big
-            } else {
-                value_big.clone()
-            };
-            
-            // Convert to string (decimal format for felt252)
-            modulo_big.to_string()
-        })
-        .collect();
-    
-    println!("[Proof] ✅ Garaga calldata generated in {:.2}s", garaga_start.elapsed().as_secs_f64());
-    println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
-    
-    // Read public signals for the response
-    let public_signals: Vec<serde_json::Value> = serde_json::from_str(
-        &fs::read_to_string(&public_file)
-            .map_err(|e| format!("Failed to read public signals: {}", e))?
-    ).map_err(|e| format!("Failed to parse public signals: {}", e))?;
-    
+/// Public signals (hex or decimal strings) to decimal strings, reduced to felt252 per the spec
+/// Fails unless there are exactly spec.expected_public_len signals and every reduced one is a valid
+/// felt252, so a circuit/contract mismatch is reported here instead of as a reverted transaction
+fn normalize_public_inputs(spec: &CircuitSpec, public_signals: &[serde_json::Value]) -> Result<Vec<String>, String> {
     // CRITICAL: Validate public_signals length BEFORE processing
-    // LP circuit has 7 public inputs: nullifier, root, tick_lower, tick_upper, liquidity, new_commitment, position_commitment
-    if public_signals.len() != 7 {
+    if public_signals.len() != spec.expected_public_len {
         return Err(format!(
-            "Invalid public signals length: expected 7 elements (LP circuit), got {}. \
-            This indicates the circuit did not generate the expected number of public inputs. \
-            Check the circuit output and ensure all 7 public inputs are being generated.",
-            public_signals.len()
+            "Invalid public signals length: expected {} elements ({} circuit), got {}. \
+            This indicates the circuit did not generate the expected number of public inputs \
+            and the contract would reject the calldata.",
+            spec.expected_public_len, spec.name, public_signals.len()
         ));
     }
-    
-    // Public inputs order (LP circuit):
-    // 0: nullifier (felt252)
-    // 1: root (felt252)
-    // 2: tick_lower (i32)
-    // 3: tick_upper (i32)
-    // 4: liquidity (u128)
-    // 5: new_commitment (felt252)
-    // 6: position_commitment (felt252)
-    //
-    // NOTE: The verifier returns values as u256, and the contract converts felt252 values
-    // (indices 0, 1, 5, 6) from u256 to felt252 using reconstruction when high != 0.
-    
-    // Read public signals and apply felt252 modulo for felt252 values
-    let public_inputs: Vec<String> = public_signals
+
+    let prime = felt_prime();
+    public_signals
         .iter()
         .enumerate()
         .map(|(idx, s)| {
             let value_str = s.as_str()
                 .ok_or_else(|| format!("Public signal at index {} is not a string: {:?}", idx, s))?;
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if value_str.starts_with("0x") {
-                BigUint::from_str_radix(&value_str[2..], 16)
-                    .map_err(|e| format!("Failed to parse hex value at index {}: {}", idx, e))?
-            } else {
-                BigUint::from_str(value_str)
-                    .map_err(|e| format!("Failed to parse decimal value at index {}: {}", idx, e))?
-            };
+            let value_big = parse_proof_value(value_str)
+                .ok_or_else(|| format!("Failed to parse value at index {}: {}", idx, value_str))?;
             
-            // Apply felt252 modulo only for felt252 values (indices 0, 1, 5, 6)
-            // tick_lower, tick_upper, liquidity are not felt252, so don't apply modulo
-            let modulo_big = if idx == 0 || idx == 1 || idx == 5 || idx == 6 {
-                if value_big >= felt_max_big {
-                    &value_big % &felt_max_big
-                } else {
-                    value_big.clone()
-                }
-            } else {
-                value_big.clone()
-            };
+            let reduce = spec.reduce_all_public_inputs || spec.felt_inputs.iter().any(|(i, _)| *i == idx);
+            let value_big = if reduce { reduce_to_felt(value_big) } else { value_big };
+            if reduce && value_big >= prime {
+                return Err(format!(
+                    "Public input at index {} ({} circuit) is not a valid felt252 after reduction: {}",
+                    idx, spec.name, value_big
+                ));
+            }
             
             // Convert to string (decimal format for felt252)
-            Ok(modulo_big.to_string())
+            Ok(value_big.to_string())
         })
-        .collect::<Result<Vec<String>, String>>()?;
-    
-    // Proof calldata should only contain the 8 proof elements (A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y)
-    // Public inputs are returned separately
-    // The contract expects: proof (8 elements) and public_inputs (7 elements) as separate arrays
-    let proof_len = proof_calldata.len();
-    
-    println!("[Proof]    Proof calldata length: {} elements (should be 8)", proof_len);
-    println!("[Proof]    Public inputs length: {} elements (should be 7)", public_inputs.len());
-    
-    // Verify proof has exactly 8 elements
-    if proof_len != 8 {
-        return Err(format!("Invalid proof length: expected 8 elements, got {}", proof_len));
-    }
-    
-    // Verify public inputs have exactly 7 elements
-    if public_inputs.len() != 7 {
-        return Err(format!("Invalid public inputs length: expected 7 elements, got {}", public_inputs.len()));
-    }
-    
-    // Log and validate felt252 values (indices 0, 1, 5, 6) to help diagnose conversion issues
-    let u128_max_big = BigUint::from_str("340282366920938463463374607431768211455") // 2^128 - 1
-        .map_err(|_| "Failed to parse U128_MAX".to_string())?;
-    
-    for idx in [0, 1, 5, 6] {
-        if let Some(val_str) = public_inputs.get(idx) {
-            let val_big = BigUint::from_str(val_str)
-                .unwrap_or_else(|_| BigUint::from(0u8));
-            let fits_in_u128 = val_big < u128_max_big;
-            let field_name = match idx {
-                0 => "nullifier",
-                1 => "root",
-                5 => "new_commitment",
-                6 => "position_commitment",
-                _ => unreachable!(),
-            };
-            println!("[Proof]    {}[{}]: {} (fits in u128: {})", field_name, idx, val_str, fits_in_u128);
-            if !fits_in_u128 {
-                println!("[Proof]    ⚠️  WARNING: {} value >= 2^128, verifier will return u256 with high != 0", field_name);
-                println!("[Proof]    ⚠️  Contract should use reconstruction (high * q128 + low) instead of .low");
-            }
-        }
-    }
-    
-    // Clean up temp files
-    let _ = fs::remove_file(&input_file);
-    let _ = fs::remove_file(&witness_file);
-    let _ = fs::remove_file(&proof_file);
-    let _ = fs::remove_file(&public_file);
+        .collect()
+}
+
+/// Garaga calldata to decimal felt252 values (BN254 field values can exceed felt252 max)
+fn normalize_proof_calldata(calldata: &[String]) -> Result<Vec<String>, String> {
+    calldata
+        .iter()
+        .enumerate()
+        .map(|(idx, val_str)| {
+            let value_big = parse_proof_value(val_str)
+                .ok_or_else(|| format!("Failed to parse proof calldata at index {}: {}", idx, val_str))?;
+            Ok(reduce_to_felt(value_big).to_string())
+        })
+        .collect()
+}
+
+/// Generate withdraw proof using Circom circuit
+pub async fn generate_withdraw_proof(
+    circuits_path: &str,
+    inputs: WithdrawProofInputs,
+) -> Result<WithdrawProof, String> {
+    // Public inputs must be in the same order as the circuit: nullifier, root, recipient, amount
+    // Felt values are passed as decimal strings (snarkjs reduces them into the BN254 field)
+    let input_json = serde_json::json!({
+        "nullifier": felt_to_decimal(&inputs.nullifier)?,
+        "root": felt_to_decimal(&inputs.root)?,
+        "recipient": felt_to_decimal(&inputs.recipient)?,
+        "amount": inputs.amount.to_string(),
+        "secret": felt_to_decimal(&inputs.secret)?,
+        "pathElements": inputs.merkle_path
+            .iter()
+            .map(|p| felt_to_decimal(p))
+            .collect::<Result<Vec<_>, _>>()?,
+        "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+    });
     
-    let elapsed = start_time.elapsed().as_secs_f64();
-    println!("[Proof] ✅ Total proof time: {:.2}s ({})", elapsed, 
-        if use_rapidsnark { "with rapidsnark" } else { "with snarkjs" });
+    println!("[Proof]    Withdraw token: {} (not a circuit input)", inputs.token_address);
     
+    generate_withdraw_proof_from_json(circuits_path, input_json).await
+}
+
+/// Convert a hex ("0x...") or decimal string to a decimal string for circuit inputs
+fn felt_to_decimal(value: &str) -> Result<String, String> {
+    use num_bigint::BigUint;
+    use num_traits::Num;
+    use std::str::FromStr;
+    let value_big = if let Some(hex) = value.strip_prefix("0x") {
+        BigUint::from_str_radix(hex, 16)
+            .map_err(|e| format!("Failed to parse hex value '{}': {}", value, e))?
+    } else {
+        BigUint::from_str(value)
+            .map_err(|e| format!("Failed to parse decimal value '{}': {}", value, e))?
+    };
+    Ok(value_big.to_string())
+}
+
+/// Generate LP proof (mint/burn) using rapidsnark (fast) with correct format conversion
+/// This function works for both mint and burn operations (same circuit)
+pub async fn generate_lp_proof(
+    circuits_path: &str,
+    input_json: serde_json::Value,
+) -> Result<LiquidityProof, String> {
+    let proof = run_circuit_proof(circuits_path, &LP_CIRCUIT, input_json, &|_| {}).await?;
     Ok(LiquidityProof {
-        proof: proof_calldata, // Only the 8 proof elements, not combined with public inputs
-        public_inputs,
+        proof: proof.proof,
+        public_inputs: proof.public_inputs,
     })
 }
 
+/// Run the withdraw circuit using rapidsnark (fast) with correct format conversion
+async fn generate_withdraw_proof_from_json(
+    circuits_path: &str,
+    input_json: serde_json::Value,
+) -> Result<WithdrawProof, String> {
+    let proof = run_circuit_proof(circuits_path, &WITHDRAW_CIRCUIT, input_json, &|_| {}).await?;
+    Ok(WithdrawProof {
+        proof: proof.proof,
+        public_inputs: proof.public_inputs,
+    })
+}
+
+/// LP circuit input (mint and burn use the same circuit and inputs)
+fn lp_circuit_input(inputs: &MintProofInputs) -> Result<serde_json::Value, String> {
+    // Public inputs first, in circuit order: nullifier, root, tick_lower, tick_upper, liquidity,
+    // new_commitment, position_commitment
+    // Ticks are signed decimals (snarkjs maps -n to BN254_PRIME - n, as the position commitment does)
+    Ok(serde_json::json!({
+        "nullifier": felt_to_decimal(&inputs.nullifier)?,
+        "root": felt_to_decimal(&inputs.root)?,
+        "tick_lower": inputs.tick_lower.to_string(),
+        "tick_upper": inputs.tick_upper.to_string(),
+        "liquidity": inputs.liquidity.to_string(),
+        "new_commitment": felt_to_decimal(&inputs.new_commitment)?,
+        "position_commitment": felt_to_decimal(&inputs.position_commitment)?,
+        "secret_in": felt_to_decimal(&inputs.secret)?,
+        "amount_in": inputs.amount.to_string(),
+        "secret_out": felt_to_decimal(&inputs.new_secret)?,
+        "nullifier_out": felt_to_decimal(&inputs.new_nullifier)?,
+        "amount_out": inputs.new_amount.to_string(),
+        "pathElements": inputs.merkle_path
+            .iter()
+            .map(|p| felt_to_decimal(p))
+            .collect::<Result<Vec<_>, _>>()?,
+        "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+    }))
+}
+
 /// Generate mint liquidity proof using Circom circuit
 pub async fn generate_mint_liquidity_proof(
     circuits_path: &str,
     inputs: MintProofInputs,
 ) -> Result<LiquidityProof, String> {
-    // Convert MintProofInputs to JSON for generate_lp_proof
-    let input_json = serde_json::json!({
-        "nullifier": inputs.nullifier,
-        "root": inputs.root,
-        "tick_lower": inputs.tick_lower,
-        "tick_upper": inputs.tick_upper,
-        "liquidity": inputs.liquidity,
-        "new_commitment": "", // Will be calculated from secret_out, nullifier_out, amount_out
-        "position_commitment": "", // Will be calculated from secret_in, tick_lower, tick_upper
-        "secret_in": inputs.secret,
-        "amount_in": inputs.amount,
-        "secret_out": inputs.new_secret,
-        "nullifier_out": inputs.new_nullifier,
-        "amount_out": inputs.new_amount,
-        "pathElements": inputs.merkle_path,
-        "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
-    });
-    
-    generate_lp_proof(circuits_path, input_json).await
+    generate_lp_proof(circuits_path, lp_circuit_input(&inputs)?).await
 }
 
 /// Generate burn liquidity proof using Circom circuit
 pub async fn generate_burn_liquidity_proof(
-    _circuits_path: &str,
-    _inputs: BurnProofInputs,
+    circuits_path: &str,
+    inputs: BurnProofInputs,
 ) -> Result<LiquidityProof, String> {
-    // TODO: Implement Circom proof generation
-    Err("Burn liquidity proof generation not yet implemented".to_string())
-}
-
-/// Format proof for Garaga verifier
-/// Garaga expects proof as array of felt252
-pub fn format_proof_for_garaga(_proof: &SwapProof) -> Vec<String> {
-    // TODO: Convert Groth16 proof format to Garaga format
-    // Garaga expects: [A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y, ...public_inputs]
-    vec![]
+    generate_lp_proof(circuits_path, lp_circuit_input(&inputs)?).await
 }
 
 // Input/Output structures
 
-pub struct SwapProofInputs {
-    pub secret: String,
-    pub nullifier: String,
-    pub amount: u128,
-    pub merkle_path: Vec<String>,
-    pub merkle_path_indices: Vec<u32>,
-    pub root: String,
-    pub new_secret: String,
-    pub new_nullifier: String,
-    pub new_amount: u128,
-    // Swap-specific
-    pub zero_for_one: bool,
-    pub amount_specified: u128,
-    pub sqrt_price_limit: Option<(u128, u128)>,
-}
-
 pub struct WithdrawProofInputs {
     pub secret: String,
     pub nullifier: String,
@@ -1137,28 +739,56 @@ pub struct MintProofInputs {
     pub new_secret: String,
     pub new_nullifier: String,
     pub new_amount: u128,
+    /// Commitment of the change note (new_secret, new_nullifier, new_amount)
+    pub new_commitment: String,
+    /// Mask(Poseidon(secret, tick_lower + tick_upper))
+    pub position_commitment: String,
 }
 
-pub struct BurnProofInputs {
-    pub secret: String,
-    pub nullifier: String,
-    pub amount: u128,
-    pub merkle_path: Vec<String>,
-    pub merkle_path_indices: Vec<u32>,
-    pub root: String,
-    pub tick_lower: i32,
-    pub tick_upper: i32,
-    pub liquidity: u128,
-    pub new_secret: String,
-    pub new_nullifier: String,
-    pub new_amount: u128,
-}
+/// Burn proves with the LP circuit too, so it takes the same inputs as mint
+pub type BurnProofInputs = MintProofInputs;
 
+#[derive(Clone)]
 pub struct SwapProof {
     pub proof: Vec<String>, // Groth16 proof formatted for Garaga
     pub public_inputs: Vec<String>,
 }
 
+/// Integrity checksum over a proof and its public inputs
+/// starknet_keccak over [len(proof), proof..., len(public_inputs), public_inputs...], each as a
+/// 32-byte big-endian word, so hex and decimal encodings of the same values give the same checksum
+pub fn proof_checksum(proof: &[String], public_inputs: &[String]) -> Result<String, String> {
+    let mut data = Vec::with_capacity((proof.len() + public_inputs.len() + 2) * 32);
+    for (name, values) in [("proof", proof), ("public_inputs", public_inputs)] {
+        push_word(&mut data, &num_bigint::BigUint::from(values.len()));
+        for (i, value) in values.iter().enumerate() {
+            let parsed = parse_proof_value(value)
+                .ok_or_else(|| format!("Invalid {}[{}]: {}", name, i, value))?;
+            if parsed.bits() > 256 {
+                return Err(format!("{}[{}] does not fit in 256 bits", name, i));
+            }
+            push_word(&mut data, &parsed);
+        }
+    }
+
+    let hash = starknet::core::utils::starknet_keccak(&data);
+    Ok(format!("0x{}", hex::encode(hash.to_bytes_be())))
+}
+
+fn parse_proof_value(value: &str) -> Option<num_bigint::BigUint> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => num_bigint::BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => num_bigint::BigUint::parse_bytes(value.as_bytes(), 10),
+    }
+}
+
+fn push_word(data: &mut Vec<u8>, value: &num_bigint::BigUint) {
+    let bytes = value.to_bytes_be();
+    data.resize(data.len() + 32 - bytes.len(), 0);
+    data.extend_from_slice(&bytes);
+}
+
 pub struct WithdrawProof {
     pub proof: Vec<String>,
     pub public_inputs: Vec<String>,
@@ -1169,3 +799,100 @@ pub struct LiquidityProof {
     pub public_inputs: Vec<String>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// BN254 scalar field modulus minus one, i.e. -1 as a circuit signal
+    const BN254_MINUS_ONE: &str =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+
+    fn signals(values: &[&str]) -> Vec<serde_json::Value> {
+        values.iter().map(|v| serde_json::Value::from(*v)).collect()
+    }
+
+    #[test]
+    fn test_reduce_to_felt_uses_the_field_prime() {
+        // Values in [2^251, p) are valid felts and must come through unchanged
+        let two_251 = num_bigint::BigUint::from(1u8) << 251u32;
+        assert_eq!(reduce_to_felt(two_251.clone()), two_251);
+        assert_eq!(reduce_to_felt(felt_prime()), num_bigint::BigUint::from(0u8));
+        assert_eq!(reduce_to_felt(felt_prime() + 5u8), num_bigint::BigUint::from(5u8));
+    }
+
+    #[test]
+    fn test_swap_public_inputs_are_all_reduced_to_felt() {
+        let public_signals = signals(&["0x1", "2", "3", "1000", "1", BN254_MINUS_ONE, "5", "6", "7"]);
+        let public_inputs = normalize_public_inputs(&SWAP_CIRCUIT, &public_signals).unwrap();
+
+        assert_eq!(&public_inputs[..5], &strings(&["1", "2", "3", "1000", "1"])[..]);
+        let prime = felt_prime();
+        let reduced = parse_proof_value(&public_inputs[5]).unwrap();
+        assert!(reduced < prime);
+        assert_eq!(reduced, parse_proof_value(BN254_MINUS_ONE).unwrap() % prime);
+    }
+
+    #[test]
+    fn test_lp_public_inputs_keep_tick_encoding() {
+        let public_signals = signals(&["1", "2", BN254_MINUS_ONE, "60", "1000", "3", BN254_MINUS_ONE]);
+        let public_inputs = normalize_public_inputs(&LP_CIRCUIT, &public_signals).unwrap();
+
+        // tick_lower = -1 stays in its BN254 encoding; position_commitment is a felt and is reduced
+        assert_eq!(public_inputs[2], BN254_MINUS_ONE);
+        assert_ne!(public_inputs[6], BN254_MINUS_ONE);
+
+        assert!(normalize_public_inputs(&LP_CIRCUIT, &signals(&["not a number"])).is_err());
+    }
+
+    #[test]
+    fn test_public_input_count_is_enforced_per_circuit() {
+        let eight = signals(&["1", "2", "3", "4", "5", "6", "7", "8"]);
+        let err = normalize_public_inputs(&SWAP_CIRCUIT, &eight).unwrap_err();
+        assert!(err.contains("expected 9") && err.contains("got 8"), "{}", err);
+
+        assert!(normalize_public_inputs(&WITHDRAW_CIRCUIT, &signals(&["1", "2", "3", "4"])).is_ok());
+        assert!(normalize_public_inputs(&WITHDRAW_CIRCUIT, &signals(&["1", "2", "3"])).is_err());
+    }
+
+    #[test]
+    fn test_proof_calldata_normalized_to_decimal() {
+        assert_eq!(
+            normalize_proof_calldata(&strings(&["0x10", "42"])).unwrap(),
+            strings(&["16", "42"])
+        );
+        assert!(normalize_proof_calldata(&strings(&["0xzz"])).is_err());
+    }
+
+    #[test]
+    fn test_proof_checksum_is_encoding_independent() {
+        let proof = strings(&["0x1", "0xff", "0x10"]);
+        let public_inputs = strings(&["0x2a"]);
+        let checksum = proof_checksum(&proof, &public_inputs).unwrap();
+
+        assert_eq!(checksum.len(), 66);
+        assert_eq!(
+            proof_checksum(&strings(&["1", "255", "16"]), &strings(&["42"])).unwrap(),
+            checksum
+        );
+    }
+
+    #[test]
+    fn test_proof_checksum_detects_changes() {
+        let proof = strings(&["0x1", "0xff", "0x10"]);
+        let public_inputs = strings(&["0x2a"]);
+        let checksum = proof_checksum(&proof, &public_inputs).unwrap();
+
+        let tampered = strings(&["0x1", "0xfe", "0x10"]);
+        assert_ne!(proof_checksum(&tampered, &public_inputs).unwrap(), checksum);
+
+        // Moving a value from the proof to the public inputs changes the checksum
+        let moved = proof_checksum(&strings(&["0x1", "0xff"]), &strings(&["0x10", "0x2a"])).unwrap();
+        assert_ne!(moved, checksum);
+
+        assert!(proof_checksum(&strings(&["not a number"]), &public_inputs).is_err());
+    }
+}
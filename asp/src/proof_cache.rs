@@ -0,0 +1,152 @@
+// Recently generated swap proofs, so a retried request (network blip, double click) doesn't pay
+// for another multi-minute proving run
+// Keyed by a SHA-256 over the circuit and its full input JSON with keys sorted, so any change to
+// the root, the Merkle path or a public or private input is a different entry. Least recently used
+// entries go first once PROOF_CACHE_SIZE is reached; entries expire after PROOF_CACHE_TTL_SECS.
+// PROOF_CACHE_SIZE=0 turns the cache off. Memory only: a restart empties it.
+
+use crate::proof::SwapProof;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PROOF_CACHE_SIZE: usize = 32;
+const DEFAULT_PROOF_CACHE_TTL_SECS: u64 = 600;
+
+struct Entry {
+    key: String,
+    proof: SwapProof,
+    stored: Instant,
+}
+
+pub struct ProofCache {
+    capacity: usize,
+    ttl: Duration,
+    /// Most recently used first
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl ProofCache {
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("PROOF_CACHE_SIZE")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_PROOF_CACHE_SIZE);
+        let ttl_secs = std::env::var("PROOF_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_PROOF_CACHE_TTL_SECS);
+        Self::new(capacity, Duration::from_secs(ttl_secs))
+    }
+
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        ProofCache {
+            capacity,
+            ttl,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Cache key of a proving run: `circuit` plus its input JSON in canonical form
+    pub fn key(circuit: &str, input: &serde_json::Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(circuit.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(canonical_json(input).as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Proof stored under `key`, unless it expired; a hit becomes the most recently used entry
+    pub fn get(&self, key: &str, now: Instant) -> Option<SwapProof> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| now.saturating_duration_since(entry.stored) < self.ttl);
+        let position = entries.iter().position(|entry| entry.key == key)?;
+        let entry = entries.remove(position);
+        let proof = entry.proof.clone();
+        entries.insert(0, entry);
+        Some(proof)
+    }
+
+    pub fn insert(&self, key: String, proof: SwapProof, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.key != key);
+        entries.insert(0, Entry { key, proof, stored: now });
+        entries.truncate(self.capacity);
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
+
+/// JSON with object keys sorted at every level, independent of how the map was built
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", serde_json::Value::from(key.as_str()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof(tag: &str) -> SwapProof {
+        SwapProof {
+            proof: vec![tag.to_string()],
+            public_inputs: vec!["0x1".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_key_covers_every_input() {
+        let input = serde_json::json!({ "root": "0x1", "pathElements": ["1", "2"], "amount_in": "10" });
+        let reordered = serde_json::json!({ "amount_in": "10", "pathElements": ["1", "2"], "root": "0x1" });
+        assert_eq!(ProofCache::key("swap", &input), ProofCache::key("swap", &reordered));
+
+        let other_root = serde_json::json!({ "root": "0x2", "pathElements": ["1", "2"], "amount_in": "10" });
+        let other_path = serde_json::json!({ "root": "0x1", "pathElements": ["2", "1"], "amount_in": "10" });
+        assert_ne!(ProofCache::key("swap", &input), ProofCache::key("swap", &other_root));
+        assert_ne!(ProofCache::key("swap", &input), ProofCache::key("swap", &other_path));
+        assert_ne!(ProofCache::key("swap", &input), ProofCache::key("lp", &input));
+    }
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let cache = ProofCache::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        cache.insert("a".to_string(), proof("a"), start);
+        cache.insert("b".to_string(), proof("b"), start);
+        // Touching "a" makes "b" the least recently used
+        assert_eq!(cache.get("a", start).unwrap().proof, vec!["a"]);
+        cache.insert("c".to_string(), proof("c"), start);
+        assert!(cache.get("b", start).is_none());
+        assert!(cache.get("a", start).is_some());
+        assert_eq!(cache.entry_count(), 2);
+
+        assert!(cache.get("a", start + Duration::from_secs(60)).is_none());
+        assert_eq!(cache.entry_count(), 0);
+
+        let off = ProofCache::new(0, Duration::from_secs(60));
+        off.insert("a".to_string(), proof("a"), start);
+        assert!(off.get("a", start).is_none());
+    }
+}
@@ -0,0 +1,259 @@
+// Where proof runs keep their input/witness/proof/public files
+// With PROOF_OUTPUT_DIR set, every run gets <dir>/<circuit>/job-<id>/ and its files are kept
+// for inspection until the retention cleanup removes them. Unset, files go to the system temp
+// dir with flat names and are deleted as soon as the run ends (the original behavior).
+// Temp files and helper scripts live in dedicated subdirectories and are removed when the
+// ProofFiles guard is dropped, so every error path (and a cancelled run) cleans up; whatever a
+// crashed process left behind is swept at startup.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_RETENTION_HOURS: u64 = 24;
+
+/// Subdirectory of the system temp dir for run files when PROOF_OUTPUT_DIR is unset
+const TEMP_SUBDIR: &str = "zylith-proofs";
+
+/// Subdirectory of the circuits dir for generated node scripts
+/// (inside the circuits dir so require('snarkjs') resolves from its node_modules)
+const SCRIPT_SUBDIR: &str = ".proof-tmp";
+
+/// Age after which a leftover temp file is assumed orphaned (longer than any proof run)
+pub const STALE_TEMP_AGE: Duration = Duration::from_secs(3600);
+
+/// PROOF_OUTPUT_DIR and PROOF_OUTPUT_RETENTION_HOURS
+pub struct ProofOutput {
+    root: Option<PathBuf>,
+    pub retention: Duration,
+}
+
+/// Files for one proof run
+pub struct ProofFiles {
+    pub input: PathBuf,
+    pub witness: PathBuf,
+    pub proof: PathBuf,
+    pub public: PathBuf,
+    /// Job directory under PROOF_OUTPUT_DIR; files there are kept
+    pub job_dir: Option<PathBuf>,
+    /// Removed on drop, whatever the outcome of the run
+    cleanup: Mutex<Vec<PathBuf>>,
+}
+
+impl ProofOutput {
+    pub fn new(root: Option<PathBuf>, retention: Duration) -> Self {
+        Self { root, retention }
+    }
+
+    pub fn from_env() -> Self {
+        let root = std::env::var("PROOF_OUTPUT_DIR")
+            .ok()
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from);
+        let hours = std::env::var("PROOF_OUTPUT_RETENTION_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RETENTION_HOURS);
+        Self::new(root, Duration::from_secs(hours * 3600))
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Paths for a run of `circuit`, creating the job directory when PROOF_OUTPUT_DIR is set
+    pub fn files(&self, circuit: &str, job_id: &str) -> Result<ProofFiles, String> {
+        match &self.root {
+            Some(root) => {
+                let job_dir = root.join(circuit).join(format!("job-{}", job_id));
+                fs::create_dir_all(&job_dir)
+                    .map_err(|e| format!("Failed to create proof output dir {:?}: {}", job_dir, e))?;
+                Ok(ProofFiles {
+                    input: job_dir.join("input.json"),
+                    witness: job_dir.join("witness.wtns"),
+                    proof: job_dir.join("proof.json"),
+                    public: job_dir.join("public.json"),
+                    job_dir: Some(job_dir),
+                    cleanup: Mutex::new(Vec::new()),
+                })
+            }
+            None => {
+                let temp_dir = std::env::temp_dir().join(TEMP_SUBDIR);
+                fs::create_dir_all(&temp_dir)
+                    .map_err(|e| format!("Failed to create proof temp dir {:?}: {}", temp_dir, e))?;
+                let input = temp_dir.join(format!("{}_input_{}.json", circuit, job_id));
+                let witness = temp_dir.join(format!("{}_witness_{}.wtns", circuit, job_id));
+                let proof = temp_dir.join(format!("{}_proof_{}.json", circuit, job_id));
+                let public = temp_dir.join(format!("{}_public_{}.json", circuit, job_id));
+                let cleanup = vec![input.clone(), witness.clone(), proof.clone(), public.clone()];
+                Ok(ProofFiles { input, witness, proof, public, job_dir: None, cleanup: Mutex::new(cleanup) })
+            }
+        }
+    }
+
+    /// Remove job directories (<dir>/<circuit>/job-*) older than the retention period
+    /// Returns how many were removed; anything else under the directory is left alone
+    pub fn prune(&self) -> usize {
+        let root = match &self.root {
+            Some(root) => root,
+            None => return 0,
+        };
+        let now = SystemTime::now();
+        let mut removed = 0;
+
+        let circuits = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+        for circuit_dir in circuits.flatten().filter(|e| e.path().is_dir()) {
+            let jobs = match fs::read_dir(circuit_dir.path()) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for job in jobs.flatten() {
+                let is_job_dir = job.path().is_dir() && job.file_name().to_string_lossy().starts_with("job-");
+                if !is_job_dir {
+                    continue;
+                }
+                let expired = job
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .is_some_and(|age| age >= self.retention);
+                if expired && fs::remove_dir_all(job.path()).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+}
+
+/// Remove files older than `max_age` left in the temp and script subdirectories by runs that
+/// never finished (crash, kill). Returns how many were removed.
+pub fn sweep_stale(circuits_dir: &Path, max_age: Duration) -> usize {
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for dir in [std::env::temp_dir().join(TEMP_SUBDIR), circuits_dir.join(SCRIPT_SUBDIR)] {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten().filter(|e| e.path().is_file()) {
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= max_age);
+            if stale && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
+
+impl ProofFiles {
+    /// Path for a generated node script in the circuits dir, removed when the run ends
+    pub fn script(&self, circuits_dir: &Path, name: &str) -> Result<PathBuf, String> {
+        let dir = circuits_dir.join(SCRIPT_SUBDIR);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create script dir {:?}: {}", dir, e))?;
+        let path = dir.join(name);
+        self.cleanup.lock().unwrap().push(path.clone());
+        Ok(path)
+    }
+
+    /// Leave `path` on disk when the run ends (e.g. a proof kept for debugging a failed conversion)
+    pub fn keep(&self, path: &Path) {
+        self.cleanup.lock().unwrap().retain(|p| p != path);
+    }
+}
+
+impl Drop for ProofFiles {
+    fn drop(&mut self) {
+        for path in self.cleanup.get_mut().unwrap().drain(..) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("zylith_proof_output_{}_{}", name, nanos))
+    }
+
+    #[test]
+    fn test_job_layout_is_per_circuit_and_kept() {
+        let root = temp_root("layout");
+        let output = ProofOutput::new(Some(root.clone()), Duration::from_secs(3600));
+
+        let files = output.files("swap", "42").unwrap();
+        assert_eq!(files.input, root.join("swap").join("job-42").join("input.json"));
+        assert_eq!(files.public, root.join("swap").join("job-42").join("public.json"));
+
+        fs::write(&files.proof, "{}").unwrap();
+        let proof = files.proof.clone();
+        drop(files);
+        assert!(proof.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_unset_dir_uses_flat_temp_files() {
+        let output = ProofOutput::new(None, Duration::from_secs(3600));
+        let files = output.files("lp", "7").unwrap();
+
+        assert_eq!(files.input, std::env::temp_dir().join(TEMP_SUBDIR).join("lp_input_7.json"));
+        assert!(files.job_dir.is_none());
+        assert_eq!(output.prune(), 0);
+    }
+
+    #[test]
+    fn test_drop_removes_temp_files_and_scripts() {
+        let circuits_dir = temp_root("circuits");
+        let output = ProofOutput::new(None, Duration::from_secs(3600));
+        let files = output.files("withdraw", "drop-test").unwrap();
+        let script = files.script(&circuits_dir, "witness_script_1.js").unwrap();
+        for path in [&files.input, &files.proof, &script] {
+            fs::write(path, "{}").unwrap();
+        }
+        files.keep(&files.proof);
+        let (input, proof) = (files.input.clone(), files.proof.clone());
+
+        drop(files);
+        assert!(!input.exists());
+        assert!(!script.exists());
+        assert!(proof.exists());
+
+        let _ = fs::remove_file(&proof);
+        let _ = fs::remove_dir_all(&circuits_dir);
+    }
+
+    #[test]
+    fn test_prune_removes_only_expired_job_dirs() {
+        let root = temp_root("prune");
+        let output = ProofOutput::new(Some(root.clone()), Duration::ZERO);
+        output.files("swap", "1").unwrap();
+        output.files("withdraw", "2").unwrap();
+        fs::write(root.join("swap").join("notes.txt"), "keep").unwrap();
+
+        assert_eq!(output.prune(), 2);
+        assert!(!root.join("swap").join("job-1").exists());
+        assert!(root.join("swap").join("notes.txt").exists());
+
+        // Nothing is old enough with a long retention
+        let kept = ProofOutput::new(Some(root.clone()), Duration::from_secs(3600));
+        kept.files("swap", "3").unwrap();
+        assert_eq!(kept.prune(), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
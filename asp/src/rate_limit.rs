@@ -0,0 +1,133 @@
+// Per-IP rate limit for proof generation (the routes in auth::PROVER_ROUTES, LP prepare included)
+// Each client IP gets a token bucket holding PROOF_RATE_LIMIT_PER_MIN requests, refilled evenly
+// over a minute. An empty bucket means 429 with Retry-After, before the request reaches the
+// prover queue (the concurrency limit in limiter.rs only bounds work already accepted).
+// PROOF_RATE_LIMIT_PER_MIN=0 turns the limit off. Full buckets are evicted periodically.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PROOF_RATE_LIMIT_PER_MIN: u32 = 10;
+
+/// How often main sweeps idle buckets
+pub const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn from_env() -> Self {
+        let per_minute = std::env::var("PROOF_RATE_LIMIT_PER_MIN")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_PROOF_RATE_LIMIT_PER_MIN);
+        Self::new(per_minute)
+    }
+
+    pub fn new(per_minute: u32) -> Self {
+        RateLimiter {
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn per_minute(&self) -> u32 {
+        self.per_minute
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.per_minute > 0
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+
+    /// Take a token for `ip`, or return how long until one is available
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        let capacity = self.per_minute as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec()).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec()))
+        }
+    }
+
+    /// Drop buckets that have refilled completely; they behave like a fresh client
+    /// Returns how many were removed
+    pub fn evict_idle(&self, now: Instant) -> usize {
+        let capacity = self.per_minute as f64;
+        let refill = self.refill_per_sec();
+        let mut buckets = self.buckets.lock().unwrap();
+        let before = buckets.len();
+        buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * refill < capacity
+        });
+        before - buckets.len()
+    }
+
+    pub fn tracked_clients(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_empties_and_refills() {
+        let limiter = RateLimiter::new(2);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = Instant::now();
+
+        assert!(limiter.check(ip, start).is_ok());
+        assert!(limiter.check(ip, start).is_ok());
+        let retry_after = limiter.check(ip, start).unwrap_err();
+        assert_eq!(retry_after.as_secs_f64().round(), 30.0);
+        // Other clients have their own bucket
+        assert!(limiter.check(other, start).is_ok());
+
+        // 2 per minute: one token back after 30s
+        assert!(limiter.check(ip, start + Duration::from_secs(30)).is_ok());
+        assert!(limiter.check(ip, start + Duration::from_secs(30)).is_err());
+    }
+
+    #[test]
+    fn test_eviction_and_disabled_limit() {
+        let limiter = RateLimiter::new(2);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let start = Instant::now();
+        limiter.check(ip, start).unwrap();
+
+        assert_eq!(limiter.evict_idle(start + Duration::from_secs(10)), 0);
+        assert_eq!(limiter.evict_idle(start + Duration::from_secs(30)), 1);
+        assert_eq!(limiter.tracked_clients(), 0);
+
+        let off = RateLimiter::new(0);
+        for _ in 0..100 {
+            assert!(off.check(ip, start).is_ok());
+        }
+        assert_eq!(off.tracked_clients(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+// Real-time event notifications over the RPC's WebSocket endpoint (SYNC_TRANSPORT=ws)
+// Notifications only wake the syncer: events are still read through the polling path from the
+// persisted cursor, so ordering, dedup and back-filling after a dropped subscription stay in one place.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// How the syncer learns about new blocks (SYNC_TRANSPORT)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncTransport {
+    /// get_events every poll interval (default)
+    Poll,
+    /// starknet_subscribeEvents on RPC_WS_URL, with a slow safety poll
+    WebSocket { url: String },
+}
+
+impl SyncTransport {
+    pub fn from_env() -> Self {
+        match std::env::var("SYNC_TRANSPORT").as_deref() {
+            Ok("ws") => match std::env::var("RPC_WS_URL") {
+                Ok(url) if !url.is_empty() => SyncTransport::WebSocket { url },
+                _ => {
+                    eprintln!("[Syncer] ⚠️  SYNC_TRANSPORT=ws needs RPC_WS_URL, falling back to polling");
+                    SyncTransport::Poll
+                }
+            },
+            _ => SyncTransport::Poll,
+        }
+    }
+}
+
+/// Why a subscription ended
+#[derive(Debug, PartialEq, Eq)]
+enum SubscriptionEnd {
+    /// The RPC rejected starknet_subscribeEvents: stop trying and keep polling
+    Unsupported(String),
+    /// Connection failed or dropped: reconnect
+    Dropped(String),
+}
+
+const RECONNECT_BACKOFF_INITIAL_SECS: u64 = 1;
+const RECONNECT_BACKOFF_MAX_SECS: u64 = 60;
+
+fn subscribe_request(contract_address: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "starknet_subscribeEvents",
+        "params": { "from_address": contract_address }
+    })
+}
+
+/// What a WebSocket message means for the syncer
+#[derive(Debug, PartialEq, Eq)]
+enum Incoming {
+    Subscribed,
+    Rejected(String),
+    /// New events or a reorg: the syncer should poll now
+    Wake,
+    Ignore,
+}
+
+fn classify(text: &str) -> Incoming {
+    let Ok(msg) = serde_json::from_str::<Value>(text) else {
+        return Incoming::Ignore;
+    };
+    if msg.get("id").and_then(|id| id.as_u64()) == Some(1) {
+        return match msg.get("error") {
+            Some(error) => Incoming::Rejected(error.to_string()),
+            None => Incoming::Subscribed,
+        };
+    }
+    match msg.get("method").and_then(|m| m.as_str()) {
+        Some("starknet_subscriptionEvents") | Some("starknet_subscriptionReorg") => Incoming::Wake,
+        _ => Incoming::Ignore,
+    }
+}
+
+/// Subscribe once and wake the syncer on every notification until the connection ends
+/// Sets `live` once the subscription is confirmed; the caller clears it
+async fn subscribe_once(
+    url: &str,
+    contract_address: &str,
+    wake: &Notify,
+    live: &AtomicBool,
+) -> SubscriptionEnd {
+    let (mut ws, _) = match connect_async(url).await {
+        Ok(conn) => conn,
+        Err(e) => return SubscriptionEnd::Dropped(format!("connect failed: {}", e)),
+    };
+    let request = subscribe_request(contract_address).to_string();
+    if let Err(e) = ws.send(Message::Text(request)).await {
+        return SubscriptionEnd::Dropped(format!("subscribe failed: {}", e));
+    }
+
+    while let Some(msg) = ws.next().await {
+        let text = match msg {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(e) => return SubscriptionEnd::Dropped(e.to_string()),
+        };
+        match classify(&text) {
+            Incoming::Subscribed => {
+                println!("[Syncer] 📡 Subscribed to contract events via {}", url);
+                live.store(true, Ordering::SeqCst);
+                // Back-fill whatever landed between the last poll and the subscription
+                wake.notify_one();
+            }
+            Incoming::Rejected(error) => return SubscriptionEnd::Unsupported(error),
+            Incoming::Wake => wake.notify_one(),
+            Incoming::Ignore => {}
+        }
+    }
+    SubscriptionEnd::Dropped("connection closed".to_string())
+}
+
+/// Keep a subscription open, reconnecting with backoff after drops
+/// Returns (and leaves the syncer polling) only if the RPC doesn't support subscriptions
+pub async fn maintain_subscription(
+    url: String,
+    contract_address: String,
+    wake: Arc<Notify>,
+    live: Arc<AtomicBool>,
+) {
+    let mut backoff = RECONNECT_BACKOFF_INITIAL_SECS;
+    loop {
+        let end = subscribe_once(&url, &contract_address, &wake, &live).await;
+        if live.swap(false, Ordering::SeqCst) {
+            backoff = RECONNECT_BACKOFF_INITIAL_SECS;
+        }
+        match end {
+            SubscriptionEnd::Unsupported(error) => {
+                eprintln!("[Syncer] ⚠️  RPC does not support starknet_subscribeEvents ({}), using polling", error);
+                return;
+            }
+            SubscriptionEnd::Dropped(reason) => {
+                eprintln!("[Syncer] ⚠️  Event subscription dropped ({}), reconnecting in {}s", reason, backoff);
+                // The polling path back-fills the missed range from the cursor
+                wake.notify_one();
+            }
+        }
+        sleep(Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX_SECS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_subscription_messages() {
+        assert_eq!(classify(r#"{"jsonrpc":"2.0","id":1,"result":"0x7"}"#), Incoming::Subscribed);
+        assert!(matches!(
+            classify(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}"#),
+            Incoming::Rejected(_)
+        ));
+        assert_eq!(
+            classify(r#"{"jsonrpc":"2.0","method":"starknet_subscriptionEvents","params":{"subscription_id":"0x7","result":{}}}"#),
+            Incoming::Wake
+        );
+        assert_eq!(
+            classify(r#"{"jsonrpc":"2.0","method":"starknet_subscriptionReorg","params":{}}"#),
+            Incoming::Wake
+        );
+        assert_eq!(classify("not json"), Incoming::Ignore);
+    }
+}
@@ -0,0 +1,186 @@
+// Compliance screening webhook
+// When SCREENING_WEBHOOK_URL is set, commitments (and depositor addresses, when known) are sent
+// to an external service that answers allow/deny before they enter the associated set.
+// SCREENING_FAIL_POLICY decides what happens when the webhook times out or errors.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const DEFAULT_TIMEOUT_MS: u64 = 3000;
+
+/// What to do when the webhook can't give an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailPolicy {
+    /// Allow the subject (availability first)
+    Open,
+    /// Deny the subject (compliance first, default)
+    Closed,
+}
+
+impl FailPolicy {
+    fn from_env() -> Self {
+        match std::env::var("SCREENING_FAIL_POLICY").as_deref() {
+            Ok("open") => FailPolicy::Open,
+            _ => FailPolicy::Closed,
+        }
+    }
+}
+
+/// What is being screened and why
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreeningRequest {
+    /// "associated_insert" or "deposit"
+    pub kind: &'static str,
+    pub commitment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+/// Webhook response body
+#[derive(Debug, Deserialize)]
+pub struct WebhookResponse {
+    pub allow: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScreeningDecision {
+    pub allowed: bool,
+    pub reason: Option<String>,
+    /// True when the decision came from the fail policy rather than the webhook
+    pub fallback: bool,
+}
+
+impl ScreeningDecision {
+    fn allow_unscreened() -> Self {
+        Self { allowed: true, reason: None, fallback: false }
+    }
+}
+
+pub struct Screener {
+    client: reqwest::Client,
+    url: Option<String>,
+    fail_policy: FailPolicy,
+    pub screen_deposits: bool,
+}
+
+impl Screener {
+    /// SCREENING_WEBHOOK_URL (unset = no screening), SCREENING_TIMEOUT_MS (default 3000),
+    /// SCREENING_FAIL_POLICY=open|closed (default closed), SCREENING_DEPOSITS=true to also
+    /// screen deposits picked up by the syncer
+    pub fn from_env() -> Self {
+        let timeout_ms = std::env::var("SCREENING_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_MS);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(timeout_ms))
+            .build()
+            .expect("Failed to build screening HTTP client");
+
+        Self {
+            client,
+            url: std::env::var("SCREENING_WEBHOOK_URL").ok().filter(|u| !u.is_empty()),
+            fail_policy: FailPolicy::from_env(),
+            screen_deposits: std::env::var("SCREENING_DEPOSITS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+
+    /// Ask the webhook about a commitment; always returns a decision (see FailPolicy)
+    pub async fn screen(&self, request: &ScreeningRequest) -> ScreeningDecision {
+        let url = match &self.url {
+            Some(url) => url,
+            None => return ScreeningDecision::allow_unscreened(),
+        };
+
+        let result = match self.client.post(url).json(request).send().await {
+            Ok(resp) if resp.status().is_success() => resp
+                .json::<WebhookResponse>()
+                .await
+                .map_err(|e| format!("invalid webhook response: {}", e)),
+            Ok(resp) => Err(format!("webhook returned {}", resp.status())),
+            Err(e) if e.is_timeout() => Err("webhook timed out".to_string()),
+            Err(e) => Err(format!("webhook request failed: {}", e)),
+        };
+
+        let decision = decide(result, self.fail_policy);
+        println!(
+            "[Screening] {} {} {} ({}{})",
+            if decision.allowed { "✅ allowed" } else { "🚫 denied" },
+            request.kind,
+            request.commitment,
+            decision.reason.as_deref().unwrap_or("no reason given"),
+            if decision.fallback { ", fail policy" } else { "" }
+        );
+        decision
+    }
+}
+
+/// Turn the webhook outcome into a decision, applying the fail policy to errors
+fn decide(result: Result<WebhookResponse, String>, fail_policy: FailPolicy) -> ScreeningDecision {
+    match result {
+        Ok(resp) => ScreeningDecision {
+            allowed: resp.allow,
+            reason: resp.reason,
+            fallback: false,
+        },
+        Err(e) => ScreeningDecision {
+            allowed: fail_policy == FailPolicy::Open,
+            reason: Some(format!("{} (fail-{})", e, if fail_policy == FailPolicy::Open { "open" } else { "closed" })),
+            fallback: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_answer_is_used() {
+        let denied = decide(
+            Ok(WebhookResponse { allow: false, reason: Some("sanctioned".to_string()) }),
+            FailPolicy::Open,
+        );
+        assert!(!denied.allowed);
+        assert!(!denied.fallback);
+        assert_eq!(denied.reason.as_deref(), Some("sanctioned"));
+
+        let allowed = decide(Ok(WebhookResponse { allow: true, reason: None }), FailPolicy::Closed);
+        assert!(allowed.allowed);
+    }
+
+    #[test]
+    fn test_failures_follow_fail_policy() {
+        let closed = decide(Err("webhook timed out".to_string()), FailPolicy::Closed);
+        assert!(!closed.allowed);
+        assert!(closed.fallback);
+
+        let open = decide(Err("webhook timed out".to_string()), FailPolicy::Open);
+        assert!(open.allowed);
+        assert!(open.fallback);
+    }
+
+    #[tokio::test]
+    async fn test_no_webhook_allows_everything() {
+        let screener = Screener {
+            client: reqwest::Client::new(),
+            url: None,
+            fail_policy: FailPolicy::Closed,
+            screen_deposits: false,
+        };
+        let request = ScreeningRequest {
+            kind: "associated_insert",
+            commitment: "0x1".to_string(),
+            address: None,
+        };
+        assert!(screener.screen(&request).await.allowed);
+    }
+}
@@ -0,0 +1,85 @@
+// Graceful shutdown
+// main waits for SIGTERM/Ctrl-C, then triggers the shared Shutdown: the HTTP server stops
+// accepting connections and the syncer saves its cursor and tree snapshot at the next batch
+// boundary instead of dying mid-sync.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+#[derive(Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once shutdown is triggered (right away if it already was)
+    pub async fn triggered(&self) {
+        let notified = self.notify.notified();
+        tokio::pin!(notified);
+        // Register before checking the flag so a trigger in between isn't missed
+        notified.as_mut().enable();
+        if self.is_triggered() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// SIGTERM (container stop) or Ctrl-C
+pub async fn signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_triggered_wakes_waiters_and_late_callers() {
+        let shutdown = Arc::new(Shutdown::default());
+        let waiter = {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move { shutdown.triggered().await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        // Already triggered: returns immediately
+        tokio::time::timeout(Duration::from_secs(1), shutdown.triggered()).await.unwrap();
+    }
+}
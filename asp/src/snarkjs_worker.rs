@@ -0,0 +1,218 @@
+// Long-lived Node process for snarkjs work (circuits/scripts/snarkjs_worker.js)
+// Spawning node and loading snarkjs for every witness/prove step costs seconds per proof, so one
+// worker is started at startup and fed JSON lines over stdin. If it exits, its in-flight requests
+// fail and the next request starts a new one. SNARKJS_WORKER=off goes back to one node process
+// per step.
+
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
+
+/// Relative to the circuits directory
+const WORKER_SCRIPT: &str = "scripts/snarkjs_worker.js";
+
+/// Node heap for the worker; it serves every circuit, so this is the largest per-circuit heap
+const DEFAULT_WORKER_HEAP_MB: u32 = 8192;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<(), String>>>>>;
+
+/// One running node process
+struct Process {
+    stdin: ChildStdin,
+    /// Requests written to this process and not answered yet
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+    /// Killed when the process is replaced
+    _child: Child,
+}
+
+pub struct SnarkjsWorker {
+    circuits_dir: PathBuf,
+    heap_mb: u32,
+    process: tokio::sync::Mutex<Option<Process>>,
+    next_id: AtomicU64,
+    /// Node processes started (the first one included)
+    spawned: AtomicU64,
+}
+
+static WORKER: OnceCell<Option<SnarkjsWorker>> = OnceCell::new();
+
+/// The shared worker, or None when SNARKJS_WORKER=off or the worker script is missing
+/// The first call decides the circuits directory
+pub fn get(circuits_dir: &Path) -> Option<&'static SnarkjsWorker> {
+    WORKER.get_or_init(|| SnarkjsWorker::from_env(circuits_dir)).as_ref()
+}
+
+/// Start the worker now instead of on the first proof (no-op when disabled)
+pub async fn start(circuits_dir: &Path) {
+    let Some(worker) = get(circuits_dir) else {
+        println!("[Proof] ℹ️  snarkjs worker disabled, each proof step runs its own node process");
+        return;
+    };
+    let mut process = worker.process.lock().await;
+    match worker.ensure_running(&mut process) {
+        Ok(()) => println!("[Proof] ✅ snarkjs worker started ({})", worker.circuits_dir.display()),
+        Err(e) => eprintln!("[Proof] ⚠️  {}", e),
+    }
+}
+
+impl SnarkjsWorker {
+    fn from_env(circuits_dir: &Path) -> Option<Self> {
+        let enabled = std::env::var("SNARKJS_WORKER")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "off" | "false" | "0" | "no"))
+            .unwrap_or(true);
+        if !enabled {
+            return None;
+        }
+        if !circuits_dir.join(WORKER_SCRIPT).exists() {
+            eprintln!("[Proof] ⚠️  {} not found in {}, snarkjs worker disabled", WORKER_SCRIPT, circuits_dir.display());
+            return None;
+        }
+        let heap_mb = std::env::var("SNARKJS_WORKER_HEAP_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_WORKER_HEAP_MB);
+        Some(Self {
+            circuits_dir: circuits_dir.to_path_buf(),
+            heap_mb,
+            process: tokio::sync::Mutex::new(None),
+            next_id: AtomicU64::new(1),
+            spawned: AtomicU64::new(0),
+        })
+    }
+
+    /// Calculate a witness (snarkjs wtns.calculate)
+    pub async fn witness(&self, input: &Path, wasm: &Path, witness: &Path) -> Result<(), String> {
+        self.call(json!({ "op": "witness", "input": input, "wasm": wasm, "witness": witness })).await
+    }
+
+    /// Groth16 proof from a witness (snarkjs groth16.prove), written to proof/public
+    pub async fn prove(&self, zkey: &Path, witness: &Path, proof: &Path, public: &Path) -> Result<(), String> {
+        self.call(json!({ "op": "prove", "zkey": zkey, "witness": witness, "proof": proof, "public": public }))
+            .await
+    }
+
+    /// Add the protocol field and trim pi_a/pi_b/pi_c for convert_garaga.py
+    pub async fn normalize_proof(&self, proof: &Path) -> Result<(), String> {
+        self.call(json!({ "op": "normalize_proof", "proof": proof })).await
+    }
+
+    async fn call(&self, mut request: Value) -> Result<(), String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        request["id"] = json!(id);
+        let line = format!("{}\n", request);
+        let (tx, rx) = oneshot::channel();
+
+        {
+            let mut guard = self.process.lock().await;
+            self.ensure_running(&mut guard)?;
+            let process = guard.as_mut().expect("worker process just started");
+            process.pending.lock().unwrap().insert(id, tx);
+            if let Err(e) = process.stdin.write_all(line.as_bytes()).await {
+                process.pending.lock().unwrap().remove(&id);
+                process.alive.store(false, Ordering::SeqCst);
+                return Err(format!("Failed to send request to snarkjs worker: {}", e));
+            }
+        }
+
+        rx.await
+            .unwrap_or_else(|_| Err("snarkjs worker exited before answering".to_string()))
+    }
+
+    /// Spawn a node process if there is none or the last one exited
+    fn ensure_running(&self, process: &mut Option<Process>) -> Result<(), String> {
+        if process.as_ref().is_some_and(|p| p.alive.load(Ordering::SeqCst)) {
+            return Ok(());
+        }
+        if process.take().is_some() {
+            eprintln!("[Proof] 🔁 Restarting snarkjs worker (restart #{})", self.spawned.load(Ordering::Relaxed));
+        }
+        *process = Some(self.spawn()?);
+        Ok(())
+    }
+
+    fn spawn(&self) -> Result<Process, String> {
+        let mut child = Command::new("node")
+            .env("NODE_OPTIONS", format!("--max-old-space-size={}", self.heap_mb))
+            .arg(WORKER_SCRIPT)
+            .current_dir(&self.circuits_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start snarkjs worker: {}", e))?;
+        self.spawned.fetch_add(1, Ordering::Relaxed);
+
+        let stdin = child.stdin.take().ok_or("snarkjs worker has no stdin")?;
+        let stdout = child.stdout.take().ok_or("snarkjs worker has no stdout")?;
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+
+        // Route responses to their callers; when the process exits, fail whatever it still owed
+        let (reader_pending, reader_alive) = (pending.clone(), alive.clone());
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match parse_response(&line) {
+                    Some((id, result)) => {
+                        if let Some(tx) = reader_pending.lock().unwrap().remove(&id) {
+                            let _ = tx.send(result);
+                        }
+                    }
+                    None => println!("[Proof] snarkjs worker: {}", line),
+                }
+            }
+            reader_alive.store(false, Ordering::SeqCst);
+            let owed: Vec<_> = reader_pending.lock().unwrap().drain().collect();
+            eprintln!(
+                "[Proof] ⚠️  snarkjs worker exited ({} requests in flight), it restarts on the next request",
+                owed.len()
+            );
+            for (_, tx) in owed {
+                let _ = tx.send(Err("snarkjs worker exited".to_string()));
+            }
+        });
+
+        Ok(Process { stdin, pending, alive, _child: child })
+    }
+}
+
+/// Parse a worker response line into (request id, result)
+/// None for anything that isn't a response (stray output from snarkjs)
+fn parse_response(line: &str) -> Option<(u64, Result<(), String>)> {
+    let msg: Value = serde_json::from_str(line).ok()?;
+    let id = msg.get("id")?.as_u64()?;
+    let result = match msg.get("ok").and_then(|ok| ok.as_bool()) {
+        Some(true) => Ok(()),
+        _ => Err(msg
+            .get("error")
+            .and_then(|e| e.as_str())
+            .unwrap_or("snarkjs worker request failed")
+            .to_string()),
+    };
+    Some((id, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse_response(r#"{"id":3,"ok":true,"elapsed":1.5}"#), Some((3, Ok(()))));
+        assert_eq!(
+            parse_response(r#"{"id":4,"ok":false,"error":"Error: Not enough values for input signal root"}"#),
+            Some((4, Err("Error: Not enough values for input signal root".to_string())))
+        );
+        assert_eq!(parse_response("Calculating witness..."), None);
+        assert_eq!(parse_response(r#"{"ok":true}"#), None);
+    }
+}
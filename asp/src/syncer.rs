@@ -1,66 +1,324 @@
-use crate::merkle::{MerkleTree, TREE_DEPTH};
+use crate::blockchain::{is_transient_error, RpcEndpoints};
+use crate::events::{DepositEvent, DepositFeed};
+use crate::screening::{Screener, ScreeningRequest};
+use crate::merkle::{MerkleTree, TREE_SNAPSHOT_FILE};
+use crate::metrics::Metrics;
+use crate::nullifiers::NullifierSet;
+use crate::persistence::Persistence;
+use crate::rpc_subscription::{maintain_subscription, SyncTransport};
+use crate::shutdown::Shutdown;
 use num_bigint::BigUint;
 use starknet::{
     core::types::{BlockId, EventFilter, FieldElement},
     core::utils::starknet_keccak,
     providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
 };
-use std::fs;
-use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
-use url::Url;
-
-/// Deposit event selector: starknet_keccak("Deposit")
-/// This is the hash of the event name used to filter deposit events
-/// Calculated as: starknet_keccak(b"Deposit") truncated to 250 bits
-const DEPOSIT_EVENT_SELECTOR: &str =
-    "0x9149d2123147c5f43d258257fef0b7b969db78269369ebcf5ebb9eef8592f2";
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{oneshot, Notify};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, error, info, warn};
 
 /// Calculate event selector from name
-fn get_event_selector(name: &str) -> FieldElement {
+pub fn get_event_selector(name: &str) -> FieldElement {
     let hash = starknet_keccak(name.as_bytes());
     // Truncate to 250 bits (Starknet field element)
     hash & FieldElement::from_hex_be("0x3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap()
 }
 
+/// Deposit event members the ASP reads (zylith::privacy::deposit::Deposit)
+#[derive(serde::Deserialize)]
+struct DepositEventData {
+    commitment: String,
+    leaf_index: String,
+}
+
+/// Commitment and leaf index of a Deposit event, decoded by member name with the embedded ABI
+pub fn decode_deposit_event(data: &[FieldElement]) -> Result<(BigUint, u32), String> {
+    let data: Vec<String> = data.iter().map(|felt| format!("0x{:x}", felt)).collect();
+    let deposit: DepositEventData = crate::abi::decode_event(crate::abi::get_zylith_abi(), "Deposit", &data)?;
+    let commitment = BigUint::parse_bytes(deposit.commitment.trim_start_matches("0x").as_bytes(), 16)
+        .ok_or_else(|| format!("Invalid Deposit commitment {}", deposit.commitment))?;
+    let leaf_index = deposit
+        .leaf_index
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid Deposit leaf_index {}", deposit.leaf_index))?;
+    Ok((commitment, leaf_index))
+}
+
+/// How the syncer treats a computed root the chain doesn't recognize (SYNC_VERIFY_ROOTS)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootVerification {
+    /// Warn and keep syncing (default)
+    Lenient,
+    /// Check every inserted batch with is_root_known and halt on an unknown root
+    Strict,
+}
+
+impl RootVerification {
+    pub fn from_env() -> Self {
+        match std::env::var("SYNC_VERIFY_ROOTS").as_deref() {
+            Ok("strict") => RootVerification::Strict,
+            _ => RootVerification::Lenient,
+        }
+    }
+}
+
+/// Deposits applied per tree lock during catch-up (SYNC_BATCH_SIZE)
+const DEFAULT_SYNC_BATCH_SIZE: usize = 100;
+
+fn sync_batch_size_from_env() -> usize {
+    std::env::var("SYNC_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_SYNC_BATCH_SIZE)
+}
+
+/// Poll interval without a live subscription (SYNC_POLL_SECS)
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+/// With a live subscription, polls only run on notifications plus this safety net
+const SUBSCRIBED_POLL_INTERVAL_SECS: u64 = 60;
+
+/// get_events page size (SYNC_CHUNK_SIZE)
+const DEFAULT_CHUNK_SIZE: u64 = 1000;
+/// Largest page RPC nodes accept for starknet_getEvents (pathfinder and juno cap it at 1024)
+const MAX_CHUNK_SIZE: u64 = 1024;
+
+fn poll_interval_from_env() -> u64 {
+    std::env::var("SYNC_POLL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_POLL_INTERVAL_SECS)
+}
+
+/// Keep a requested page size within what the RPC accepts
+fn clamp_chunk_size(requested: u64) -> u64 {
+    let clamped = requested.clamp(1, MAX_CHUNK_SIZE);
+    if clamped != requested {
+        warn!("[Syncer] ⚠️  SYNC_CHUNK_SIZE={} is outside 1..={}, using {}", requested, MAX_CHUNK_SIZE, clamped);
+    }
+    clamped
+}
+
+fn chunk_size_from_env() -> u64 {
+    std::env::var("SYNC_CHUNK_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(clamp_chunk_size)
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+}
+
+/// Sync health shared with /health
+#[derive(Default)]
+pub struct SyncStatus {
+    /// Set when strict root verification halted the syncer
+    halted: Mutex<Option<String>>,
+}
+
+impl SyncStatus {
+    pub fn halted_reason(&self) -> Option<String> {
+        self.halted.lock().unwrap().clone()
+    }
+
+    fn halt(&self, reason: String) {
+        *self.halted.lock().unwrap() = Some(reason);
+    }
+
+    fn resume(&self) {
+        *self.halted.lock().unwrap() = None;
+    }
+}
+
+/// Pending rewind: the new cursor, the tree to continue from (None: empty) and the waiter
+type ResyncRequest = (u64, Option<MerkleTree>, oneshot::Sender<u64>);
+
+/// Rewind requests from POST /deposit/resync and /deposit/import, applied by the running syncer
+/// at its next loop
+#[derive(Default)]
+pub struct ResyncSignal {
+    /// Latest request; a newer one replaces it
+    pending: Mutex<Option<ResyncRequest>>,
+    notify: Notify,
+}
+
+impl ResyncSignal {
+    /// Ask the syncer to rebuild the tree from `from_block`
+    /// Resolves with the new cursor once applied, or errors if a newer request replaced this one
+    pub fn request(&self, from_block: u64) -> oneshot::Receiver<u64> {
+        self.send(from_block, None)
+    }
+
+    /// Ask the syncer to replace its tree with `tree` and continue after `last_synced_block`
+    pub fn request_import(&self, tree: MerkleTree, last_synced_block: u64) -> oneshot::Receiver<u64> {
+        self.send(last_synced_block, Some(tree))
+    }
+
+    fn send(&self, block: u64, tree: Option<MerkleTree>) -> oneshot::Receiver<u64> {
+        let (tx, rx) = oneshot::channel();
+        *self.pending.lock().unwrap() = Some((block, tree, tx));
+        self.notify.notify_one();
+        rx
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending.lock().unwrap().is_some()
+    }
+
+    fn take(&self) -> Option<ResyncRequest> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
+/// Source of truth for roots the contract has seen
+pub trait RootOracle {
+    async fn is_root_known(&self, root: &str) -> Result<bool, String>;
+}
+
+impl RootOracle for crate::blockchain::BlockchainClient {
+    async fn is_root_known(&self, root: &str) -> Result<bool, String> {
+        crate::blockchain::BlockchainClient::is_root_known(self, root).await
+    }
+}
+
+/// Outcome of checking a batch's computed root against the chain
+#[derive(Debug, PartialEq)]
+enum RootCheck {
+    Known,
+    /// Chain doesn't know the root, or the check failed, in lenient mode
+    Warned,
+    /// Chain doesn't know the root in strict mode
+    Halt(String),
+}
+
+async fn verify_batch_root<R: RootOracle>(oracle: &R, mode: RootVerification, root: &str) -> RootCheck {
+    match oracle.is_root_known(root).await {
+        Ok(true) => RootCheck::Known,
+        Ok(false) if mode == RootVerification::Strict => {
+            RootCheck::Halt(format!("Computed root {} is not known on-chain", root))
+        }
+        Ok(false) => {
+            warn!("[Syncer] ⚠️  Computed root {} is not known on-chain, continuing", root);
+            RootCheck::Warned
+        }
+        Err(e) => {
+            // A failed RPC call isn't evidence of drift; retry with the next batch
+            warn!("[Syncer] ⚠️  Could not verify computed root {}: {}", root, e);
+            RootCheck::Warned
+        }
+    }
+}
+
 /// State file for persistence
-const STATE_FILE: &str = "asp_state.json";
+pub const STATE_FILE: &str = "asp_state.json";
+
+/// Restart backoff for the supervised syncer task (doubles up to the max)
+const RESTART_BACKOFF_INITIAL_SECS: u64 = 1;
+const RESTART_BACKOFF_MAX_SECS: u64 = 60;
+/// A run this long is considered healthy and resets the backoff
+const HEALTHY_RUN_SECS: u64 = 300;
 
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 struct SyncerState {
     last_synced_block: u64,
+    /// Spent nullifiers seen up to last_synced_block (hex)
+    #[serde(default)]
+    spent_nullifiers: Vec<String>,
 }
 
 pub struct Syncer {
-    pub provider: Arc<JsonRpcClient<HttpTransport>>,
+    /// RPC endpoints, shared with the BlockchainClient so both prefer the same healthy one
+    pub rpc: Arc<RpcEndpoints>,
     pub contract_address: FieldElement,
-    pub tree: Arc<Mutex<MerkleTree>>,
+    pub tree: Arc<RwLock<MerkleTree>>,
+    /// Depth the tree is rebuilt with on a resync
+    depth: usize,
     pub deposit_selector: FieldElement,
     pub swap_selector: FieldElement,
     pub pool_event_selector: FieldElement,
+    pub nullifier_selector: FieldElement,
     pub blockchain_client: Option<Arc<crate::blockchain::BlockchainClient>>,
+    pub deposit_feed: Option<Arc<DepositFeed>>,
+    /// RESYNC_FROM_BLOCK only applies to the first run, not to watchdog restarts
+    resync_env_applied: AtomicBool,
+    pub root_verification: RootVerification,
+    pub status: Arc<SyncStatus>,
+    pub screener: Option<Arc<Screener>>,
+    /// Deposits inserted per tree lock; the lock is released between batches
+    pub batch_size: usize,
+    /// Where the cursor is kept; PERSISTENCE=memory never touches asp_state.json and
+    /// restarts sync from scratch
+    pub persistence: Persistence,
+    /// SYNC_TRANSPORT=ws: poll when the RPC notifies new events instead of every 5s
+    pub transport: SyncTransport,
+    wake: Arc<Notify>,
+    subscription_live: Arc<AtomicBool>,
+    /// Seconds between polls (SYNC_POLL_SECS)
+    pub poll_interval_secs: u64,
+    /// Events requested per get_events page (SYNC_CHUNK_SIZE)
+    pub chunk_size: u64,
+    /// Last block covered by the tree snapshot loaded at startup
+    snapshot_block: Option<u64>,
+    /// Leaf count in the last written snapshot (snapshots are only rewritten when it changes)
+    snapshot_leaf_count: AtomicU32,
+    /// Spent nullifiers from NullifierSpent events, shared with the API
+    pub nullifiers: Arc<NullifierSet>,
+    /// Sync gauges for GET /metrics
+    pub metrics: Arc<Metrics>,
+    /// Stop at the next batch boundary, saving the cursor and tree snapshot
+    pub shutdown: Arc<Shutdown>,
+    /// Rewind requests from the API
+    pub resync: Arc<ResyncSignal>,
+    /// Cursor file and tree snapshot file (per pool when syncing several contracts)
+    state_file: String,
+    snapshot_file: String,
 }
 
 impl Syncer {
-    pub fn new(rpc_url: &str, contract_address: &str, tree: Arc<Mutex<MerkleTree>>) -> Self {
-        let provider = Arc::new(JsonRpcClient::new(HttpTransport::new(
-            Url::parse(rpc_url).unwrap(),
-        )));
-        let contract_address = FieldElement::from_hex_be(contract_address).unwrap();
-        let deposit_selector = FieldElement::from_hex_be(DEPOSIT_EVENT_SELECTOR).unwrap();
-        
-        // Calculate selectors for other events
+    /// `depth` is the configured tree depth (MERKLE_TREE_DEPTH); `tree` must have been built with it
+    pub fn new(rpc: Arc<RpcEndpoints>, contract_address: &str, tree: Arc<RwLock<MerkleTree>>, depth: usize) -> Self {
+        let tree_depth = tree.read().unwrap().depth;
+        assert_eq!(tree_depth, depth, "Syncer configured for depth {} but the tree has depth {}", depth, tree_depth);
+        let contract_address = crate::util::parse_felt(contract_address)
+            .unwrap_or_else(|e| panic!("Invalid contract address: {}", e));
+        let deposit_selector = get_event_selector("Deposit");
         let swap_selector = get_event_selector("Swap");
         let pool_event_selector = get_event_selector("PoolEvent");
+        let nullifier_selector = get_event_selector("NullifierSpent");
 
         Self {
-            provider,
+            rpc,
             contract_address,
             tree,
+            depth,
             deposit_selector,
             swap_selector,
             pool_event_selector,
+            nullifier_selector,
             blockchain_client: None,
+            deposit_feed: None,
+            resync_env_applied: AtomicBool::new(false),
+            root_verification: RootVerification::from_env(),
+            status: Arc::new(SyncStatus::default()),
+            screener: None,
+            batch_size: sync_batch_size_from_env(),
+            persistence: Persistence::from_env(),
+            transport: SyncTransport::from_env(),
+            wake: Arc::new(Notify::new()),
+            subscription_live: Arc::new(AtomicBool::new(false)),
+            poll_interval_secs: poll_interval_from_env(),
+            chunk_size: chunk_size_from_env(),
+            snapshot_block: None,
+            snapshot_leaf_count: AtomicU32::new(0),
+            nullifiers: Arc::new(NullifierSet::default()),
+            metrics: Arc::new(Metrics::default()),
+            shutdown: Arc::new(Shutdown::default()),
+            resync: Arc::new(ResyncSignal::default()),
+            state_file: STATE_FILE.to_string(),
+            snapshot_file: TREE_SNAPSHOT_FILE.to_string(),
         }
     }
 
@@ -69,29 +327,172 @@ impl Syncer {
         self
     }
 
-    /// Load persisted state
-    fn load_state() -> SyncerState {
-        fs::read_to_string(STATE_FILE)
-            .ok()
+    /// Share sync health (strict root verification halts) with the API
+    pub fn with_status(mut self, status: Arc<SyncStatus>) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Screen newly synced deposits (only if SCREENING_DEPOSITS is on and a webhook is set)
+    /// Deposits are on-chain already, so the decision is only logged, never blocks the tree
+    pub fn with_screener(mut self, screener: Arc<Screener>) -> Self {
+        if screener.screen_deposits && screener.is_enabled() {
+            self.screener = Some(screener);
+        }
+        self
+    }
+
+    /// Publish inserted deposits to WebSocket subscribers (never blocks the sync loop)
+    pub fn with_deposit_feed(mut self, feed: Arc<DepositFeed>) -> Self {
+        self.deposit_feed = Some(feed);
+        self
+    }
+
+    /// The tree was loaded from a snapshot covering deposits up to `block`
+    /// The first run resumes from there if the cursor is further ahead
+    pub fn with_snapshot(mut self, block: u64) -> Self {
+        self.snapshot_block = Some(block);
+        self.snapshot_leaf_count = AtomicU32::new(self.tree.read().unwrap().get_leaf_count());
+        self
+    }
+
+    /// Record spent nullifiers in a set the API can read
+    pub fn with_nullifiers(mut self, nullifiers: Arc<NullifierSet>) -> Self {
+        self.nullifiers = nullifiers;
+        self
+    }
+
+    /// Report sync progress and chain head to the API's metrics
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Stop syncing (and stop restarting) once `shutdown` is triggered
+    pub fn with_shutdown(mut self, shutdown: Arc<Shutdown>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Take rewind requests from `resync` (POST /deposit/resync)
+    pub fn with_resync(mut self, resync: Arc<ResyncSignal>) -> Self {
+        self.resync = resync;
+        self
+    }
+
+    /// Keep the cursor and tree snapshot in these files instead of the defaults, so several
+    /// syncers (one per pool contract) don't share them
+    pub fn with_files(mut self, state_file: String, snapshot_file: String) -> Self {
+        self.state_file = state_file;
+        self.snapshot_file = snapshot_file;
+        self
+    }
+
+    /// Snapshot the tree as of `block` if it gained (or lost) leaves since the last snapshot
+    fn save_tree_snapshot(&self, block: u64) {
+        let tree = self.tree.read().unwrap();
+        let leaf_count = tree.get_leaf_count();
+        if leaf_count == self.snapshot_leaf_count.load(Ordering::SeqCst) {
+            return;
+        }
+        match tree.save_to_file(self.persistence, &self.snapshot_file, block) {
+            Ok(()) => self.snapshot_leaf_count.store(leaf_count, Ordering::SeqCst),
+            Err(e) => warn!("[Syncer] ⚠️  {}", e),
+        }
+    }
+
+    /// Load persisted state (default in memory mode)
+    fn load_state(&self) -> SyncerState {
+        self.persistence
+            .read(&self.state_file)
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default()
     }
 
-    /// Save state to file
-    fn save_state(state: &SyncerState) {
-        if let Ok(json) = serde_json::to_string(state) {
-            let _ = fs::write(STATE_FILE, json);
+    /// Save state to file (no-op in memory mode)
+    /// Always writes the live nullifier set, whatever `state` was loaded with
+    fn save_state(&self, state: &SyncerState) {
+        let state = SyncerState {
+            last_synced_block: state.last_synced_block,
+            spent_nullifiers: self.nullifiers.to_hex(),
+        };
+        if let Ok(json) = serde_json::to_string(&state) {
+            if let Err(e) = self.persistence.write(&self.state_file, &json) {
+                warn!("[Syncer] ⚠️  {}", e);
+            }
+        }
+    }
+
+    /// Run the syncer in a child task and respawn it whenever it exits or panics
+    /// The cursor lives in the state file, so a restarted syncer resumes where it stopped
+    pub async fn supervise(self: Arc<Self>, restarts: Arc<AtomicU64>) {
+        let mut backoff = RESTART_BACKOFF_INITIAL_SECS;
+
+        // The subscription outlives syncer restarts; it only wakes the poll loop
+        if let SyncTransport::WebSocket { url } = &self.transport {
+            tokio::spawn(maintain_subscription(
+                url.clone(),
+                format!("0x{:x}", self.contract_address),
+                self.wake.clone(),
+                self.subscription_live.clone(),
+            ));
+        }
+
+        loop {
+            let started = Instant::now();
+            let syncer = self.clone();
+            let result = tokio::spawn(async move { syncer.run().await }).await;
+
+            if self.shutdown.is_triggered() && result.is_ok() {
+                info!("[Syncer] 💾 Syncer stopped for shutdown");
+                return;
+            }
+            match result {
+                Ok(()) => error!("[Syncer] 🚨 SYNCER TASK EXITED UNEXPECTEDLY - deposit tree is no longer updating"),
+                Err(e) if e.is_panic() => {
+                    error!("[Syncer] 🚨 SYNCER TASK PANICKED - deposit tree is no longer updating: {}", e)
+                }
+                Err(e) => error!("[Syncer] 🚨 SYNCER TASK CANCELLED: {}", e),
+            }
+
+            // A panic while holding the tree lock poisons it for every handler too
+            if self.tree.is_poisoned() {
+                warn!("[Syncer] ⚠️  Deposit tree lock was poisoned, clearing it");
+                self.tree.clear_poison();
+            }
+
+            if started.elapsed() >= Duration::from_secs(HEALTHY_RUN_SECS) {
+                backoff = RESTART_BACKOFF_INITIAL_SECS;
+            }
+
+            let count = restarts.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!("[Syncer] 🔁 Restarting syncer in {}s (restart #{})", backoff, count);
+            sleep(Duration::from_secs(backoff)).await;
+            backoff = (backoff * 2).min(RESTART_BACKOFF_MAX_SECS);
         }
     }
 
     pub async fn run(&self) {
-        let mut state = Self::load_state();
+        let mut state = self.load_state();
         
         // Check if we should force re-sync from a specific block
-        if let Ok(reset_block_str) = std::env::var("RESYNC_FROM_BLOCK") {
-            if let Ok(reset_block) = reset_block_str.parse::<u64>() {
-                state.last_synced_block = reset_block;
-                Self::save_state(&state);
+        // Only on the first run: a restarted syncer resumes from the persisted cursor
+        if !self.resync_env_applied.swap(true, Ordering::SeqCst) {
+            self.nullifiers.extend_from_hex(&state.spent_nullifiers);
+            // Cursor saved after the snapshot: re-read the blocks in between (replays are no-ops)
+            if let Some(snapshot_block) = self.snapshot_block {
+                if snapshot_block < state.last_synced_block {
+                    info!("[Syncer] 🌳 Tree snapshot is at block {}, cursor at {} - resuming from the snapshot",
+                        snapshot_block, state.last_synced_block);
+                    state.last_synced_block = snapshot_block;
+                    self.save_state(&state);
+                }
+            }
+            if let Ok(reset_block_str) = std::env::var("RESYNC_FROM_BLOCK") {
+                if let Ok(reset_block) = reset_block_str.parse::<u64>() {
+                    state.last_synced_block = reset_block;
+                    self.save_state(&state);
+                }
             }
         }
         
@@ -99,13 +500,13 @@ impl Syncer {
         // This ensures we sync ALL events from the beginning
         if state.last_synced_block == 0 {
             state.last_synced_block = 0;
-            Self::save_state(&state);
-            println!("[Syncer] 🚀 Starting fresh sync from block 0 (genesis)");
+            self.save_state(&state);
+            info!("[Syncer] 🚀 Starting fresh sync from block 0 (genesis)");
         }
         
         // Check if tree is empty but contract has deposits
         let leaf_count = {
-            let tree = self.tree.lock().unwrap();
+            let tree = self.tree.read().unwrap();
             tree.get_leaf_count()
         };
         
@@ -115,8 +516,8 @@ impl Syncer {
                     Ok(contract_root) if contract_root != "0x0" && contract_root != "0x0000000000000000000000000000000000000000000000000000000000000000" => {
                         // If contract has deposits but tree is empty, start from block 0 to sync everything
                         state.last_synced_block = 0;
-                        Self::save_state(&state);
-                        println!("[Syncer] 🚀 Contract has deposits but tree is empty - starting sync from block 0");
+                        self.save_state(&state);
+                        info!("[Syncer] 🚀 Contract has deposits but tree is empty - starting sync from block 0");
                     }
                     _ => {}
                 }
@@ -124,31 +525,49 @@ impl Syncer {
         }
 
         loop {
-            // Reload state from file in each iteration to pick up resync requests
-            // This allows the /deposit/resync endpoint to trigger immediate resync
-            let current_state = Self::load_state();
-            if current_state.last_synced_block < state.last_synced_block {
-                // State file was reset to an earlier block - force resync
-                println!("[Syncer] 🔄 Detected resync request - resetting to block {}", current_state.last_synced_block);
-                state.last_synced_block = current_state.last_synced_block;
-                
-                // Clear the tree to force full resync
+            if self.shutdown.is_triggered() {
+                self.save_state(&state);
+                self.save_tree_snapshot(state.last_synced_block);
+                info!("[Syncer] 💾 Saved cursor at block {} before shutdown", state.last_synced_block);
+                return;
+            }
+
+            if let Some((from_block, imported, applied)) = self.resync.take() {
+                match &imported {
+                    Some(tree) => info!(
+                        "[Syncer] 📥 Tree import requested - {} leaves, continuing after block {}",
+                        tree.get_leaf_count(),
+                        from_block
+                    ),
+                    None => info!("[Syncer] 🔄 Resync requested - rebuilding the tree from block {}", from_block),
+                }
+                state.last_synced_block = from_block;
                 {
-                    let mut tree = self.tree.lock().unwrap();
-                    *tree = MerkleTree::new(TREE_DEPTH); // Reset tree - use TREE_DEPTH constant
+                    let mut tree = self.tree.write().unwrap();
+                    *tree = imported.unwrap_or_else(|| MerkleTree::new(self.depth));
+                }
+                // Always rewrite the snapshot, even if the leaf count happens to be unchanged
+                self.snapshot_leaf_count.store(u32::MAX, Ordering::SeqCst);
+                if let Some(feed) = &self.deposit_feed {
+                    feed.clear_replay();
+                }
+                self.save_state(&state);
+                // The old snapshot would otherwise be reloaded on the next start
+                self.save_tree_snapshot(state.last_synced_block);
+                if self.status.halted_reason().is_some() {
+                    info!("[Syncer] ▶️  Resync requested, resuming halted syncer");
+                    self.status.resume();
                 }
-            } else if current_state.last_synced_block != state.last_synced_block {
-                // State was updated but not reset - just update our state
-                state.last_synced_block = current_state.last_synced_block;
+                let _ = applied.send(from_block);
             }
-            
+
             // TEMPORARILY DISABLED FOR DEBUGGING - Root check causes infinite loop
             // First, verify our tree root matches the contract
             // Do this check separately to avoid Send issues - must drop lock before await
             let _should_resync = if let Some(ref blockchain) = self.blockchain_client {
                 // Get local root first (drop lock before await)
                 let local_root = {
-                    let tree = self.tree.lock().unwrap();
+                    let tree = self.tree.read().unwrap();
                     format!("0x{:x}", tree.get_root())
                 };
                 
@@ -159,22 +578,22 @@ impl Syncer {
                     Ok(contract_root) => {
                         // Log comparison but don't resync for debugging
                         if contract_root != local_root {
-                            println!("[Syncer] 🛑 Root mismatch detected (DEBUG MODE - resync disabled):");
-                            println!("[Syncer]    Local root:     {}", local_root);
-                            println!("[Syncer]    On-chain root: {}", contract_root);
+                            warn!("[Syncer] 🛑 Root mismatch detected (DEBUG MODE - resync disabled):");
+                            info!("[Syncer]    Local root:     {}", local_root);
+                            info!("[Syncer]    On-chain root: {}", contract_root);
                             
                             // Show tree status for debugging
-                            let tree = self.tree.lock().unwrap();
+                            let tree = self.tree.read().unwrap();
                             let leaf_count = tree.get_leaf_count();
-                            println!("[Syncer]    Tree has {} leaves", leaf_count);
+                            info!("[Syncer]    Tree has {} leaves", leaf_count);
                             drop(tree);
                         } else {
-                            println!("[Syncer] ✅ Roots match: {}", local_root);
+                            info!("[Syncer] ✅ Roots match: {}", local_root);
                         }
                         false // Don't resync in debug mode
                     }
                     Err(e) => {
-                        eprintln!("[Syncer] ❌ Failed to get contract root: {:?}", e);
+                        error!("[Syncer] ❌ Failed to get contract root: {:?}", e);
                         false
                     }
                 }
@@ -187,52 +606,125 @@ impl Syncer {
             // If root mismatch, do a full resync from block 0
             // This ensures we sync ALL deposits from the beginning
             if should_resync {
-                let tree = self.tree.lock().unwrap();
+                let tree = self.tree.read().unwrap();
                 let leaf_count = tree.get_leaf_count();
                 drop(tree);
                 
-                println!("[Syncer] 🔄 Root mismatch detected - starting full resync from block 0");
-                println!("[Syncer]    Current tree has {} leaves", leaf_count);
+                info!("[Syncer] 🔄 Root mismatch detected - starting full resync from block 0");
+                info!("[Syncer]    Current tree has {} leaves", leaf_count);
                 state.last_synced_block = 0; // Start from genesis to sync everything
-                Self::save_state(&state);
+                self.save_state(&state);
                 
                 // Clear the tree to force full resync
                 {
-                    let mut tree = self.tree.lock().unwrap();
+                    let mut tree = self.tree.write().unwrap();
                     *tree = MerkleTree::new(TREE_DEPTH); // Reset tree - use TREE_DEPTH constant
                 }
-                println!("[Syncer] ✅ Tree cleared, will sync all events from block 0");
+                info!("[Syncer] ✅ Tree cleared, will sync all events from block 0");
             }
             */
 
+            // Strict root verification or gap recovery failed: stay halted until a resync is requested
+            if let Some(reason) = self.status.halted_reason() {
+                error!("[Syncer] 🛑 Halted: {} (POST /deposit/resync to rebuild)", reason);
+                sleep(Duration::from_secs(self.poll_interval_secs)).await;
+                continue;
+            }
+
+            let leaf_count_before = self.tree.read().unwrap().get_leaf_count();
+
             match self.sync_events(state.last_synced_block).await {
+                // Stopped early for a resync: the partial pass is discarded at the top of the loop
+                Ok(_) if self.resync.is_pending() => continue,
                 Ok(new_last_block) => {
+                    if let Some(reason) = self.check_batch_root(leaf_count_before).await {
+                        error!("[Syncer] 🛑 {} - halting sync at block {}", reason, state.last_synced_block);
+                        self.status.halt(reason);
+                        continue;
+                    }
+                    self.nullifiers.mark_synced(new_last_block);
+                    self.metrics.set_synced(new_last_block);
+
                     if new_last_block > state.last_synced_block {
                         let old_block = state.last_synced_block;
                         state.last_synced_block = new_last_block;
-                        Self::save_state(&state);
+                        self.save_state(&state);
+                        self.save_tree_snapshot(new_last_block);
                         
                         // Log progress if we synced a significant number of blocks
                         if new_last_block - old_block > 100 {
-                            let tree = self.tree.lock().unwrap();
+                            let tree = self.tree.read().unwrap();
                             let leaf_count = tree.get_leaf_count();
                             drop(tree);
-                            println!("[Syncer] ✅ Synced from block {} to {} ({} leaves in tree)", 
+                            info!("[Syncer] ✅ Synced from block {} to {} ({} leaves in tree)", 
                                 old_block, new_last_block, leaf_count);
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("[Syncer] ❌ Sync error: {:?}", e);
+                    error!("[Syncer] ❌ Sync error: {:?}", e);
                     // Continue trying - don't exit on error
                 }
             }
-            sleep(Duration::from_secs(5)).await;
+            self.wait_for_next_poll().await;
         }
     }
 
-    async fn sync_events(&self, from_block: u64) -> Result<u64, Box<dyn std::error::Error>> {
-        let latest_block = self.provider.block_number().await?;
+    /// Sleep until the next poll: the interval elapses or the subscription reports new events
+    /// (also fired after a reconnect, so the missed range is back-filled right away)
+    async fn wait_for_next_poll(&self) {
+        let interval = if self.subscription_live.load(Ordering::SeqCst) {
+            SUBSCRIBED_POLL_INTERVAL_SECS.max(self.poll_interval_secs)
+        } else {
+            self.poll_interval_secs
+        };
+        tokio::select! {
+            _ = sleep(Duration::from_secs(interval)) => {}
+            _ = self.wake.notified() => {}
+            _ = self.resync.notify.notified() => {}
+            _ = self.shutdown.triggered() => {}
+        }
+    }
+
+    /// Verify the root after a batch that inserted leaves
+    /// Returns the halt reason when strict verification fails
+    async fn check_batch_root(&self, leaf_count_before: u32) -> Option<String> {
+        let blockchain = self.blockchain_client.as_ref()?;
+        let root = {
+            let tree = self.tree.read().unwrap();
+            if tree.get_leaf_count() == leaf_count_before {
+                return None;
+            }
+            format!("0x{:x}", tree.get_root())
+        };
+
+        match verify_batch_root(blockchain.as_ref(), self.root_verification, &root).await {
+            RootCheck::Halt(reason) => Some(reason),
+            RootCheck::Known | RootCheck::Warned => None,
+        }
+    }
+
+    /// One sync pass on the preferred RPC endpoint; a transient failure makes the next endpoint
+    /// preferred for the following pass (continuation tokens are per endpoint, so a pass never
+    /// switches endpoints halfway)
+    async fn sync_events(&self, from_block: u64) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let (endpoint, provider) = self.rpc.current();
+        let result = self.sync_events_on(provider, from_block).await;
+        if let Err(e) = &result {
+            if is_transient_error(&e.to_string()) {
+                self.rpc.mark_failed(endpoint);
+            }
+        }
+        result
+    }
+
+    async fn sync_events_on(
+        &self,
+        provider: &JsonRpcClient<HttpTransport>,
+        from_block: u64,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let latest_block = provider.block_number().await?;
+        self.metrics.set_chain_head(latest_block);
         if from_block >= latest_block {
             return Ok(from_block);
         }
@@ -248,37 +740,45 @@ impl Syncer {
             keys: None, // Don't filter by keys - we'll check in the loop for nested events
         };
 
-        let chunk_size = 1000;
+        let chunk_size = self.chunk_size;
         let mut continuation_token = None;
         let mut swap_events_seen = 0u32;
         let mut _total_events_seen = 0u32;
         let mut _is_first_page = true;
 
         loop {
-            let events_page = self
-                .provider
+            let events_page = provider
                 .get_events(filter.clone(), continuation_token.clone(), chunk_size)
                 .await?;
             
             _total_events_seen += events_page.events.len() as u32;
+            let mut pending = Vec::new();
             
             for event in events_page.events {
+                // PrivacyEvent::NullifierSpent: data[0] = nullifier
+                if event.keys.contains(&self.nullifier_selector) {
+                    if let Some(nullifier) = event.data.first() {
+                        self.nullifiers.insert(BigUint::from_bytes_be(&nullifier.to_bytes_be()));
+                    }
+                    continue;
+                }
+
                 // For nested enum events (PrivacyEvent::Deposit), the structure is:
                 // keys[0] = PrivacyEvent enum selector
                 // keys[1] = Deposit variant selector (if nested)
                 // OR keys[0] = Deposit selector (if direct)
                 // Check all keys to find the Deposit variant selector
                 let is_deposit_event = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == self.deposit_selector);
+                    event.keys.contains(&self.deposit_selector);
                 
                 // Check for PoolEvent enum (which contains Swap)
                 // Structure: keys[0] = Event enum, keys[1] = PoolEvent enum, keys[2] = Swap variant
                 let is_pool_event = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == self.pool_event_selector);
+                    event.keys.contains(&self.pool_event_selector);
                 
                 // Check for Swap events - can be at keys[1] or keys[2] depending on nesting
                 let is_swap_event = !event.keys.is_empty() && (
-                    event.keys.iter().any(|key| *key == self.swap_selector) ||
+                    event.keys.contains(&self.swap_selector) ||
                     (is_pool_event && event.keys.len() >= 2 && event.keys[1] == self.swap_selector) ||
                     (is_pool_event && event.keys.len() >= 3 && event.keys[2] == self.swap_selector)
                 );
@@ -287,14 +787,14 @@ impl Syncer {
                 if !is_deposit_event {
                     if is_swap_event {
                         swap_events_seen += 1;
-                        println!(
+                        info!(
                             "[Syncer] 🔄 Swap event #{} detected: keys={:?}, data_len={}",
                             swap_events_seen,
                             event.keys.iter().map(|k| format!("0x{:x}", k)).collect::<Vec<_>>(),
                             event.data.len()
                         );
                         if event.data.len() >= 6 {
-                            println!(
+                            debug!(
                                 "  📊 Swap details: sender=0x{:x}, recipient=0x{:x}, amount0={:?}, amount1={:?}",
                                 event.data[0], event.data[1], event.data[2], event.data[3]
                             );
@@ -305,60 +805,53 @@ impl Syncer {
                 
                 // Skip verbose deposit event logging - only log summary
 
-                // Parse Deposit event data:
-                // For nested events, the data structure is:
-                // data[0] = commitment (felt252)
-                // data[1] = leaf_index (u32)
-                // data[2] = root (felt252)
-                if event.data.len() >= 3 {
-                        let commitment_felt = event.data[0];
-                    let leaf_index_felt = event.data[1];
-
-                    // Convert to BigUint for our Merkle tree
-                    let commitment = BigUint::from_bytes_be(&commitment_felt.to_bytes_be());
-                    let leaf_index: u32 = {
-                        let bytes = leaf_index_felt.to_bytes_be();
-                        let mut arr = [0u8; 4];
-                        let start = bytes.len().saturating_sub(4);
-                        arr.copy_from_slice(&bytes[start..]);
-                        u32::from_be_bytes(arr)
-                    };
-
-                    // Get zero leaf and current count before acquiring mutable lock
-                    let (current_count, zero_leaf) = {
-                        let tree = self.tree.lock().unwrap();
-                        (tree.get_leaf_count(), tree.zeros[0].clone())
-                    };
-
-                    // Insert into our tree
-                    let mut tree = self.tree.lock().unwrap();
+                // Decoded by member name with the ABI's Deposit layout
+                match decode_deposit_event(&event.data) {
+                    Ok((commitment, leaf_index)) => pending.push(PendingDeposit {
+                        leaf_index,
+                        commitment,
+                        block: event.block_number.unwrap_or(latest_block),
+                    }),
+                    Err(e) => error!("[Syncer] ❌ Skipping undecodable Deposit event: {}", e),
+                }
+            }
 
-                    // Handle gaps: if leaf_index is greater than current count, insert empty leaves
-                    if leaf_index > current_count {
-                        let gaps = leaf_index - current_count;
-                        // Insert empty leaves (zeros) to fill the gap
-                        for i in 0..gaps {
-                            tree.insert_at_index(current_count + i, zero_leaf.clone());
-                        }
-                    } else if leaf_index < current_count {
-                        // Check if this commitment already exists at this index
-                        if let Some(existing_leaf) = tree.nodes.get(&(0, leaf_index)) {
-                            if existing_leaf == &commitment {
-                                // Skip silently - already processed
-                                continue;
-                            }
-                        }
-                    }
+            // A leaf index past the next free one means Deposit events were missed; inserting
+            // anyway would misalign every later leaf, so fetch the missing ones first
+            if let Some(reason) = self.fill_gaps(provider, &mut pending, latest_block).await? {
+                error!("[Syncer] 🛑 {} - halting sync at block {}", reason, from_block);
+                self.status.halt(reason);
+                return Ok(from_block);
+            }
 
-                    // Insert the commitment at the correct index
-                    if leaf_index == current_count {
-                        // Normal sequential insert
-                        tree.insert(commitment.clone());
-                    } else {
-                        // Insert at specific index (filling gaps already handled above)
-                        tree.insert_at_index(leaf_index, commitment.clone());
-                    }
-                    // Process silently - no logging
+            // Insert this page in bounded batches so readers get the tree lock in between
+            let inserted = apply_deposits_batched(&self.tree, pending, self.batch_size, |deposit, root| {
+                let commitment = format!("0x{:x}", deposit.commitment);
+                if let Some(feed) = &self.deposit_feed {
+                    feed.publish(DepositEvent {
+                        leaf_index: deposit.leaf_index,
+                        commitment: commitment.clone(),
+                        root: format!("0x{:x}", root),
+                    });
+                }
+                if let Some(screener) = &self.screener {
+                    let screener = screener.clone();
+                    let request = ScreeningRequest {
+                        kind: "deposit",
+                        commitment,
+                        address: None,
+                    };
+                    // Screened in the background; the decision is logged by the screener
+                    tokio::spawn(async move {
+                        screener.screen(&request).await;
+                    });
+                }
+            })
+            .await;
+            // The on-chain root moved with these deposits
+            if inserted > 0 {
+                if let Some(blockchain) = &self.blockchain_client {
+                    blockchain.invalidate_root_cache();
                 }
             }
 
@@ -366,13 +859,371 @@ impl Syncer {
             if continuation_token.is_none() {
                 break;
             }
+            // Stopping mid-range: keep the old cursor; the pages already applied are in the tree
+            // and replay as no-ops on the next start
+            if self.shutdown.is_triggered() {
+                info!("[Syncer] 💾 Shutdown requested, stopping before block {}", latest_block);
+                return Ok(from_block);
+            }
+            if self.resync.is_pending() {
+                info!("[Syncer] 🔄 Resync requested, stopping before block {}", latest_block);
+                return Ok(from_block);
+            }
         }
 
         // Only log if swap events were found
         if swap_events_seen > 0 {
-            println!("[Syncer] 🔄 Found {} swap event(s)", swap_events_seen);
+            info!("[Syncer] 🔄 Found {} swap event(s)", swap_events_seen);
         }
 
         Ok(latest_block)
     }
+
+    /// Add the deposits missing before `pending` can be applied, re-read from the chain up to
+    /// `to_block`. Returns the reason to halt when some leaf index isn't in any Deposit event
+    /// (RPC failures are errors, retried on the next pass)
+    async fn fill_gaps(
+        &self,
+        provider: &JsonRpcClient<HttpTransport>,
+        pending: &mut Vec<PendingDeposit>,
+        to_block: u64,
+    ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        loop {
+            let leaf_count = self.tree.read().unwrap().get_leaf_count();
+            let Some(missing) = find_gap(leaf_count, pending) else { return Ok(None) };
+            warn!(
+                "[Syncer] ⚠️  Deposit gap: leaf {} arrived with leaves {}..{} missing - re-reading events up to block {}",
+                missing.end, missing.start, missing.end, to_block
+            );
+
+            let found = self.find_deposits(provider, missing.clone(), to_block).await?;
+            if found.len() < missing.len() {
+                let absent = missing.clone().filter(|index| !found.contains_key(index)).count();
+                return Ok(Some(format!(
+                    "Deposit gap unrecoverable: {} of leaves {}..{} not found in events up to block {}",
+                    absent, missing.start, missing.end, to_block
+                )));
+            }
+            info!("[Syncer] ✅ Recovered {} missed deposit(s)", found.len());
+            pending.extend(
+                found
+                    .into_iter()
+                    .map(|(leaf_index, (commitment, block))| PendingDeposit { leaf_index, commitment, block }),
+            );
+            pending.sort_by_key(|deposit| deposit.leaf_index);
+        }
+    }
+
+    /// Deposit commitments and their blocks for the leaf indices in `wanted`, from every Deposit
+    /// event up to `to_block`
+    async fn find_deposits(
+        &self,
+        provider: &JsonRpcClient<HttpTransport>,
+        wanted: Range<u32>,
+        to_block: u64,
+    ) -> Result<BTreeMap<u32, (BigUint, u64)>, Box<dyn std::error::Error + Send + Sync>> {
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(0)),
+            to_block: Some(BlockId::Number(to_block)),
+            address: Some(self.contract_address),
+            keys: None,
+        };
+        let mut found = BTreeMap::new();
+        let mut continuation_token = None;
+        loop {
+            let page = provider
+                .get_events(filter.clone(), continuation_token.clone(), self.chunk_size)
+                .await?;
+            for event in page.events {
+                if !event.keys.contains(&self.deposit_selector) {
+                    continue;
+                }
+                // Undecodable events were already logged by the pass that skipped them
+                if let Ok((commitment, leaf_index)) = decode_deposit_event(&event.data) {
+                    if wanted.contains(&leaf_index) {
+                        found.insert(leaf_index, (commitment, event.block_number.unwrap_or(to_block)));
+                    }
+                }
+            }
+            continuation_token = page.continuation_token;
+            if found.len() == wanted.len() || continuation_token.is_none() {
+                return Ok(found);
+            }
+        }
+    }
+}
+
+/// Leaf indices missing before `deposits` (in event order) can go on top of `leaf_count` leaves
+/// Replayed deposits below the next free index are not gaps
+fn find_gap(leaf_count: u32, deposits: &[PendingDeposit]) -> Option<Range<u32>> {
+    let mut next = leaf_count;
+    for deposit in deposits {
+        if deposit.leaf_index > next {
+            return Some(next..deposit.leaf_index);
+        }
+        next = next.max(deposit.leaf_index + 1);
+    }
+    None
+}
+
+/// Deposit parsed from an event, waiting to be inserted into the tree
+struct PendingDeposit {
+    leaf_index: u32,
+    commitment: BigUint,
+    /// Block the Deposit event was emitted in
+    block: u64,
+}
+
+/// Apply deposits taking the tree lock once per `batch_size` deposits and yielding between
+/// batches, so a large catch-up doesn't starve the API's readers of the lock.
+/// `on_inserted` runs after each batch's lock is released, with the root after that insert.
+/// Returns how many deposits were inserted.
+async fn apply_deposits_batched(
+    tree: &RwLock<MerkleTree>,
+    deposits: Vec<PendingDeposit>,
+    batch_size: usize,
+    mut on_inserted: impl FnMut(&PendingDeposit, &BigUint),
+) -> usize {
+    let mut inserted_total = 0;
+    let mut deposits = deposits.into_iter().peekable();
+
+    while deposits.peek().is_some() {
+        let mut inserted = Vec::new();
+        {
+            let mut tree = tree.write().unwrap();
+            for deposit in deposits.by_ref().take(batch_size.max(1)) {
+                match apply_deposit(&mut tree, deposit.leaf_index, deposit.commitment.clone()) {
+                    DepositOutcome::Inserted => {
+                        tree.record_deposit_block(deposit.leaf_index, deposit.block);
+                        let root = tree.get_root();
+                        inserted.push((deposit, root));
+                    }
+                    DepositOutcome::Duplicate => {
+                        warn!(
+                            "[Syncer] ⚠️  Replayed Deposit event for index {} (commitment 0x{:x}) - skipping",
+                            deposit.leaf_index, deposit.commitment
+                        );
+                    }
+                    DepositOutcome::Conflict { existing_index } => {
+                        error!(
+                            "[Syncer] 🛑 INCONSISTENCY: commitment 0x{:x} already in tree at index {}, but event reports index {} - not inserting",
+                            deposit.commitment, existing_index, deposit.leaf_index
+                        );
+                    }
+                }
+            }
+        }
+
+        inserted_total += inserted.len();
+        for (deposit, root) in &inserted {
+            on_inserted(deposit, root);
+        }
+        tokio::task::yield_now().await;
+    }
+
+    inserted_total
+}
+
+/// Result of applying a single Deposit event to the local tree
+#[derive(Debug, PartialEq)]
+enum DepositOutcome {
+    /// Commitment was inserted at the event's leaf index
+    Inserted,
+    /// Commitment is already held at the same index (replayed event) - tree untouched
+    Duplicate,
+    /// Commitment is already held at a different index - tree and chain disagree
+    Conflict { existing_index: u32 },
+}
+
+/// Insert a deposit commitment at `leaf_index`, tolerating at-least-once event delivery.
+/// Gaps before `leaf_index` are filled with zero leaves (matching Cairo contract behavior).
+fn apply_deposit(tree: &mut MerkleTree, leaf_index: u32, commitment: BigUint) -> DepositOutcome {
+    if let Some(existing_index) = tree.find_commitment_index(&commitment) {
+        if existing_index == leaf_index {
+            return DepositOutcome::Duplicate;
+        }
+        return DepositOutcome::Conflict { existing_index };
+    }
+
+    let current_count = tree.get_leaf_count();
+    let zero_leaf = tree.zeros[0].clone();
+
+    // Handle gaps: if leaf_index is greater than current count, insert empty leaves
+    if leaf_index > current_count {
+        for i in current_count..leaf_index {
+            tree.insert_at_index(i, zero_leaf.clone());
+        }
+    }
+
+    if leaf_index == tree.get_leaf_count() {
+        // Normal sequential insert
+        tree.insert(commitment);
+    } else {
+        // Insert at specific index (filling gaps already handled above)
+        tree.insert_at_index(leaf_index, commitment);
+    }
+
+    DepositOutcome::Inserted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::TREE_DEPTH;
+
+    #[test]
+    fn test_replayed_deposit_leaves_tree_unchanged() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        let commitment = BigUint::from(12345u64);
+
+        assert_eq!(apply_deposit(&mut tree, 0, commitment.clone()), DepositOutcome::Inserted);
+        let root = tree.get_root();
+        let leaf_count = tree.get_leaf_count();
+
+        // Same event delivered again
+        assert_eq!(apply_deposit(&mut tree, 0, commitment), DepositOutcome::Duplicate);
+        assert_eq!(tree.get_root(), root);
+        assert_eq!(tree.get_leaf_count(), leaf_count);
+    }
+
+    #[test]
+    fn test_same_commitment_at_other_index_is_conflict() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        let commitment = BigUint::from(12345u64);
+
+        apply_deposit(&mut tree, 0, commitment.clone());
+        apply_deposit(&mut tree, 1, BigUint::from(67890u64));
+        let root = tree.get_root();
+
+        assert_eq!(
+            apply_deposit(&mut tree, 2, commitment),
+            DepositOutcome::Conflict { existing_index: 0 }
+        );
+        assert_eq!(tree.get_root(), root);
+        assert_eq!(tree.get_leaf_count(), 2);
+    }
+
+    /// Chain that only knows a fixed set of roots
+    struct MockChain {
+        known_roots: Vec<String>,
+    }
+
+    impl RootOracle for MockChain {
+        async fn is_root_known(&self, root: &str) -> Result<bool, String> {
+            Ok(self.known_roots.iter().any(|r| r == root))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_root_halts_only_in_strict_mode() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        apply_deposit(&mut tree, 0, BigUint::from(12345u64));
+        let known_root = format!("0x{:x}", tree.get_root());
+        let chain = MockChain { known_roots: vec![known_root.clone()] };
+
+        assert_eq!(verify_batch_root(&chain, RootVerification::Strict, &known_root).await, RootCheck::Known);
+
+        // A root the chain never produced, e.g. after a missed or corrupted event
+        apply_deposit(&mut tree, 1, BigUint::from(99u64));
+        let drifted_root = format!("0x{:x}", tree.get_root());
+
+        match verify_batch_root(&chain, RootVerification::Strict, &drifted_root).await {
+            RootCheck::Halt(reason) => assert!(reason.contains(&drifted_root)),
+            other => panic!("expected strict mode to halt, got {:?}", other),
+        }
+        assert_eq!(
+            verify_batch_root(&chain, RootVerification::Lenient, &drifted_root).await,
+            RootCheck::Warned
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_served_during_catch_up() {
+        // Stands in for a provider returning a large backlog of Deposit events
+        let deposits: Vec<PendingDeposit> = (0..200u32)
+            .map(|i| PendingDeposit { leaf_index: i, commitment: BigUint::from(i + 1), block: 0 })
+            .collect();
+        let tree = Arc::new(RwLock::new(MerkleTree::new(TREE_DEPTH)));
+
+        // Polls the tree like GET /deposit/root until catch-up finishes
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (tree, done) = (tree.clone(), done.clone());
+            tokio::spawn(async move {
+                let mut seen = Vec::new();
+                while !done.load(Ordering::Relaxed) {
+                    seen.push(tree.read().unwrap().get_leaf_count());
+                    tokio::task::yield_now().await;
+                }
+                seen
+            })
+        };
+
+        let mut published = 0;
+        let inserted = apply_deposits_batched(&tree, deposits, 20, |_, _| published += 1).await;
+        done.store(true, Ordering::Relaxed);
+        let seen = reader.await.unwrap();
+
+        assert_eq!(inserted, 200);
+        assert_eq!(published, 200);
+        assert_eq!(tree.read().unwrap().get_leaf_count(), 200);
+        // The reader got the lock between batches and saw partial progress, never a half batch
+        assert!(seen.iter().any(|count| *count > 0 && *count < 200));
+        assert!(seen.iter().all(|count| count % 20 == 0));
+    }
+
+    #[test]
+    fn test_chunk_size_is_clamped_to_rpc_limit() {
+        assert_eq!(clamp_chunk_size(1000), 1000);
+        assert_eq!(clamp_chunk_size(5000), MAX_CHUNK_SIZE);
+        assert_eq!(clamp_chunk_size(0), 1);
+    }
+
+    #[test]
+    fn test_memory_persistence_keeps_cursor_off_disk() {
+        let tree = Arc::new(RwLock::new(MerkleTree::new(TREE_DEPTH)));
+        let rpc = Arc::new(RpcEndpoints::new(&["http://localhost:5050".to_string()]).unwrap());
+        let mut syncer = Syncer::new(rpc, "0x1", tree, TREE_DEPTH);
+        syncer.persistence = Persistence::Memory;
+
+        syncer.save_state(&SyncerState { last_synced_block: 42, ..Default::default() });
+        assert_eq!(syncer.load_state().last_synced_block, 0);
+    }
+
+    #[test]
+    fn test_find_gap() {
+        let deposits = |indices: &[u32]| -> Vec<PendingDeposit> {
+            indices.iter().map(|i| PendingDeposit { leaf_index: *i, commitment: BigUint::from(*i + 1), block: 0 }).collect()
+        };
+        // Contiguous, including a replay of an existing leaf
+        assert_eq!(find_gap(3, &deposits(&[2, 3, 4])), None);
+        assert_eq!(find_gap(0, &[]), None);
+        // Leaves 3 and 4 were never seen
+        assert_eq!(find_gap(3, &deposits(&[5, 6])), Some(3..5));
+        // Gap in the middle of a page
+        assert_eq!(find_gap(0, &deposits(&[0, 1, 4])), Some(2..4));
+    }
+
+    #[test]
+    fn test_deposit_gap_is_zero_filled() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+
+        assert_eq!(apply_deposit(&mut tree, 2, BigUint::from(7u8)), DepositOutcome::Inserted);
+        assert_eq!(tree.get_leaf_count(), 3);
+        assert_eq!(tree.nodes.get(&(0, 0)), Some(&BigUint::from(0u8)));
+    }
+    #[tokio::test]
+    async fn test_newer_resync_request_replaces_older() {
+        let signal = ResyncSignal::default();
+        let older = signal.request(100);
+        let newer = signal.request(200);
+        assert!(signal.is_pending());
+
+        let (block, _, applied) = signal.take().unwrap();
+        assert_eq!(block, 200);
+        applied.send(block).unwrap();
+        assert_eq!(newer.await, Ok(200));
+        assert!(older.await.is_err());
+        assert!(signal.take().is_none());
+    }
 }
@@ -0,0 +1,190 @@
+// Tick <-> sqrt price conversion
+// Mirrors zylith/src/clmm/math.cairo (MVP approximation, not the Uniswap log formula)
+// so the ASP derives exactly the tick the contract will store.
+
+use num_bigint::BigUint;
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+/// zylith/src/clmm/tick.cairo TICK_SPACING
+pub const TICK_SPACING: i32 = 60;
+
+/// 2^128
+pub fn q128() -> BigUint {
+    BigUint::from(1u8) << 128u32
+}
+
+/// 2^64 (contract MIN_SQRT_RATIO)
+pub fn min_sqrt_ratio() -> BigUint {
+    BigUint::from(1u8) << 64u32
+}
+
+/// 2^192 (contract MAX_SQRT_RATIO)
+pub fn max_sqrt_ratio() -> BigUint {
+    BigUint::from(1u8) << 192u32
+}
+
+/// Sqrt price at a tick (same as math::get_sqrt_ratio_at_tick)
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<BigUint, String> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(format!("Tick {} out of bounds [{}, {}]", tick, MIN_TICK, MAX_TICK));
+    }
+
+    let q128 = q128();
+    if tick == 0 {
+        return Ok(q128);
+    }
+
+    let abs_tick = tick.unsigned_abs();
+    let denominator: u32 = if abs_tick <= 100 {
+        20000
+    } else if abs_tick <= 1000 {
+        2000
+    } else {
+        200
+    };
+    let delta = (&q128 * BigUint::from(abs_tick)) / BigUint::from(denominator);
+
+    if tick > 0 {
+        let ratio = q128 + delta;
+        Ok(ratio.min(max_sqrt_ratio()))
+    } else if delta < q128 {
+        Ok((q128 - delta).max(min_sqrt_ratio()))
+    } else {
+        Ok(min_sqrt_ratio())
+    }
+}
+
+/// Tick at a sqrt price (same as math::get_tick_at_sqrt_ratio)
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x128: &BigUint) -> Result<i32, String> {
+    if *sqrt_price_x128 == q128() {
+        return Ok(0);
+    }
+    if *sqrt_price_x128 < min_sqrt_ratio() || *sqrt_price_x128 > max_sqrt_ratio() {
+        return Err("Sqrt price out of bounds".to_string());
+    }
+
+    let mut low = MIN_TICK;
+    let mut high = MAX_TICK;
+    while low < high {
+        let mid = (low + high + 1).div_euclid(2);
+        if get_sqrt_ratio_at_tick(mid)? <= *sqrt_price_x128 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Validate an initial pool sqrt price and return the tick the contract will derive
+/// Rejects prices outside [sqrt_ratio(MIN_TICK), sqrt_ratio(MAX_TICK)]: past those bounds the
+/// contract's tick search saturates and the stored tick no longer matches the price
+pub fn validate_initial_sqrt_price(sqrt_price_x128: &BigUint) -> Result<i32, String> {
+    let min_allowed = get_sqrt_ratio_at_tick(MIN_TICK)?;
+    let max_allowed = get_sqrt_ratio_at_tick(MAX_TICK)?;
+
+    if *sqrt_price_x128 < min_allowed || *sqrt_price_x128 > max_allowed {
+        return Err(format!(
+            "sqrt_price_x128 {} implies a tick outside [{}, {}]. Allowed sqrt_price_x128 range: [{}, {}]",
+            sqrt_price_x128, MIN_TICK, MAX_TICK, min_allowed, max_allowed
+        ));
+    }
+
+    let tick = get_tick_at_sqrt_ratio(sqrt_price_x128)?;
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(format!(
+            "Derived tick {} outside [{}, {}]. Allowed sqrt_price_x128 range: [{}, {}]",
+            tick, MIN_TICK, MAX_TICK, min_allowed, max_allowed
+        ));
+    }
+
+    Ok(tick)
+}
+
+/// Swap price limit from a request: "low,high" (u128 limbs) or a single decimal u256
+/// Without one, the widest limit for the direction (MIN_SQRT_RATIO selling token0, MAX_SQRT_RATIO
+/// selling token1), i.e. no slippage bound. The contract clamps limits to that range, so values
+/// outside it are rejected rather than silently widened or narrowed.
+pub fn parse_sqrt_price_limit(limit: Option<&str>, zero_for_one: bool) -> Result<BigUint, String> {
+    let Some(limit) = limit.map(str::trim).filter(|l| !l.is_empty()) else {
+        return Ok(if zero_for_one { min_sqrt_ratio() } else { max_sqrt_ratio() });
+    };
+    let limb = |name: &str, value: &str| {
+        value
+            .trim()
+            .parse::<u128>()
+            .map_err(|_| format!("sqrt_price_limit {} '{}' is not a u128", name, value.trim()))
+    };
+    let value = match limit.split_once(',') {
+        Some((low, high)) => (BigUint::from(limb("high", high)?) << 128u32) + limb("low", low)?,
+        None => limit
+            .parse::<BigUint>()
+            .map_err(|_| format!("sqrt_price_limit '{}' must be \"low,high\" or a decimal u256", limit))?,
+    };
+    if value < min_sqrt_ratio() || value > max_sqrt_ratio() {
+        return Err(format!(
+            "sqrt_price_limit {} is outside [{}, {}]",
+            value,
+            min_sqrt_ratio(),
+            max_sqrt_ratio()
+        ));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt_price_limit_default_and_explicit() {
+        // No limit: the whole range in the swap's direction
+        assert_eq!(parse_sqrt_price_limit(None, true), Ok(min_sqrt_ratio()));
+        assert_eq!(parse_sqrt_price_limit(Some(""), false), Ok(max_sqrt_ratio()));
+
+        // 2^128 + 5 as limbs and as a single number
+        let expected = q128() + 5u32;
+        assert_eq!(parse_sqrt_price_limit(Some("5,1"), true), Ok(expected.clone()));
+        assert_eq!(parse_sqrt_price_limit(Some(" 5 , 1 "), false), Ok(expected.clone()));
+        assert_eq!(parse_sqrt_price_limit(Some(&expected.to_string()), true), Ok(expected));
+
+        assert!(parse_sqrt_price_limit(Some("5,-1"), true).is_err());
+        assert!(parse_sqrt_price_limit(Some("abc"), true).is_err());
+        // Outside [MIN_SQRT_RATIO, MAX_SQRT_RATIO]
+        assert!(parse_sqrt_price_limit(Some("0,0"), true).is_err());
+        assert!(parse_sqrt_price_limit(Some(&(max_sqrt_ratio() + 1u32).to_string()), false).is_err());
+    }
+
+    #[test]
+    fn test_one_to_one_price_is_tick_zero() {
+        assert_eq!(validate_initial_sqrt_price(&q128()), Ok(0));
+    }
+
+    #[test]
+    fn test_boundary_prices_map_to_boundary_ticks() {
+        let max_price = get_sqrt_ratio_at_tick(MAX_TICK).unwrap();
+        assert_eq!(validate_initial_sqrt_price(&max_price), Ok(MAX_TICK));
+
+        let min_price = get_sqrt_ratio_at_tick(MIN_TICK).unwrap();
+        assert_eq!(min_price, min_sqrt_ratio());
+        assert!(validate_initial_sqrt_price(&min_price).is_ok());
+
+        // Round trip for an ordinary tick
+        let price = get_sqrt_ratio_at_tick(500).unwrap();
+        assert_eq!(get_tick_at_sqrt_ratio(&price), Ok(500));
+    }
+
+    #[test]
+    fn test_out_of_range_prices_are_rejected() {
+        let above_max_tick = get_sqrt_ratio_at_tick(MAX_TICK).unwrap() + 1u8;
+        let err = validate_initial_sqrt_price(&above_max_tick).unwrap_err();
+        assert!(err.contains("Allowed sqrt_price_x128 range"));
+
+        let below_min = min_sqrt_ratio() - 1u8;
+        assert!(validate_initial_sqrt_price(&below_min).is_err());
+
+        assert!(validate_initial_sqrt_price(&(max_sqrt_ratio() + 1u8)).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// Parsing of felt252 values from user input, shared by the RPC client, calldata builders and handlers
+// Addresses, hashes, roots and commitment lookups are hex, with or without 0x. Proof values,
+// public inputs and the commitments written into calldata come out of the prover as decimal
+// strings, so they have their own parser that also takes 0x-prefixed hex. Both trim whitespace and
+// reject anything at or above the Starknet prime instead of reducing it.
+
+use crate::felt_conv::felt_to_biguint;
+use num_bigint::BigUint;
+use num_traits::Num;
+use starknet::core::types::FieldElement;
+
+/// Starknet field prime: 2^251 + 17 * 2^192 + 1
+const FELT_PRIME_HEX: &str = "800000000000011000000000000000000000000000000000000000000000001";
+
+/// Hex digits in a felt252 (32 bytes); longer input is refused even with leading zeros
+const MAX_FELT_HEX_DIGITS: usize = 64;
+
+/// felt252 from hex, "0x"-prefixed or bare ("1f" is 0x1f, never decimal 1f)
+pub fn parse_felt(value: &str) -> Result<FieldElement, String> {
+    let trimmed = value.trim();
+    let hex = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    parse_digits(value, hex, 16)
+}
+
+/// `parse_felt` as a BigUint, the form the Merkle tree and nullifier set key on
+pub fn parse_felt_biguint(value: &str) -> Result<BigUint, String> {
+    parse_felt(value).map(|felt| felt_to_biguint(&felt))
+}
+
+/// felt252 from a decimal string, as the prover emits proof values and public inputs, or from
+/// 0x-prefixed hex
+pub fn parse_felt_or_decimal(value: &str) -> Result<FieldElement, String> {
+    let trimmed = value.trim();
+    match trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")) {
+        Some(hex) => parse_digits(value, hex, 16),
+        None => parse_digits(value, trimmed, 10),
+    }
+}
+
+fn parse_digits(original: &str, digits: &str, radix: u32) -> Result<FieldElement, String> {
+    if digits.is_empty() {
+        return Err(format!("Invalid felt252 '{}': empty value", original));
+    }
+    if radix == 16 && digits.len() > MAX_FELT_HEX_DIGITS {
+        return Err(format!(
+            "Invalid felt252 '{}': {} hex digits, at most {}",
+            original,
+            digits.len(),
+            MAX_FELT_HEX_DIGITS
+        ));
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        let kind = if radix == 16 { "hex" } else { "decimal" };
+        return Err(format!("Invalid felt252 '{}': not a {} number", original, kind));
+    }
+    let value = BigUint::from_str_radix(digits, radix)
+        .map_err(|e| format!("Invalid felt252 '{}': {}", original, e))?;
+    if value >= felt_prime() {
+        return Err(format!("Invalid felt252 '{}': not below the Starknet field prime", original));
+    }
+    FieldElement::from_byte_slice_be(&value.to_bytes_be())
+        .map_err(|e| format!("Invalid felt252 '{}': {}", original, e))
+}
+
+/// The Starknet field prime, the bound every felt252 is below
+pub fn felt_prime() -> BigUint {
+    BigUint::from_str_radix(FELT_PRIME_HEX, 16).expect("the field prime is valid hex")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_felt_hex_forms() {
+        let expected = FieldElement::from(0x1fu128);
+        assert_eq!(parse_felt("0x1f").unwrap(), expected);
+        assert_eq!(parse_felt("1f").unwrap(), expected);
+        assert_eq!(parse_felt("  0X001F\n").unwrap(), expected);
+        // Bare digits are hex too
+        assert_eq!(parse_felt("10").unwrap(), FieldElement::from(16u128));
+
+        assert!(parse_felt("").unwrap_err().contains("empty"));
+        assert!(parse_felt("0x").unwrap_err().contains("empty"));
+        assert!(parse_felt("0xg1").unwrap_err().contains("not a hex number"));
+        assert!(parse_felt("0x0x10").unwrap_err().contains("not a hex number"));
+        assert!(parse_felt(&format!("0x{}", "0".repeat(65))).unwrap_err().contains("65 hex digits"));
+    }
+
+    #[test]
+    fn test_prime_bound() {
+        let below = "0x800000000000011000000000000000000000000000000000000000000000000";
+        assert!(parse_felt(below).is_ok());
+        let prime = format!("0x{}", FELT_PRIME_HEX);
+        assert!(parse_felt(&prime).unwrap_err().contains("field prime"));
+        assert!(parse_felt("0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff").is_err());
+    }
+
+    #[test]
+    fn test_parse_felt_or_decimal() {
+        assert_eq!(parse_felt_or_decimal("31").unwrap(), FieldElement::from(31u128));
+        assert_eq!(parse_felt_or_decimal(" 0x1f ").unwrap(), FieldElement::from(31u128));
+        assert!(parse_felt_or_decimal("1f").unwrap_err().contains("not a decimal number"));
+        assert!(parse_felt_or_decimal("").is_err());
+
+        let prime = felt_prime().to_string();
+        assert!(parse_felt_or_decimal(&prime).unwrap_err().contains("field prime"));
+        let below = (felt_prime() - 1u32).to_string();
+        assert!(parse_felt_or_decimal(&below).is_ok());
+    }
+}
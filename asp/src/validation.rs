@@ -0,0 +1,85 @@
+// Request amount validation shared by the swap and liquidity flows
+// A zero amount produces a valid but no-op proof, so it is rejected before any proving work.
+
+use crate::tick_math::{MAX_TICK, MIN_TICK, TICK_SPACING};
+
+/// Parse a u128 amount and require it to be nonzero
+pub fn parse_nonzero_amount(field: &str, value: &str) -> Result<u128, String> {
+    let amount = value
+        .trim()
+        .parse::<u128>()
+        .map_err(|_| format!("Invalid {} format", field))?;
+    if amount == 0 {
+        return Err(format!("{} must be greater than zero", field));
+    }
+    Ok(amount)
+}
+
+/// Swap flows: amount_specified must be nonzero, and so must the output amount when known
+/// (prepare_swap only has amount_specified; the proof request also carries amount_out)
+pub fn validate_swap_amounts(amount_specified: &str, amount_out: Option<&str>) -> Result<u128, String> {
+    let amount_specified = parse_nonzero_amount("amount_specified", amount_specified)?;
+    if let Some(amount_out) = amount_out {
+        parse_nonzero_amount("amount_out", amount_out)?;
+    }
+    Ok(amount_specified)
+}
+
+/// Mint/burn liquidity flows
+pub fn validate_liquidity_amount(liquidity: &str) -> Result<u128, String> {
+    parse_nonzero_amount("liquidity", liquidity)
+}
+
+/// Liquidity position range, checked the same way private_mint_liquidity does after
+/// verifying the proof: MIN_TICK <= tick_lower < tick_upper <= MAX_TICK, both multiples of TICK_SPACING
+pub fn validate_tick_range(tick_lower: i32, tick_upper: i32) -> Result<(), String> {
+    if tick_lower >= tick_upper {
+        return Err("tick_lower must be less than tick_upper".to_string());
+    }
+    if tick_lower < MIN_TICK || tick_upper > MAX_TICK {
+        return Err(format!("Ticks must be within [{}, {}]", MIN_TICK, MAX_TICK));
+    }
+    if tick_lower % TICK_SPACING != 0 || tick_upper % TICK_SPACING != 0 {
+        return Err(format!("Ticks must be multiples of the tick spacing ({})", TICK_SPACING));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_swap_rejects_zero_amount_specified() {
+        let err = validate_swap_amounts("0", None).unwrap_err();
+        assert_eq!(err, "amount_specified must be greater than zero");
+        assert_eq!(validate_swap_amounts("1000", None), Ok(1000));
+    }
+
+    #[test]
+    fn test_swap_proof_rejects_zero_amounts() {
+        assert!(validate_swap_amounts("0", Some("500")).is_err());
+        let err = validate_swap_amounts("1000", Some("0")).unwrap_err();
+        assert_eq!(err, "amount_out must be greater than zero");
+        assert_eq!(validate_swap_amounts("1000", Some("500")), Ok(1000));
+    }
+
+    #[test]
+    fn test_liquidity_rejects_zero() {
+        assert_eq!(
+            validate_liquidity_amount("0").unwrap_err(),
+            "liquidity must be greater than zero"
+        );
+        assert!(validate_liquidity_amount("abc").is_err());
+        assert_eq!(validate_liquidity_amount("42"), Ok(42));
+    }
+
+    #[test]
+    fn test_tick_range() {
+        assert_eq!(validate_tick_range(-120, 60), Ok(()));
+        assert!(validate_tick_range(60, 60).is_err());
+        assert!(validate_tick_range(60, -60).is_err());
+        assert!(validate_tick_range(-30, 60).is_err());
+        assert!(validate_tick_range(MIN_TICK - 60, 0).is_err());
+    }
+}
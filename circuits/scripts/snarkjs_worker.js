@@ -0,0 +1,77 @@
+/**
+ * Long-lived snarkjs worker for the ASP server (asp/src/snarkjs_worker.rs)
+ *
+ * Loads snarkjs once and serves requests as JSON lines on stdin, one response line per
+ * request on stdout. Requests run concurrently; responses carry the request id.
+ *
+ *   {"id": 1, "op": "witness", "input": "<input.json>", "wasm": "<circuit.wasm>", "witness": "<out.wtns>"}
+ *   {"id": 2, "op": "prove", "zkey": "<circuit.zkey>", "witness": "<in.wtns>", "proof": "<proof.json>", "public": "<public.json>"}
+ *   {"id": 3, "op": "normalize_proof", "proof": "<proof.json>"}
+ *
+ *   -> {"id": 1, "ok": true, "elapsed": 1.23}
+ *   -> {"id": 1, "ok": false, "error": "..."}
+ *
+ * Logs go to stderr so stdout only carries responses.
+ */
+
+const snarkjs = require("snarkjs");
+const fs = require("fs");
+const readline = require("readline");
+
+const ops = {
+    async witness({ input, wasm, witness }) {
+        const data = JSON.parse(fs.readFileSync(input, "utf8"));
+        await snarkjs.wtns.calculate(data, wasm, witness);
+    },
+
+    async prove({ zkey, witness, proof, public: publicFile }) {
+        const result = await snarkjs.groth16.prove(zkey, witness);
+        fs.writeFileSync(proof, JSON.stringify(result.proof, null, 2));
+        fs.writeFileSync(publicFile, JSON.stringify(result.publicSignals, null, 2));
+    },
+
+    // Protocol field and 2-element pi_a/pi_b/pi_c, as convert_garaga.py expects
+    async normalize_proof({ proof }) {
+        const data = JSON.parse(fs.readFileSync(proof, "utf8"));
+        if (!data.protocol) {
+            data.protocol = "groth16";
+        }
+        for (const key of ["pi_a", "pi_b", "pi_c"]) {
+            if (data[key] && data[key].length > 2) {
+                data[key] = [data[key][0], data[key][1]];
+            }
+        }
+        fs.writeFileSync(proof, JSON.stringify(data, null, 2));
+    },
+};
+
+function respond(message) {
+    process.stdout.write(JSON.stringify(message) + "\n");
+}
+
+const lines = readline.createInterface({ input: process.stdin });
+
+lines.on("line", async (line) => {
+    let request;
+    try {
+        request = JSON.parse(line);
+    } catch (error) {
+        console.error("[snarkjs-worker] Invalid request line:", line);
+        return;
+    }
+
+    const start = Date.now();
+    try {
+        const op = ops[request.op];
+        if (!op) {
+            throw new Error(`Unknown op: ${request.op}`);
+        }
+        await op(request);
+        respond({ id: request.id, ok: true, elapsed: (Date.now() - start) / 1000 });
+    } catch (error) {
+        respond({ id: request.id, ok: false, error: error.stack || String(error) });
+    }
+});
+
+// The ASP closed our stdin (shutdown or restart)
+lines.on("close", () => process.exit(0));
@@ -74,7 +74,14 @@ export class ASPClient {
     return this.fetch<TreeInfo>(`/deposit/info`, undefined, true); // Use direct ASP URL
   }
 
-  async getDepositIndex(commitment: string | bigint): Promise<{ index?: number; found: boolean; message?: string }> {
+  // 200: synced (proof available), 202: on-chain but pending sync (index is final), 404: never deposited
+  async getDepositIndex(commitment: string | bigint): Promise<{
+    index?: number;
+    found: boolean;
+    synced?: boolean;
+    status?: "synced" | "pending_sync" | "not_found";
+    message?: string;
+  }> {
     // Convert BigInt to hex string if needed
     let commitmentStr: string;
     if (typeof commitment === 'bigint') {
@@ -88,13 +95,15 @@ export class ASPClient {
     
     console.log(`[ASP Client] Getting deposit index for commitment: ${commitmentPath.substring(0, 20)}...`)
     
-    return this.fetch<{ index?: number; found: boolean; message?: string }>(
-      `/deposit/index/${commitmentPath}`,
-      {
-        method: "GET",
-      },
-      true // Use direct ASP URL
-    );
+    const response = await fetch(`${this.getAspUrl()}/deposit/index/${commitmentPath}`, { method: "GET" });
+    if (response.status === 404) {
+      return { found: false, status: "not_found", message: "Commitment was never deposited" };
+    }
+    if (!response.ok) {
+      const errorText = await response.text().catch(() => "Unknown error");
+      throw new Error(`ASP API Error: ${response.status} ${response.statusText} - ${errorText}`);
+    }
+    return response.json();
   }
 
   async getHealth(): Promise<{ status: string }> {